@@ -1,18 +1,28 @@
+mod always_allowed;
+mod confirmation_handlers;
 mod connection;
 mod diff;
+mod file_system_access;
+mod generation_parameters;
 mod mention;
+mod sessions;
 mod terminal;
 
 use ::terminal::terminal_settings::TerminalSettings;
-use agent_settings::AgentSettings;
+use agent_settings::{AgentSettings, AutoApprove};
+pub use always_allowed::*;
 use collections::HashSet;
+pub use confirmation_handlers::*;
 pub use connection::*;
 pub use diff::*;
+pub use file_system_access::*;
+pub use generation_parameters::*;
 use language::language_settings::FormatOnSave;
 pub use mention::*;
 use project::lsp_store::{FormatTrigger, LspFormatTarget};
 use serde::{Deserialize, Serialize};
-use settings::{Settings as _, SettingsLocation};
+pub use sessions::*;
+use settings::{Settings as _, SettingsLocation, ToolPermissionProfile, update_settings_file};
 use task::{Shell, ShellBuilder};
 pub use terminal::*;
 
@@ -44,6 +54,48 @@ pub struct UserMessage {
     pub content: ContentBlock,
     pub chunks: Vec<acp::ContentBlock>,
     pub checkpoint: Option<Checkpoint>,
+    /// How the latest turn answering this message went, once it ended.
+    pub turn_stats: Option<TurnStats>,
+    /// Whether the agent was asked to respond with JSON, to show its response as a tree.
+    pub structured_response: bool,
+}
+
+/// How long a turn took and, when the agent reports them in the `_meta` of its response as
+/// `{"model": "…", "usage": {"inputTokens": 1200, "outputTokens": 300}}`, the model and the
+/// tokens it used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnStats {
+    pub duration: Duration,
+    /// How long the agent took to start responding.
+    pub first_response: Option<Duration>,
+    pub model: Option<SharedString>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+}
+
+impl TurnStats {
+    fn new(
+        duration: Duration,
+        first_response: Option<Duration>,
+        meta: Option<&serde_json::Value>,
+    ) -> Self {
+        let usage = meta.and_then(|meta| meta.get("usage"));
+        let tokens = |key: &str| {
+            usage
+                .and_then(|usage| usage.get(key))
+                .and_then(|tokens| tokens.as_u64())
+        };
+        Self {
+            duration,
+            first_response,
+            model: meta
+                .and_then(|meta| meta.get("model"))
+                .and_then(|model| model.as_str())
+                .map(|model| model.to_string().into()),
+            input_tokens: tokens("inputTokens"),
+            output_tokens: tokens("outputTokens"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -179,6 +231,11 @@ pub struct ToolCall {
     pub resolved_locations: Vec<Option<AgentLocation>>,
     pub raw_input: Option<serde_json::Value>,
     pub raw_output: Option<serde_json::Value>,
+    /// Whether the call was allowed from a confirmation prompt.
+    pub approved: bool,
+    /// The name of the called tool, for agents that send it in the `_meta` of the tool
+    /// call as `{"tool_name": "…"}`.
+    pub tool_name: Option<SharedString>,
 }
 
 impl ToolCall {
@@ -194,6 +251,12 @@ impl ToolCall {
         } else {
             tool_call.title
         };
+        let tool_name = tool_call
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("tool_name"))
+            .and_then(|tool_name| tool_name.as_str())
+            .map(|tool_name| SharedString::from(tool_name.to_string()));
         let mut content = Vec::with_capacity(tool_call.content.len());
         for item in tool_call.content {
             content.push(ToolCallContent::from_acp(
@@ -215,6 +278,8 @@ impl ToolCall {
             status,
             raw_input: tool_call.raw_input,
             raw_output: tool_call.raw_output,
+            approved: false,
+            tool_name,
         };
         Ok(result)
     }
@@ -610,20 +675,24 @@ impl ToolCallContent {
         terminals: &HashMap<acp::TerminalId, Entity<Terminal>>,
         cx: &mut App,
     ) -> Result<()> {
-        let needs_update = match (&self, &new) {
-            (Self::Diff(old_diff), acp::ToolCallContent::Diff { diff: new_diff }) => {
-                old_diff.read(cx).needs_update(
-                    new_diff.old_text.as_deref().unwrap_or(""),
-                    &new_diff.new_text,
-                    cx,
-                )
+        if let (Self::Diff(old_diff), acp::ToolCallContent::Diff { diff: new_diff }) = (&self, &new)
+        {
+            let old_text = new_diff.old_text.as_deref().unwrap_or("");
+            if !old_diff
+                .read(cx)
+                .needs_update(old_text, &new_diff.new_text, cx)
+            {
+                return Ok(());
+            }
+            // Keep the diff of an edit being streamed, so that its view isn't rebuilt.
+            let path = new_diff.path.to_string_lossy();
+            if old_diff.read(cx).is_streamed_by(&path, old_text) {
+                old_diff.update(cx, |diff, cx| diff.stream_new_text(&new_diff.new_text, cx));
+                return Ok(());
             }
-            _ => true,
-        };
-
-        if needs_update {
-            *self = Self::from_acp(new, language_registry, terminals, cx)?;
         }
+
+        *self = Self::from_acp(new, language_registry, terminals, cx)?;
         Ok(())
     }
 
@@ -788,6 +857,12 @@ pub struct RetryStatus {
     pub duration: Duration,
 }
 
+/// Sent to the agent along with every message of a thread in plan mode.
+const PLAN_MODE_INSTRUCTIONS: &str = "The user turned on plan mode: don't edit, create, delete or move any files, and don't run any commands. Only read and search the project, and propose a plan, showing the changes you would make as diffs, for the user to review.";
+
+/// Sent to the agent along with messages asking for a structured response.
+const STRUCTURED_RESPONSE_INSTRUCTIONS: &str = "The user asked for a structured response: respond with a single JSON object or array, and nothing else. Don't wrap it in a code block or explain it, as it will be parsed and shown as a tree.";
+
 pub struct AcpThread {
     title: SharedString,
     entries: Vec<AgentThreadEntry>,
@@ -799,11 +874,21 @@ pub struct AcpThread {
     connection: Rc<dyn AgentConnection>,
     session_id: acp::SessionId,
     token_usage: Option<TokenUsage>,
+    /// When the running turn started and when the agent first responded to it.
+    turn_started_at: Option<Instant>,
+    first_response_at: Option<Instant>,
     prompt_capabilities: acp::PromptCapabilities,
     _observe_prompt_capabilities: Task<anyhow::Result<()>>,
     terminals: HashMap<acp::TerminalId, Entity<Terminal>>,
     pending_terminal_output: HashMap<acp::TerminalId, Vec<Vec<u8>>>,
     pending_terminal_exit: HashMap<acp::TerminalId, acp::TerminalExitStatus>,
+    /// Whether the agent is only to propose changes, rejecting the tool calls that make them.
+    plan_mode: bool,
+    /// Whether the agent's edits are allowed without confirmation, on this thread only.
+    auto_accept_edits: bool,
+    /// Whether nobody is there to confirm tool calls, so the thread answers them itself.
+    unattended: bool,
+    generation_parameters: GenerationParameters,
 }
 
 #[derive(Debug)]
@@ -999,6 +1084,13 @@ impl AcpThread {
         cx: &mut Context<Self>,
     ) -> Self {
         let prompt_capabilities = prompt_capabilities_rx.borrow().clone();
+        // The thread can only be read once it's constructed.
+        let this = cx.weak_entity();
+        cx.defer(move |cx| {
+            if let Some(thread) = this.upgrade() {
+                AgentSessions::global(cx).update(cx, |sessions, cx| sessions.register(&thread, cx));
+            }
+        });
         let task = cx.spawn::<_, anyhow::Result<()>>(async move |this, cx| {
             loop {
                 let caps = prompt_capabilities_rx.recv().await?;
@@ -1020,11 +1112,17 @@ impl AcpThread {
             connection,
             session_id,
             token_usage: None,
+            turn_started_at: None,
+            first_response_at: None,
             prompt_capabilities,
             _observe_prompt_capabilities: task,
             terminals: HashMap::default(),
             pending_terminal_output: HashMap::default(),
             pending_terminal_exit: HashMap::default(),
+            plan_mode: false,
+            auto_accept_edits: false,
+            unattended: false,
+            generation_parameters: GenerationParameters::default(),
         }
     }
 
@@ -1068,6 +1166,70 @@ impl AcpThread {
         self.token_usage.as_ref()
     }
 
+    pub fn plan_mode(&self) -> bool {
+        self.plan_mode
+    }
+
+    pub fn set_plan_mode(&mut self, plan_mode: bool, cx: &mut Context<Self>) {
+        self.plan_mode = plan_mode;
+        cx.notify();
+    }
+
+    pub fn generation_parameters(&self) -> &GenerationParameters {
+        &self.generation_parameters
+    }
+
+    pub fn set_generation_parameters(
+        &mut self,
+        generation_parameters: GenerationParameters,
+        cx: &mut Context<Self>,
+    ) {
+        self.generation_parameters = generation_parameters;
+        cx.notify();
+    }
+
+    pub fn auto_accept_edits(&self) -> bool {
+        self.auto_accept_edits
+    }
+
+    /// Turns accepting the agent's edits without confirmation on or off, accepting the edits
+    /// already waiting for confirmation when turned on.
+    pub fn set_auto_accept_edits(&mut self, auto_accept_edits: bool, cx: &mut Context<Self>) {
+        self.auto_accept_edits = auto_accept_edits;
+        let waiting = self
+            .entries
+            .iter()
+            .filter_map(|entry| match entry {
+                AgentThreadEntry::ToolCall(tool_call) => Some(tool_call.id.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for id in waiting {
+            if let Some((option_id, option_kind)) = self.auto_accept_decision(&id) {
+                self.authorize_tool_call(id, option_id, option_kind, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn unattended(&self) -> bool {
+        self.unattended
+    }
+
+    /// Makes the thread answer the tool calls that would wait for confirmation, for threads
+    /// that run in the background: reads and searches are allowed once, the rest rejected.
+    pub fn set_unattended(&mut self, unattended: bool, cx: &mut Context<Self>) {
+        self.unattended = unattended;
+        cx.notify();
+    }
+
+    /// The files the agent may read and write through Zed, which it may not write in plan mode.
+    pub fn file_system_access(&self, cx: &App) -> FileSystemAccess {
+        let mut access = FileSystemAccess::for_project(self.project.read(cx), cx);
+        access.writes_allowed &= !self.plan_mode;
+        access
+    }
+
     pub fn has_pending_edit_tool_calls(&self) -> bool {
         for entry in self.entries.iter().rev() {
             match entry {
@@ -1163,6 +1325,8 @@ impl AcpThread {
                     content,
                     chunks: vec![chunk],
                     checkpoint: None,
+                    turn_stats: None,
+                    structured_response: false,
                 }),
                 cx,
             );
@@ -1175,6 +1339,9 @@ impl AcpThread {
         is_thought: bool,
         cx: &mut Context<Self>,
     ) {
+        if is_thought && AgentSettings::get_global(cx).discard_thinking {
+            return;
+        }
         let language_registry = self.project.read(cx).languages().clone();
         let entries_len = self.entries.len();
         if let Some(last_entry) = self.entries.last_mut()
@@ -1214,14 +1381,17 @@ impl AcpThread {
     }
 
     fn push_entry(&mut self, entry: AgentThreadEntry, cx: &mut Context<Self>) {
+        if self.turn_started_at.is_some()
+            && self.first_response_at.is_none()
+            && !matches!(entry, AgentThreadEntry::UserMessage(_))
+        {
+            self.first_response_at = Some(Instant::now());
+        }
         self.entries.push(entry);
         cx.emit(AcpThreadEvent::NewEntry);
     }
 
-    pub fn can_set_title(&mut self, cx: &mut Context<Self>) -> bool {
-        self.connection.set_title(&self.session_id, cx).is_some()
-    }
-
+    /// Renames the thread. The title is only kept in Zed for agents that don't name their threads.
     pub fn set_title(&mut self, title: SharedString, cx: &mut Context<Self>) -> Task<Result<()>> {
         if title != self.title {
             self.title = title.clone();
@@ -1272,6 +1442,8 @@ impl AcpThread {
                     resolved_locations: Vec::new(),
                     raw_input: None,
                     raw_output: None,
+                    approved: false,
+                    tool_name: None,
                 };
                 self.push_entry(AgentThreadEntry::ToolCall(failed_tool_call), cx);
                 return Ok(());
@@ -1465,7 +1637,18 @@ impl AcpThread {
     ) -> Result<BoxFuture<'static, acp::RequestPermissionOutcome>> {
         let (tx, rx) = oneshot::channel();
 
-        if respect_always_allow_setting && AgentSettings::get_global(cx).always_allow_tool_actions {
+        let kind = tool_call.fields.kind.or_else(|| {
+            self.tool_call(&tool_call.id)
+                .map(|(_, existing)| existing.kind)
+        });
+        let settings = AgentSettings::get_global(cx);
+        let auto_approved = settings.always_allow_tool_actions
+            || kind.is_some_and(|kind| is_auto_approved(kind, &settings.auto_approve));
+        let denied = kind.is_some_and(|kind| {
+            (self.plan_mode && plan_mode_rejects(kind))
+                || profile_allows(kind, settings.tool_permission_profile) == Some(false)
+        });
+        if respect_always_allow_setting && auto_approved && !denied {
             // Don't use AllowAlways, because then if you were to turn off always_allow_tool_actions,
             // some tools would (incorrectly) continue to auto-accept.
             if let Some(allow_once_option) = options.iter().find_map(|option| {
@@ -1485,13 +1668,25 @@ impl AcpThread {
             }
         }
 
+        let id = tool_call.id.clone();
         let status = ToolCallStatus::WaitingForConfirmation {
             options,
             respond_tx: tx,
         };
 
         self.upsert_tool_call_inner(tool_call, status, cx)?;
-        cx.emit(AcpThreadEvent::ToolAuthorizationRequired);
+        if let Some((option_id, option_kind)) = self
+            .plan_mode_decision(&id)
+            .or_else(|| self.profile_decision(&id, cx))
+            .or_else(|| self.auto_accept_decision(&id))
+            .or_else(|| self.handler_decision(&id, cx))
+            .or_else(|| self.always_allowed_decision(&id, cx))
+            .or_else(|| self.unattended_decision(&id))
+        {
+            self.authorize_tool_call(id, option_id, option_kind, cx);
+        } else {
+            cx.emit(AcpThreadEvent::ToolAuthorizationRequired);
+        }
 
         let fut = async {
             match rx.await {
@@ -1504,6 +1699,114 @@ impl AcpThread {
         Ok(fut)
     }
 
+    /// The option picked by the [`ToolCallConfirmationHandler`] of a tool call waiting for
+    /// confirmation, if it decides on it.
+    fn handler_decision(
+        &mut self,
+        id: &acp::ToolCallId,
+        cx: &App,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        let handler = tool_call_confirmation_handler(tool_call, cx)?;
+        let option_id = handler.decide(tool_call, options, cx)?;
+        let option = options.iter().find(|option| option.id == option_id)?;
+        Some((option.id.clone(), option.kind))
+    }
+
+    /// The option rejecting a tool call waiting for confirmation once, if it would change the
+    /// project while the thread is in plan mode.
+    fn plan_mode_decision(
+        &self,
+        id: &acp::ToolCallId,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        if !self.plan_mode {
+            return None;
+        }
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        if !plan_mode_rejects(tool_call.kind) {
+            return None;
+        }
+        once_option(options, false)
+    }
+
+    /// The option allowing an edit waiting for confirmation once, if the thread accepts edits
+    /// without confirmation and isn't in plan mode.
+    fn auto_accept_decision(
+        &self,
+        id: &acp::ToolCallId,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        if !self.auto_accept_edits || self.plan_mode {
+            return None;
+        }
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        if tool_call.kind != acp::ToolKind::Edit {
+            return None;
+        }
+        once_option(options, true)
+    }
+
+    /// The option allowing a read or search waiting for confirmation once, or rejecting any
+    /// other tool call, if the thread is unattended.
+    fn unattended_decision(
+        &self,
+        id: &acp::ToolCallId,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        if !self.unattended {
+            return None;
+        }
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        let allow = matches!(tool_call.kind, acp::ToolKind::Read | acp::ToolKind::Search);
+        once_option(options, allow)
+    }
+
+    /// The option allowing or rejecting a tool call waiting for confirmation once, if the
+    /// `agent.tool_permission_profile` setting decides on tool calls of its kind.
+    fn profile_decision(
+        &self,
+        id: &acp::ToolCallId,
+        cx: &App,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        let allow = profile_allows(
+            tool_call.kind,
+            AgentSettings::get_global(cx).tool_permission_profile,
+        )?;
+        once_option(options, allow)
+    }
+
+    /// The option allowing a tool call waiting for confirmation once, if "Always Allow" was
+    /// chosen for a tool call it shares a [`ToolPermission`] with.
+    fn always_allowed_decision(
+        &self,
+        id: &acp::ToolCallId,
+        cx: &App,
+    ) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+        let (_, tool_call) = self.tool_call(id)?;
+        let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+            return None;
+        };
+        let permission = ToolPermission::for_tool_call(tool_call)?;
+        if !permission.is_granted(&AgentSettings::get_global(cx).always_allowed) {
+            return None;
+        }
+        once_option(options, true)
+    }
+
     pub fn authorize_tool_call(
         &mut self,
         id: acp::ToolCallId,
@@ -1514,12 +1817,18 @@ impl AcpThread {
         let Some((ix, call)) = self.tool_call_mut(&id) else {
             return;
         };
+        let granted_permission = if option_kind == acp::PermissionOptionKind::AllowAlways {
+            ToolPermission::for_tool_call(call)
+        } else {
+            None
+        };
 
         let new_status = match option_kind {
             acp::PermissionOptionKind::RejectOnce | acp::PermissionOptionKind::RejectAlways => {
                 ToolCallStatus::Rejected
             }
             acp::PermissionOptionKind::AllowOnce | acp::PermissionOptionKind::AllowAlways => {
+                call.approved = true;
                 ToolCallStatus::InProgress
             }
         };
@@ -1533,6 +1842,21 @@ impl AcpThread {
         }
 
         cx.emit(AcpThreadEvent::EntryUpdated(ix));
+
+        if let Some(permission) = granted_permission
+            && !permission.is_granted(&AgentSettings::get_global(cx).always_allowed)
+        {
+            let fs = self.project.read(cx).fs().clone();
+            update_settings_file(fs, cx, move |settings, _| {
+                permission.grant(
+                    settings
+                        .agent
+                        .get_or_insert_default()
+                        .always_allowed
+                        .get_or_insert_default(),
+                );
+            });
+        }
     }
 
     pub fn first_tool_awaiting_confirmation(&self) -> Option<&ToolCall> {
@@ -1614,16 +1938,49 @@ impl AcpThread {
         &mut self,
         message: Vec<acp::ContentBlock>,
         cx: &mut Context<Self>,
+    ) -> BoxFuture<'static, Result<()>> {
+        self.send_message(message, false, cx)
+    }
+
+    /// Sends the message, asking the agent to respond with JSON only.
+    pub fn send_for_structured_response(
+        &mut self,
+        message: Vec<acp::ContentBlock>,
+        cx: &mut Context<Self>,
+    ) -> BoxFuture<'static, Result<()>> {
+        self.send_message(message, true, cx)
+    }
+
+    fn send_message(
+        &mut self,
+        message: Vec<acp::ContentBlock>,
+        structured_response: bool,
+        cx: &mut Context<Self>,
     ) -> BoxFuture<'static, Result<()>> {
         let block = ContentBlock::new_combined(
             message.clone(),
             self.project.read(cx).languages().clone(),
             cx,
         );
+        let mut prompt = message.clone();
+        if self.plan_mode {
+            prompt.push(acp::ContentBlock::Text(acp::TextContent {
+                text: PLAN_MODE_INSTRUCTIONS.into(),
+                annotations: None,
+                meta: None,
+            }));
+        }
+        if structured_response {
+            prompt.push(acp::ContentBlock::Text(acp::TextContent {
+                text: STRUCTURED_RESPONSE_INSTRUCTIONS.into(),
+                annotations: None,
+                meta: None,
+            }));
+        }
         let request = acp::PromptRequest {
-            prompt: message.clone(),
+            prompt,
             session_id: self.session_id.clone(),
-            meta: None,
+            meta: self.generation_parameters.to_meta(),
         };
         let git_store = self.project.read(cx).git_store().clone();
 
@@ -1633,6 +1990,18 @@ impl AcpThread {
             None
         };
 
+        // Agents that keep a title for the thread generate it themselves, the others are
+        // named after their first message.
+        if self.connection.set_title(&self.session_id, cx).is_none()
+            && !self
+                .entries
+                .iter()
+                .any(|entry| entry.user_message().is_some())
+            && let Some(title) = title_from_prompt(&message)
+        {
+            self.set_title(title, cx).detach_and_log_err(cx);
+        }
+
         self.run_turn(cx, async move |this, cx| {
             this.update(cx, |this, cx| {
                 this.push_entry(
@@ -1641,6 +2010,8 @@ impl AcpThread {
                         content: block,
                         chunks: message,
                         checkpoint: None,
+                        turn_stats: None,
+                        structured_response,
                     }),
                     cx,
                 );
@@ -1690,6 +2061,8 @@ impl AcpThread {
 
         let (tx, rx) = oneshot::channel();
         let cancel_task = self.cancel(cx);
+        self.turn_started_at = Some(Instant::now());
+        self.first_response_at = None;
 
         self.send_task = Some(cx.spawn(async move |this, cx| {
             cancel_task.await;
@@ -1716,10 +2089,17 @@ impl AcpThread {
                             result,
                             Ok(Ok(acp::PromptResponse {
                                 stop_reason: acp::StopReason::Cancelled,
-                                meta: None,
+                                ..
                             }))
                         );
 
+                        // A canceled turn may have been replaced by one that already started.
+                        if let Ok(Ok(response)) = &result
+                            && !canceled
+                        {
+                            this.finish_turn_stats(response.meta.as_ref(), cx);
+                        }
+
                         // We only take the task if the current prompt wasn't canceled.
                         //
                         // This prompt may have been canceled because another one was sent
@@ -1895,6 +2275,21 @@ impl AcpThread {
         })
     }
 
+    fn finish_turn_stats(&mut self, meta: Option<&serde_json::Value>, cx: &mut Context<Self>) {
+        let Some(started_at) = self.turn_started_at.take() else {
+            return;
+        };
+        let first_response = self
+            .first_response_at
+            .take()
+            .map(|first_response_at| first_response_at.duration_since(started_at));
+        let stats = TurnStats::new(started_at.elapsed(), first_response, meta);
+        if let Some((ix, message)) = self.last_user_message() {
+            message.turn_stats = Some(stats);
+            cx.emit(AcpThreadEvent::EntryUpdated(ix));
+        }
+    }
+
     fn last_user_message(&mut self) -> Option<(usize, &mut UserMessage)> {
         self.entries
             .iter_mut()
@@ -1931,6 +2326,9 @@ impl AcpThread {
         reuse_shared_snapshot: bool,
         cx: &mut Context<Self>,
     ) -> Task<Result<String, acp::Error>> {
+        if let Err(error) = self.file_system_access(cx).check_read(&path) {
+            return Task::ready(Err(error));
+        }
         // Args are 1-based, move to 0-based
         let line = line.unwrap_or_default().saturating_sub(1);
         let limit = limit.unwrap_or(u32::MAX);
@@ -2009,6 +2407,9 @@ impl AcpThread {
         content: String,
         cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
+        if let Err(error) = self.file_system_access(cx).check_write(&path) {
+            return Task::ready(Err(error.into()));
+        }
         let project = self.project.clone();
         let action_log = self.action_log.clone();
         cx.spawn(async move |this, cx| {
@@ -2307,6 +2708,84 @@ fn markdown_for_raw_output(
     }
 }
 
+/// A short title for a thread, taken from the first line of its first message.
+fn title_from_prompt(prompt: &[acp::ContentBlock]) -> Option<SharedString> {
+    const MAX_TITLE_LEN: usize = 40;
+
+    let line = prompt.iter().find_map(|block| match block {
+        acp::ContentBlock::Text(text) => text
+            .text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty()),
+        _ => None,
+    })?;
+    let truncated = util::truncate(line, MAX_TITLE_LEN);
+    if truncated.len() == line.len() {
+        return Some(line.to_string().into());
+    }
+
+    // Cut at the last word boundary, so that the title doesn't end mid-word.
+    let truncated = truncated
+        .rsplit_once(char::is_whitespace)
+        .map_or(truncated, |(head, _)| head);
+    Some(format!("{}…", truncated.trim_end()).into())
+}
+
+/// Whether the `agent.auto_approve` settings allow tool calls of this kind without confirmation.
+fn is_auto_approved(kind: acp::ToolKind, auto_approve: &AutoApprove) -> bool {
+    match kind {
+        acp::ToolKind::Read => auto_approve.reads,
+        acp::ToolKind::Search => auto_approve.searches,
+        acp::ToolKind::Fetch => auto_approve.fetches,
+        _ => false,
+    }
+}
+
+/// Whether plan mode rejects tool calls of the kind, as they change the project.
+pub fn plan_mode_rejects(kind: acp::ToolKind) -> bool {
+    matches!(
+        kind,
+        acp::ToolKind::Edit | acp::ToolKind::Delete | acp::ToolKind::Move | acp::ToolKind::Execute
+    )
+}
+
+/// What the `agent.tool_permission_profile` setting decides for tool calls of this kind:
+/// `Some(true)` to allow them, `Some(false)` to reject them, and `None` to ask.
+fn profile_allows(kind: acp::ToolKind, profile: ToolPermissionProfile) -> Option<bool> {
+    match profile {
+        ToolPermissionProfile::Ask => None,
+        ToolPermissionProfile::AllowReads => {
+            matches!(kind, acp::ToolKind::Read | acp::ToolKind::Search).then_some(true)
+        }
+        ToolPermissionProfile::AllowAll => Some(true),
+        ToolPermissionProfile::DenyExecute => (kind == acp::ToolKind::Execute).then_some(false),
+    }
+}
+
+/// The option allowing or rejecting a tool call once, falling back to the one doing so always.
+fn once_option(
+    options: &[acp::PermissionOption],
+    allow: bool,
+) -> Option<(acp::PermissionOptionId, acp::PermissionOptionKind)> {
+    let (once, always) = if allow {
+        (
+            acp::PermissionOptionKind::AllowOnce,
+            acp::PermissionOptionKind::AllowAlways,
+        )
+    } else {
+        (
+            acp::PermissionOptionKind::RejectOnce,
+            acp::PermissionOptionKind::RejectAlways,
+        )
+    };
+    let option = options
+        .iter()
+        .find(|option| option.kind == once)
+        .or_else(|| options.iter().find(|option| option.kind == always))?;
+    Some((option.id.clone(), option.kind))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2335,10 +2814,39 @@ mod tests {
             let settings_store = SettingsStore::test(cx);
             cx.set_global(settings_store);
             Project::init_settings(cx);
+            AgentSettings::register(cx);
             language::init(cx);
         });
     }
 
+    #[test]
+    fn test_is_auto_approved() {
+        let auto_approve = AutoApprove {
+            reads: true,
+            searches: true,
+            fetches: false,
+        };
+        assert!(is_auto_approved(acp::ToolKind::Read, &auto_approve));
+        assert!(is_auto_approved(acp::ToolKind::Search, &auto_approve));
+        assert!(!is_auto_approved(acp::ToolKind::Fetch, &auto_approve));
+        assert!(!is_auto_approved(acp::ToolKind::Edit, &auto_approve));
+        assert!(!is_auto_approved(acp::ToolKind::Execute, &auto_approve));
+    }
+
+    #[test]
+    fn test_profile_allows() {
+        use ToolPermissionProfile::*;
+        assert_eq!(profile_allows(acp::ToolKind::Execute, Ask), None);
+        assert_eq!(profile_allows(acp::ToolKind::Read, AllowReads), Some(true));
+        assert_eq!(profile_allows(acp::ToolKind::Edit, AllowReads), None);
+        assert_eq!(profile_allows(acp::ToolKind::Execute, AllowAll), Some(true));
+        assert_eq!(
+            profile_allows(acp::ToolKind::Execute, DenyExecute),
+            Some(false)
+        );
+        assert_eq!(profile_allows(acp::ToolKind::Edit, DenyExecute), None);
+    }
+
     #[gpui::test]
     async fn test_terminal_output_buffered_before_created_renders(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -2479,111 +2987,614 @@ mod tests {
     }
 
     #[gpui::test]
-    async fn test_push_user_content_block(cx: &mut gpui::TestAppContext) {
+    async fn test_agent_sessions(cx: &mut gpui::TestAppContext) {
         init_test(cx);
 
         let fs = FakeFs::new(cx.executor());
         let project = Project::test(fs, [], cx).await;
+        let sessions = cx.update(AgentSessions::global);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let _subscription = cx.update(|cx| {
+            let events = events.clone();
+            cx.subscribe(&sessions, move |_, event: &AgentSessionEvent, _| {
+                events.borrow_mut().push(event.clone());
+            })
+        });
+
         let connection = Rc::new(FakeAgentConnection::new());
         let thread = cx
             .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
             .await
             .unwrap();
+        cx.run_until_parked();
 
-        // Test creating a new user message
-        thread.update(cx, |thread, cx| {
-            thread.push_user_content_block(
-                None,
-                acp::ContentBlock::Text(acp::TextContent {
-                    annotations: None,
-                    text: "Hello, ".to_string(),
-                    meta: None,
-                }),
-                cx,
-            );
+        let session_id = thread.read_with(cx, |thread, _| thread.session_id().clone());
+        assert_eq!(
+            *events.borrow(),
+            vec![AgentSessionEvent::Started(session_id.clone())]
+        );
+        sessions.read_with(cx, |sessions, _| {
+            assert_eq!(sessions.session(&session_id), Some(thread.clone()));
+            assert_eq!(sessions.sessions().count(), 1);
         });
 
-        thread.update(cx, |thread, cx| {
-            assert_eq!(thread.entries.len(), 1);
-            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[0] {
-                assert_eq!(user_msg.id, None);
-                assert_eq!(user_msg.content.to_markdown(cx), "Hello, ");
-            } else {
-                panic!("Expected UserMessage");
-            }
+        drop(thread);
+        cx.run_until_parked();
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                AgentSessionEvent::Started(session_id.clone()),
+                AgentSessionEvent::Ended(session_id.clone())
+            ]
+        );
+        sessions.read_with(cx, |sessions, _| {
+            assert_eq!(sessions.session(&session_id), None);
+            assert_eq!(sessions.sessions().count(), 0);
         });
+    }
 
-        // Test appending to existing user message
-        let message_1_id = UserMessageId::new();
-        thread.update(cx, |thread, cx| {
-            thread.push_user_content_block(
-                Some(message_1_id.clone()),
-                acp::ContentBlock::Text(acp::TextContent {
-                    annotations: None,
-                    text: "world!".to_string(),
-                    meta: None,
-                }),
-                cx,
-            );
-        });
+    #[gpui::test]
+    fn test_mention_sources(cx: &mut gpui::App) {
+        struct TestSource(&'static str, &'static str);
 
-        thread.update(cx, |thread, cx| {
-            assert_eq!(thread.entries.len(), 1);
-            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[0] {
-                assert_eq!(user_msg.id, Some(message_1_id));
-                assert_eq!(user_msg.content.to_markdown(cx), "Hello, world!");
-            } else {
-                panic!("Expected UserMessage");
+        impl MentionSource for TestSource {
+            fn keyword(&self) -> SharedString {
+                self.0.into()
             }
-        });
 
-        // Test creating new user message after assistant message
-        thread.update(cx, |thread, cx| {
-            thread.push_assistant_content_block(
-                acp::ContentBlock::Text(acp::TextContent {
-                    annotations: None,
-                    text: "Assistant response".to_string(),
-                    meta: None,
-                }),
-                false,
-                cx,
-            );
-        });
+            fn label(&self) -> SharedString {
+                self.1.into()
+            }
 
-        let message_2_id = UserMessageId::new();
-        thread.update(cx, |thread, cx| {
-            thread.push_user_content_block(
-                Some(message_2_id.clone()),
-                acp::ContentBlock::Text(acp::TextContent {
-                    annotations: None,
-                    text: "New user message".to_string(),
-                    meta: None,
-                }),
-                cx,
-            );
-        });
+            fn search(&self, _query: String, _cx: &mut App) -> Task<Vec<MentionSourceItem>> {
+                Task::ready(Vec::new())
+            }
 
-        thread.update(cx, |thread, cx| {
-            assert_eq!(thread.entries.len(), 3);
-            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[2] {
-                assert_eq!(user_msg.id, Some(message_2_id));
-                assert_eq!(user_msg.content.to_markdown(cx), "New user message");
-            } else {
-                panic!("Expected UserMessage at index 2");
+            fn load(&self, _id: String, _cx: &mut App) -> Task<Result<String>> {
+                Task::ready(Ok(String::new()))
             }
+        }
+
+        let sessions = AgentSessions::global(cx);
+        sessions.update(cx, |sessions, _| {
+            sessions.register_mention_source(Rc::new(TestSource("issue", "Issues")));
+            sessions.register_mention_source(Rc::new(TestSource("doc", "Docs")));
+            sessions.register_mention_source(Rc::new(TestSource("issue", "Tickets")));
+
+            let labels = sessions
+                .mention_sources()
+                .map(|source| source.label().to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(labels, vec!["Docs", "Tickets"]);
+
+            sessions.unregister_mention_source("issue");
+            assert!(sessions.mention_source("issue").is_none());
+            assert!(sessions.mention_source("doc").is_some());
         });
     }
 
     #[gpui::test]
-    async fn test_thinking_concatenation(cx: &mut gpui::TestAppContext) {
+    async fn test_title_from_first_message(cx: &mut gpui::TestAppContext) {
         init_test(cx);
 
         let fs = FakeFs::new(cx.executor());
         let project = Project::test(fs, [], cx).await;
-        let connection = Rc::new(FakeAgentConnection::new().on_user_message(
-            |_, thread, mut cx| {
-                async move {
-                    thread.update(&mut cx, |thread, cx| {
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        thread
+            .update(cx, |thread, cx| {
+                thread.send_raw("\nFix the flaky login test\nIt fails on CI", cx)
+            })
+            .await
+            .unwrap();
+        thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.title(), "Fix the flaky login test");
+        });
+
+        thread
+            .update(cx, |thread, cx| thread.send_raw("Also the signup test", cx))
+            .await
+            .unwrap();
+        thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.title(), "Fix the flaky login test");
+        });
+
+        thread
+            .update(cx, |thread, cx| thread.set_title("Login tests".into(), cx))
+            .await
+            .unwrap();
+        thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.title(), "Login tests");
+        });
+
+        assert_eq!(
+            title_from_prompt(&[
+                "Why does the build fail when the cache directory is missing?".into()
+            ]),
+            Some("Why does the build fail when the cache…".into())
+        );
+        assert_eq!(title_from_prompt(&["  ".into()]), None);
+    }
+
+    #[gpui::test]
+    async fn test_tool_call_confirmation_handler(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        struct RejectDeploys;
+
+        impl ToolCallConfirmationHandler for RejectDeploys {
+            fn matches(&self, tool_call: &ToolCall, _cx: &App) -> bool {
+                tool_call.kind == acp::ToolKind::Execute
+            }
+
+            fn decide(
+                &self,
+                tool_call: &ToolCall,
+                options: &[acp::PermissionOption],
+                cx: &App,
+            ) -> Option<acp::PermissionOptionId> {
+                if !tool_call.label.read(cx).source().contains("deploy") {
+                    return None;
+                }
+                options
+                    .iter()
+                    .find(|option| option.kind == acp::PermissionOptionKind::RejectOnce)
+                    .map(|option| option.id.clone())
+            }
+        }
+
+        cx.update(|cx| register_tool_call_confirmation_handler(RejectDeploys, cx));
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        let options = vec![
+            acp::PermissionOption {
+                id: acp::PermissionOptionId("allow".into()),
+                name: "Allow".into(),
+                kind: acp::PermissionOptionKind::AllowOnce,
+                meta: None,
+            },
+            acp::PermissionOption {
+                id: acp::PermissionOptionId("reject".into()),
+                name: "Reject".into(),
+                kind: acp::PermissionOptionKind::RejectOnce,
+                meta: None,
+            },
+        ];
+        let request = |id: &str, title: &str, cx: &mut gpui::TestAppContext| {
+            thread.update(cx, |thread, cx| {
+                thread
+                    .request_tool_call_authorization(
+                        acp::ToolCall {
+                            id: acp::ToolCallId(id.into()),
+                            title: title.into(),
+                            kind: acp::ToolKind::Execute,
+                            status: acp::ToolCallStatus::Pending,
+                            content: Vec::new(),
+                            locations: Vec::new(),
+                            raw_input: None,
+                            raw_output: None,
+                            meta: None,
+                        }
+                        .into(),
+                        options.clone(),
+                        false,
+                        cx,
+                    )
+                    .unwrap()
+            })
+        };
+
+        let outcome = request("deploy", "./deploy --prod", cx).await;
+        assert!(matches!(
+            outcome,
+            acp::RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == "reject"
+        ));
+
+        let _outcome = request("test", "cargo test", cx);
+        thread.update(cx, |thread, _| {
+            let (_, deploy) = thread.tool_call(&acp::ToolCallId("deploy".into())).unwrap();
+            assert!(matches!(deploy.status, ToolCallStatus::Rejected));
+            let (_, test) = thread.tool_call(&acp::ToolCallId("test".into())).unwrap();
+            assert!(matches!(
+                test.status,
+                ToolCallStatus::WaitingForConfirmation { .. }
+            ));
+        });
+    }
+
+    fn permission_options() -> Vec<acp::PermissionOption> {
+        vec![
+            acp::PermissionOption {
+                id: acp::PermissionOptionId("allow-always".into()),
+                name: "Always Allow".into(),
+                kind: acp::PermissionOptionKind::AllowAlways,
+                meta: None,
+            },
+            acp::PermissionOption {
+                id: acp::PermissionOptionId("allow".into()),
+                name: "Allow".into(),
+                kind: acp::PermissionOptionKind::AllowOnce,
+                meta: None,
+            },
+            acp::PermissionOption {
+                id: acp::PermissionOptionId("reject".into()),
+                name: "Reject".into(),
+                kind: acp::PermissionOptionKind::RejectOnce,
+                meta: None,
+            },
+        ]
+    }
+
+    /// Requests confirmation of a tool call, as agents do before running it.
+    fn request_authorization(
+        thread: &Entity<AcpThread>,
+        id: &str,
+        kind: acp::ToolKind,
+        raw_input: Option<serde_json::Value>,
+        options: &[acp::PermissionOption],
+        cx: &mut TestAppContext,
+    ) -> BoxFuture<'static, acp::RequestPermissionOutcome> {
+        thread.update(cx, |thread, cx| {
+            thread
+                .request_tool_call_authorization(
+                    acp::ToolCall {
+                        id: acp::ToolCallId(id.into()),
+                        title: id.into(),
+                        kind,
+                        status: acp::ToolCallStatus::Pending,
+                        content: Vec::new(),
+                        locations: Vec::new(),
+                        raw_input,
+                        raw_output: None,
+                        meta: None,
+                    }
+                    .into(),
+                    options.to_vec(),
+                    true,
+                    cx,
+                )
+                .unwrap()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_always_allowed_tool_calls(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .agent
+                        .get_or_insert_default()
+                        .always_allowed
+                        .get_or_insert_default()
+                        .commands = Some(vec!["cargo".into()]);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        let options = permission_options();
+        let request = |id: &str, command: &str, cx: &mut TestAppContext| {
+            request_authorization(
+                &thread,
+                id,
+                acp::ToolKind::Execute,
+                Some(json!({ "command": command })),
+                &options,
+                cx,
+            )
+        };
+
+        // Allowed once, so that the agent doesn't take it for a new "Always Allow".
+        let outcome = request("build", "cargo build --release", cx).await;
+        assert!(matches!(
+            outcome,
+            acp::RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == "allow"
+        ));
+
+        let _chained = request("chained", "cargo build && ./deploy", cx);
+        let _other = request("other", "npm test", cx);
+        thread.update(cx, |thread, _| {
+            for id in ["chained", "other"] {
+                let (_, tool_call) = thread.tool_call(&acp::ToolCallId(id.into())).unwrap();
+                assert!(matches!(
+                    tool_call.status,
+                    ToolCallStatus::WaitingForConfirmation { .. }
+                ));
+            }
+        });
+    }
+
+    #[gpui::test]
+    async fn test_plan_mode(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .agent
+                        .get_or_insert_default()
+                        .always_allow_tool_actions = Some(true);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+        thread.update(cx, |thread, cx| thread.set_plan_mode(true, cx));
+
+        let options = permission_options();
+        let request = |id: &str, kind: acp::ToolKind, cx: &mut TestAppContext| {
+            request_authorization(&thread, id, kind, None, &options, cx)
+        };
+
+        for (id, kind, expected) in [
+            ("edit", acp::ToolKind::Edit, "reject"),
+            ("execute", acp::ToolKind::Execute, "reject"),
+            ("read", acp::ToolKind::Read, "allow"),
+        ] {
+            let outcome = request(id, kind, cx).await;
+            assert!(matches!(
+                outcome,
+                acp::RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == expected
+            ));
+        }
+        thread.read_with(cx, |thread, cx| {
+            assert!(!thread.file_system_access(cx).writes_allowed);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_auto_accept_edits(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        let options = permission_options();
+        let request = |id: &str, kind: acp::ToolKind, cx: &mut TestAppContext| {
+            request_authorization(&thread, id, kind, None, &options, cx)
+        };
+        let is_allowed = |outcome: acp::RequestPermissionOutcome| {
+            matches!(
+                outcome,
+                acp::RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == "allow"
+            )
+        };
+
+        // Edits already waiting for confirmation are accepted when it's turned on.
+        let waiting = request("waiting", acp::ToolKind::Edit, cx);
+        thread.update(cx, |thread, cx| thread.set_auto_accept_edits(true, cx));
+        assert!(is_allowed(waiting.await));
+        assert!(is_allowed(request("edit", acp::ToolKind::Edit, cx).await));
+
+        let _execute = request("execute", acp::ToolKind::Execute, cx);
+        thread.update(cx, |thread, _| {
+            let (_, tool_call) = thread
+                .tool_call(&acp::ToolCallId("execute".into()))
+                .unwrap();
+            assert!(matches!(
+                tool_call.status,
+                ToolCallStatus::WaitingForConfirmation { .. }
+            ));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_unattended(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+        thread.update(cx, |thread, cx| thread.set_unattended(true, cx));
+
+        let options = permission_options();
+        for (id, kind, expected) in [
+            ("read", acp::ToolKind::Read, "allow"),
+            ("search", acp::ToolKind::Search, "allow"),
+            ("fetch", acp::ToolKind::Fetch, "reject"),
+            ("other", acp::ToolKind::Other, "reject"),
+            ("execute", acp::ToolKind::Execute, "reject"),
+        ] {
+            let outcome = request_authorization(&thread, id, kind, None, &options, cx).await;
+            assert!(matches!(
+                outcome,
+                acp::RequestPermissionOutcome::Selected { option_id } if option_id.0.as_ref() == expected
+            ));
+        }
+    }
+
+    #[gpui::test]
+    async fn test_structured_response(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let prompts = Rc::new(RefCell::new(Vec::new()));
+        let connection = Rc::new(FakeAgentConnection::new().on_user_message({
+            let prompts = prompts.clone();
+            move |request, _, _| {
+                prompts.borrow_mut().push(request.prompt);
+                async move {
+                    Ok(acp::PromptResponse {
+                        stop_reason: acp::StopReason::EndTurn,
+                        meta: None,
+                    })
+                }
+                .boxed_local()
+            }
+        }));
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        let message = |text: &str| -> Vec<acp::ContentBlock> { vec![text.into()] };
+        thread
+            .update(cx, |thread, cx| {
+                thread.send_for_structured_response(message("List the crates"), cx)
+            })
+            .await
+            .unwrap();
+        thread
+            .update(cx, |thread, cx| thread.send(message("Thanks"), cx))
+            .await
+            .unwrap();
+
+        let prompts = prompts.borrow();
+        assert_eq!(prompts[0].len(), 2);
+        assert_eq!(prompts[1].len(), 1);
+        thread.read_with(cx, |thread, _| {
+            let structured_responses = thread
+                .entries()
+                .iter()
+                .filter_map(|entry| entry.user_message())
+                .map(|message| message.structured_response)
+                .collect::<Vec<_>>();
+            assert_eq!(structured_responses, vec![true, false]);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_push_user_content_block(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        // Test creating a new user message
+        thread.update(cx, |thread, cx| {
+            thread.push_user_content_block(
+                None,
+                acp::ContentBlock::Text(acp::TextContent {
+                    annotations: None,
+                    text: "Hello, ".to_string(),
+                    meta: None,
+                }),
+                cx,
+            );
+        });
+
+        thread.update(cx, |thread, cx| {
+            assert_eq!(thread.entries.len(), 1);
+            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[0] {
+                assert_eq!(user_msg.id, None);
+                assert_eq!(user_msg.content.to_markdown(cx), "Hello, ");
+            } else {
+                panic!("Expected UserMessage");
+            }
+        });
+
+        // Test appending to existing user message
+        let message_1_id = UserMessageId::new();
+        thread.update(cx, |thread, cx| {
+            thread.push_user_content_block(
+                Some(message_1_id.clone()),
+                acp::ContentBlock::Text(acp::TextContent {
+                    annotations: None,
+                    text: "world!".to_string(),
+                    meta: None,
+                }),
+                cx,
+            );
+        });
+
+        thread.update(cx, |thread, cx| {
+            assert_eq!(thread.entries.len(), 1);
+            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[0] {
+                assert_eq!(user_msg.id, Some(message_1_id));
+                assert_eq!(user_msg.content.to_markdown(cx), "Hello, world!");
+            } else {
+                panic!("Expected UserMessage");
+            }
+        });
+
+        // Test creating new user message after assistant message
+        thread.update(cx, |thread, cx| {
+            thread.push_assistant_content_block(
+                acp::ContentBlock::Text(acp::TextContent {
+                    annotations: None,
+                    text: "Assistant response".to_string(),
+                    meta: None,
+                }),
+                false,
+                cx,
+            );
+        });
+
+        let message_2_id = UserMessageId::new();
+        thread.update(cx, |thread, cx| {
+            thread.push_user_content_block(
+                Some(message_2_id.clone()),
+                acp::ContentBlock::Text(acp::TextContent {
+                    annotations: None,
+                    text: "New user message".to_string(),
+                    meta: None,
+                }),
+                cx,
+            );
+        });
+
+        thread.update(cx, |thread, cx| {
+            assert_eq!(thread.entries.len(), 3);
+            if let AgentThreadEntry::UserMessage(user_msg) = &thread.entries[2] {
+                assert_eq!(user_msg.id, Some(message_2_id));
+                assert_eq!(user_msg.content.to_markdown(cx), "New user message");
+            } else {
+                panic!("Expected UserMessage at index 2");
+            }
+        });
+    }
+
+    #[gpui::test]
+    async fn test_thinking_concatenation(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new().on_user_message(
+            |_, thread, mut cx| {
+                async move {
+                    thread.update(&mut cx, |thread, cx| {
                         thread
                             .handle_session_update(
                                 acp::SessionUpdate::AgentThoughtChunk {
@@ -2638,6 +3649,91 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_discard_thinking(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.agent.get_or_insert_default().discard_thinking = Some(true);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new().on_user_message(
+            |_, thread, mut cx| {
+                async move {
+                    thread.update(&mut cx, |thread, cx| {
+                        thread
+                            .handle_session_update(
+                                acp::SessionUpdate::AgentThoughtChunk {
+                                    content: "Thinking hard!".into(),
+                                },
+                                cx,
+                            )
+                            .unwrap();
+                        thread
+                            .handle_session_update(
+                                acp::SessionUpdate::AgentMessageChunk {
+                                    content: "Hi!".into(),
+                                },
+                                cx,
+                            )
+                            .unwrap();
+                    })?;
+                    Ok(acp::PromptResponse {
+                        stop_reason: acp::StopReason::EndTurn,
+                        meta: None,
+                    })
+                }
+                .boxed_local()
+            },
+        ));
+
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        thread
+            .update(cx, |thread, cx| thread.send_raw("Hello from Zed!", cx))
+            .await
+            .unwrap();
+
+        let output = thread.read_with(cx, |thread, cx| thread.to_markdown(cx));
+        assert_eq!(
+            output,
+            indoc! {r#"
+            ## User
+
+            Hello from Zed!
+
+            ## Assistant
+
+            Hi!
+
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_turn_stats_from_meta() {
+        let meta = json!({
+            "model": "Test Model",
+            "usage": { "inputTokens": 1200, "outputTokens": 300 },
+        });
+        let stats = TurnStats::new(Duration::from_secs(3), None, Some(&meta));
+        assert_eq!(stats.model.as_deref(), Some("Test Model"));
+        assert_eq!(stats.input_tokens, Some(1200));
+        assert_eq!(stats.output_tokens, Some(300));
+
+        let stats = TurnStats::new(Duration::from_secs(3), None, None);
+        assert_eq!(stats.model, None);
+        assert_eq!(stats.input_tokens, None);
+    }
+
     #[gpui::test]
     async fn test_edits_concurrently_to_user(cx: &mut TestAppContext) {
         init_test(cx);
@@ -3003,6 +4099,87 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_streamed_diff_is_updated_in_place(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let connection = Rc::new(FakeAgentConnection::new());
+        let thread = cx
+            .update(|cx| connection.new_thread(project, Path::new(path!("/test")), cx))
+            .await
+            .unwrap();
+
+        let diff = |new_text: &str| acp::ToolCallContent::Diff {
+            diff: acp::Diff {
+                path: path!("/test/main.rs").into(),
+                old_text: Some("fn main() {}\n".into()),
+                new_text: new_text.into(),
+                meta: None,
+            },
+        };
+        let edit_diff = |thread: &AcpThread| match &thread.entries[0] {
+            AgentThreadEntry::ToolCall(ToolCall { content, .. }) => match &content[..] {
+                [ToolCallContent::Diff(diff)] => diff.clone(),
+                _ => panic!("expected a diff"),
+            },
+            _ => panic!("expected a tool call"),
+        };
+
+        thread
+            .update(cx, |thread, cx| {
+                thread.handle_session_update(
+                    acp::SessionUpdate::ToolCall(acp::ToolCall {
+                        id: acp::ToolCallId("edit".into()),
+                        title: "Edit".into(),
+                        kind: acp::ToolKind::Edit,
+                        status: acp::ToolCallStatus::InProgress,
+                        content: vec![diff("fn main() {\n")],
+                        locations: vec![],
+                        raw_input: None,
+                        raw_output: None,
+                        meta: None,
+                    }),
+                    cx,
+                )
+            })
+            .unwrap();
+        cx.run_until_parked();
+        let streamed_diff = thread.read_with(cx, |thread, _| edit_diff(thread));
+
+        let new_text = "fn main() {\n    println!(\"hi\");\n}\n";
+        thread
+            .update(cx, |thread, cx| {
+                thread.handle_session_update(
+                    acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                        id: acp::ToolCallId("edit".into()),
+                        fields: acp::ToolCallUpdateFields {
+                            content: Some(vec![diff(new_text)]),
+                            ..Default::default()
+                        },
+                        meta: None,
+                    }),
+                    cx,
+                )
+            })
+            .unwrap();
+        cx.run_until_parked();
+
+        thread.read_with(cx, |thread, cx| {
+            let diff = edit_diff(thread);
+            assert_eq!(diff.entity_id(), streamed_diff.entity_id());
+            assert!(!diff.read(cx).needs_update("fn main() {}\n", new_text, cx));
+            assert!(
+                diff.read(cx)
+                    .multibuffer()
+                    .read(cx)
+                    .read(cx)
+                    .text()
+                    .contains("println!")
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_no_pending_edits_if_tool_calls_are_completed(cx: &mut TestAppContext) {
         init_test(cx);
@@ -0,0 +1,155 @@
+use agent_client_protocol as acp;
+use agent_settings::AlwaysAllowed;
+use settings::AlwaysAllowedContent;
+
+use crate::ToolCall;
+
+/// What choosing "Always Allow" for a tool call allows on every thread from then on, as
+/// stored in the `agent.always_allowed` settings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToolPermission {
+    /// Terminal commands running this program.
+    Command(String),
+    /// Calls of the tool with this name, such as an MCP tool.
+    Tool(String),
+    /// Tool calls of this kind, for agents that don't name the tools they call.
+    Kind(acp::ToolKind),
+}
+
+impl ToolPermission {
+    /// The permission covering the tool call, if any. Terminal commands chaining or
+    /// redirecting to other commands aren't covered by any, so they are always confirmed.
+    pub fn for_tool_call(tool_call: &ToolCall) -> Option<Self> {
+        let command = tool_call
+            .raw_input
+            .as_ref()
+            .and_then(|input| input.get("command"))
+            .and_then(|command| command.as_str());
+        if tool_call.kind == acp::ToolKind::Execute
+            && let Some(command) = command
+        {
+            return root_command(command).map(|program| Self::Command(program.to_string()));
+        }
+        if let Some(tool_name) = &tool_call.tool_name {
+            return Some(Self::Tool(tool_name.to_string()));
+        }
+        Some(Self::Kind(tool_call.kind))
+    }
+
+    /// The permissions granted in the settings.
+    pub fn granted(always_allowed: &AlwaysAllowed) -> Vec<Self> {
+        let commands = always_allowed.commands.iter().cloned().map(Self::Command);
+        let tools = always_allowed.tools.iter().cloned().map(Self::Tool);
+        let kinds = always_allowed
+            .kinds
+            .iter()
+            .filter_map(|kind| serde_json::from_value(kind.as_str().into()).ok())
+            .map(Self::Kind);
+        commands.chain(tools).chain(kinds).collect()
+    }
+
+    pub fn is_granted(&self, always_allowed: &AlwaysAllowed) -> bool {
+        match self {
+            Self::Command(program) => always_allowed.commands.contains(program),
+            Self::Tool(name) => always_allowed.tools.contains(name),
+            Self::Kind(kind) => always_allowed.kinds.contains(&kind_name(*kind)),
+        }
+    }
+
+    pub fn grant(&self, content: &mut AlwaysAllowedContent) {
+        let (list, entry) = self.entry(content);
+        let list = list.get_or_insert_default();
+        if !list.contains(&entry) {
+            list.push(entry);
+        }
+    }
+
+    pub fn revoke(&self, content: &mut AlwaysAllowedContent) {
+        let (list, entry) = self.entry(content);
+        if let Some(list) = list {
+            list.retain(|existing| existing != &entry);
+        }
+    }
+
+    /// Describes what the permission allows, e.g. "`cargo` commands".
+    pub fn label(&self) -> String {
+        match self {
+            Self::Command(program) => format!("`{program}` commands"),
+            Self::Tool(name) => format!("The `{name}` tool"),
+            Self::Kind(kind) => format!("Tool calls of kind `{}`", kind_name(*kind)),
+        }
+    }
+
+    fn entry<'a>(
+        &self,
+        content: &'a mut AlwaysAllowedContent,
+    ) -> (&'a mut Option<Vec<String>>, String) {
+        match self {
+            Self::Command(program) => (&mut content.commands, program.clone()),
+            Self::Tool(name) => (&mut content.tools, name.clone()),
+            Self::Kind(kind) => (&mut content.kinds, kind_name(*kind)),
+        }
+    }
+}
+
+/// The program a terminal command runs, unless it chains or redirects to other commands.
+fn root_command(command: &str) -> Option<&str> {
+    const OPERATORS: [&str; 7] = ["&", "|", ";", "`", "$(", ">", "<"];
+    if command.contains('\n') || OPERATORS.iter().any(|operator| command.contains(operator)) {
+        return None;
+    }
+    command.split_whitespace().next()
+}
+
+/// The name of the kind in settings, as the protocol serializes it.
+fn kind_name(kind: acp::ToolKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(ToString::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_command() {
+        assert_eq!(root_command("cargo test -p acp_thread"), Some("cargo"));
+        assert_eq!(root_command("  git status"), Some("git"));
+        assert_eq!(root_command("cargo test && rm -rf target"), None);
+        assert_eq!(root_command("cat Cargo.toml | grep name"), None);
+        assert_eq!(root_command("echo $(whoami)"), None);
+        assert_eq!(root_command("ls > files.txt"), None);
+        assert_eq!(root_command("ls\nrm -rf target"), None);
+        assert_eq!(root_command(""), None);
+    }
+
+    #[test]
+    fn test_grant_and_revoke() {
+        let mut content = AlwaysAllowedContent::default();
+        let cargo = ToolPermission::Command("cargo".into());
+        let edits = ToolPermission::Kind(acp::ToolKind::Edit);
+        cargo.grant(&mut content);
+        cargo.grant(&mut content);
+        edits.grant(&mut content);
+        assert_eq!(content.commands, Some(vec!["cargo".to_string()]));
+        assert_eq!(content.kinds, Some(vec!["edit".to_string()]));
+
+        let always_allowed = AlwaysAllowed {
+            commands: content.commands.clone().unwrap(),
+            tools: Vec::new(),
+            kinds: content.kinds.clone().unwrap(),
+        };
+        assert!(cargo.is_granted(&always_allowed));
+        assert!(edits.is_granted(&always_allowed));
+        assert!(!ToolPermission::Tool("fetch".into()).is_granted(&always_allowed));
+        assert_eq!(
+            ToolPermission::granted(&always_allowed),
+            vec![cargo.clone(), edits]
+        );
+
+        cargo.revoke(&mut content);
+        assert_eq!(content.commands, Some(Vec::new()));
+    }
+}
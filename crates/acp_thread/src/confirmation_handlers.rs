@@ -0,0 +1,92 @@
+use std::rc::Rc;
+
+use agent_client_protocol as acp;
+use gpui::{AnyElement, App, Global, WeakEntity, Window};
+
+use crate::{AcpThread, ToolCall};
+
+/// Takes over the confirmation of the tool calls it [matches](Self::matches), such as a
+/// company-internal deploy command, to decide on them without asking or to ask with its own UI.
+///
+/// Tool calls that no handler matches, or that a handler neither decides nor renders, are
+/// confirmed with the built-in buttons.
+pub trait ToolCallConfirmationHandler: 'static {
+    fn matches(&self, tool_call: &ToolCall, cx: &App) -> bool;
+
+    /// Picks one of the options without asking, or `None` to let the user pick one.
+    fn decide(
+        &self,
+        _tool_call: &ToolCall,
+        _options: &[acp::PermissionOption],
+        _cx: &App,
+    ) -> Option<acp::PermissionOptionId> {
+        None
+    }
+
+    /// Rendered in place of the built-in buttons, which are shown when this returns `None`.
+    fn render(
+        &self,
+        _tool_call: &ToolCall,
+        _confirmation: ToolCallConfirmation,
+        _window: &Window,
+        _cx: &App,
+    ) -> Option<AnyElement> {
+        None
+    }
+}
+
+/// A tool call waiting for confirmation, for the UI of a [`ToolCallConfirmationHandler`] to
+/// respond to.
+#[derive(Clone)]
+pub struct ToolCallConfirmation {
+    pub thread: WeakEntity<AcpThread>,
+    pub tool_call_id: acp::ToolCallId,
+    pub options: Vec<acp::PermissionOption>,
+}
+
+impl ToolCallConfirmation {
+    pub fn respond(&self, option_id: &acp::PermissionOptionId, cx: &mut App) {
+        let Some(option) = self.options.iter().find(|option| &option.id == option_id) else {
+            return;
+        };
+        self.thread
+            .update(cx, |thread, cx| {
+                thread.authorize_tool_call(
+                    self.tool_call_id.clone(),
+                    option.id.clone(),
+                    option.kind,
+                    cx,
+                )
+            })
+            .ok();
+    }
+}
+
+#[derive(Default)]
+struct GlobalToolCallConfirmationHandlers(Vec<Rc<dyn ToolCallConfirmationHandler>>);
+
+impl Global for GlobalToolCallConfirmationHandlers {}
+
+/// Registers a handler for the tool calls of every agent. Handlers registered later take
+/// precedence over the earlier ones matching the same tool call.
+pub fn register_tool_call_confirmation_handler(
+    handler: impl ToolCallConfirmationHandler,
+    cx: &mut App,
+) {
+    cx.default_global::<GlobalToolCallConfirmationHandlers>()
+        .0
+        .push(Rc::new(handler));
+}
+
+/// The handler taking over the confirmation of the tool call, if any.
+pub fn tool_call_confirmation_handler(
+    tool_call: &ToolCall,
+    cx: &App,
+) -> Option<Rc<dyn ToolCallConfirmationHandler>> {
+    cx.try_global::<GlobalToolCallConfirmationHandlers>()?
+        .0
+        .iter()
+        .rev()
+        .find(|handler| handler.matches(tool_call, cx))
+        .cloned()
+}
@@ -6,7 +6,14 @@ use gpui::{Entity, SharedString, Task};
 use language_model::LanguageModelProviderId;
 use project::Project;
 use serde::{Deserialize, Serialize};
-use std::{any::Any, error::Error, fmt, path::Path, rc::Rc, sync::Arc};
+use std::{
+    any::Any,
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 use ui::{App, IconName};
 use uuid::Uuid;
 
@@ -84,6 +91,16 @@ pub trait AgentConnection {
         None
     }
 
+    /// Returns a way to tell the agent about files that changed outside of its tool calls,
+    /// if the agent accepts such notifications.
+    fn file_changes(
+        &self,
+        _session_id: &acp::SessionId,
+        _cx: &App,
+    ) -> Option<Rc<dyn AgentSessionFileChanges>> {
+        None
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any>;
 }
 
@@ -105,6 +122,12 @@ pub trait AgentSessionSetTitle {
     fn run(&self, title: SharedString, cx: &mut App) -> Task<Result<()>>;
 }
 
+pub trait AgentSessionFileChanges {
+    /// Tells the agent that the files at these absolute paths were created, changed or
+    /// removed, so that it doesn't rely on stale contents.
+    fn notify(&self, paths: Vec<PathBuf>, cx: &mut App) -> Task<Result<()>>;
+}
+
 pub trait AgentTelemetry {
     /// The name of the agent used for telemetry.
     fn agent_name(&self) -> String;
@@ -254,6 +277,7 @@ mod test_support {
         sessions: Arc<Mutex<HashMap<acp::SessionId, Session>>>,
         permission_requests: HashMap<acp::ToolCallId, Vec<acp::PermissionOption>>,
         next_prompt_updates: Arc<Mutex<Vec<acp::SessionUpdate>>>,
+        notified_file_changes: Arc<Mutex<Vec<PathBuf>>>,
     }
 
     struct Session {
@@ -267,9 +291,15 @@ mod test_support {
                 next_prompt_updates: Default::default(),
                 permission_requests: HashMap::default(),
                 sessions: Arc::default(),
+                notified_file_changes: Arc::default(),
             }
         }
 
+        /// The paths of every file change the agent was told about.
+        pub fn notified_file_changes(&self) -> Vec<PathBuf> {
+            self.notified_file_changes.lock().clone()
+        }
+
         pub fn set_next_prompt_updates(&self, updates: Vec<acp::SessionUpdate>) {
             *self.next_prompt_updates.lock() = updates;
         }
@@ -450,11 +480,30 @@ mod test_support {
             Some(Rc::new(StubAgentSessionEditor))
         }
 
+        fn file_changes(
+            &self,
+            _session_id: &acp::SessionId,
+            _cx: &App,
+        ) -> Option<Rc<dyn AgentSessionFileChanges>> {
+            Some(Rc::new(StubAgentSessionFileChanges(
+                self.notified_file_changes.clone(),
+            )))
+        }
+
         fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
             self
         }
     }
 
+    struct StubAgentSessionFileChanges(Arc<Mutex<Vec<PathBuf>>>);
+
+    impl AgentSessionFileChanges for StubAgentSessionFileChanges {
+        fn notify(&self, paths: Vec<PathBuf>, _: &mut App) -> Task<Result<()>> {
+            self.0.lock().extend(paths);
+            Task::ready(Ok(()))
+        }
+    }
+
     struct StubAgentSessionEditor;
 
     impl AgentSessionTruncate for StubAgentSessionEditor {
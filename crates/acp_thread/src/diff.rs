@@ -29,6 +29,7 @@ impl Diff {
             let multibuffer = multibuffer.clone();
             let path = path.clone();
             let buffer = new_buffer.clone();
+            let language_registry = language_registry.clone();
             async move |_, cx| {
                 let language = language_registry
                     .load_language_for_file_path(Path::new(&path))
@@ -47,22 +48,7 @@ impl Diff {
 
                 multibuffer
                     .update(cx, |multibuffer, cx| {
-                        let hunk_ranges = {
-                            let buffer = buffer.read(cx);
-                            let diff = diff.read(cx);
-                            diff.hunks_intersecting_range(Anchor::MIN..Anchor::MAX, buffer, cx)
-                                .map(|diff_hunk| diff_hunk.buffer_range.to_point(buffer))
-                                .collect::<Vec<_>>()
-                        };
-
-                        multibuffer.set_excerpts_for_path(
-                            PathKey::for_buffer(&buffer, cx),
-                            buffer.clone(),
-                            hunk_ranges,
-                            multibuffer_context_lines(cx),
-                            cx,
-                        );
-                        multibuffer.add_diff(diff, cx);
+                        show_hunks(multibuffer, &buffer, diff, cx)
                     })
                     .log_err();
 
@@ -75,7 +61,8 @@ impl Diff {
             path,
             base_text,
             new_buffer,
-            _update_diff: task,
+            language_registry: Some(language_registry),
+            update_diff: task,
         })
     }
 
@@ -129,6 +116,22 @@ impl Diff {
         }
     }
 
+    /// Whether a diff of the file sent by the agent continues this one, as when the agent
+    /// streams an edit, so that it can be applied in place instead of replacing the diff.
+    pub fn is_streamed_by(&self, path: &str, old_text: &str) -> bool {
+        match self {
+            Diff::Finalized(diff) => diff.path == path && diff.base_text.as_str() == old_text,
+            Diff::Pending(_) => false,
+        }
+    }
+
+    /// Shows the new text of an edit streamed by the agent, updating the hunks shown so far.
+    pub fn stream_new_text(&mut self, new_text: &str, cx: &mut Context<Self>) {
+        if let Diff::Finalized(diff) = self {
+            diff.set_new_text(new_text, cx);
+        }
+    }
+
     pub fn multibuffer(&self) -> &Entity<MultiBuffer> {
         match self {
             Self::Pending(PendingDiff { multibuffer, .. }) => multibuffer,
@@ -136,6 +139,26 @@ impl Diff {
         }
     }
 
+    /// The buffer with the new text of the file.
+    pub fn new_buffer(&self) -> &Entity<Buffer> {
+        match self {
+            Self::Pending(PendingDiff { new_buffer, .. })
+            | Self::Finalized(FinalizedDiff { new_buffer, .. }) => new_buffer,
+        }
+    }
+
+    pub fn path(&self, cx: &App) -> Option<String> {
+        match self {
+            Diff::Pending(PendingDiff {
+                new_buffer: buffer, ..
+            }) => buffer
+                .read(cx)
+                .file()
+                .map(|file| file.path().display(file.path_style(cx)).into_owned()),
+            Diff::Finalized(FinalizedDiff { path, .. }) => Some(path.clone()),
+        }
+    }
+
     pub fn to_markdown(&self, cx: &App) -> String {
         let buffer_text = self
             .multibuffer()
@@ -144,22 +167,21 @@ impl Diff {
             .iter()
             .map(|buffer| buffer.read(cx).text())
             .join("\n");
-        let path = match self {
-            Diff::Pending(PendingDiff {
-                new_buffer: buffer, ..
-            }) => buffer
-                .read(cx)
-                .file()
-                .map(|file| file.path().display(file.path_style(cx))),
-            Diff::Finalized(FinalizedDiff { path, .. }) => Some(path.as_str().into()),
-        };
         format!(
             "Diff: {}\n```\n{}\n```\n",
-            path.unwrap_or("untitled".into()),
+            self.path(cx).unwrap_or("untitled".into()),
             buffer_text
         )
     }
 
+    /// Whether the diff starts from an empty file, as when the file is created.
+    pub fn creates_file(&self) -> bool {
+        match self {
+            Diff::Pending(PendingDiff { base_text, .. })
+            | Diff::Finalized(FinalizedDiff { base_text, .. }) => base_text.is_empty(),
+        }
+    }
+
     pub fn has_revealed_range(&self, cx: &App) -> bool {
         self.multibuffer().read(cx).excerpt_paths().next().is_some()
     }
@@ -262,37 +284,42 @@ impl PendingDiff {
 
         let buffer_diff = cx.spawn({
             let buffer = buffer.clone();
+            let language_registry = language_registry.clone();
             async move |_this, cx| {
                 build_buffer_diff(base_text, &buffer, language_registry, cx).await
             }
         });
 
-        let update_diff = cx.spawn(async move |this, cx| {
-            let buffer_diff = buffer_diff.await?;
-            this.update(cx, |this, cx| {
-                this.multibuffer().update(cx, |multibuffer, cx| {
-                    let path_key = PathKey::for_buffer(&buffer, cx);
-                    multibuffer.clear(cx);
-                    multibuffer.set_excerpts_for_path(
-                        path_key,
-                        buffer,
-                        ranges,
-                        multibuffer_context_lines(cx),
-                        cx,
-                    );
-                    multibuffer.add_diff(buffer_diff.clone(), cx);
-                });
+        let update_diff = cx.spawn({
+            let buffer = buffer.clone();
+            async move |this, cx| {
+                let buffer_diff = buffer_diff.await?;
+                this.update(cx, |this, cx| {
+                    this.multibuffer().update(cx, |multibuffer, cx| {
+                        let path_key = PathKey::for_buffer(&buffer, cx);
+                        multibuffer.clear(cx);
+                        multibuffer.set_excerpts_for_path(
+                            path_key,
+                            buffer,
+                            ranges,
+                            multibuffer_context_lines(cx),
+                            cx,
+                        );
+                        multibuffer.add_diff(buffer_diff.clone(), cx);
+                    });
 
-                cx.notify();
-            })
+                    cx.notify();
+                })
+            }
         });
 
         FinalizedDiff {
             path,
             base_text: self.base_text.clone(),
             multibuffer: self.multibuffer.clone(),
-            new_buffer: self.new_buffer.clone(),
-            _update_diff: update_diff,
+            new_buffer: buffer,
+            language_registry,
+            update_diff,
         }
     }
 
@@ -348,9 +375,63 @@ impl PendingDiff {
 pub struct FinalizedDiff {
     path: String,
     base_text: Arc<String>,
+    /// A copy of the new text owned by the diff, never the edited buffer itself.
     new_buffer: Entity<Buffer>,
     multibuffer: Entity<MultiBuffer>,
-    _update_diff: Task<Result<()>>,
+    language_registry: Option<Arc<LanguageRegistry>>,
+    update_diff: Task<Result<()>>,
+}
+
+impl FinalizedDiff {
+    fn set_new_text(&mut self, new_text: &str, cx: &mut Context<Diff>) {
+        self.new_buffer.update(cx, |buffer, cx| {
+            // Streamed text usually extends the text received so far.
+            let len = buffer.len();
+            let extends_text = new_text.is_char_boundary(len)
+                && buffer.as_rope().chunks().equals_str(&new_text[..len]);
+            if extends_text {
+                buffer.edit([(len..len, &new_text[len..])], None, cx);
+            } else {
+                buffer.set_text(new_text, cx);
+            }
+        });
+
+        let base_text = self.base_text.clone();
+        let buffer = self.new_buffer.clone();
+        let multibuffer = self.multibuffer.clone();
+        let language_registry = self.language_registry.clone();
+        self.update_diff = cx.spawn(async move |_, cx| {
+            let diff = build_buffer_diff(base_text, &buffer, language_registry, cx).await?;
+            multibuffer.update(cx, |multibuffer, cx| {
+                show_hunks(multibuffer, &buffer, diff, cx)
+            })
+        });
+    }
+}
+
+/// Shows the hunks of the buffer's diff in the multibuffer, replacing the ones shown before.
+fn show_hunks(
+    multibuffer: &mut MultiBuffer,
+    buffer: &Entity<Buffer>,
+    diff: Entity<BufferDiff>,
+    cx: &mut Context<MultiBuffer>,
+) {
+    let hunk_ranges = {
+        let buffer = buffer.read(cx);
+        let diff = diff.read(cx);
+        diff.hunks_intersecting_range(Anchor::MIN..Anchor::MAX, buffer, cx)
+            .map(|diff_hunk| diff_hunk.buffer_range.to_point(buffer))
+            .collect::<Vec<_>>()
+    };
+
+    multibuffer.set_excerpts_for_path(
+        PathKey::for_buffer(buffer, cx),
+        buffer.clone(),
+        hunk_ranges,
+        multibuffer_context_lines(cx),
+        cx,
+    );
+    multibuffer.add_diff(diff, cx);
 }
 
 async fn build_buffer_diff(
@@ -405,8 +486,10 @@ async fn build_buffer_diff(
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use gpui::{AppContext as _, TestAppContext};
-    use language::Buffer;
+    use language::{Buffer, LanguageRegistry};
 
     use crate::Diff;
 
@@ -419,4 +502,25 @@ mod tests {
         });
         cx.run_until_parked();
     }
+
+    #[gpui::test]
+    async fn test_finalized_diff_creating_file(cx: &mut TestAppContext) {
+        let language_registry = Arc::new(LanguageRegistry::test(cx.executor()));
+        let diff = cx.new(|cx| {
+            Diff::finalized(
+                "/project/new.txt".into(),
+                None,
+                "one\ntwo\n".into(),
+                language_registry,
+                cx,
+            )
+        });
+        cx.run_until_parked();
+
+        diff.read_with(cx, |diff, cx| {
+            assert!(diff.creates_file());
+            assert_eq!(diff.path(cx).as_deref(), Some("/project/new.txt"));
+            assert_eq!(diff.new_buffer().read(cx).text(), "one\ntwo\n");
+        });
+    }
 }
@@ -0,0 +1,106 @@
+use agent_client_protocol as acp;
+use agent_settings::AgentSettings;
+use gpui::App;
+use project::Project;
+use settings::Settings as _;
+use std::path::{Component, Path, PathBuf};
+
+/// The files agents may read and write through Zed: those in the project's root directories,
+/// which they may only write when `agent.allow_agent_file_writes` is on.
+///
+/// Agents are told about it when they connect, and it's enforced whenever they read or write
+/// files through Zed, so that it doesn't depend on agents honoring it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSystemAccess {
+    pub roots: Vec<PathBuf>,
+    pub writes_allowed: bool,
+}
+
+impl FileSystemAccess {
+    pub fn for_project(project: &Project, cx: &App) -> Self {
+        Self {
+            roots: project
+                .visible_worktrees(cx)
+                .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+                .collect(),
+            writes_allowed: AgentSettings::get_global(cx).allow_agent_file_writes,
+        }
+    }
+
+    /// The capability sent to agents on initialization, with the roots in its `_meta`.
+    pub fn capability(&self) -> acp::FileSystemCapability {
+        acp::FileSystemCapability {
+            read_text_file: true,
+            write_text_file: self.writes_allowed,
+            meta: Some(serde_json::json!({ "roots": self.roots })),
+        }
+    }
+
+    pub fn check_read(&self, path: &Path) -> Result<(), acp::Error> {
+        if self.contains(path) {
+            Ok(())
+        } else {
+            Err(acp::Error::resource_not_found(Some(
+                path.display().to_string(),
+            )))
+        }
+    }
+
+    pub fn check_write(&self, path: &Path) -> Result<(), acp::Error> {
+        if !self.writes_allowed {
+            return Err(acp::Error::invalid_params().with_data(format!(
+                "Can't write {}: agents aren't allowed to write files",
+                path.display()
+            )));
+        }
+        self.check_read(path)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        // `..` could lead out of a root that the path starts with.
+        !path
+            .components()
+            .any(|component| component == Component::ParentDir)
+            && self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_read_and_write() {
+        let mut access = FileSystemAccess {
+            roots: vec![PathBuf::from("/project"), PathBuf::from("/notes.md")],
+            writes_allowed: true,
+        };
+        assert!(access.check_read(Path::new("/project/src/main.rs")).is_ok());
+        assert!(access.check_read(Path::new("/notes.md")).is_ok());
+        assert!(access.check_read(Path::new("/etc/passwd")).is_err());
+        assert!(access.check_read(Path::new("/project-other/a.rs")).is_err());
+        assert!(
+            access
+                .check_read(Path::new("/project/../etc/passwd"))
+                .is_err()
+        );
+        assert!(
+            access
+                .check_write(Path::new("/project/src/main.rs"))
+                .is_ok()
+        );
+        assert!(access.check_write(Path::new("/etc/passwd")).is_err());
+
+        access.writes_allowed = false;
+        assert!(access.check_read(Path::new("/project/src/main.rs")).is_ok());
+        assert!(
+            access
+                .check_write(Path::new("/project/src/main.rs"))
+                .is_err()
+        );
+        assert_eq!(
+            access.capability().meta,
+            Some(serde_json::json!({ "roots": ["/project", "/notes.md"] }))
+        );
+    }
+}
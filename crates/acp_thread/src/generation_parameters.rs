@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// Advanced parameters of the model's generation, chosen for a thread. They are sent to the
+/// agent in the `_meta` of every prompt request as
+/// `{"generationParameters": {"maxOutputTokens": 4096, "temperature": 0.2, "stopSequences": ["END"]}}`,
+/// and agents may ignore the ones they don't support.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationParameters {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl GenerationParameters {
+    const META_KEY: &'static str = "generationParameters";
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The `_meta` of prompt requests, unless the agent's defaults are used.
+    pub fn to_meta(&self) -> Option<serde_json::Value> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(serde_json::json!({ Self::META_KEY: self }))
+    }
+
+    pub fn from_meta(meta: Option<&serde_json::Value>) -> Option<Self> {
+        serde_json::from_value(meta?.get(Self::META_KEY)?.clone()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta() {
+        assert_eq!(GenerationParameters::default().to_meta(), None);
+
+        let parameters = GenerationParameters {
+            max_output_tokens: Some(4096),
+            temperature: None,
+            stop_sequences: vec!["END".into()],
+        };
+        let meta = parameters.to_meta();
+        assert_eq!(
+            meta,
+            Some(serde_json::json!({
+                "generationParameters": { "maxOutputTokens": 4096, "stopSequences": ["END"] }
+            }))
+        );
+        assert_eq!(
+            GenerationParameters::from_meta(meta.as_ref()),
+            Some(parameters)
+        );
+        assert_eq!(
+            GenerationParameters::from_meta(Some(&serde_json::json!({ "model": "m" }))),
+            None
+        );
+    }
+}
@@ -46,6 +46,20 @@ pub enum MentionUri {
     Fetch {
         url: Url,
     },
+    /// The facts saved for the project, attached to the first message of a thread.
+    ProjectMemory,
+    /// The errors and warnings of the project.
+    Diagnostics,
+    /// Where the user is in the workspace when sending a message, like the active editor,
+    /// attached to the message without being mentioned in it.
+    WorkspaceState,
+    /// A mention offered by a registered [`MentionSource`](crate::MentionSource), identified
+    /// by its keyword.
+    Source {
+        source: String,
+        id: String,
+        name: String,
+    },
 }
 
 impl MentionUri {
@@ -117,6 +131,27 @@ impl MentionUri {
                     })
                 } else if path.starts_with("/agent/pasted-image") {
                     Ok(Self::PastedImage)
+                } else if path.starts_with("/agent/project-memory") {
+                    Ok(Self::ProjectMemory)
+                } else if path.starts_with("/agent/diagnostics") {
+                    Ok(Self::Diagnostics)
+                } else if path.starts_with("/agent/workspace-state") {
+                    Ok(Self::WorkspaceState)
+                } else if let Some(source) = path.strip_prefix("/agent/source/") {
+                    let mut id = None;
+                    let mut name = None;
+                    for (key, value) in url.query_pairs() {
+                        match key.as_ref() {
+                            "id" => id = Some(value.into_owned()),
+                            "name" => name = Some(value.into_owned()),
+                            _ => bail!("invalid query parameter"),
+                        }
+                    }
+                    Ok(Self::Source {
+                        source: source.to_string(),
+                        id: id.context("Missing mention id")?,
+                        name: name.context("Missing mention name")?,
+                    })
                 } else if path.starts_with("/agent/untitled-buffer") {
                     let fragment = url
                         .fragment()
@@ -186,10 +221,14 @@ impl MentionUri {
                 ..
             } => selection_name(path.as_deref(), line_range),
             MentionUri::Fetch { url } => url.to_string(),
+            MentionUri::ProjectMemory => "Project Memory".to_string(),
+            MentionUri::Diagnostics => "Diagnostics".to_string(),
+            MentionUri::WorkspaceState => "Workspace State".to_string(),
+            MentionUri::Source { name, .. } => name.clone(),
         }
     }
 
-    pub fn icon_path(&self, cx: &mut App) -> SharedString {
+    pub fn icon_path(&self, cx: &App) -> SharedString {
         match self {
             MentionUri::File { abs_path } => {
                 FileIcons::get_icon(abs_path, cx).unwrap_or_else(|| IconName::File.path().into())
@@ -203,6 +242,10 @@ impl MentionUri {
             MentionUri::Rule { .. } => IconName::Reader.path().into(),
             MentionUri::Selection { .. } => IconName::Reader.path().into(),
             MentionUri::Fetch { .. } => IconName::ToolWeb.path().into(),
+            MentionUri::ProjectMemory => IconName::Notepad.path().into(),
+            MentionUri::Diagnostics => IconName::Warning.path().into(),
+            MentionUri::WorkspaceState => IconName::FileCode.path().into(),
+            MentionUri::Source { .. } => IconName::Hash.path().into(),
         }
     }
 
@@ -284,6 +327,17 @@ impl MentionUri {
                 url
             }
             MentionUri::Fetch { url } => url.clone(),
+            MentionUri::ProjectMemory => Url::parse("zed:///agent/project-memory").unwrap(),
+            MentionUri::Diagnostics => Url::parse("zed:///agent/diagnostics").unwrap(),
+            MentionUri::WorkspaceState => Url::parse("zed:///agent/workspace-state").unwrap(),
+            MentionUri::Source { source, id, name } => {
+                let mut url = Url::parse("zed:///").unwrap();
+                url.set_path(&format!("/agent/source/{source}"));
+                url.query_pairs_mut()
+                    .append_pair("id", id)
+                    .append_pair("name", name);
+                url
+            }
         }
     }
 }
@@ -469,6 +523,45 @@ mod tests {
         assert_eq!(parsed.to_uri().to_string(), rule_uri);
     }
 
+    #[test]
+    fn test_parse_project_memory_uri() {
+        let memory_uri = "zed:///agent/project-memory";
+        let parsed = MentionUri::parse(memory_uri).unwrap();
+        assert_eq!(parsed, MentionUri::ProjectMemory);
+        assert_eq!(parsed.to_uri().to_string(), memory_uri);
+    }
+
+    #[test]
+    fn test_parse_diagnostics_uri() {
+        let diagnostics_uri = "zed:///agent/diagnostics";
+        let parsed = MentionUri::parse(diagnostics_uri).unwrap();
+        assert_eq!(parsed, MentionUri::Diagnostics);
+        assert_eq!(parsed.to_uri().to_string(), diagnostics_uri);
+    }
+
+    #[test]
+    fn test_parse_workspace_state_uri() {
+        let workspace_state_uri = "zed:///agent/workspace-state";
+        let parsed = MentionUri::parse(workspace_state_uri).unwrap();
+        assert_eq!(parsed, MentionUri::WorkspaceState);
+        assert_eq!(parsed.to_uri().to_string(), workspace_state_uri);
+    }
+
+    #[test]
+    fn test_parse_source_uri() {
+        let source_uri = "zed:///agent/source/issue?id=org%2Frepo%2342&name=Crash+on+startup";
+        let parsed = MentionUri::parse(source_uri).unwrap();
+        assert_eq!(
+            parsed,
+            MentionUri::Source {
+                source: "issue".to_string(),
+                id: "org/repo#42".to_string(),
+                name: "Crash on startup".to_string(),
+            }
+        );
+        assert_eq!(parsed.to_uri().to_string(), source_uri);
+    }
+
     #[test]
     fn test_parse_fetch_http_uri() {
         let http_uri = "http://example.com/path?query=value#fragment";
@@ -0,0 +1,140 @@
+use std::rc::Rc;
+
+use agent_client_protocol as acp;
+use anyhow::Result;
+use gpui::{
+    App, AppContext as _, Context, Entity, EventEmitter, Global, SharedString, Subscription, Task,
+    WeakEntity,
+};
+
+use crate::AcpThread;
+
+/// The agent sessions that are open in any window, for code outside of the agent panel,
+/// such as extensions, that observes or drives agents.
+///
+/// Each session is an [`AcpThread`]: subscribe to it for its [`AcpThreadEvent`]s, read its
+/// [`entries`](AcpThread::entries) and [`send`](AcpThread::send) messages to it.
+///
+/// Code outside of the agent panel can also offer its own `@`-mentions in the message editor
+/// by registering a [`MentionSource`].
+///
+/// [`AcpThreadEvent`]: crate::AcpThreadEvent
+#[derive(Default)]
+pub struct AgentSessions {
+    sessions: Vec<Session>,
+    mention_sources: Vec<Rc<dyn MentionSource>>,
+}
+
+/// A source of `@`-mentions for the message editor, such as the issues of a tracker.
+///
+/// Its mentions are listed after typing `@` followed by its [`keyword`](Self::keyword), and
+/// are sent to the agent as [`MentionUri::Source`](crate::MentionUri::Source).
+pub trait MentionSource: 'static {
+    /// The word typed after `@` to list this source's mentions, such as `issue`.
+    fn keyword(&self) -> SharedString;
+
+    /// The name of the source in the `@` menu.
+    fn label(&self) -> SharedString;
+
+    /// The mentions matching the text typed after the keyword, best match first.
+    fn search(&self, query: String, cx: &mut App) -> Task<Vec<MentionSourceItem>>;
+
+    /// The text sent to the agent for the mention with the given id.
+    fn load(&self, id: String, cx: &mut App) -> Task<Result<String>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MentionSourceItem {
+    /// Identifies the mention to [`MentionSource::load`].
+    pub id: String,
+    /// What the mention is shown as in the message.
+    pub name: String,
+}
+
+struct Session {
+    session_id: acp::SessionId,
+    thread: WeakEntity<AcpThread>,
+    _release_subscription: Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentSessionEvent {
+    Started(acp::SessionId),
+    Ended(acp::SessionId),
+}
+
+impl EventEmitter<AgentSessionEvent> for AgentSessions {}
+
+struct GlobalAgentSessions(Entity<AgentSessions>);
+
+impl Global for GlobalAgentSessions {}
+
+impl AgentSessions {
+    pub fn global(cx: &mut App) -> Entity<Self> {
+        if let Some(sessions) = cx.try_global::<GlobalAgentSessions>() {
+            return sessions.0.clone();
+        }
+        let sessions = cx.new(|_| Self::default());
+        cx.set_global(GlobalAgentSessions(sessions.clone()));
+        sessions
+    }
+
+    /// The open sessions, oldest first.
+    pub fn sessions(&self) -> impl Iterator<Item = Entity<AcpThread>> + '_ {
+        self.sessions
+            .iter()
+            .filter_map(|session| session.thread.upgrade())
+    }
+
+    pub fn session(&self, session_id: &acp::SessionId) -> Option<Entity<AcpThread>> {
+        self.sessions
+            .iter()
+            .find(|session| &session.session_id == session_id)?
+            .thread
+            .upgrade()
+    }
+
+    /// Offers the source's mentions in the message editor, replacing any source registered
+    /// with the same keyword.
+    pub fn register_mention_source(&mut self, source: Rc<dyn MentionSource>) {
+        let keyword = source.keyword();
+        self.mention_sources
+            .retain(|existing| existing.keyword() != keyword);
+        self.mention_sources.push(source);
+    }
+
+    pub fn unregister_mention_source(&mut self, keyword: &str) {
+        self.mention_sources
+            .retain(|source| source.keyword().as_ref() != keyword);
+    }
+
+    /// The registered mention sources, in the order they were registered.
+    pub fn mention_sources(&self) -> impl Iterator<Item = &Rc<dyn MentionSource>> {
+        self.mention_sources.iter()
+    }
+
+    pub fn mention_source(&self, keyword: &str) -> Option<Rc<dyn MentionSource>> {
+        self.mention_sources
+            .iter()
+            .find(|source| source.keyword().as_ref() == keyword)
+            .cloned()
+    }
+
+    pub(crate) fn register(&mut self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        let session_id = thread.read(cx).session_id().clone();
+        let release_subscription = cx.observe_release(thread, {
+            let session_id = session_id.clone();
+            move |this, _, cx| {
+                this.sessions
+                    .retain(|session| session.session_id != session_id);
+                cx.emit(AgentSessionEvent::Ended(session_id.clone()));
+            }
+        });
+        self.sessions.push(Session {
+            session_id: session_id.clone(),
+            thread: thread.downgrade(),
+            _release_subscription: release_subscription,
+        });
+        cx.emit(AgentSessionEvent::Started(session_id));
+    }
+}
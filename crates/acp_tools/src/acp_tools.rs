@@ -25,16 +25,34 @@ use workspace::{
     Item, ItemHandle, ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView, Workspace,
 };
 
+mod agent_processes;
+
+pub use agent_processes::AgentProcess;
+use agent_processes::AgentProcesses;
+
 actions!(dev, [OpenAcpLogs]);
 
+actions!(
+    agent,
+    [
+        /// Lists the running agent processes of all windows, with their resource usage.
+        OpenAgentProcesses
+    ]
+);
+
 pub fn init(cx: &mut App) {
     cx.observe_new(
         |workspace: &mut Workspace, _window, _cx: &mut Context<Workspace>| {
-            workspace.register_action(|workspace, _: &OpenAcpLogs, window, cx| {
-                let acp_tools =
-                    Box::new(cx.new(|cx| AcpTools::new(workspace.project().clone(), cx)));
-                workspace.add_item_to_active_pane(acp_tools, None, true, window, cx);
-            });
+            workspace
+                .register_action(|workspace, _: &OpenAcpLogs, window, cx| {
+                    let acp_tools =
+                        Box::new(cx.new(|cx| AcpTools::new(workspace.project().clone(), cx)));
+                    workspace.add_item_to_active_pane(acp_tools, None, true, window, cx);
+                })
+                .register_action(|workspace, _: &OpenAgentProcesses, window, cx| {
+                    let agent_processes = Box::new(cx.new(AgentProcesses::new));
+                    workspace.add_item_to_active_pane(agent_processes, None, true, window, cx);
+                });
         },
     )
     .detach();
@@ -47,6 +65,7 @@ impl Global for GlobalAcpConnectionRegistry {}
 #[derive(Default)]
 pub struct AcpConnectionRegistry {
     active_connection: RefCell<Option<ActiveConnection>>,
+    processes: Vec<AgentProcess>,
 }
 
 struct ActiveConnection {
@@ -0,0 +1,246 @@
+use std::{
+    any::Any,
+    path::PathBuf,
+    rc::Weak,
+    time::{Duration, Instant},
+};
+
+use collections::HashMap;
+use gpui::{App, Entity, EventEmitter, FocusHandle, Focusable, Subscription, Task, Window};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use ui::{Tooltip, prelude::*};
+use util::{size::format_file_size, time::duration_alt_display};
+use workspace::Item;
+
+use crate::AcpConnectionRegistry;
+
+/// How often the resource usage of the listed processes is sampled.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An agent process spawned for a thread, listed until it exits or its connection is
+/// dropped.
+pub struct AgentProcess {
+    pub server_name: SharedString,
+    pub root_dir: PathBuf,
+    pub pid: u32,
+    pub started_at: Instant,
+    pub(crate) connection: Weak<dyn Any>,
+}
+
+impl AgentProcess {
+    pub fn is_connected(&self) -> bool {
+        self.connection.strong_count() > 0
+    }
+}
+
+impl AcpConnectionRegistry {
+    pub fn register_process(
+        &mut self,
+        server_name: impl Into<SharedString>,
+        root_dir: PathBuf,
+        pid: u32,
+        connection: Weak<dyn Any>,
+        cx: &mut Context<Self>,
+    ) {
+        self.prune_processes(cx);
+        self.processes.push(AgentProcess {
+            server_name: server_name.into(),
+            root_dir,
+            pid,
+            started_at: Instant::now(),
+            connection,
+        });
+        cx.notify();
+    }
+
+    /// Stops listing the process, once it has exited.
+    pub fn unregister_process(&mut self, pid: u32, cx: &mut Context<Self>) {
+        let len = self.processes.len();
+        self.processes.retain(|process| process.pid != pid);
+        if self.processes.len() != len {
+            cx.notify();
+        }
+    }
+
+    /// Stops listing the processes whose connection was dropped, as those are shut down.
+    pub fn prune_processes(&mut self, cx: &mut Context<Self>) {
+        let len = self.processes.len();
+        self.processes.retain(AgentProcess::is_connected);
+        if self.processes.len() != len {
+            cx.notify();
+        }
+    }
+
+    /// The agent processes of every window whose connection is still open.
+    pub fn processes(&self) -> impl Iterator<Item = &AgentProcess> {
+        self.processes
+            .iter()
+            .filter(|process| process.is_connected())
+    }
+}
+
+/// Lists the running agent processes of all windows, with their resource usage.
+pub(crate) struct AgentProcesses {
+    registry: Entity<AcpConnectionRegistry>,
+    focus_handle: FocusHandle,
+    system: System,
+    /// Resident memory in bytes, by pid; missing for processes that already exited.
+    memory: HashMap<u32, u64>,
+    _refresh_task: Task<()>,
+    _subscription: Subscription,
+}
+
+impl AgentProcesses {
+    pub(crate) fn new(cx: &mut Context<Self>) -> Self {
+        let registry = AcpConnectionRegistry::default_global(cx);
+        let subscription = cx.observe(&registry, |this, _, cx| {
+            this.refresh(cx);
+        });
+        let refresh_task = cx.spawn(async move |this, cx| {
+            loop {
+                cx.background_executor().timer(REFRESH_INTERVAL).await;
+                if this.update(cx, |this, cx| this.refresh(cx)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut this = Self {
+            registry,
+            focus_handle: cx.focus_handle(),
+            system: System::new(),
+            memory: HashMap::default(),
+            _refresh_task: refresh_task,
+            _subscription: subscription,
+        };
+        this.refresh(cx);
+        this
+    }
+
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        self.registry
+            .update(cx, |registry, cx| registry.prune_processes(cx));
+        let pids = self
+            .registry
+            .read(cx)
+            .processes()
+            .map(|process| Pid::from_u32(process.pid))
+            .collect::<Vec<_>>();
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&pids),
+            true,
+            ProcessRefreshKind::nothing().with_memory(),
+        );
+        self.memory = pids
+            .iter()
+            .filter_map(|pid| Some((pid.as_u32(), self.system.process(*pid)?.memory())))
+            .collect();
+        cx.notify();
+    }
+
+    fn kill(&mut self, pid: u32, cx: &mut Context<Self>) {
+        if let Some(process) = self.system.process(Pid::from_u32(pid)) {
+            process.kill();
+        }
+        self.refresh(cx);
+    }
+
+    fn render_process(&self, process: &AgentProcess, cx: &Context<Self>) -> impl IntoElement {
+        let pid = process.pid;
+        let memory = self.memory.get(&pid).copied();
+        h_flex()
+            .id(("agent-process", pid as usize))
+            .px_3()
+            .py_1p5()
+            .gap_3()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                v_flex()
+                    .flex_1()
+                    .min_w_0()
+                    .child(Label::new(process.server_name.clone()))
+                    .child(
+                        Label::new(process.root_dir.to_string_lossy().into_owned())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .truncate(),
+                    ),
+            )
+            .child(
+                Label::new(format!("PID {pid}"))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                Label::new(match memory {
+                    Some(memory) => format_file_size(memory, false),
+                    None => "Exited".to_string(),
+                })
+                .size(LabelSize::Small)
+                .color(if memory.is_some() {
+                    Color::Default
+                } else {
+                    Color::Warning
+                }),
+            )
+            .child(
+                Label::new(duration_alt_display(process.started_at.elapsed()))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                Button::new(("kill-agent-process", pid as usize), "Kill")
+                    .label_size(LabelSize::Small)
+                    .disabled(memory.is_none())
+                    .tooltip(Tooltip::text("Kill the agent process"))
+                    .on_click(cx.listener(move |this, _, _, cx| this.kill(pid, cx))),
+            )
+    }
+}
+
+impl EventEmitter<()> for AgentProcesses {}
+
+impl Item for AgentProcesses {
+    type Event = ();
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        "Agent Processes".into()
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::Server))
+    }
+}
+
+impl Focusable for AgentProcesses {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for AgentProcesses {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let registry = self.registry.read(cx);
+        let processes = registry
+            .processes()
+            .map(|process| self.render_process(process, cx).into_any_element())
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .id("agent-processes")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .overflow_y_scroll()
+            .bg(cx.theme().colors().editor_background)
+            .map(|this| {
+                if processes.is_empty() {
+                    this.items_center()
+                        .justify_center()
+                        .child(Label::new("No agent processes are running").color(Color::Muted))
+                } else {
+                    this.children(processes)
+                }
+            })
+    }
+}
@@ -532,11 +532,13 @@ impl ActionLog {
 
     /// Mark a buffer as created by agent, so we can refresh it in the context
     pub fn buffer_created(&mut self, buffer: Entity<Buffer>, cx: &mut Context<Self>) {
+        self.mark_edited_in_project(&buffer, cx);
         self.track_buffer_internal(buffer, true, cx);
     }
 
     /// Mark a buffer as edited by agent, so we can refresh it in the context
     pub fn buffer_edited(&mut self, buffer: Entity<Buffer>, cx: &mut Context<Self>) {
+        self.mark_edited_in_project(&buffer, cx);
         let tracked_buffer = self.track_buffer_internal(buffer, false, cx);
         if let TrackedBufferStatus::Deleted = tracked_buffer.status {
             tracked_buffer.status = TrackedBufferStatus::Modified;
@@ -544,6 +546,15 @@ impl ActionLog {
         tracked_buffer.schedule_diff_update(ChangeAuthor::Agent, cx);
     }
 
+    /// Lets the project badge the buffer's file as edited by the agent, until the user opens it.
+    fn mark_edited_in_project(&self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        if let Some(project_path) = buffer.read(cx).project_path(cx) {
+            self.project.update(cx, |project, cx| {
+                project.mark_edited_by_agent(project_path, cx)
+            });
+        }
+    }
+
     pub fn will_delete_buffer(&mut self, buffer: Entity<Buffer>, cx: &mut Context<Self>) {
         let tracked_buffer = self.track_buffer_internal(buffer.clone(), false, cx);
         match tracked_buffer.status {
@@ -4,6 +4,7 @@ mod history_store;
 mod legacy_thread;
 mod native_agent_server;
 pub mod outline;
+mod project_memory;
 mod templates;
 mod thread;
 mod tool_schema;
@@ -15,6 +16,7 @@ mod tests;
 pub use db::*;
 pub use history_store::*;
 pub use native_agent_server::NativeAgentServer;
+pub use project_memory::*;
 pub use templates::*;
 pub use thread::*;
 pub use tools::*;
@@ -313,8 +315,9 @@ impl NativeAgent {
         let project = thread.project.clone();
         let action_log = thread.action_log.clone();
         let prompt_capabilities_rx = thread.prompt_capabilities_rx.clone();
+        let generation_parameters = thread.generation_parameters().clone();
         let acp_thread = cx.new(|cx| {
-            acp_thread::AcpThread::new(
+            let mut acp_thread = acp_thread::AcpThread::new(
                 title,
                 connection,
                 project.clone(),
@@ -322,7 +325,9 @@ impl NativeAgent {
                 session_id.clone(),
                 prompt_capabilities_rx,
                 cx,
-            )
+            );
+            acp_thread.set_generation_parameters(generation_parameters, cx);
+            acp_thread
         });
 
         let registry = LanguageModelRegistry::read_global(cx);
@@ -759,11 +764,28 @@ impl NativeAgentConnection {
         };
         log::debug!("Found session for: {}", session_id);
 
-        let response_stream = match f(thread, cx) {
+        let plan_mode = acp_thread
+            .upgrade()
+            .is_some_and(|acp_thread| acp_thread.read(cx).plan_mode());
+        thread.update(cx, |thread, _| thread.set_plan_mode(plan_mode));
+        // They are chosen in the thread's view, and saved with the thread.
+        if let Some(acp_thread) = acp_thread.upgrade() {
+            let generation_parameters = acp_thread.read(cx).generation_parameters().clone();
+            thread.update(cx, |thread, cx| {
+                thread.set_generation_parameters(generation_parameters, cx)
+            });
+        }
+
+        let response_stream = match f(thread.clone(), cx) {
             Ok(stream) => stream,
             Err(err) => return Task::ready(Err(err)),
         };
-        Self::handle_thread_events(response_stream, acp_thread, cx)
+        let response = Self::handle_thread_events(response_stream, acp_thread, cx);
+        cx.spawn(async move |cx| {
+            let mut response = response.await?;
+            response.meta = thread.read_with(cx, |thread, _| turn_meta(thread)).ok();
+            Ok(response)
+        })
     }
 
     fn handle_thread_events(
@@ -878,6 +900,24 @@ impl NativeAgentConnection {
     }
 }
 
+/// The model and the tokens of the last request of a turn, reported in the `_meta` of its
+/// response for the thread view to show.
+fn turn_meta(thread: &Thread) -> serde_json::Value {
+    let mut meta = serde_json::json!({});
+    if let Some(model) = thread.model() {
+        meta["model"] = model.name().0.to_string().into();
+    }
+    if let Some(usage) = thread.latest_request_token_usage() {
+        meta["usage"] = serde_json::json!({
+            "inputTokens": usage.input_tokens
+                + usage.cache_creation_input_tokens
+                + usage.cache_read_input_tokens,
+            "outputTokens": usage.output_tokens,
+        });
+    }
+    meta
+}
+
 struct NativeAgentModelSelector {
     session_id: acp::SessionId,
     connection: NativeAgentConnection,
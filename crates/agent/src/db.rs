@@ -1,5 +1,5 @@
 use crate::{AgentMessage, AgentMessageContent, UserMessage, UserMessageContent};
-use acp_thread::UserMessageId;
+use acp_thread::{AcpThread, AgentThreadEntry, GenerationParameters, UserMessageId};
 use agent_client_protocol as acp;
 use agent_settings::{AgentProfileId, CompletionMode};
 use anyhow::{Result, anyhow};
@@ -35,6 +35,8 @@ pub struct DbThreadMetadata {
 pub struct DbThread {
     pub title: SharedString,
     pub messages: Vec<DbMessage>,
+    #[serde(default)]
+    pub context_summary: Option<crate::ContextSummary>,
     pub updated_at: DateTime<Utc>,
     #[serde(default)]
     pub detailed_summary: Option<SharedString>,
@@ -50,6 +52,8 @@ pub struct DbThread {
     pub completion_mode: Option<CompletionMode>,
     #[serde(default)]
     pub profile: Option<AgentProfileId>,
+    #[serde(default)]
+    pub generation_parameters: GenerationParameters,
 }
 
 impl DbThread {
@@ -195,6 +199,7 @@ impl DbThread {
         Ok(Self {
             title: thread.summary,
             messages,
+            context_summary: None,
             updated_at: thread.updated_at,
             detailed_summary: match thread.detailed_summary_state {
                 crate::legacy_thread::DetailedSummaryState::NotGenerated
@@ -209,6 +214,56 @@ impl DbThread {
             profile: thread.profile,
         })
     }
+
+    /// Saves the transcript of a thread run by an external agent, so that it can be reopened from
+    /// the history. Tool calls and their diffs are kept as text, since they can't be replayed.
+    pub fn from_acp_thread(thread: &AcpThread, cx: &App) -> Self {
+        let mut messages = Vec::new();
+        for entry in thread.entries() {
+            let text = match entry {
+                AgentThreadEntry::UserMessage(message) => {
+                    messages.push(crate::Message::User(UserMessage {
+                        id: message.id.clone().unwrap_or_else(UserMessageId::new),
+                        content: vec![UserMessageContent::Text(
+                            message.content.to_markdown(cx).to_string(),
+                        )],
+                    }));
+                    continue;
+                }
+                AgentThreadEntry::AssistantMessage(message) => message
+                    .to_markdown(cx)
+                    .trim_start_matches("## Assistant")
+                    .trim()
+                    .to_string(),
+                AgentThreadEntry::ToolCall(_) => entry.to_markdown(cx).trim().to_string(),
+            };
+
+            // Consecutive replies and tool calls make up a single agent message.
+            if let Some(crate::Message::Agent(message)) = messages.last_mut() {
+                message.content.push(AgentMessageContent::Text(text));
+            } else {
+                messages.push(crate::Message::Agent(AgentMessage {
+                    content: vec![AgentMessageContent::Text(text)],
+                    tool_results: IndexMap::default(),
+                }));
+            }
+        }
+
+        Self {
+            title: thread.title(),
+            messages,
+            context_summary: None,
+            updated_at: Utc::now(),
+            detailed_summary: None,
+            initial_project_snapshot: None,
+            cumulative_token_usage: Default::default(),
+            request_token_usage: HashMap::default(),
+            model: None,
+            completion_mode: None,
+            profile: None,
+            generation_parameters: GenerationParameters::default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -188,6 +188,24 @@ impl HistoryStore {
         })
     }
 
+    /// Saves a thread run by an external agent, which doesn't save its threads itself. When
+    /// reopened from the history, the conversation continues with the Zed agent.
+    pub fn save_external_thread(
+        &mut self,
+        thread: &Entity<acp_thread::AcpThread>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let thread = thread.read(cx);
+        let id = thread.session_id().clone();
+        let db_thread = DbThread::from_acp_thread(thread, cx);
+        let database_future = ThreadsDatabase::connect(cx);
+        cx.spawn(async move |this, cx| {
+            let database = database_future.await.map_err(|err| anyhow!(err))?;
+            database.save_thread(id, db_thread).await?;
+            this.update(cx, |this, cx| this.reload(cx))
+        })
+    }
+
     pub fn delete_text_thread(
         &mut self,
         path: Arc<Path>,
@@ -29,6 +29,10 @@ impl AgentServer for NativeAgentServer {
         "Zed Agent".into()
     }
 
+    fn placeholder(&self) -> SharedString {
+        "Message the Zed Agent — @ to include context".into()
+    }
+
     fn logo(&self) -> ui::IconName {
         ui::IconName::ZedAgent
     }
@@ -0,0 +1,165 @@
+use std::fmt::Write as _;
+
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use futures::{FutureExt as _, future::Shared};
+use gpui::{App, AppContext as _, Context, Entity, EntityId, Global, Task};
+use project::Project;
+use util::ResultExt as _;
+
+const PROJECT_MEMORY_KEY_PREFIX: &str = "agent_project_memory";
+
+/// Facts about a project, such as "we use pnpm, not npm", saved by the user or the agent
+/// and attached to the first message of every new thread in that project.
+pub struct ProjectMemory {
+    /// Identifies the project by the paths of its visible worktrees, so that the
+    /// facts outlive the window. `None` for projects without a worktree.
+    key: Option<String>,
+    facts: Vec<String>,
+    /// Saving is held back until the stored facts are loaded, so that they aren't overwritten.
+    loaded: bool,
+    load_task: Shared<Task<()>>,
+}
+
+/// The memory of each open project, kept until the project is released so that facts added
+/// by short-lived callers, such as a tool call, are saved.
+#[derive(Default)]
+struct ProjectMemories(HashMap<EntityId, Entity<ProjectMemory>>);
+
+impl Global for ProjectMemories {}
+
+impl ProjectMemory {
+    /// The memory of the project, shared by every thread in it.
+    pub fn for_project(project: &Entity<Project>, cx: &mut App) -> Entity<Self> {
+        let project_id = project.entity_id();
+        if let Some(memory) = cx.default_global::<ProjectMemories>().0.get(&project_id) {
+            return memory.clone();
+        }
+
+        let key = Self::key(project, cx);
+        let memory = cx.new(|cx| {
+            let load_task = match key.clone() {
+                Some(key) => cx.spawn(async move |this, cx| {
+                    let facts = cx
+                        .background_spawn(async move { KEY_VALUE_STORE.read_kvp(&key) })
+                        .await
+                        .log_err()
+                        .flatten()
+                        .and_then(|facts| serde_json::from_str::<Vec<String>>(&facts).log_err())
+                        .unwrap_or_default();
+                    this.update(cx, |this, cx| {
+                        // Keep the facts that were added while loading.
+                        let added_while_loading = !this.facts.is_empty();
+                        this.facts.splice(0..0, facts);
+                        this.loaded = true;
+                        if added_while_loading {
+                            this.save(cx).detach();
+                        } else {
+                            cx.notify();
+                        }
+                    })
+                    .ok();
+                }),
+                None => Task::ready(()),
+            };
+            Self {
+                loaded: key.is_none(),
+                key,
+                facts: Vec::new(),
+                load_task: load_task.shared(),
+            }
+        });
+        cx.default_global::<ProjectMemories>()
+            .0
+            .insert(project_id, memory.clone());
+        cx.observe_release(project, move |_, cx| {
+            cx.default_global::<ProjectMemories>().0.remove(&project_id);
+        })
+        .detach();
+        memory
+    }
+
+    /// The memory of the project, once its stored facts are loaded.
+    pub fn load(project: &Entity<Project>, cx: &mut App) -> Task<Entity<Self>> {
+        let memory = Self::for_project(project, cx);
+        let loaded = memory.read(cx).load_task.clone();
+        cx.spawn(async move |_| {
+            loaded.await;
+            memory
+        })
+    }
+
+    fn key(project: &Entity<Project>, cx: &App) -> Option<String> {
+        let mut paths = project
+            .read(cx)
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).abs_path().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return None;
+        }
+        paths.sort();
+        Some(format!("{PROJECT_MEMORY_KEY_PREFIX}-{}", paths.join(":")))
+    }
+
+    pub fn facts(&self) -> &[String] {
+        &self.facts
+    }
+
+    /// Adds a fact, resolving once it's saved.
+    pub fn add(&mut self, fact: impl Into<String>, cx: &mut Context<Self>) -> Task<()> {
+        let fact = fact.into();
+        let fact = fact.trim();
+        if fact.is_empty() || self.facts.iter().any(|existing| existing == fact) {
+            return Task::ready(());
+        }
+        self.facts.push(fact.to_string());
+        self.save(cx)
+    }
+
+    pub fn replace(&mut self, ix: usize, fact: impl Into<String>, cx: &mut Context<Self>) {
+        let fact = fact.into();
+        let fact = fact.trim();
+        if fact.is_empty() {
+            self.remove(ix, cx);
+        } else if let Some(existing) = self.facts.get_mut(ix) {
+            *existing = fact.to_string();
+            self.save(cx).detach();
+        }
+    }
+
+    pub fn remove(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if ix < self.facts.len() {
+            self.facts.remove(ix);
+            self.save(cx).detach();
+        }
+    }
+
+    /// The facts as a markdown list, to be sent to the agent.
+    pub fn to_markdown(&self) -> Option<String> {
+        if self.facts.is_empty() {
+            return None;
+        }
+        let mut markdown = String::new();
+        for fact in &self.facts {
+            writeln!(markdown, "- {fact}").ok();
+        }
+        Some(markdown)
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) -> Task<()> {
+        cx.notify();
+        if !self.loaded {
+            return Task::ready(());
+        }
+        let Some(key) = self.key.clone() else {
+            return Task::ready(());
+        };
+        let Some(facts) = serde_json::to_string(&self.facts).log_err() else {
+            return Task::ready(());
+        };
+        cx.background_spawn(async move {
+            KEY_VALUE_STORE.write_kvp(key, facts).await.log_err();
+        })
+    }
+}
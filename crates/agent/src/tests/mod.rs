@@ -1,5 +1,5 @@
 use super::*;
-use acp_thread::{AgentConnection, AgentModelGroupName, AgentModelList, UserMessageId};
+use acp_thread::{AgentConnection, AgentModelGroupName, AgentModelList, MentionUri, UserMessageId};
 use agent_client_protocol::{self as acp};
 use agent_settings::AgentProfileId;
 use anyhow::Result;
@@ -750,6 +750,101 @@ async fn test_send_after_tool_use_limit(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_remembered_facts_reach_new_threads(cx: &mut TestAppContext) {
+    let ThreadTest {
+        model,
+        thread,
+        project_context,
+        context_server_store,
+        fs,
+    } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+    let fact = "We use pnpm, not npm.";
+
+    let mut events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(RememberTool::new(thread.project().clone()));
+            thread.send(UserMessageId::new(), ["Remember that we use pnpm"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "tool_1".into(),
+            name: RememberTool::name().into(),
+            raw_input: json!({ "fact": fact }).to_string(),
+            input: json!({ "fact": fact }),
+            is_input_complete: true,
+        },
+    ));
+    fake_model.end_last_completion_stream();
+    // The tool completes once the fact is saved.
+    loop {
+        let event = events.next().await.unwrap().unwrap();
+        if let ThreadEvent::ToolCallUpdate(acp_thread::ToolCallUpdate::UpdateFields(update)) = event
+            && update.fields.status == Some(acp::ToolCallStatus::Completed)
+        {
+            break;
+        }
+    }
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_text_chunk("Noted.");
+    fake_model.end_last_completion_stream();
+    events.collect::<Vec<_>>().await;
+
+    // A new project for the same directory loads the fact back from the store.
+    let project = Project::test(fs.clone(), [path!("/test").as_ref()], cx).await;
+    let memory = cx.update(|cx| ProjectMemory::load(&project, cx)).await;
+    let facts = memory
+        .read_with(cx, |memory, _| memory.to_markdown())
+        .unwrap();
+    assert_eq!(facts, format!("- {fact}\n"));
+
+    // New threads get the facts as context.
+    let context_server_registry = cx.new(|cx| ContextServerRegistry::new(context_server_store, cx));
+    let thread = cx.new(|cx| {
+        Thread::new(
+            project,
+            project_context,
+            context_server_registry,
+            Templates::new(),
+            Some(model.clone()),
+            cx,
+        )
+    });
+    let memory_context = acp::ContentBlock::Resource(acp::EmbeddedResource {
+        annotations: None,
+        resource: acp::EmbeddedResourceResource::TextResourceContents(acp::TextResourceContents {
+            mime_type: None,
+            text: facts,
+            uri: MentionUri::ProjectMemory.to_uri().to_string(),
+            meta: None,
+        }),
+        meta: None,
+    });
+    let _events = thread
+        .update(cx, |thread, cx| {
+            thread.send(
+                UserMessageId::new(),
+                vec![
+                    acp::ContentBlock::from("Which package manager do we use?"),
+                    memory_context,
+                ],
+                cx,
+            )
+        })
+        .unwrap();
+    cx.run_until_parked();
+    let completion = fake_model.pending_completions().pop().unwrap();
+    assert!(
+        completion.messages[1]
+            .content
+            .iter()
+            .any(|content| matches!(content, MessageContent::Text(text) if text.contains(fact)))
+    );
+}
+
 async fn expect_tool_call(events: &mut UnboundedReceiver<Result<ThreadEvent>>) -> acp::ToolCall {
     let event = events
         .next()
@@ -1732,6 +1827,82 @@ async fn test_title_generation(cx: &mut TestAppContext) {
     thread.read_with(cx, |thread, _| assert_eq!(thread.title(), "Hello world"));
 }
 
+#[gpui::test]
+async fn test_summarize_context(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    let send = thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Message 1"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_text_chunk("Response to Message 1");
+    fake_model.end_last_completion_stream();
+    send.collect::<Vec<_>>().await;
+
+    let summary_model = Arc::new(FakeLanguageModel::default());
+    thread.update(cx, |thread, cx| {
+        thread.set_summarization_model(Some(summary_model.clone()), cx)
+    });
+    let summarize = thread.update(cx, |thread, cx| thread.summarize_context(cx));
+    cx.run_until_parked();
+    thread.read_with(cx, |thread, _| assert!(thread.is_summarizing_context()));
+
+    summary_model.send_last_completion_stream_text_chunk("The user sent\n");
+    summary_model.send_last_completion_stream_text_chunk("a first message.");
+    summary_model.end_last_completion_stream();
+    summarize.await.unwrap();
+    thread.read_with(cx, |thread, _| {
+        assert!(!thread.is_summarizing_context());
+        assert_eq!(
+            thread.context_summary(),
+            Some(&ContextSummary {
+                message_count: 2,
+                text: "The user sent\na first message.".into(),
+            })
+        );
+        assert_eq!(thread.first_message_after_context_summary(), None);
+    });
+
+    // The summary replaces the summarized messages in subsequent requests,
+    // while the thread keeps them.
+    let message_id = UserMessageId::new();
+    thread
+        .update(cx, |thread, cx| {
+            thread.send(message_id.clone(), ["Message 2"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    let completion = fake_model.pending_completions().pop().unwrap();
+    assert_eq!(
+        completion.messages[1..],
+        vec![
+            LanguageModelRequestMessage {
+                role: Role::User,
+                content: vec![
+                    "The earlier part of this conversation was summarized to fit the context window:\n\nThe user sent\na first message."
+                        .into()
+                ],
+                cache: false
+            },
+            LanguageModelRequestMessage {
+                role: Role::User,
+                content: vec!["Message 2".into()],
+                cache: true
+            }
+        ]
+    );
+    thread.read_with(cx, |thread, _| {
+        assert!(thread.to_markdown().contains("Response to Message 1"));
+        assert_eq!(
+            thread.first_message_after_context_summary(),
+            Some(&message_id)
+        );
+    });
+}
+
 #[gpui::test]
 async fn test_building_request_with_pending_tools(cx: &mut TestAppContext) {
     let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
@@ -2347,6 +2518,7 @@ async fn setup(cx: &mut TestAppContext, model: TestModel) -> ThreadTest {
                             ToolRequiringPermission::name(): true,
                             InfiniteTool::name(): true,
                             ThinkingTool::name(): true,
+                            RememberTool::name(): true,
                         }
                     }
                 }
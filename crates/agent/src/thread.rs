@@ -1,10 +1,11 @@
 use crate::{
-    ContextServerRegistry, CopyPathTool, CreateDirectoryTool, DbLanguageModel, DbThread,
-    DeletePathTool, DiagnosticsTool, EditFileTool, FetchTool, FindPathTool, GrepTool,
-    ListDirectoryTool, MovePathTool, NowTool, OpenTool, ProjectSnapshot, ReadFileTool,
-    SystemPromptTemplate, Template, Templates, TerminalTool, ThinkingTool, WebSearchTool,
+    CodeActionTool, ContextServerRegistry, CopyPathTool, CreateDirectoryTool, DbLanguageModel,
+    DbThread, DeletePathTool, DiagnosticsTool, EditFileTool, FetchTool, FindPathTool, GrepTool,
+    ListDirectoryTool, MovePathTool, NowTool, OpenTool, OrganizeImportsTool, ProjectSnapshot,
+    ReadFileTool, RememberTool, RenameSymbolTool, SystemPromptTemplate, Template, Templates,
+    TerminalTool, ThinkingTool, WebSearchTool, WorkspaceEditTool,
 };
-use acp_thread::{MentionUri, UserMessageId};
+use acp_thread::{GenerationParameters, MentionUri, UserMessageId};
 use action_log::ActionLog;
 
 use agent_client_protocol as acp;
@@ -17,7 +18,6 @@ use chrono::{DateTime, Utc};
 use client::{ModelRequestUsage, RequestUsage, UserStore};
 use cloud_llm_client::{CompletionIntent, CompletionRequestStatus, Plan, UsageLimit};
 use collections::{HashMap, HashSet, IndexMap};
-use fs::Fs;
 use futures::stream;
 use futures::{
     FutureExt,
@@ -39,7 +39,7 @@ use project::Project;
 use prompt_store::ProjectContext;
 use schemars::{JsonSchema, Schema};
 use serde::{Deserialize, Serialize};
-use settings::{Settings, update_settings_file};
+use settings::{Settings, ToolPermissionProfile};
 use smol::stream::StreamExt;
 use std::{
     collections::BTreeMap,
@@ -188,6 +188,9 @@ impl UserMessage {
         const OPEN_SELECTIONS_TAG: &str = "<selections>";
         const OPEN_THREADS_TAG: &str = "<threads>";
         const OPEN_FETCH_TAG: &str = "<fetched_urls>";
+        const OPEN_DIAGNOSTICS_TAG: &str = "<diagnostics>";
+        const OPEN_WORKSPACE_STATE_TAG: &str = "<workspace_state>";
+        const OPEN_SOURCES_TAG: &str = "<mentioned_items>";
         const OPEN_RULES_TAG: &str =
             "<rules>\nThe user has specified the following rules that should be applied:\n";
 
@@ -197,6 +200,9 @@ impl UserMessage {
         let mut selection_context = OPEN_SELECTIONS_TAG.to_string();
         let mut thread_context = OPEN_THREADS_TAG.to_string();
         let mut fetch_context = OPEN_FETCH_TAG.to_string();
+        let mut diagnostics_context = OPEN_DIAGNOSTICS_TAG.to_string();
+        let mut workspace_state_context = OPEN_WORKSPACE_STATE_TAG.to_string();
+        let mut source_context = OPEN_SOURCES_TAG.to_string();
         let mut rules_context = OPEN_RULES_TAG.to_string();
 
         for chunk in &self.content {
@@ -279,6 +285,25 @@ impl UserMessage {
                         MentionUri::Fetch { url } => {
                             write!(&mut fetch_context, "\nFetch: {}\n\n{}", url, content).ok();
                         }
+                        MentionUri::Diagnostics => {
+                            write!(&mut diagnostics_context, "\n{}\n", content).ok();
+                        }
+                        MentionUri::Source { source, name, .. } => {
+                            write!(&mut source_context, "\n{source}: {name}\n\n{content}\n").ok();
+                        }
+                        MentionUri::WorkspaceState => {
+                            write!(&mut workspace_state_context, "\n{}\n", content).ok();
+                            // It's attached to the message rather than mentioned in it.
+                            continue;
+                        }
+                        MentionUri::ProjectMemory => {
+                            write!(
+                                &mut rules_context,
+                                "\nFacts about this project that were saved to be remembered:\n{}",
+                                content
+                            )
+                            .ok();
+                        }
                     }
 
                     language_model::MessageContent::Text(uri.as_link().to_string())
@@ -332,6 +357,27 @@ impl UserMessage {
                 .push(language_model::MessageContent::Text(fetch_context));
         }
 
+        if diagnostics_context.len() > OPEN_DIAGNOSTICS_TAG.len() {
+            diagnostics_context.push_str("</diagnostics>\n");
+            message
+                .content
+                .push(language_model::MessageContent::Text(diagnostics_context));
+        }
+
+        if source_context.len() > OPEN_SOURCES_TAG.len() {
+            source_context.push_str("</mentioned_items>\n");
+            message
+                .content
+                .push(language_model::MessageContent::Text(source_context));
+        }
+
+        if workspace_state_context.len() > OPEN_WORKSPACE_STATE_TAG.len() {
+            workspace_state_context.push_str("</workspace_state>\n");
+            message.content.push(language_model::MessageContent::Text(
+                workspace_state_context,
+            ));
+        }
+
         if rules_context.len() > OPEN_RULES_TAG.len() {
             rules_context.push_str("</user_rules>\n");
             message
@@ -520,6 +566,15 @@ pub enum AgentMessageContent {
     ToolUse(LanguageModelToolUse),
 }
 
+/// A summary of the earliest messages of a thread, sent to the model in their place once
+/// the context window fills up. The messages are kept so that the transcript stays intact.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextSummary {
+    /// How many of the thread's messages the summary replaces.
+    pub message_count: usize,
+    pub text: SharedString,
+}
+
 pub trait TerminalHandle {
     fn id(&self, cx: &AsyncApp) -> Result<acp::TerminalId>;
     fn current_output(&self, cx: &AsyncApp) -> Result<acp::TerminalOutputResponse>;
@@ -582,6 +637,8 @@ pub struct Thread {
     pending_summary_generation: Option<Shared<Task<Option<SharedString>>>>,
     summary: Option<SharedString>,
     messages: Vec<Message>,
+    context_summary: Option<ContextSummary>,
+    summarizing_context: bool,
     user_store: Entity<UserStore>,
     completion_mode: CompletionMode,
     /// Holds the task that handles agent interaction until the end of the turn.
@@ -598,6 +655,9 @@ pub struct Thread {
     initial_project_snapshot: Shared<Task<Option<Arc<ProjectSnapshot>>>>,
     context_server_registry: Entity<ContextServerRegistry>,
     profile_id: AgentProfileId,
+    /// Whether the model is only to propose changes, so it isn't given the tools making them.
+    plan_mode: bool,
+    generation_parameters: GenerationParameters,
     project_context: Entity<ProjectContext>,
     templates: Arc<Templates>,
     model: Option<Arc<dyn LanguageModel>>,
@@ -640,6 +700,8 @@ impl Thread {
             pending_summary_generation: None,
             summary: None,
             messages: Vec::new(),
+            context_summary: None,
+            summarizing_context: false,
             user_store: project.read(cx).user_store(),
             completion_mode: AgentSettings::get_global(cx).preferred_completion_mode,
             running_turn: None,
@@ -656,6 +718,8 @@ impl Thread {
             },
             context_server_registry,
             profile_id,
+            plan_mode: false,
+            generation_parameters: GenerationParameters::default(),
             project_context,
             templates,
             model,
@@ -757,11 +821,7 @@ impl Thread {
             .as_ref()
             .and_then(|result| result.output.clone());
         if let Some(output) = output.clone() {
-            let tool_event_stream = ToolCallEventStream::new(
-                tool_use.id.clone(),
-                stream.clone(),
-                Some(self.project.read(cx).fs().clone()),
-            );
+            let tool_event_stream = ToolCallEventStream::new(tool_use.id.clone(), stream.clone());
             tool.replay(tool_use.input.clone(), output, tool_event_stream, cx)
                 .log_err();
         }
@@ -828,6 +888,8 @@ impl Thread {
             pending_summary_generation: None,
             summary: db_thread.detailed_summary,
             messages: db_thread.messages,
+            context_summary: db_thread.context_summary,
+            summarizing_context: false,
             user_store: project.read(cx).user_store(),
             completion_mode: db_thread.completion_mode.unwrap_or_default(),
             running_turn: None,
@@ -839,6 +901,8 @@ impl Thread {
             initial_project_snapshot: Task::ready(db_thread.initial_project_snapshot).shared(),
             context_server_registry,
             profile_id,
+            plan_mode: false,
+            generation_parameters: db_thread.generation_parameters,
             project_context,
             templates,
             model,
@@ -856,6 +920,7 @@ impl Thread {
         let mut thread = DbThread {
             title: self.title(),
             messages: self.messages.clone(),
+            context_summary: self.context_summary.clone(),
             updated_at: self.updated_at,
             detailed_summary: self.summary.clone(),
             initial_project_snapshot: None,
@@ -867,6 +932,7 @@ impl Thread {
             }),
             completion_mode: Some(self.completion_mode),
             profile: Some(self.profile_id.clone()),
+            generation_parameters: self.generation_parameters.clone(),
         };
 
         cx.background_spawn(async move {
@@ -966,6 +1032,10 @@ impl Thread {
         cx: &mut Context<Self>,
     ) {
         let language_registry = self.project.read(cx).languages().clone();
+        self.add_tool(CodeActionTool::new(
+            self.project.clone(),
+            self.action_log.clone(),
+        ));
         self.add_tool(CopyPathTool::new(self.project.clone()));
         self.add_tool(CreateDirectoryTool::new(self.project.clone()));
         self.add_tool(DeletePathTool::new(
@@ -986,13 +1056,26 @@ impl Thread {
         self.add_tool(MovePathTool::new(self.project.clone()));
         self.add_tool(NowTool);
         self.add_tool(OpenTool::new(self.project.clone()));
+        self.add_tool(OrganizeImportsTool::new(
+            self.project.clone(),
+            self.action_log.clone(),
+        ));
         self.add_tool(ReadFileTool::new(
             self.project.clone(),
             self.action_log.clone(),
         ));
+        self.add_tool(RememberTool::new(self.project.clone()));
+        self.add_tool(RenameSymbolTool::new(
+            self.project.clone(),
+            self.action_log.clone(),
+        ));
         self.add_tool(TerminalTool::new(self.project.clone(), environment));
         self.add_tool(ThinkingTool);
         self.add_tool(WebSearchTool);
+        self.add_tool(WorkspaceEditTool::new(
+            self.project.clone(),
+            self.action_log.clone(),
+        ));
     }
 
     pub fn add_tool<T: AgentTool>(&mut self, tool: T) {
@@ -1011,6 +1094,27 @@ impl Thread {
         self.profile_id = profile_id;
     }
 
+    pub fn set_plan_mode(&mut self, plan_mode: bool) {
+        self.plan_mode = plan_mode;
+    }
+
+    pub fn generation_parameters(&self) -> &GenerationParameters {
+        &self.generation_parameters
+    }
+
+    /// Uses the temperature and stop sequences of the parameters for the next requests. The
+    /// maximum of output tokens is left to the model.
+    pub fn set_generation_parameters(
+        &mut self,
+        generation_parameters: GenerationParameters,
+        cx: &mut Context<Self>,
+    ) {
+        if self.generation_parameters != generation_parameters {
+            self.generation_parameters = generation_parameters;
+            cx.notify();
+        }
+    }
+
     pub fn cancel(&mut self, cx: &mut Context<Self>) {
         if let Some(running_turn) = self.running_turn.take() {
             running_turn.cancel();
@@ -1037,6 +1141,13 @@ impl Thread {
             return Err(anyhow!("Message not found"));
         };
 
+        if self
+            .context_summary
+            .as_ref()
+            .is_some_and(|summary| summary.message_count > position)
+        {
+            self.context_summary = None;
+        }
         for message in self.messages.drain(position..) {
             match message {
                 Message::User(message) => {
@@ -1516,9 +1627,7 @@ impl Thread {
             }));
         };
 
-        let fs = self.project.read(cx).fs().clone();
-        let tool_event_stream =
-            ToolCallEventStream::new(tool_use.id.clone(), event_stream.clone(), Some(fs));
+        let tool_event_stream = ToolCallEventStream::new(tool_use.id.clone(), event_stream.clone());
         tool_event_stream.update_fields(acp::ToolCallUpdateFields {
             status: Some(acp::ToolCallStatus::InProgress),
             ..Default::default()
@@ -1665,6 +1774,105 @@ impl Thread {
         task
     }
 
+    pub fn context_summary(&self) -> Option<&ContextSummary> {
+        self.context_summary.as_ref()
+    }
+
+    /// The first user message sent after the context was summarized, if any.
+    pub fn first_message_after_context_summary(&self) -> Option<&UserMessageId> {
+        let summary = self.context_summary.as_ref()?;
+        self.messages[summary.message_count.min(self.messages.len())..]
+            .iter()
+            .find_map(|message| match message {
+                Message::User(message) => Some(&message.id),
+                Message::Agent(_) | Message::Resume => None,
+            })
+    }
+
+    pub fn is_summarizing_context(&self) -> bool {
+        self.summarizing_context
+    }
+
+    /// Condenses the messages sent so far into a summary that replaces them in subsequent
+    /// requests, so that the thread can continue once the context window fills up.
+    pub fn summarize_context(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        if self.running_turn.is_some() {
+            return Task::ready(Err(anyhow!("Can't summarize while the agent is running")));
+        }
+        if self.summarizing_context {
+            return Task::ready(Ok(()));
+        }
+        let Some(model) = self.summarization_model.clone() else {
+            return Task::ready(Err(anyhow!("No summarization model available")));
+        };
+        let message_count = self.messages.len();
+        if self
+            .context_summary
+            .as_ref()
+            .is_some_and(|summary| summary.message_count >= message_count)
+        {
+            return Task::ready(Ok(()));
+        }
+
+        let mut request = LanguageModelRequest {
+            intent: Some(CompletionIntent::ThreadContextSummarization),
+            temperature: AgentSettings::temperature_for_model(&model, cx),
+            ..Default::default()
+        };
+        request.messages = self.build_request_messages(cx);
+        if let Some(last_message) = request.messages.last_mut() {
+            last_message.cache = false;
+        }
+        request.messages.push(LanguageModelRequestMessage {
+            role: Role::User,
+            content: vec![SUMMARIZE_THREAD_DETAILED_PROMPT.into()],
+            cache: false,
+        });
+
+        let task = cx.spawn(async move |this, cx| {
+            let result = async {
+                let mut text = String::new();
+                let mut events = model.stream_completion(request, cx).await?;
+                while let Some(event) = events.next().await {
+                    match event? {
+                        LanguageModelCompletionEvent::Text(chunk) => text.push_str(&chunk),
+                        LanguageModelCompletionEvent::StatusUpdate(
+                            CompletionRequestStatus::UsageUpdated { amount, limit },
+                        ) => {
+                            this.update(cx, |thread, cx| {
+                                thread.update_model_request_usage(amount, limit, cx);
+                            })?;
+                        }
+                        _ => {}
+                    }
+                }
+                anyhow::Ok(text)
+            }
+            .await;
+
+            this.update(cx, |this, cx| {
+                this.summarizing_context = false;
+                cx.notify();
+                let text = result?;
+                let text = text.trim();
+                if text.is_empty() {
+                    return Err(anyhow!("The model returned an empty summary"));
+                }
+                this.context_summary = Some(ContextSummary {
+                    message_count,
+                    text: text.to_string().into(),
+                });
+                // The usage reported so far includes the summarized messages.
+                this.request_token_usage.clear();
+                cx.emit(TokenUsageUpdated(None));
+                Ok(())
+            })?
+        });
+        self.summarizing_context = true;
+        cx.notify();
+        task
+    }
+
     fn generate_title(&mut self, cx: &mut Context<Self>) {
         let Some(model) = self.summarization_model.clone() else {
             return;
@@ -1828,8 +2036,11 @@ impl Thread {
             messages,
             tools,
             tool_choice: None,
-            stop: Vec::new(),
-            temperature: AgentSettings::temperature_for_model(model, cx),
+            stop: self.generation_parameters.stop_sequences.clone(),
+            temperature: self
+                .generation_parameters
+                .temperature
+                .or_else(|| AgentSettings::temperature_for_model(model, cx)),
             thinking_allowed: true,
         };
 
@@ -1859,6 +2070,7 @@ impl Thread {
             .filter_map(|(tool_name, tool)| {
                 if tool.supports_provider(&model.provider_id())
                     && profile.is_tool_enabled(tool_name)
+                    && !(self.plan_mode && acp_thread::plan_mode_rejects(tool.kind()))
                 {
                     Some((truncate(tool_name), tool.clone()))
                 } else {
@@ -1927,7 +2139,24 @@ impl Thread {
             content: vec![system_prompt.into()],
             cache: false,
         }];
-        for message in &self.messages {
+        let summarized_messages = match self.context_summary.as_ref() {
+            Some(summary) => {
+                messages.push(LanguageModelRequestMessage {
+                    role: Role::User,
+                    content: vec![
+                        format!(
+                            "The earlier part of this conversation was summarized to fit the context window:\n\n{}",
+                            summary.text
+                        )
+                        .into(),
+                    ],
+                    cache: false,
+                });
+                summary.message_count.min(self.messages.len())
+            }
+            None => 0,
+        };
+        for message in &self.messages[summarized_messages..] {
             messages.extend(message.to_request());
         }
 
@@ -2358,7 +2587,6 @@ impl ThreadEventStream {
 pub struct ToolCallEventStream {
     tool_use_id: LanguageModelToolUseId,
     stream: ThreadEventStream,
-    fs: Option<Arc<dyn Fs>>,
 }
 
 impl ToolCallEventStream {
@@ -2366,20 +2594,15 @@ impl ToolCallEventStream {
     pub fn test() -> (Self, ToolCallEventStreamReceiver) {
         let (events_tx, events_rx) = mpsc::unbounded::<Result<ThreadEvent>>();
 
-        let stream = ToolCallEventStream::new("test_id".into(), ThreadEventStream(events_tx), None);
+        let stream = ToolCallEventStream::new("test_id".into(), ThreadEventStream(events_tx));
 
         (stream, ToolCallEventStreamReceiver(events_rx))
     }
 
-    fn new(
-        tool_use_id: LanguageModelToolUseId,
-        stream: ThreadEventStream,
-        fs: Option<Arc<dyn Fs>>,
-    ) -> Self {
+    fn new(tool_use_id: LanguageModelToolUseId, stream: ThreadEventStream) -> Self {
         Self {
             tool_use_id,
             stream,
-            fs,
         }
     }
 
@@ -2402,7 +2625,12 @@ impl ToolCallEventStream {
     }
 
     pub fn authorize(&self, title: impl Into<String>, cx: &mut App) -> Task<Result<()>> {
-        if agent_settings::AgentSettings::get_global(cx).always_allow_tool_actions {
+        // The thread rejects the tool calls the permission profile denies, even when all
+        // of them are allowed otherwise.
+        let settings = agent_settings::AgentSettings::get_global(cx);
+        if settings.always_allow_tool_actions
+            && settings.tool_permission_profile != ToolPermissionProfile::DenyExecute
+        {
             return Task::ready(Ok(()));
         }
 
@@ -2443,23 +2671,10 @@ impl ToolCallEventStream {
                 },
             )))
             .ok();
-        let fs = self.fs.clone();
-        cx.spawn(async move |cx| match response_rx.await?.0.as_ref() {
-            "always_allow" => {
-                if let Some(fs) = fs.clone() {
-                    cx.update(|cx| {
-                        update_settings_file(fs, cx, |settings, _| {
-                            settings
-                                .agent
-                                .get_or_insert_default()
-                                .set_always_allow_tool_actions(true);
-                        });
-                    })?;
-                }
-
-                Ok(())
-            }
-            "allow" => Ok(()),
+        // The thread remembers what "Always Allow" was chosen for, to allow it on every
+        // thread from then on.
+        cx.spawn(async move |_| match response_rx.await?.0.as_ref() {
+            "always_allow" | "allow" => Ok(()),
             _ => Err(anyhow!("Permission to run tool denied by user")),
         })
     }
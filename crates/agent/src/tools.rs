@@ -1,3 +1,4 @@
+mod code_action_tool;
 mod context_server_registry;
 mod copy_path_tool;
 mod create_directory_tool;
@@ -7,18 +8,24 @@ mod edit_file_tool;
 mod fetch_tool;
 mod find_path_tool;
 mod grep_tool;
+mod language_server_edits;
 mod list_directory_tool;
 mod move_path_tool;
 mod now_tool;
 mod open_tool;
+mod organize_imports_tool;
 mod read_file_tool;
+mod remember_tool;
+mod rename_symbol_tool;
 mod terminal_tool;
 mod thinking_tool;
 mod web_search_tool;
+mod workspace_edit_tool;
 
 use crate::AgentTool;
 use language_model::{LanguageModelRequestTool, LanguageModelToolSchemaFormat};
 
+pub use code_action_tool::*;
 pub use context_server_registry::*;
 pub use copy_path_tool::*;
 pub use create_directory_tool::*;
@@ -32,10 +39,14 @@ pub use list_directory_tool::*;
 pub use move_path_tool::*;
 pub use now_tool::*;
 pub use open_tool::*;
+pub use organize_imports_tool::*;
 pub use read_file_tool::*;
+pub use remember_tool::*;
+pub use rename_symbol_tool::*;
 pub use terminal_tool::*;
 pub use thinking_tool::*;
 pub use web_search_tool::*;
+pub use workspace_edit_tool::*;
 
 macro_rules! tools {
     ($($tool:ty),* $(,)?) => {
@@ -75,6 +86,7 @@ macro_rules! tools {
 }
 
 tools! {
+    CodeActionTool,
     CopyPathTool,
     CreateDirectoryTool,
     DeletePathTool,
@@ -87,8 +99,12 @@ tools! {
     MovePathTool,
     NowTool,
     OpenTool,
+    OrganizeImportsTool,
     ReadFileTool,
+    RememberTool,
+    RenameSymbolTool,
     TerminalTool,
     ThinkingTool,
     WebSearchTool,
+    WorkspaceEditTool,
 }
@@ -0,0 +1,163 @@
+use super::language_server_edits;
+use crate::{AgentTool, ToolCallEventStream};
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Context as _, Result, bail};
+use gpui::{App, Entity, SharedString, Task};
+use language::Point;
+use project::Project;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Lists or applies the code actions that the language server of a file offers on a line, such as adding a missing import, implementing missing members, filling in match arms or extracting a function.
+///
+/// Call this tool without `action` to list the actions available on the line, then again with the title of the one to apply.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CodeActionToolInput {
+    /// The path of the file, starting with one of the project's root directories.
+    pub path: String,
+    /// The one-based number of the line to get the actions for.
+    pub line: u32,
+    /// The title of the action to apply, exactly as it was listed. The available actions are listed when this is omitted.
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+pub struct CodeActionTool {
+    project: Entity<Project>,
+    action_log: Entity<ActionLog>,
+}
+
+impl CodeActionTool {
+    pub fn new(project: Entity<Project>, action_log: Entity<ActionLog>) -> Self {
+        Self {
+            project,
+            action_log,
+        }
+    }
+}
+
+impl AgentTool for CodeActionTool {
+    type Input = CodeActionToolInput;
+    type Output = String;
+
+    fn name() -> &'static str {
+        "code_action"
+    }
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Edit
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        match input {
+            Ok(CodeActionToolInput {
+                action: Some(action),
+                ..
+            }) => format!("Apply \"{action}\"").into(),
+            Ok(input) => format!("List code actions in `{}`", input.path).into(),
+            Err(_) => "Code actions".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: Self::Input,
+        event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String>> {
+        cx.spawn(async move |cx| {
+            let (buffer, abs_path) = language_server_edits::open_buffer(
+                &input.path,
+                &self.project,
+                &self.action_log,
+                cx,
+            )
+            .await?;
+            let range = buffer.read_with(cx, |buffer, _| {
+                let row = input
+                    .line
+                    .checked_sub(1)
+                    .filter(|row| *row <= buffer.max_point().row)
+                    .with_context(|| format!("Line {} is out of range", input.line))?;
+                anyhow::Ok(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+            })??;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(vec![acp::ToolCallLocation {
+                    path: abs_path,
+                    line: Some(range.start.row),
+                    meta: None,
+                }]),
+                ..Default::default()
+            });
+            let actions = self
+                .project
+                .update(cx, |project, cx| {
+                    project.code_actions(&buffer, range, None, cx)
+                })?
+                .await?
+                .context("The file has no language server offering code actions")?;
+            let titles = actions
+                .iter()
+                .map(|action| action.lsp_action.title())
+                .collect::<Vec<_>>();
+
+            let Some(title) = input.action else {
+                if titles.is_empty() {
+                    return Ok(format!("No code actions are available on line {}", input.line));
+                }
+                return Ok(format!(
+                    "Code actions available on line {}:\n{}",
+                    input.line,
+                    titles
+                        .iter()
+                        .map(|title| format!("- {title}"))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ));
+            };
+            let Some(action) = actions
+                .iter()
+                .find(|action| action.lsp_action.title() == title)
+                .cloned()
+            else {
+                bail!(
+                    "No code action named \"{title}\" is available on line {}; list the actions again",
+                    input.line
+                );
+            };
+
+            cx.update(|cx| event_stream.authorize(format!("Apply \"{title}\""), cx))?
+                .await?;
+            let transaction = self
+                .project
+                .update(cx, |project, cx| {
+                    project.apply_code_action(buffer, action, true, cx)
+                })?
+                .await
+                .with_context(|| format!("Applying \"{title}\""))?;
+            let files =
+                language_server_edits::track_and_save(transaction, &self.project, &self.action_log, cx)
+                    .await?;
+            if files.is_empty() {
+                return Ok(format!("Applied \"{title}\", which didn't change any files"));
+            }
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(files.iter().map(|file| file.location()).collect()),
+                content: Some(files.iter().map(|file| file.diff()).collect()),
+                ..Default::default()
+            });
+            Ok(format!(
+                "Applied \"{title}\" to {}",
+                language_server_edits::describe(&files)
+            ))
+        })
+    }
+}
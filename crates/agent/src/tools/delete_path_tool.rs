@@ -1,6 +1,6 @@
 use crate::{AgentTool, ToolCallEventStream};
 use action_log::ActionLog;
-use agent_client_protocol::ToolKind;
+use agent_client_protocol::{self as acp, ToolKind};
 use anyhow::{Context as _, Result, anyhow};
 use futures::{SinkExt, StreamExt, channel::mpsc};
 use gpui::{App, AppContext, Entity, SharedString, Task};
@@ -67,7 +67,7 @@ impl AgentTool for DeletePathTool {
     fn run(
         self: Arc<Self>,
         input: Self::Input,
-        _event_stream: ToolCallEventStream,
+        event_stream: ToolCallEventStream,
         cx: &mut App,
     ) -> Task<Result<Self::Output>> {
         let path = input.path;
@@ -87,6 +87,18 @@ impl AgentTool for DeletePathTool {
             )));
         };
 
+        if let Some(abs_path) = self.project.read(cx).absolute_path(&project_path, cx) {
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(vec![acp::ToolCallLocation {
+                    path: abs_path,
+                    line: None,
+                    meta: None,
+                }]),
+                ..Default::default()
+            });
+        }
+        let authorize = event_stream.authorize(format!("Delete “`{path}`”"), cx);
+
         let worktree_snapshot = worktree.read(cx).snapshot();
         let (mut paths_tx, mut paths_rx) = mpsc::channel(256);
         cx.background_spawn({
@@ -113,6 +125,8 @@ impl AgentTool for DeletePathTool {
         let project = self.project.clone();
         let action_log = self.action_log.clone();
         cx.spawn(async move |cx| {
+            authorize.await?;
+
             while let Some(path) = paths_rx.next().await {
                 if let Ok(buffer) = project
                     .update(cx, |project, cx| project.open_buffer(path, cx))?
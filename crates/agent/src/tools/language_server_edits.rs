@@ -0,0 +1,116 @@
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Context as _, Result};
+use gpui::{AsyncApp, Entity};
+use language::Buffer;
+use project::{Project, ProjectPath, ProjectTransaction};
+use std::path::PathBuf;
+
+/// A file edited by a language server, with its text before and after the edit.
+pub(super) struct EditedFile {
+    pub abs_path: PathBuf,
+    /// The path starting with the project's root directory, as the model refers to files.
+    pub full_path: PathBuf,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+impl EditedFile {
+    pub fn diff(&self) -> acp::ToolCallContent {
+        acp::ToolCallContent::Diff {
+            diff: acp::Diff {
+                path: self.abs_path.clone(),
+                old_text: Some(self.old_text.clone()),
+                new_text: self.new_text.clone(),
+                meta: None,
+            },
+        }
+    }
+
+    pub fn location(&self) -> acp::ToolCallLocation {
+        acp::ToolCallLocation {
+            path: self.abs_path.clone(),
+            line: None,
+            meta: None,
+        }
+    }
+}
+
+/// Opens the file for a language server to act on, tracking it in the action log, which also
+/// starts the language servers of the file.
+pub(super) async fn open_buffer(
+    path: &str,
+    project: &Entity<Project>,
+    action_log: &Entity<ActionLog>,
+    cx: &mut AsyncApp,
+) -> Result<(Entity<Buffer>, PathBuf)> {
+    let project_path = project
+        .read_with(cx, |project, cx| project.find_project_path(path, cx))?
+        .with_context(|| format!("{path} isn't in the project"))?;
+    let abs_path = project
+        .read_with(cx, |project, cx| project.absolute_path(&project_path, cx))?
+        .with_context(|| format!("{path} isn't in the project"))?;
+    let buffer = project
+        .update(cx, |project, cx| project.open_buffer(project_path, cx))?
+        .await
+        .with_context(|| format!("Opening {path}"))?;
+    action_log.update(cx, |log, cx| log.buffer_read(buffer.clone(), cx))?;
+    Ok((buffer, abs_path))
+}
+
+/// Records the edits a language server made in the action log, so that they can be reviewed
+/// and rejected like the agent's own edits, and saves the edited files.
+pub(super) async fn track_and_save(
+    transaction: ProjectTransaction,
+    project: &Entity<Project>,
+    action_log: &Entity<ActionLog>,
+    cx: &mut AsyncApp,
+) -> Result<Vec<EditedFile>> {
+    let mut files = Vec::new();
+    for (buffer, transaction) in transaction.0 {
+        // The action log takes edits it isn't told about for the user's, so undo the edit and
+        // make it again as the agent.
+        let new_text = buffer.update(cx, |buffer, cx| {
+            let new_text = buffer.text();
+            buffer.undo_transaction(transaction.id, cx);
+            new_text
+        })?;
+        action_log.update(cx, |log, cx| log.buffer_read(buffer.clone(), cx))?;
+        let (old_text, diff) = buffer.read_with(cx, |buffer, cx| {
+            (buffer.text(), buffer.diff(new_text.clone(), cx))
+        })?;
+        let diff = diff.await;
+        buffer.update(cx, |buffer, cx| buffer.apply_diff(diff, cx))?;
+        action_log.update(cx, |log, cx| log.buffer_edited(buffer.clone(), cx))?;
+
+        let (abs_path, full_path) = project
+            .read_with(cx, |project, cx| {
+                let file = buffer.read(cx).file()?;
+                let abs_path =
+                    project.absolute_path(&ProjectPath::from_file(file.as_ref(), cx), cx)?;
+                Some((abs_path, file.full_path(cx)))
+            })?
+            .context("A language server edited a file outside of the project")?;
+        project
+            .update(cx, |project, cx| project.save_buffer(buffer, cx))?
+            .await
+            .with_context(|| format!("Saving {}", full_path.display()))?;
+        files.push(EditedFile {
+            abs_path,
+            full_path,
+            old_text,
+            new_text,
+        });
+    }
+    files.sort_by(|a, b| a.full_path.cmp(&b.full_path));
+    Ok(files)
+}
+
+/// Describes the edited files for the model.
+pub(super) fn describe(files: &[EditedFile]) -> String {
+    files
+        .iter()
+        .map(|file| file.full_path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
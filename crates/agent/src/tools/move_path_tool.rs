@@ -1,5 +1,5 @@
 use crate::{AgentTool, ToolCallEventStream};
-use agent_client_protocol::ToolKind;
+use agent_client_protocol::{self as acp, ToolKind};
 use anyhow::{Context as _, Result, anyhow};
 use gpui::{App, AppContext, Entity, SharedString, Task};
 use project::Project;
@@ -66,21 +66,7 @@ impl AgentTool for MovePathTool {
         _cx: &mut App,
     ) -> SharedString {
         if let Ok(input) = input {
-            let src = MarkdownInlineCode(&input.source_path);
-            let dest = MarkdownInlineCode(&input.destination_path);
-            let src_path = Path::new(&input.source_path);
-            let dest_path = Path::new(&input.destination_path);
-
-            match dest_path
-                .file_name()
-                .and_then(|os_str| os_str.to_os_string().into_string().ok())
-            {
-                Some(filename) if src_path.parent() == dest_path.parent() => {
-                    let filename = MarkdownInlineCode(&filename);
-                    format!("Rename {src} to {filename}").into()
-                }
-                _ => format!("Move {src} to {dest}").into(),
-            }
+            move_title(&input.source_path, &input.destination_path)
         } else {
             "Move path".into()
         }
@@ -89,29 +75,56 @@ impl AgentTool for MovePathTool {
     fn run(
         self: Arc<Self>,
         input: Self::Input,
-        _event_stream: ToolCallEventStream,
+        event_stream: ToolCallEventStream,
         cx: &mut App,
     ) -> Task<Result<Self::Output>> {
-        let rename_task = self.project.update(cx, |project, cx| {
-            match project
-                .find_project_path(&input.source_path, cx)
-                .and_then(|project_path| project.entry_for_path(&project_path, cx))
-            {
-                Some(entity) => match project.find_project_path(&input.destination_path, cx) {
-                    Some(project_path) => project.rename_entry(entity.id, project_path, cx),
+        // The source comes first and the destination second, for the thread to show both.
+        let locations = {
+            let project = self.project.read(cx);
+            [&input.source_path, &input.destination_path]
+                .into_iter()
+                .filter_map(|path| {
+                    let project_path = project.find_project_path(path, cx)?;
+                    Some(acp::ToolCallLocation {
+                        path: project.absolute_path(&project_path, cx)?,
+                        line: None,
+                        meta: None,
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        if locations.len() == 2 {
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(locations),
+                ..Default::default()
+            });
+        }
+        let authorize =
+            event_stream.authorize(move_title(&input.source_path, &input.destination_path), cx);
+
+        let project = self.project.clone();
+        cx.spawn(async move |cx| {
+            authorize.await?;
+
+            let rename_task = project.update(cx, |project, cx| {
+                match project
+                    .find_project_path(&input.source_path, cx)
+                    .and_then(|project_path| project.entry_for_path(&project_path, cx))
+                {
+                    Some(entity) => match project.find_project_path(&input.destination_path, cx) {
+                        Some(project_path) => project.rename_entry(entity.id, project_path, cx),
+                        None => Task::ready(Err(anyhow!(
+                            "Destination path {} was outside the project.",
+                            input.destination_path
+                        ))),
+                    },
                     None => Task::ready(Err(anyhow!(
-                        "Destination path {} was outside the project.",
-                        input.destination_path
+                        "Source path {} was not found in the project.",
+                        input.source_path
                     ))),
-                },
-                None => Task::ready(Err(anyhow!(
-                    "Source path {} was not found in the project.",
-                    input.source_path
-                ))),
-            }
-        });
+                }
+            })?;
 
-        cx.background_spawn(async move {
             let _ = rename_task.await.with_context(|| {
                 format!("Moving {} to {}", input.source_path, input.destination_path)
             })?;
@@ -122,3 +135,21 @@ impl AgentTool for MovePathTool {
         })
     }
 }
+
+fn move_title(source_path: &str, destination_path: &str) -> SharedString {
+    let src = MarkdownInlineCode(source_path);
+    let dest = MarkdownInlineCode(destination_path);
+    let src_path = Path::new(source_path);
+    let dest_path = Path::new(destination_path);
+
+    match dest_path
+        .file_name()
+        .and_then(|os_str| os_str.to_os_string().into_string().ok())
+    {
+        Some(filename) if src_path.parent() == dest_path.parent() => {
+            let filename = MarkdownInlineCode(&filename);
+            format!("Rename {src} to {filename}").into()
+        }
+        _ => format!("Move {src} to {dest}").into(),
+    }
+}
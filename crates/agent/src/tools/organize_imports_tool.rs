@@ -0,0 +1,118 @@
+use super::language_server_edits;
+use crate::{AgentTool, ToolCallEventStream};
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Context as _, Result};
+use collections::HashSet;
+use gpui::{App, Entity, SharedString, Task};
+use lsp::CodeActionKind;
+use project::Project;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Sorts the imports of a file and removes the unused ones, using the language server of the file.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OrganizeImportsToolInput {
+    /// The path of the file, starting with one of the project's root directories.
+    pub path: String,
+}
+
+pub struct OrganizeImportsTool {
+    project: Entity<Project>,
+    action_log: Entity<ActionLog>,
+}
+
+impl OrganizeImportsTool {
+    pub fn new(project: Entity<Project>, action_log: Entity<ActionLog>) -> Self {
+        Self {
+            project,
+            action_log,
+        }
+    }
+}
+
+impl AgentTool for OrganizeImportsTool {
+    type Input = OrganizeImportsToolInput;
+    type Output = String;
+
+    fn name() -> &'static str {
+        "organize_imports"
+    }
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Edit
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        match input {
+            Ok(input) => format!("Organize imports in `{}`", input.path).into(),
+            Err(_) => "Organize imports".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: Self::Input,
+        event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String>> {
+        cx.spawn(async move |cx| {
+            let (buffer, abs_path) = language_server_edits::open_buffer(
+                &input.path,
+                &self.project,
+                &self.action_log,
+                cx,
+            )
+            .await?;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(vec![acp::ToolCallLocation {
+                    path: abs_path,
+                    line: None,
+                    meta: None,
+                }]),
+                ..Default::default()
+            });
+            cx.update(|cx| {
+                event_stream.authorize(format!("Organize imports in `{}`", input.path), cx)
+            })?
+            .await?;
+
+            let transaction = self
+                .project
+                .update(cx, |project, cx| {
+                    project.apply_code_action_kind(
+                        HashSet::from_iter([buffer]),
+                        CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+                        true,
+                        cx,
+                    )
+                })?
+                .await
+                .with_context(|| format!("Organizing the imports of {}", input.path))?;
+            if transaction.0.is_empty() {
+                return Ok(format!(
+                    "The imports of {} were already organized, or its language server can't organize them",
+                    input.path
+                ));
+            }
+            let files =
+                language_server_edits::track_and_save(transaction, &self.project, &self.action_log, cx)
+                    .await?;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                content: Some(files.iter().map(|file| file.diff()).collect()),
+                ..Default::default()
+            });
+            Ok(format!(
+                "Organized the imports in {}",
+                language_server_edits::describe(&files)
+            ))
+        })
+    }
+}
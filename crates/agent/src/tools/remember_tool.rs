@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use agent_client_protocol as acp;
+use anyhow::Result;
+use gpui::{App, Entity, SharedString, Task};
+use project::Project;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{AgentTool, ProjectMemory, ToolCallEventStream};
+
+/// Saves a fact about the project that should be known in every future conversation, such as "we use pnpm, not npm".
+/// Only use this tool when the user asks you to remember something, or when you learn a lasting convention of the project that you would otherwise get wrong again.
+/// Don't save facts about the current task.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RememberToolInput {
+    /// The fact to remember, as a single short sentence.
+    fact: String,
+}
+
+pub struct RememberTool {
+    project: Entity<Project>,
+}
+
+impl RememberTool {
+    pub fn new(project: Entity<Project>) -> Self {
+        Self { project }
+    }
+}
+
+impl AgentTool for RememberTool {
+    type Input = RememberToolInput;
+    type Output = String;
+
+    fn name() -> &'static str {
+        "remember"
+    }
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Other
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        match input {
+            Ok(input) => format!("Remember: {}", input.fact).into(),
+            Err(_) => "Remember".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: Self::Input,
+        _event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String>> {
+        let memory = ProjectMemory::load(&self.project, cx);
+        cx.spawn(async move |cx| {
+            let memory = memory.await;
+            memory
+                .update(cx, |memory, cx| memory.add(input.fact, cx))?
+                .await;
+            Ok("Saved the fact to the project memory.".to_string())
+        })
+    }
+}
@@ -0,0 +1,287 @@
+use super::language_server_edits;
+use crate::{AgentTool, ToolCallEventStream};
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Context as _, Result, bail};
+use gpui::{App, Entity, SharedString, Task};
+use language::{Buffer, Point};
+use project::Project;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Renames a symbol, such as a function, type, variable or field, everywhere it's used, using the language server of the file.
+///
+/// Prefer this tool over editing files to rename a symbol: the language server only renames references to the symbol, across all the files of the project.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RenameSymbolToolInput {
+    /// The path of a file the symbol appears in, starting with one of the project's root directories.
+    pub path: String,
+    /// The one-based number of a line the symbol appears on.
+    pub line: u32,
+    /// The current name of the symbol, as it appears on that line.
+    pub symbol: String,
+    /// The new name of the symbol.
+    pub new_name: String,
+}
+
+pub struct RenameSymbolTool {
+    project: Entity<Project>,
+    action_log: Entity<ActionLog>,
+}
+
+impl RenameSymbolTool {
+    pub fn new(project: Entity<Project>, action_log: Entity<ActionLog>) -> Self {
+        Self {
+            project,
+            action_log,
+        }
+    }
+}
+
+impl AgentTool for RenameSymbolTool {
+    type Input = RenameSymbolToolInput;
+    type Output = String;
+
+    fn name() -> &'static str {
+        "rename_symbol"
+    }
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Edit
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        match input {
+            Ok(input) => format!("Rename `{}` to `{}`", input.symbol, input.new_name).into(),
+            Err(_) => "Rename symbol".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: Self::Input,
+        event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String>> {
+        cx.spawn(async move |cx| {
+            let (buffer, abs_path) = language_server_edits::open_buffer(
+                &input.path,
+                &self.project,
+                &self.action_log,
+                cx,
+            )
+            .await?;
+            let position = buffer.read_with(cx, |buffer, _| {
+                symbol_position(buffer, input.line, &input.symbol)
+            })??;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(vec![acp::ToolCallLocation {
+                    path: abs_path,
+                    line: Some(position.row),
+                    meta: None,
+                }]),
+                ..Default::default()
+            });
+            cx.update(|cx| {
+                event_stream.authorize(
+                    format!("Rename `{}` to `{}`", input.symbol, input.new_name),
+                    cx,
+                )
+            })?
+            .await?;
+
+            let transaction = self
+                .project
+                .update(cx, |project, cx| {
+                    project.perform_rename(buffer, position, input.new_name.clone(), cx)
+                })?
+                .await
+                .with_context(|| format!("Renaming `{}`", input.symbol))?;
+            if transaction.0.is_empty() {
+                bail!(
+                    "The language server didn't rename `{}`; it may not support renaming",
+                    input.symbol
+                );
+            }
+            let files = language_server_edits::track_and_save(
+                transaction,
+                &self.project,
+                &self.action_log,
+                cx,
+            )
+            .await?;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(files.iter().map(|file| file.location()).collect()),
+                content: Some(files.iter().map(|file| file.diff()).collect()),
+                ..Default::default()
+            });
+            Ok(format!(
+                "Renamed `{}` to `{}` in {}",
+                input.symbol,
+                input.new_name,
+                language_server_edits::describe(&files)
+            ))
+        })
+    }
+}
+
+/// The position of the first occurrence of the symbol as a whole word on the one-based line.
+fn symbol_position(buffer: &Buffer, line: u32, symbol: &str) -> Result<Point> {
+    let row = line
+        .checked_sub(1)
+        .filter(|row| *row <= buffer.max_point().row)
+        .with_context(|| format!("Line {line} is out of range"))?;
+    let line_text = buffer
+        .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+        .collect::<String>();
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let column = line_text
+        .match_indices(symbol)
+        .map(|(ix, _)| ix)
+        .find(|&ix| {
+            !line_text[..ix].ends_with(is_word_char)
+                && !line_text[ix + symbol.len()..].starts_with(is_word_char)
+        })
+        .with_context(|| format!("`{symbol}` doesn't appear on line {line}"))?;
+    Ok(Point::new(row, column as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::Fs as _;
+    use futures::StreamExt as _;
+    use gpui::{AppContext as _, TestAppContext};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::path;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            agent_settings::AgentSettings::register(cx);
+            Project::init_settings(cx);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_rename_symbol(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "one.rs": "const ONE: usize = 1;",
+                "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+        language_registry.add(Arc::new(language::Language::new(
+            language::LanguageConfig {
+                name: "Rust".into(),
+                matcher: language::LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        let mut fake_servers = language_registry.register_fake_lsp(
+            "Rust",
+            language::FakeLspAdapter {
+                capabilities: lsp::ServerCapabilities {
+                    rename_provider: Some(lsp::OneOf::Left(true)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let tool = Arc::new(RenameSymbolTool::new(project, action_log.clone()));
+
+        let (event_stream, mut events) = ToolCallEventStream::test();
+        let task = cx.update(|cx| {
+            tool.run(
+                RenameSymbolToolInput {
+                    path: "root/two.rs".into(),
+                    line: 1,
+                    symbol: "ONE".into(),
+                    new_name: "THREE".into(),
+                },
+                event_stream,
+                cx,
+            )
+        });
+
+        let fields = events.expect_update_fields().await;
+        assert_eq!(fields.locations.unwrap()[0].line, Some(0));
+        let fake_server = fake_servers.next().await.unwrap();
+        fake_server.set_request_handler::<lsp::request::Rename, _, _>(|params, _| async move {
+            assert_eq!(
+                params.text_document_position.position,
+                lsp::Position::new(0, 24)
+            );
+            let edit = |start, end| {
+                lsp::TextEdit::new(
+                    lsp::Range::new(lsp::Position::new(0, start), lsp::Position::new(0, end)),
+                    params.new_name.clone(),
+                )
+            };
+            Ok(Some(lsp::WorkspaceEdit {
+                changes: Some(
+                    [
+                        (
+                            lsp::Uri::from_file_path(path!("/root/one.rs")).unwrap(),
+                            vec![edit(6, 9)],
+                        ),
+                        (
+                            lsp::Uri::from_file_path(path!("/root/two.rs")).unwrap(),
+                            vec![edit(24, 27), edit(35, 38)],
+                        ),
+                    ]
+                    .into_iter()
+                    .collect(),
+                ),
+                ..Default::default()
+            }))
+        });
+        let authorization = events.expect_authorization().await;
+        authorization
+            .response
+            .send(acp::PermissionOptionId("allow".into()))
+            .unwrap();
+        let output = task.await.unwrap();
+        assert_eq!(
+            output,
+            "Renamed `ONE` to `THREE` in root/one.rs, root/two.rs"
+        );
+
+        assert_eq!(
+            fs.load(path!("/root/one.rs").as_ref()).await.unwrap(),
+            "const THREE: usize = 1;"
+        );
+        assert_eq!(
+            fs.load(path!("/root/two.rs").as_ref()).await.unwrap(),
+            "const TWO: usize = one::THREE + one::THREE;"
+        );
+        cx.run_until_parked();
+        assert_eq!(
+            action_log.read_with(cx, |log, cx| log.changed_buffers(cx).len()),
+            2,
+            "The renamed files can be reviewed like the agent's own edits"
+        );
+    }
+}
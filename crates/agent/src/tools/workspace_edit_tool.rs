@@ -0,0 +1,467 @@
+use crate::{AgentTool, ToolCallEventStream};
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Context as _, Result, anyhow, bail};
+use futures::future;
+use gpui::{App, AsyncApp, Entity, SharedString, Task};
+use language::Buffer;
+use project::{Project, ProjectPath};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
+use util::ResultExt as _;
+
+/// Edits several files as a single change, such as renaming a symbol everywhere it's used, which the user approves as a whole.
+///
+/// The edits are applied together: if any of them can't be applied, or any of the files can't be saved, none of the files are changed. Files with unsaved changes can't be edited, as saving them would also save the user's changes.
+///
+/// Use this tool instead of several `edit_file` calls when the edits only make sense together.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceEditToolInput {
+    /// A one-line description of the change, such as "Rename `Foo` to `Bar`".
+    pub title: String,
+    /// The edits to make, in order. A file can be edited more than once.
+    pub edits: Vec<FileEdit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FileEdit {
+    /// The path of the file to edit, starting with one of the project's root directories.
+    pub path: String,
+    /// The exact text to replace, which must appear exactly once in the file.
+    pub old_text: String,
+    /// The text to replace it with.
+    pub new_text: String,
+}
+
+pub struct WorkspaceEditTool {
+    project: Entity<Project>,
+    action_log: Entity<ActionLog>,
+}
+
+impl WorkspaceEditTool {
+    pub fn new(project: Entity<Project>, action_log: Entity<ActionLog>) -> Self {
+        Self {
+            project,
+            action_log,
+        }
+    }
+}
+
+/// The edits to one file, with its text before and after them.
+struct FileChange {
+    project_path: ProjectPath,
+    abs_path: PathBuf,
+    buffer: Entity<Buffer>,
+    old_text: String,
+    new_text: String,
+}
+
+impl AgentTool for WorkspaceEditTool {
+    type Input = WorkspaceEditToolInput;
+    type Output = String;
+
+    fn name() -> &'static str {
+        "workspace_edit"
+    }
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Edit
+    }
+
+    fn initial_title(
+        &self,
+        input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        match input {
+            Ok(input) => input.title.into(),
+            Err(_) => "Edit files".into(),
+        }
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: Self::Input,
+        event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String>> {
+        cx.spawn(async move |cx| {
+            let changes = self.prepare_changes(input.edits, cx).await?;
+
+            event_stream.update_fields(acp::ToolCallUpdateFields {
+                locations: Some(
+                    changes
+                        .iter()
+                        .map(|change| acp::ToolCallLocation {
+                            path: change.abs_path.clone(),
+                            line: None,
+                            meta: None,
+                        })
+                        .collect(),
+                ),
+                content: Some(
+                    changes
+                        .iter()
+                        .map(|change| acp::ToolCallContent::Diff {
+                            diff: acp::Diff {
+                                path: change.abs_path.clone(),
+                                old_text: Some(change.old_text.clone()),
+                                new_text: change.new_text.clone(),
+                                meta: None,
+                            },
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            });
+            cx.update(|cx| event_stream.authorize(input.title, cx))?
+                .await?;
+
+            self.apply_changes(&changes, cx).await?;
+            Ok(format!(
+                "Edited {}",
+                changes
+                    .iter()
+                    .map(|change| change.abs_path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })
+    }
+}
+
+impl WorkspaceEditTool {
+    /// Opens the edited files and applies the edits to their text, failing if any of them
+    /// doesn't apply.
+    async fn prepare_changes(
+        &self,
+        edits: Vec<FileEdit>,
+        cx: &mut AsyncApp,
+    ) -> Result<Vec<FileChange>> {
+        let mut changes: Vec<FileChange> = Vec::new();
+        for edit in edits {
+            let project_path = self
+                .project
+                .read_with(cx, |project, cx| project.find_project_path(&edit.path, cx))?
+                .with_context(|| format!("{} isn't in the project", edit.path))?;
+            let ix = match changes
+                .iter()
+                .position(|change| change.project_path == project_path)
+            {
+                Some(ix) => ix,
+                None => {
+                    let buffer = self
+                        .project
+                        .update(cx, |project, cx| {
+                            project.open_buffer(project_path.clone(), cx)
+                        })?
+                        .await
+                        .with_context(|| format!("Opening {}", edit.path))?;
+                    let abs_path = self
+                        .project
+                        .read_with(cx, |project, cx| project.absolute_path(&project_path, cx))?
+                        .with_context(|| format!("{} isn't in the project", edit.path))?;
+                    let old_text = buffer.read_with(cx, |buffer, _| buffer.text())?;
+                    self.action_log.update(cx, |log, cx| {
+                        log.buffer_read(buffer.clone(), cx);
+                    })?;
+                    changes.push(FileChange {
+                        project_path,
+                        abs_path,
+                        buffer,
+                        new_text: old_text.clone(),
+                        old_text,
+                    });
+                    changes.len() - 1
+                }
+            };
+            let change = &mut changes[ix];
+            change.new_text =
+                replace_once(&change.new_text, &edit.old_text, &edit.new_text, &edit.path)?;
+        }
+        Ok(changes)
+    }
+
+    /// Applies the changes and saves the files, restoring all of them if any change can't be
+    /// applied or any file can't be saved.
+    async fn apply_changes(&self, changes: &[FileChange], cx: &mut AsyncApp) -> Result<()> {
+        for change in changes {
+            let (current_text, is_dirty) = change
+                .buffer
+                .read_with(cx, |buffer, _| (buffer.text(), buffer.is_dirty()))?;
+            if is_dirty {
+                bail!(
+                    "{} has unsaved changes; ask the user to save or discard them first",
+                    change.abs_path.display()
+                );
+            }
+            if current_text != change.old_text {
+                bail!(
+                    "{} changed while the edits were waiting for approval; read it again",
+                    change.abs_path.display()
+                );
+            }
+        }
+
+        let mut applied = Vec::new();
+        let result = async {
+            for change in changes {
+                set_text(&change.buffer, &change.new_text, cx)
+                    .await
+                    .with_context(|| format!("Editing {}", change.abs_path.display()))?;
+                applied.push(change);
+                self.action_log.update(cx, |log, cx| {
+                    log.buffer_edited(change.buffer.clone(), cx);
+                })?;
+            }
+
+            let saves = self.project.update(cx, |project, cx| {
+                changes
+                    .iter()
+                    .map(|change| project.save_buffer(change.buffer.clone(), cx))
+                    .collect::<Vec<_>>()
+            })?;
+            future::join_all(saves)
+                .await
+                .into_iter()
+                .zip(changes)
+                .try_for_each(|(result, change)| {
+                    result.with_context(|| format!("Saving {}", change.abs_path.display()))
+                })
+        }
+        .await;
+        let Err(error) = result else {
+            return Ok(());
+        };
+
+        // The files weren't dirty, so saving their old text restores them on disk. This undoes
+        // the agent's edits rather than being one, so it isn't reported to the action log.
+        for change in applied {
+            if set_text(&change.buffer, &change.old_text, cx)
+                .await
+                .log_err()
+                .is_none()
+            {
+                continue;
+            }
+            if let Ok(save) = self.project.update(cx, |project, cx| {
+                project.save_buffer(change.buffer.clone(), cx)
+            }) {
+                save.await.log_err();
+            }
+        }
+        Err(error.context("None of the files were changed"))
+    }
+}
+
+async fn set_text(buffer: &Entity<Buffer>, text: &str, cx: &mut AsyncApp) -> Result<()> {
+    let diff = buffer
+        .read_with(cx, |buffer, cx| buffer.diff(text.to_string(), cx))?
+        .await;
+    buffer.update(cx, |buffer, cx| buffer.apply_diff(diff, cx))?;
+    Ok(())
+}
+
+fn replace_once(text: &str, old_text: &str, new_text: &str, path: &str) -> Result<String> {
+    if old_text.is_empty() {
+        bail!("An edit of {path} has no old text to replace");
+    }
+    let mut matches = text.match_indices(old_text).map(|(ix, _)| ix);
+    let ix = matches.next().ok_or_else(|| {
+        anyhow!("The old text of an edit wasn't found in {path}; read the file again")
+    })?;
+    if matches.next().is_some() {
+        bail!("The old text of an edit matches more than one position in {path}; extend it");
+    }
+    Ok(format!(
+        "{}{new_text}{}",
+        &text[..ix],
+        &text[ix + old_text.len()..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::Fs as _;
+    use gpui::{AppContext as _, TestAppContext};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::path;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            language::init(cx);
+            agent_settings::AgentSettings::register(cx);
+            Project::init_settings(cx);
+        });
+    }
+
+    fn edit(path: &str, old_text: &str, new_text: &str) -> FileEdit {
+        FileEdit {
+            path: path.into(),
+            old_text: old_text.into(),
+            new_text: new_text.into(),
+        }
+    }
+
+    #[gpui::test]
+    async fn test_workspace_edit_is_approved_once(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "a.rs": "fn foo() {}\n",
+                "b.rs": "fn main() { foo(); foo(); }\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let tool = Arc::new(WorkspaceEditTool::new(project, action_log));
+
+        let (event_stream, mut events) = ToolCallEventStream::test();
+        let task = cx.update(|cx| {
+            tool.run(
+                WorkspaceEditToolInput {
+                    title: "Rename `foo` to `bar`".into(),
+                    edits: vec![
+                        edit("root/a.rs", "fn foo()", "fn bar()"),
+                        edit("root/b.rs", "{ foo();", "{ bar();"),
+                        edit("root/b.rs", " foo(); }", " bar(); }"),
+                    ],
+                },
+                event_stream,
+                cx,
+            )
+        });
+
+        let fields = events.expect_update_fields().await;
+        assert_eq!(fields.content.map(|content| content.len()), Some(2));
+        let authorization = events.expect_authorization().await;
+        authorization
+            .response
+            .send(acp::PermissionOptionId("allow".into()))
+            .unwrap();
+        task.await.unwrap();
+
+        assert_eq!(
+            fs.load(path!("/root/a.rs").as_ref()).await.unwrap(),
+            "fn bar() {}\n"
+        );
+        assert_eq!(
+            fs.load(path!("/root/b.rs").as_ref()).await.unwrap(),
+            "fn main() { bar(); bar(); }\n"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_workspace_edit_is_all_or_nothing(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "a.rs": "fn foo() {}\n",
+                "b.rs": "fn main() { foo(); foo(); }\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let tool = Arc::new(WorkspaceEditTool::new(project, action_log));
+
+        // The second edit is ambiguous, so the first one isn't applied either.
+        let (event_stream, _events) = ToolCallEventStream::test();
+        let result = cx
+            .update(|cx| {
+                tool.run(
+                    WorkspaceEditToolInput {
+                        title: "Rename `foo` to `bar`".into(),
+                        edits: vec![
+                            edit("root/a.rs", "fn foo()", "fn bar()"),
+                            edit("root/b.rs", "foo();", "bar();"),
+                        ],
+                    },
+                    event_stream,
+                    cx,
+                )
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            fs.load(path!("/root/a.rs").as_ref()).await.unwrap(),
+            "fn foo() {}\n"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_workspace_edit_is_rolled_back_when_a_save_fails(cx: &mut TestAppContext) {
+        init_test(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({
+                "a.rs": "fn foo() {}\n",
+                "b.rs": "fn main() { foo(); }\n",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [path!("/root").as_ref()], cx).await;
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let tool = Arc::new(WorkspaceEditTool::new(project.clone(), action_log));
+
+        let (event_stream, mut events) = ToolCallEventStream::test();
+        let task = cx.update(|cx| {
+            tool.run(
+                WorkspaceEditToolInput {
+                    title: "Rename `foo` to `bar`".into(),
+                    edits: vec![
+                        edit("root/a.rs", "fn foo()", "fn bar()"),
+                        edit("root/b.rs", "foo();", "bar();"),
+                    ],
+                },
+                event_stream,
+                cx,
+            )
+        });
+        events.expect_update_fields().await;
+        let authorization = events.expect_authorization().await;
+
+        // Replace b.rs with a directory behind the project's back, so that it can't be saved
+        // after a.rs is.
+        fs.pause_events();
+        fs.remove_file(path!("/root/b.rs").as_ref(), Default::default())
+            .await
+            .unwrap();
+        fs.create_dir(path!("/root/b.rs").as_ref()).await.unwrap();
+        authorization
+            .response
+            .send(acp::PermissionOptionId("allow".into()))
+            .unwrap();
+        assert!(task.await.is_err());
+
+        assert_eq!(
+            fs.load(path!("/root/a.rs").as_ref()).await.unwrap(),
+            "fn foo() {}\n"
+        );
+        for (path, text) in [
+            ("root/a.rs", "fn foo() {}\n"),
+            ("root/b.rs", "fn main() { foo(); }\n"),
+        ] {
+            let buffer = project
+                .update(cx, |project, cx| {
+                    let project_path = project.find_project_path(path, cx).unwrap();
+                    project.open_buffer(project_path, cx)
+                })
+                .await
+                .unwrap();
+            buffer.read_with(cx, |buffer, _| assert_eq!(buffer.text(), text));
+        }
+    }
+}
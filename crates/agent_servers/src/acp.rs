@@ -5,6 +5,7 @@ use agent_client_protocol::{self as acp, Agent as _, ErrorCode};
 use anyhow::anyhow;
 use collections::HashMap;
 use futures::AsyncBufReadExt as _;
+use futures::FutureExt as _;
 use futures::io::BufReader;
 use project::Project;
 use project::agent_server_store::AgentServerCommand;
@@ -12,14 +13,21 @@ use serde::Deserialize;
 use util::ResultExt as _;
 
 use std::path::PathBuf;
+use std::time::Duration;
 use std::{any::Any, cell::RefCell};
-use std::{path::Path, rc::Rc};
+use std::{
+    path::Path,
+    rc::{Rc, Weak},
+};
 use thiserror::Error;
 
 use anyhow::{Context as _, Result};
-use gpui::{App, AppContext as _, AsyncApp, Entity, SharedString, Task, WeakEntity};
+use gpui::{
+    App, AppContext as _, AsyncApp, BackgroundExecutor, Entity, SharedString, Subscription, Task,
+    WeakEntity,
+};
 
-use acp_thread::{AcpThread, AuthRequired, LoadError, TerminalProviderEvent};
+use acp_thread::{AcpThread, AuthRequired, FileSystemAccess, LoadError, TerminalProviderEvent};
 use terminal::TerminalBuilder;
 use terminal::terminal_settings::{AlternateScroll, CursorShape};
 
@@ -27,6 +35,17 @@ use terminal::terminal_settings::{AlternateScroll, CursorShape};
 #[error("Unsupported version")]
 pub struct UnsupportedVersion;
 
+/// How long an agent gets to exit on its own once its connection is closed, so that it
+/// can flush its state, before it is killed.
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(2);
+/// The grace period when Zed quits, which must leave time to kill the agent within the
+/// time the app gives quit handlers.
+const QUIT_GRACE_PERIOD: Duration = Duration::from_millis(50);
+
+/// The extension notification telling an agent about files changed outside of its tool calls.
+/// Only sent to agents that set `"file_changes": true` in the `_meta` of their capabilities.
+const FILE_CHANGES_METHOD: &str = "zed.dev/file_changes";
+
 pub struct AcpConnection {
     server_name: SharedString,
     connection: Rc<acp::ClientSideConnection>,
@@ -37,10 +56,15 @@ pub struct AcpConnection {
     root_dir: PathBuf,
     // NB: Don't move this into the wait_task, since we need to ensure the process is
     // killed on drop (setting kill_on_drop on the command seems to not always work).
-    child: smol::process::Child,
-    _io_task: Task<Result<(), acp::Error>>,
+    // It's taken by whichever of dropping the connection and quitting the app shuts it down first.
+    child: Rc<RefCell<Option<smol::process::Child>>>,
+    background_executor: BackgroundExecutor,
+    // Dropping the io task closes the agent's stdin, which asks it to exit. It's dropped by
+    // whichever of dropping the connection and quitting the app shuts the agent down first.
+    io_task: Rc<RefCell<Option<Task<Result<(), acp::Error>>>>>,
     _wait_task: Task<Result<()>>,
     _stderr_task: Task<Result<()>>,
+    _quit_subscription: Subscription,
 }
 
 pub struct AcpSession {
@@ -54,6 +78,7 @@ pub async fn connect(
     server_name: SharedString,
     command: AgentServerCommand,
     root_dir: &Path,
+    file_system_access: FileSystemAccess,
     default_mode: Option<acp::SessionModeId>,
     is_remote: bool,
     cx: &mut AsyncApp,
@@ -62,6 +87,7 @@ pub async fn connect(
         server_name,
         command.clone(),
         root_dir,
+        file_system_access,
         default_mode,
         is_remote,
         cx,
@@ -77,6 +103,7 @@ impl AcpConnection {
         server_name: SharedString,
         command: AgentServerCommand,
         root_dir: &Path,
+        file_system_access: FileSystemAccess,
         default_mode: Option<acp::SessionModeId>,
         is_remote: bool,
         cx: &mut AsyncApp,
@@ -91,7 +118,17 @@ impl AcpConnection {
         if !is_remote {
             child.current_dir(root_dir);
         }
-        let mut child = child.spawn()?;
+        let mut child = child.spawn().map_err(|error| {
+            if error.kind() == std::io::ErrorKind::NotFound {
+                anyhow!(
+                    "Could not find `{}` to launch {server_name}. Install it, or set the agent's \
+                    `command` under `agent_servers` in your settings.",
+                    command.path.display()
+                )
+            } else {
+                anyhow!("Failed to launch `{}`: {error}", command.path.display())
+            }
+        })?;
 
         let stdout = child.stdout.take().context("Failed to take stdout")?;
         let stdin = child.stdin.take().context("Failed to take stdin")?;
@@ -116,7 +153,7 @@ impl AcpConnection {
             }
         });
 
-        let io_task = cx.background_spawn(io_task);
+        let io_task = Rc::new(RefCell::new(Some(cx.background_spawn(io_task))));
 
         let stderr_task = cx.background_spawn(async move {
             let mut stderr = BufReader::new(stderr);
@@ -130,12 +167,18 @@ impl AcpConnection {
             Ok(())
         });
 
+        let pid = child.id();
         let wait_task = cx.spawn({
             let sessions = sessions.clone();
             let status_fut = child.status();
             async move |cx| {
                 let status = status_fut.await?;
 
+                cx.update(|cx| {
+                    AcpConnectionRegistry::default_global(cx)
+                        .update(cx, |registry, cx| registry.unregister_process(pid, cx));
+                })?;
+
                 for session in sessions.borrow().values() {
                     session
                         .thread
@@ -161,11 +204,7 @@ impl AcpConnection {
             .initialize(acp::InitializeRequest {
                 protocol_version: acp::VERSION,
                 client_capabilities: acp::ClientCapabilities {
-                    fs: acp::FileSystemCapability {
-                        read_text_file: true,
-                        write_text_file: true,
-                        meta: None,
-                    },
+                    fs: file_system_access.capability(),
                     terminal: true,
                     meta: Some(serde_json::json!({
                         // Experimental: Allow for rendering terminal output from the agents
@@ -180,6 +219,35 @@ impl AcpConnection {
             return Err(UnsupportedVersion.into());
         }
 
+        let child = Rc::new(RefCell::new(Some(child)));
+        cx.update(|cx| {
+            AcpConnectionRegistry::default_global(cx).update(cx, |registry, cx| {
+                registry.register_process(
+                    server_name.clone(),
+                    root_dir.to_owned(),
+                    pid,
+                    Rc::downgrade(&child) as Weak<dyn Any>,
+                    cx,
+                )
+            });
+        })?;
+        let quit_subscription = cx.update(|cx| {
+            cx.on_app_quit({
+                let io_task = io_task.clone();
+                let child = child.clone();
+                move |cx| {
+                    io_task.borrow_mut().take();
+                    let child = child.borrow_mut().take();
+                    let executor = cx.background_executor().clone();
+                    async move {
+                        if let Some(child) = child {
+                            shut_down(child, QUIT_GRACE_PERIOD, executor).await;
+                        }
+                    }
+                }
+            })
+        })?;
+
         Ok(Self {
             auth_methods: response.auth_methods,
             root_dir: root_dir.to_owned(),
@@ -188,9 +256,11 @@ impl AcpConnection {
             sessions,
             agent_capabilities: response.agent_capabilities,
             default_mode,
-            _io_task: io_task,
+            background_executor: cx.background_executor().clone(),
+            io_task,
             _wait_task: wait_task,
             _stderr_task: stderr_task,
+            _quit_subscription: quit_subscription,
             child,
         })
     }
@@ -202,12 +272,59 @@ impl AcpConnection {
     pub fn root_dir(&self) -> &Path {
         &self.root_dir
     }
+
+    /// The id of the agent process, until it's shut down.
+    pub fn pid(&self) -> Option<u32> {
+        self.child.borrow().as_ref().map(|child| child.id())
+    }
 }
 
 impl Drop for AcpConnection {
     fn drop(&mut self) {
-        // See the comment on the child field.
-        self.child.kill().log_err();
+        // See the comments on the io_task and child fields.
+        self.io_task.borrow_mut().take();
+        if let Some(child) = self.child.borrow_mut().take() {
+            self.background_executor
+                .spawn(shut_down(
+                    child,
+                    SHUTDOWN_GRACE_PERIOD,
+                    self.background_executor.clone(),
+                ))
+                .detach();
+        }
+    }
+}
+
+/// Waits up to `grace_period` for the agent to exit on its own, then kills it. The agent is
+/// also killed if the future is dropped before then, e.g. when the app quits.
+async fn shut_down(
+    child: smol::process::Child,
+    grace_period: Duration,
+    executor: BackgroundExecutor,
+) {
+    let mut child = KillOnDrop(child);
+    let exited = {
+        let exited = child.0.status().fuse();
+        let timeout = executor.timer(grace_period).fuse();
+        futures::pin_mut!(exited, timeout);
+        futures::select_biased! {
+            _ = exited => true,
+            _ = timeout => false,
+        }
+    };
+    if !exited {
+        log::debug!("Agent didn't exit within {grace_period:?}, killing it");
+    }
+}
+
+/// Kills the agent process when dropped, unless it has exited.
+struct KillOnDrop(smol::process::Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        if !matches!(self.0.try_status(), Ok(Some(_))) {
+            self.0.kill().log_err();
+        }
     }
 }
 
@@ -489,11 +606,57 @@ impl AgentConnection for AcpConnection {
         }
     }
 
+    fn file_changes(
+        &self,
+        session_id: &acp::SessionId,
+        _cx: &App,
+    ) -> Option<Rc<dyn acp_thread::AgentSessionFileChanges>> {
+        let supported = self
+            .agent_capabilities
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("file_changes"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false);
+        if !supported || !self.sessions.borrow().contains_key(session_id) {
+            return None;
+        }
+        Some(Rc::new(AcpSessionFileChanges {
+            session_id: session_id.clone(),
+            connection: self.connection.clone(),
+        }) as _)
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
         self
     }
 }
 
+struct AcpSessionFileChanges {
+    session_id: acp::SessionId,
+    connection: Rc<acp::ClientSideConnection>,
+}
+
+impl acp_thread::AgentSessionFileChanges for AcpSessionFileChanges {
+    fn notify(&self, paths: Vec<PathBuf>, cx: &mut App) -> Task<Result<()>> {
+        let connection = self.connection.clone();
+        let session_id = self.session_id.clone();
+        cx.foreground_executor().spawn(async move {
+            let params = serde_json::value::to_raw_value(&serde_json::json!({
+                "sessionId": session_id,
+                "paths": paths,
+            }))?;
+            connection
+                .ext_notification(acp::ExtNotification {
+                    method: FILE_CHANGES_METHOD.into(),
+                    params: params.into(),
+                })
+                .await?;
+            Ok(())
+        })
+    }
+}
+
 struct AcpSessionModes {
     session_id: acp::SessionId,
     connection: Rc<acp::ClientSideConnection>,
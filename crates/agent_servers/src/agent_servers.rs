@@ -2,6 +2,7 @@ mod acp;
 mod claude;
 mod codex;
 mod custom;
+mod debug;
 mod gemini;
 
 #[cfg(any(test, feature = "test-support"))]
@@ -12,6 +13,7 @@ use client::ProxySettings;
 pub use codex::*;
 use collections::HashMap;
 pub use custom::*;
+pub use debug::*;
 use fs::Fs;
 pub use gemini::*;
 use http_client::read_no_proxy_from_env;
@@ -57,6 +59,10 @@ pub trait AgentServer: Send {
     fn logo(&self) -> ui::IconName;
     fn name(&self) -> SharedString;
     fn telemetry_id(&self) -> &'static str;
+    /// The placeholder of the message editor of the agent's threads.
+    fn placeholder(&self) -> SharedString {
+        format!("Message {} — @ to include context", self.name()).into()
+    }
     fn default_mode(&self, _cx: &mut App) -> Option<agent_client_protocol::SessionModeId> {
         None
     }
@@ -75,6 +81,12 @@ pub trait AgentServer: Send {
         cx: &mut App,
     ) -> Task<Result<(Rc<dyn AgentConnection>, Option<task::SpawnInTerminal>)>>;
 
+    /// Whether connecting starts an agent process, in which case new threads
+    /// only connect once they're first used.
+    fn spawns_process(&self) -> bool {
+        false
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any>;
 }
 
@@ -11,7 +11,7 @@ use gpui::{App, AppContext as _, SharedString, Task};
 use project::agent_server_store::{AllAgentServersSettings, CLAUDE_CODE_NAME};
 
 use crate::{AgentServer, AgentServerDelegate, load_proxy_env};
-use acp_thread::AgentConnection;
+use acp_thread::{AgentConnection, FileSystemAccess};
 
 #[derive(Clone)]
 pub struct ClaudeCode;
@@ -30,6 +30,10 @@ impl AgentServer for ClaudeCode {
         "Claude Code".into()
     }
 
+    fn placeholder(&self) -> SharedString {
+        "Message Claude Code — @ to include context, / for commands".into()
+    }
+
     fn logo(&self) -> ui::IconName {
         ui::IconName::AiClaude
     }
@@ -64,6 +68,7 @@ impl AgentServer for ClaudeCode {
         let name = self.name();
         let root_dir = root_dir.map(|root_dir| root_dir.to_string_lossy().into_owned());
         let is_remote = delegate.project.read(cx).is_via_remote_server();
+        let file_system_access = FileSystemAccess::for_project(delegate.project.read(cx), cx);
         let store = delegate.store.downgrade();
         let extra_env = load_proxy_env(cx);
         let default_mode = self.default_mode(cx);
@@ -87,6 +92,7 @@ impl AgentServer for ClaudeCode {
                 name,
                 command,
                 root_dir.as_ref(),
+                file_system_access,
                 default_mode,
                 is_remote,
                 cx,
@@ -96,6 +102,10 @@ impl AgentServer for ClaudeCode {
         })
     }
 
+    fn spawns_process(&self) -> bool {
+        true
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
         self
     }
@@ -2,7 +2,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 use std::{any::Any, path::Path};
 
-use acp_thread::AgentConnection;
+use acp_thread::{AgentConnection, FileSystemAccess};
 use agent_client_protocol as acp;
 use anyhow::{Context as _, Result};
 use fs::Fs;
@@ -31,6 +31,10 @@ impl AgentServer for Codex {
         "Codex".into()
     }
 
+    fn placeholder(&self) -> SharedString {
+        "Message Codex — @ to include context, / for commands".into()
+    }
+
     fn logo(&self) -> ui::IconName {
         ui::IconName::AiOpenAi
     }
@@ -65,6 +69,7 @@ impl AgentServer for Codex {
         let name = self.name();
         let root_dir = root_dir.map(|root_dir| root_dir.to_string_lossy().into_owned());
         let is_remote = delegate.project.read(cx).is_via_remote_server();
+        let file_system_access = FileSystemAccess::for_project(delegate.project.read(cx), cx);
         let store = delegate.store.downgrade();
         let extra_env = load_proxy_env(cx);
         let default_mode = self.default_mode(cx);
@@ -91,6 +96,7 @@ impl AgentServer for Codex {
                 name,
                 command,
                 root_dir.as_ref(),
+                file_system_access,
                 default_mode,
                 is_remote,
                 cx,
@@ -100,6 +106,10 @@ impl AgentServer for Codex {
         })
     }
 
+    fn spawns_process(&self) -> bool {
+        true
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
         self
     }
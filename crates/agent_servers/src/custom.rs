@@ -1,5 +1,5 @@
 use crate::{AgentServerDelegate, load_proxy_env};
-use acp_thread::AgentConnection;
+use acp_thread::{AgentConnection, FileSystemAccess};
 use agent_client_protocol as acp;
 use anyhow::{Context as _, Result};
 use fs::Fs;
@@ -69,6 +69,7 @@ impl crate::AgentServer for CustomAgentServer {
         let name = self.name();
         let root_dir = root_dir.map(|root_dir| root_dir.to_string_lossy().into_owned());
         let is_remote = delegate.project.read(cx).is_via_remote_server();
+        let file_system_access = FileSystemAccess::for_project(delegate.project.read(cx), cx);
         let default_mode = self.default_mode(cx);
         let store = delegate.store.downgrade();
         let extra_env = load_proxy_env(cx);
@@ -94,6 +95,7 @@ impl crate::AgentServer for CustomAgentServer {
                 name,
                 command,
                 root_dir.as_ref(),
+                file_system_access,
                 default_mode,
                 is_remote,
                 cx,
@@ -103,6 +105,10 @@ impl crate::AgentServer for CustomAgentServer {
         })
     }
 
+    fn spawns_process(&self) -> bool {
+        true
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn std::any::Any> {
         self
     }
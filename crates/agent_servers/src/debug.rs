@@ -0,0 +1,353 @@
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use acp_thread::{AcpThread, AgentConnection, UserMessageId};
+use action_log::ActionLog;
+use agent_client_protocol as acp;
+use anyhow::{Result, anyhow};
+use collections::HashMap;
+use gpui::{App, AppContext as _, Entity, SharedString, Task, WeakEntity};
+use project::Project;
+
+use crate::{AgentServer, AgentServerDelegate};
+
+/// How long the debug agent waits between scripted updates, so that they appear to stream in.
+const STEP_DELAY: Duration = Duration::from_millis(150);
+
+/// A built-in agent that replays a scripted turn instead of talking to a model, so that
+/// the thread view can be developed and tested without network access or an agent installed.
+///
+/// Prompts starting with "error" or "refuse" end the turn with an error or a refusal instead.
+pub struct DebugAgent;
+
+impl AgentServer for DebugAgent {
+    fn telemetry_id(&self) -> &'static str {
+        "debug"
+    }
+
+    fn name(&self) -> SharedString {
+        "Debug Agent".into()
+    }
+
+    fn logo(&self) -> ui::IconName {
+        ui::IconName::Debug
+    }
+
+    fn connect(
+        &self,
+        _root_dir: Option<&Path>,
+        _delegate: AgentServerDelegate,
+        _cx: &mut App,
+    ) -> Task<Result<(Rc<dyn AgentConnection>, Option<task::SpawnInTerminal>)>> {
+        let connection: Rc<dyn AgentConnection> = Rc::new(DebugAgentConnection::default());
+        Task::ready(Ok((connection, None)))
+    }
+
+    fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
+        self
+    }
+}
+
+#[derive(Default)]
+struct DebugAgentConnection {
+    sessions: RefCell<HashMap<acp::SessionId, Session>>,
+}
+
+struct Session {
+    thread: WeakEntity<AcpThread>,
+    cwd: PathBuf,
+    /// Set when the running turn is canceled, to stop replaying it.
+    cancelled: Rc<Cell<bool>>,
+}
+
+enum Step {
+    Update(acp::SessionUpdate),
+    Authorize {
+        tool_call: acp::ToolCall,
+        options: Vec<acp::PermissionOption>,
+    },
+}
+
+impl AgentConnection for DebugAgentConnection {
+    fn new_thread(
+        self: Rc<Self>,
+        project: Entity<Project>,
+        cwd: &Path,
+        cx: &mut App,
+    ) -> Task<Result<Entity<AcpThread>>> {
+        let session_id = acp::SessionId(uuid::Uuid::new_v4().to_string().into());
+        let action_log = cx.new(|_| ActionLog::new(project.clone()));
+        let thread = cx.new(|cx| {
+            AcpThread::new(
+                "Debug Agent",
+                self.clone(),
+                project,
+                action_log,
+                session_id.clone(),
+                watch::Receiver::constant(acp::PromptCapabilities {
+                    image: true,
+                    audio: false,
+                    embedded_context: true,
+                    meta: None,
+                }),
+                cx,
+            )
+        });
+        self.sessions.borrow_mut().insert(
+            session_id,
+            Session {
+                thread: thread.downgrade(),
+                cwd: cwd.to_path_buf(),
+                cancelled: Rc::default(),
+            },
+        );
+        Task::ready(Ok(thread))
+    }
+
+    fn auth_methods(&self) -> &[acp::AuthMethod] {
+        &[]
+    }
+
+    fn authenticate(&self, _method: acp::AuthMethodId, _cx: &mut App) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn prompt(
+        &self,
+        _user_message_id: Option<UserMessageId>,
+        params: acp::PromptRequest,
+        cx: &mut App,
+    ) -> Task<Result<acp::PromptResponse>> {
+        let mut sessions = self.sessions.borrow_mut();
+        let Some(session) = sessions.get_mut(&params.session_id) else {
+            return Task::ready(Err(anyhow!("Session not found")));
+        };
+        let thread = session.thread.clone();
+        let script = script(&session.cwd);
+        let cancelled = Rc::new(Cell::new(false));
+        session.cancelled = cancelled.clone();
+
+        let prompt = params
+            .prompt
+            .iter()
+            .find_map(|block| match block {
+                acp::ContentBlock::Text(text) => Some(text.text.trim().to_lowercase()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        cx.spawn(async move |cx| {
+            cx.background_executor().timer(STEP_DELAY).await;
+            if prompt.starts_with("error") {
+                return Err(anyhow!("The debug agent was asked to fail"));
+            }
+            if prompt.starts_with("refuse") {
+                return Ok(acp::PromptResponse {
+                    stop_reason: acp::StopReason::Refusal,
+                    meta: None,
+                });
+            }
+
+            for step in script {
+                if cancelled.get() {
+                    return Ok(acp::PromptResponse {
+                        stop_reason: acp::StopReason::Cancelled,
+                        meta: None,
+                    });
+                }
+                match step {
+                    Step::Update(update) => {
+                        thread
+                            .update(cx, |thread, cx| thread.handle_session_update(update, cx))??;
+                    }
+                    Step::Authorize { tool_call, options } => {
+                        let id = tool_call.id.clone();
+                        let outcome = thread
+                            .update(cx, |thread, cx| {
+                                thread.request_tool_call_authorization(
+                                    tool_call.into(),
+                                    options.clone(),
+                                    false,
+                                    cx,
+                                )
+                            })??
+                            .await;
+                        let allowed = match outcome {
+                            acp::RequestPermissionOutcome::Selected { option_id } => {
+                                options.iter().any(|option| {
+                                    option.id == option_id
+                                        && matches!(
+                                            option.kind,
+                                            acp::PermissionOptionKind::AllowOnce
+                                                | acp::PermissionOptionKind::AllowAlways
+                                        )
+                                })
+                            }
+                            acp::RequestPermissionOutcome::Cancelled => false,
+                        };
+                        if allowed {
+                            cx.background_executor().timer(STEP_DELAY).await;
+                            thread.update(cx, |thread, cx| {
+                                thread.handle_session_update(
+                                    acp::SessionUpdate::ToolCallUpdate(acp::ToolCallUpdate {
+                                        id,
+                                        fields: acp::ToolCallUpdateFields {
+                                            status: Some(acp::ToolCallStatus::Completed),
+                                            content: Some(vec!["Deployed to staging.".into()]),
+                                            ..Default::default()
+                                        },
+                                        meta: None,
+                                    }),
+                                    cx,
+                                )
+                            })??;
+                        }
+                    }
+                }
+                cx.background_executor().timer(STEP_DELAY).await;
+            }
+
+            Ok(acp::PromptResponse {
+                stop_reason: acp::StopReason::EndTurn,
+                meta: Some(serde_json::json!({
+                    "model": "Debug Model",
+                    "usage": { "inputTokens": 1200, "outputTokens": 300 },
+                })),
+            })
+        })
+    }
+
+    fn cancel(&self, session_id: &acp::SessionId, _cx: &mut App) {
+        if let Some(session) = self.sessions.borrow().get(session_id) {
+            session.cancelled.set(true);
+        }
+    }
+
+    fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
+        self
+    }
+}
+
+/// A turn exercising every kind of entry the thread view renders.
+fn script(cwd: &Path) -> Vec<Step> {
+    let text = |text: &str| acp::SessionUpdate::AgentMessageChunk {
+        content: text.into(),
+    };
+    let tool_call = |id: &str, title: &str, kind, status, content| acp::ToolCall {
+        id: acp::ToolCallId(id.into()),
+        title: title.into(),
+        kind,
+        status,
+        content,
+        locations: Vec::new(),
+        raw_input: None,
+        raw_output: None,
+        meta: None,
+    };
+    let plan_entry = |content: &str, status| acp::PlanEntry {
+        content: content.into(),
+        priority: acp::PlanEntryPriority::Medium,
+        status,
+        meta: None,
+    };
+    let option = |id: &str, name: &str, kind| acp::PermissionOption {
+        id: acp::PermissionOptionId(id.into()),
+        name: name.into(),
+        kind,
+        meta: None,
+    };
+    let path = cwd.join("debug_agent.rs");
+
+    vec![
+        Step::Update(acp::SessionUpdate::AgentThoughtChunk {
+            content: "The user wants to see every kind of entry. ".into(),
+        }),
+        Step::Update(acp::SessionUpdate::AgentThoughtChunk {
+            content: "I'll read a file, edit it and ask before deploying.".into(),
+        }),
+        Step::Update(acp::SessionUpdate::Plan(acp::Plan {
+            entries: vec![
+                plan_entry("Read the file", acp::PlanEntryStatus::Completed),
+                plan_entry("Edit the file", acp::PlanEntryStatus::InProgress),
+                plan_entry("Deploy to staging", acp::PlanEntryStatus::Pending),
+            ],
+            meta: None,
+        })),
+        Step::Update(text("Sure, let me start by **reading** ")),
+        Step::Update(text("the file.\n\n```rust\nfn main() {}\n```")),
+        Step::Update(acp::SessionUpdate::ToolCall(acp::ToolCall {
+            locations: vec![acp::ToolCallLocation {
+                path: path.clone(),
+                line: Some(1),
+                meta: None,
+            }],
+            ..tool_call(
+                "read",
+                "Read `debug_agent.rs`",
+                acp::ToolKind::Read,
+                acp::ToolCallStatus::Completed,
+                vec!["fn main() {}".into()],
+            )
+        })),
+        Step::Update(acp::SessionUpdate::ToolCall(tool_call(
+            "edit",
+            "Edit `debug_agent.rs`",
+            acp::ToolKind::Edit,
+            acp::ToolCallStatus::Completed,
+            vec![acp::ToolCallContent::Diff {
+                diff: acp::Diff {
+                    path,
+                    old_text: Some("fn main() {}\n".into()),
+                    new_text: "fn main() {\n    println!(\"Hello from the debug agent\");\n}\n"
+                        .into(),
+                    meta: None,
+                },
+            }],
+        ))),
+        Step::Update(acp::SessionUpdate::ToolCall(tool_call(
+            "search",
+            "Search for `TODO`",
+            acp::ToolKind::Search,
+            acp::ToolCallStatus::Failed,
+            vec!["The search timed out.".into()],
+        ))),
+        Step::Authorize {
+            tool_call: tool_call(
+                "deploy",
+                "Run `./deploy --staging`",
+                acp::ToolKind::Execute,
+                acp::ToolCallStatus::Pending,
+                Vec::new(),
+            ),
+            options: vec![
+                option(
+                    "allow-always",
+                    "Always Allow",
+                    acp::PermissionOptionKind::AllowAlways,
+                ),
+                option("allow", "Allow", acp::PermissionOptionKind::AllowOnce),
+                option("reject", "Reject", acp::PermissionOptionKind::RejectOnce),
+                option(
+                    "reject-always",
+                    "Always Reject",
+                    acp::PermissionOptionKind::RejectAlways,
+                ),
+            ],
+        },
+        Step::Update(acp::SessionUpdate::Plan(acp::Plan {
+            entries: vec![
+                plan_entry("Read the file", acp::PlanEntryStatus::Completed),
+                plan_entry("Edit the file", acp::PlanEntryStatus::Completed),
+                plan_entry("Deploy to staging", acp::PlanEntryStatus::Completed),
+            ],
+            meta: None,
+        })),
+        Step::Update(text("All done. Send a message starting with ")),
+        Step::Update(text("\"error\" or \"refuse\" to see how those are shown.")),
+    ]
+}
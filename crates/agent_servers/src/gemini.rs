@@ -2,7 +2,7 @@ use std::rc::Rc;
 use std::{any::Any, path::Path};
 
 use crate::{AgentServer, AgentServerDelegate, load_proxy_env};
-use acp_thread::AgentConnection;
+use acp_thread::{AgentConnection, FileSystemAccess};
 use anyhow::{Context as _, Result};
 use gpui::{App, SharedString, Task};
 use language_models::provider::google::GoogleLanguageModelProvider;
@@ -33,6 +33,7 @@ impl AgentServer for Gemini {
         let name = self.name();
         let root_dir = root_dir.map(|root_dir| root_dir.to_string_lossy().into_owned());
         let is_remote = delegate.project.read(cx).is_via_remote_server();
+        let file_system_access = FileSystemAccess::for_project(delegate.project.read(cx), cx);
         let store = delegate.store.downgrade();
         let mut extra_env = load_proxy_env(cx);
         let default_mode = self.default_mode(cx);
@@ -66,6 +67,7 @@ impl AgentServer for Gemini {
                 name,
                 command,
                 root_dir.as_ref(),
+                file_system_access,
                 default_mode,
                 is_remote,
                 cx,
@@ -75,6 +77,10 @@ impl AgentServer for Gemini {
         })
     }
 
+    fn spawns_process(&self) -> bool {
+        true
+    }
+
     fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
         self
     }
@@ -1,6 +1,6 @@
 mod agent_profile;
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use collections::IndexMap;
 use gpui::{App, Pixels, px};
@@ -9,8 +9,9 @@ use project::DisableAiSettings;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{
-    DefaultAgentView, DockPosition, LanguageModelParameters, LanguageModelSelection,
-    NotifyWhenAgentWaiting, Settings,
+    AgentAutomationContent, AgentNotificationKind, DefaultAgentView, DockPosition,
+    LanguageModelParameters, LanguageModelSelection, NotifyWhenAgentWaiting, Settings,
+    ToolPermissionProfile,
 };
 
 pub use crate::agent_profile::*;
@@ -21,6 +22,7 @@ pub const SUMMARIZE_THREAD_DETAILED_PROMPT: &str =
 
 pub fn init(cx: &mut App) {
     AgentSettings::register(cx);
+    IssueLinkSettings::register(cx);
 }
 
 #[derive(Clone, Debug)]
@@ -39,8 +41,11 @@ pub struct AgentSettings {
     pub default_view: DefaultAgentView,
     pub profiles: IndexMap<AgentProfileId, AgentProfileSettings>,
     pub always_allow_tool_actions: bool,
+    pub auto_approve: AutoApprove,
+    pub auto_approve_countdown: AutoApproveCountdown,
     pub notify_when_agent_waiting: NotifyWhenAgentWaiting,
     pub play_sound_when_agent_done: bool,
+    pub notifications: AgentNotifications,
     pub single_file_review: bool,
     pub model_parameters: Vec<LanguageModelParameters>,
     pub preferred_completion_mode: CompletionMode,
@@ -49,6 +54,22 @@ pub struct AgentSettings {
     pub expand_terminal_card: bool,
     pub use_modifier_to_send: bool,
     pub message_editor_min_lines: usize,
+    pub include_symbol_reference_counts: bool,
+    pub include_active_editor_context: bool,
+    pub notify_agent_of_branch_changes: bool,
+    pub notify_agent_of_file_changes: bool,
+    pub max_live_entries: usize,
+    pub minimal_transcript: bool,
+    pub discard_thinking: bool,
+    pub enable_thread_sharing: bool,
+    pub process_memory_warning_mb: u64,
+    pub auto_summarize_context: bool,
+    pub expand_entries: bool,
+    pub always_allowed: AlwaysAllowed,
+    pub tool_permission_profile: ToolPermissionProfile,
+    pub allow_agent_file_writes: bool,
+    pub show_commands_in_terminal_panel: bool,
+    pub automations: Vec<AgentAutomationContent>,
 }
 
 impl AgentSettings {
@@ -100,6 +121,81 @@ impl AgentSettings {
     }
 }
 
+/// Kinds of low-risk tool calls that are allowed without asking for confirmation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoApprove {
+    pub reads: bool,
+    pub searches: bool,
+    pub fetches: bool,
+}
+
+impl From<settings::AutoApproveContent> for AutoApprove {
+    fn from(content: settings::AutoApproveContent) -> Self {
+        Self {
+            reads: content.reads.unwrap(),
+            searches: content.searches.unwrap(),
+            fetches: content.fetches.unwrap(),
+        }
+    }
+}
+
+/// Tool calls allowed without confirmation, because "Always Allow" was chosen for them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlwaysAllowed {
+    pub commands: Vec<String>,
+    pub tools: Vec<String>,
+    pub kinds: Vec<String>,
+}
+
+impl From<settings::AlwaysAllowedContent> for AlwaysAllowed {
+    fn from(content: settings::AlwaysAllowedContent) -> Self {
+        Self {
+            commands: content.commands.unwrap(),
+            tools: content.tools.unwrap(),
+            kinds: content.kinds.unwrap(),
+        }
+    }
+}
+
+/// Countdowns, per kind of tool call, after which a pending confirmation is allowed.
+/// A zero duration waits for an explicit choice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoApproveCountdown {
+    pub edits: Duration,
+    pub executes: Duration,
+    pub fetches: Duration,
+    pub others: Duration,
+}
+
+impl From<settings::AutoApproveCountdownContent> for AutoApproveCountdown {
+    fn from(content: settings::AutoApproveCountdownContent) -> Self {
+        Self {
+            edits: Duration::from_secs(content.edits.unwrap()),
+            executes: Duration::from_secs(content.executes.unwrap()),
+            fetches: Duration::from_secs(content.fetches.unwrap()),
+            others: Duration::from_secs(content.others.unwrap()),
+        }
+    }
+}
+
+/// How to notify about each change of a thread's status.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgentNotifications {
+    pub done: AgentNotificationKind,
+    pub approval_needed: AgentNotificationKind,
+    pub error: AgentNotificationKind,
+}
+
+impl From<settings::AgentNotificationsContent> for AgentNotifications {
+    fn from(content: settings::AgentNotificationsContent) -> Self {
+        Self {
+            done: content.done.unwrap(),
+            approval_needed: content.approval_needed.unwrap(),
+            error: content.error.unwrap(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum CompletionMode {
@@ -171,8 +267,11 @@ impl Settings for AgentSettings {
                 .map(|(key, val)| (AgentProfileId(key), val.into()))
                 .collect(),
             always_allow_tool_actions: agent.always_allow_tool_actions.unwrap(),
+            auto_approve: agent.auto_approve.unwrap().into(),
+            auto_approve_countdown: agent.auto_approve_countdown.unwrap().into(),
             notify_when_agent_waiting: agent.notify_when_agent_waiting.unwrap(),
             play_sound_when_agent_done: agent.play_sound_when_agent_done.unwrap(),
+            notifications: agent.notifications.unwrap().into(),
             single_file_review: agent.single_file_review.unwrap(),
             model_parameters: agent.model_parameters,
             preferred_completion_mode: agent.preferred_completion_mode.unwrap().into(),
@@ -181,6 +280,39 @@ impl Settings for AgentSettings {
             expand_terminal_card: agent.expand_terminal_card.unwrap(),
             use_modifier_to_send: agent.use_modifier_to_send.unwrap(),
             message_editor_min_lines: agent.message_editor_min_lines.unwrap(),
+            include_symbol_reference_counts: agent.include_symbol_reference_counts.unwrap(),
+            include_active_editor_context: agent.include_active_editor_context.unwrap(),
+            notify_agent_of_branch_changes: agent.notify_agent_of_branch_changes.unwrap(),
+            notify_agent_of_file_changes: agent.notify_agent_of_file_changes.unwrap(),
+            max_live_entries: agent.max_live_entries.unwrap(),
+            minimal_transcript: agent.minimal_transcript.unwrap(),
+            discard_thinking: agent.discard_thinking.unwrap(),
+            enable_thread_sharing: agent.enable_thread_sharing.unwrap(),
+            process_memory_warning_mb: agent.process_memory_warning_mb.unwrap(),
+            auto_summarize_context: agent.auto_summarize_context.unwrap(),
+            expand_entries: agent.expand_entries.unwrap(),
+            always_allowed: agent.always_allowed.unwrap().into(),
+            tool_permission_profile: agent.tool_permission_profile.unwrap(),
+            allow_agent_file_writes: agent.allow_agent_file_writes.unwrap(),
+            show_commands_in_terminal_panel: agent.show_commands_in_terminal_panel.unwrap(),
+            automations: agent.automations.unwrap(),
+        }
+    }
+}
+
+/// How references to issues in agent threads are linked, per project.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IssueLinkSettings {
+    pub url_template: Option<String>,
+    pub patterns: Vec<String>,
+}
+
+impl Settings for IssueLinkSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        let issue_links = content.project.issue_links.clone().unwrap();
+        Self {
+            url_template: issue_links.url_template,
+            patterns: issue_links.patterns.unwrap(),
         }
     }
 }
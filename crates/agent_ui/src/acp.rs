@@ -1,12 +1,17 @@
 mod completion_provider;
+mod composer_commands;
 mod entry_view_state;
+mod issue_links;
 mod message_editor;
 mod mode_selector;
 mod model_selector;
 mod model_selector_popover;
 mod thread_history;
+mod thread_share;
 mod thread_view;
 
+pub use composer_commands::{ComposerCommand, register_composer_command};
+pub use message_editor::MessageEditor;
 pub use mode_selector::ModeSelector;
 pub use model_selector::AcpModelSelector;
 pub use model_selector_popover::AcpModelSelectorPopover;
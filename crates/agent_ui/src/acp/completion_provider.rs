@@ -5,7 +5,7 @@ use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::AtomicBool;
 
-use acp_thread::MentionUri;
+use acp_thread::{AgentSessions, MentionSourceItem, MentionUri};
 use agent::{HistoryEntry, HistoryStore};
 use agent_client_protocol as acp;
 use anyhow::Result;
@@ -27,6 +27,7 @@ use util::rel_path::RelPath;
 use workspace::Workspace;
 
 use crate::AgentPanel;
+use crate::acp::composer_commands::composer_commands;
 use crate::acp::message_editor::MessageEditor;
 use crate::context_picker::file_context_picker::{FileMatch, search_files};
 use crate::context_picker::rules_context_picker::{RulesContextEntry, search_rules};
@@ -45,6 +46,18 @@ pub(crate) enum Match {
     Fetch(SharedString),
     Rules(RulesContextEntry),
     Entry(EntryMatch),
+    Source(SourceMatch),
+    SourceItem {
+        keyword: SharedString,
+        item: MentionSourceItem,
+    },
+}
+
+/// A registered [`acp_thread::MentionSource`], listed in the `@` menu like a mode.
+pub struct SourceMatch {
+    mat: Option<StringMatch>,
+    keyword: SharedString,
+    label: SharedString,
 }
 
 pub struct EntryMatch {
@@ -57,6 +70,8 @@ impl Match {
         match self {
             Match::File(file) => file.mat.score,
             Match::Entry(mode) => mode.mat.as_ref().map(|mat| mat.score).unwrap_or(1.),
+            Match::Source(source) => source.mat.as_ref().map(|mat| mat.score).unwrap_or(1.),
+            Match::SourceItem { .. } => 1.,
             Match::Thread(_) => 1.,
             Match::RecentThread(_) => 1.,
             Match::Symbol(_) => 1.,
@@ -121,6 +136,53 @@ impl ContextPickerCompletionProvider {
         }
     }
 
+    fn completion_for_source(source: SourceMatch, source_range: Range<Anchor>) -> Completion {
+        Completion {
+            replace_range: source_range,
+            new_text: format!("@{} ", source.keyword),
+            label: CodeLabel::plain(source.label.to_string(), None),
+            icon_path: Some(IconName::Hash.path().into()),
+            documentation: None,
+            source: project::CompletionSource::Custom,
+            insert_text_mode: None,
+            // Keeps the menu open to list the source's mentions.
+            confirm: Some(Arc::new(|_, _, _| true)),
+        }
+    }
+
+    fn completion_for_source_item(
+        keyword: SharedString,
+        item: MentionSourceItem,
+        source_range: Range<Anchor>,
+        editor: WeakEntity<MessageEditor>,
+        cx: &mut App,
+    ) -> Completion {
+        let uri = MentionUri::Source {
+            source: keyword.to_string(),
+            id: item.id,
+            name: item.name.clone(),
+        };
+        let new_text = format!("{} ", uri.as_link());
+        let new_text_len = new_text.len();
+        let icon_path = uri.icon_path(cx);
+        Completion {
+            replace_range: source_range.clone(),
+            new_text,
+            label: CodeLabel::plain(item.name.clone(), None),
+            documentation: None,
+            insert_text_mode: None,
+            source: project::CompletionSource::Custom,
+            icon_path: Some(icon_path),
+            confirm: Some(confirm_completion_callback(
+                item.name.into(),
+                source_range.start,
+                new_text_len - 1,
+                editor,
+                uri,
+            )),
+        }
+    }
+
     fn completion_for_thread(
         thread_entry: HistoryEntry,
         source_range: Range<Anchor>,
@@ -392,7 +454,20 @@ impl ContextPickerCompletionProvider {
         query: String,
         cx: &mut App,
     ) -> Task<Vec<acp::AvailableCommand>> {
-        let commands = self.available_commands.borrow().clone();
+        let mut commands = self.available_commands.borrow().clone();
+        for command in composer_commands(cx) {
+            if !commands
+                .iter()
+                .any(|existing| existing.name == *command.name())
+            {
+                commands.push(acp::AvailableCommand {
+                    name: command.name().to_string(),
+                    description: command.description().to_string(),
+                    input: None,
+                    meta: None,
+                });
+            }
+        }
         if commands.is_empty() {
             return Task::ready(Vec::new());
         }
@@ -425,6 +500,7 @@ impl ContextPickerCompletionProvider {
     fn search_mentions(
         &self,
         mode: Option<ContextPickerMode>,
+        source: Option<SharedString>,
         query: String,
         cancellation_flag: Arc<AtomicBool>,
         cx: &mut App,
@@ -432,6 +508,22 @@ impl ContextPickerCompletionProvider {
         let Some(workspace) = self.workspace.upgrade() else {
             return Task::ready(Vec::default());
         };
+        if let Some(keyword) = source {
+            let Some(source) = AgentSessions::global(cx).read(cx).mention_source(&keyword) else {
+                return Task::ready(Vec::new());
+            };
+            let search_task = source.search(query, cx);
+            return cx.background_spawn(async move {
+                search_task
+                    .await
+                    .into_iter()
+                    .map(|item| Match::SourceItem {
+                        keyword: keyword.clone(),
+                        item,
+                    })
+                    .collect()
+            });
+        }
         match mode {
             Some(ContextPickerMode::File) => {
                 let search_files_task = search_files(query, cancellation_flag, &workspace, cx);
@@ -505,6 +597,16 @@ impl ContextPickerCompletionProvider {
                         }),
                 );
 
+                matches.extend(self.available_mention_sources(cx).into_iter().map(
+                    |(keyword, label)| {
+                        Match::Source(SourceMatch {
+                            mat: None,
+                            keyword,
+                            label,
+                        })
+                    },
+                ));
+
                 Task::ready(matches)
             }
             None => {
@@ -514,10 +616,13 @@ impl ContextPickerCompletionProvider {
                     search_files(query.clone(), cancellation_flag, &workspace, cx);
 
                 let entries = self.available_context_picker_entries(&workspace, cx);
+                let sources = self.available_mention_sources(cx);
                 let entry_candidates = entries
                     .iter()
+                    .map(|entry| entry.keyword())
+                    .chain(sources.iter().map(|(keyword, _)| keyword.as_ref()))
                     .enumerate()
-                    .map(|(ix, entry)| StringMatchCandidate::new(ix, entry.keyword()))
+                    .map(|(ix, keyword)| StringMatchCandidate::new(ix, keyword))
                     .collect::<Vec<_>>();
 
                 cx.background_spawn(async move {
@@ -539,10 +644,20 @@ impl ContextPickerCompletionProvider {
                     .await;
 
                     matches.extend(entry_matches.into_iter().map(|mat| {
-                        Match::Entry(EntryMatch {
-                            entry: entries[mat.candidate_id],
-                            mat: Some(mat),
-                        })
+                        if let Some(entry) = entries.get(mat.candidate_id) {
+                            Match::Entry(EntryMatch {
+                                entry: *entry,
+                                mat: Some(mat),
+                            })
+                        } else {
+                            let (keyword, label) =
+                                sources[mat.candidate_id - entries.len()].clone();
+                            Match::Source(SourceMatch {
+                                mat: Some(mat),
+                                keyword,
+                                label,
+                            })
+                        }
                     }));
 
                     matches.sort_by(|a, b| {
@@ -672,6 +787,25 @@ impl ContextPickerCompletionProvider {
 
         entries
     }
+
+    /// The keywords and labels of the registered mention sources, which need embedded context.
+    fn available_mention_sources(&self, cx: &mut App) -> Vec<(SharedString, SharedString)> {
+        if !self.prompt_capabilities.borrow().embedded_context {
+            return Vec::new();
+        }
+        AgentSessions::global(cx)
+            .read(cx)
+            .mention_sources()
+            .map(|source| (source.keyword(), source.label()))
+            .collect()
+    }
+
+    fn mention_source_keywords(&self, cx: &mut App) -> Vec<SharedString> {
+        self.available_mention_sources(cx)
+            .into_iter()
+            .map(|(keyword, _)| keyword)
+            .collect()
+    }
 }
 
 fn build_code_label_for_full_path(file_name: &str, directory: Option<&str>, cx: &App) -> CodeLabel {
@@ -698,6 +832,7 @@ impl CompletionProvider for ContextPickerCompletionProvider {
         _window: &mut Window,
         cx: &mut Context<Editor>,
     ) -> Task<Result<Vec<CompletionResponse>>> {
+        let mention_sources = self.mention_source_keywords(cx);
         let state = buffer.update(cx, |buffer, _cx| {
             let position = buffer_position.to_point(buffer);
             let line_start = Point::new(position.row, 0);
@@ -708,6 +843,7 @@ impl CompletionProvider for ContextPickerCompletionProvider {
                 line,
                 offset_to_line,
                 self.prompt_capabilities.borrow().embedded_context,
+                &mention_sources,
             )
         });
         let Some(state) = state else {
@@ -794,10 +930,15 @@ impl CompletionProvider for ContextPickerCompletionProvider {
                     }])
                 })
             }
-            ContextCompletion::Mention(MentionCompletion { mode, argument, .. }) => {
+            ContextCompletion::Mention(MentionCompletion {
+                mode,
+                source,
+                argument,
+                ..
+            }) => {
                 let query = argument.unwrap_or_default();
                 let search_task =
-                    self.search_mentions(mode, query, Arc::<AtomicBool>::default(), cx);
+                    self.search_mentions(mode, source, query, Arc::<AtomicBool>::default(), cx);
 
                 cx.spawn(async move |_, cx| {
                     let matches = search_task.await;
@@ -873,6 +1014,20 @@ impl CompletionProvider for ContextPickerCompletionProvider {
                                         cx,
                                     )
                                 }
+
+                                Match::Source(source) => {
+                                    Some(Self::completion_for_source(source, source_range.clone()))
+                                }
+
+                                Match::SourceItem { keyword, item } => {
+                                    Some(Self::completion_for_source_item(
+                                        keyword,
+                                        item,
+                                        source_range.clone(),
+                                        editor.clone(),
+                                        cx,
+                                    ))
+                                }
                             })
                             .collect()
                     })?;
@@ -900,6 +1055,7 @@ impl CompletionProvider for ContextPickerCompletionProvider {
         _menu_is_open: bool,
         cx: &mut Context<Editor>,
     ) -> bool {
+        let mention_sources = self.mention_source_keywords(cx);
         let buffer = buffer.read(cx);
         let position = position.to_point(buffer);
         let line_start = Point::new(position.row, 0);
@@ -910,6 +1066,7 @@ impl CompletionProvider for ContextPickerCompletionProvider {
                 line,
                 offset_to_line,
                 self.prompt_capabilities.borrow().embedded_context,
+                &mention_sources,
             )
             .filter(|completion| {
                 // Right now we don't support completing arguments of slash commands
@@ -986,12 +1143,20 @@ impl ContextCompletion {
         }
     }
 
-    fn try_parse(line: &str, offset_to_line: usize, allow_non_file_mentions: bool) -> Option<Self> {
+    fn try_parse(
+        line: &str,
+        offset_to_line: usize,
+        allow_non_file_mentions: bool,
+        mention_sources: &[SharedString],
+    ) -> Option<Self> {
         if let Some(command) = SlashCommandCompletion::try_parse(line, offset_to_line) {
             Some(Self::SlashCommand(command))
-        } else if let Some(mention) =
-            MentionCompletion::try_parse(allow_non_file_mentions, line, offset_to_line)
-        {
+        } else if let Some(mention) = MentionCompletion::try_parse(
+            allow_non_file_mentions,
+            mention_sources,
+            line,
+            offset_to_line,
+        ) {
             Some(Self::Mention(mention))
         } else {
             None
@@ -1048,11 +1213,18 @@ impl SlashCommandCompletion {
 struct MentionCompletion {
     source_range: Range<usize>,
     mode: Option<ContextPickerMode>,
+    /// The keyword of the registered mention source to list mentions from.
+    source: Option<SharedString>,
     argument: Option<String>,
 }
 
 impl MentionCompletion {
-    fn try_parse(allow_non_file_mentions: bool, line: &str, offset_to_line: usize) -> Option<Self> {
+    fn try_parse(
+        allow_non_file_mentions: bool,
+        mention_sources: &[SharedString],
+        line: &str,
+        offset_to_line: usize,
+    ) -> Option<Self> {
         let last_mention_start = line.rfind('@')?;
 
         // No whitespace immediately after '@'
@@ -1077,6 +1249,7 @@ impl MentionCompletion {
         let rest_of_line = &line[last_mention_start + 1..];
 
         let mut mode = None;
+        let mut source = None;
         let mut argument = None;
 
         let mut parts = rest_of_line.split_whitespace();
@@ -1090,6 +1263,12 @@ impl MentionCompletion {
                 && (allow_non_file_mentions || matches!(parsed_mode, ContextPickerMode::File))
             {
                 mode = Some(parsed_mode);
+            } else if allow_non_file_mentions
+                && let Some(keyword) = mention_sources
+                    .iter()
+                    .find(|keyword| keyword.as_ref() == mode_text)
+            {
+                source = Some(keyword.clone());
             } else {
                 argument = Some(mode_text.to_string());
             }
@@ -1098,7 +1277,7 @@ impl MentionCompletion {
                     if let Some(argument_text) = parts.next() {
                         // If mode wasn't recognized but we have an argument, don't suggest completions
                         // (e.g. '@something word')
-                        if mode.is_none() && !argument_text.is_empty() {
+                        if mode.is_none() && source.is_none() && !argument_text.is_empty() {
                             return None;
                         }
 
@@ -1116,6 +1295,7 @@ impl MentionCompletion {
         Some(Self {
             source_range: last_mention_start + offset_to_line..end + offset_to_line,
             mode,
+            source,
             argument,
         })
     }
@@ -1194,117 +1374,148 @@ mod tests {
 
     #[test]
     fn test_mention_completion_parse() {
-        assert_eq!(MentionCompletion::try_parse(true, "Lorem Ipsum", 0), None);
+        assert_eq!(
+            MentionCompletion::try_parse(true, &[], "Lorem Ipsum", 0),
+            None
+        );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @", 0),
             Some(MentionCompletion {
                 source_range: 6..7,
                 mode: None,
+                source: None,
                 argument: None,
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @file", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @file", 0),
             Some(MentionCompletion {
                 source_range: 6..11,
                 mode: Some(ContextPickerMode::File),
+                source: None,
                 argument: None,
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @file ", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @file ", 0),
             Some(MentionCompletion {
                 source_range: 6..12,
                 mode: Some(ContextPickerMode::File),
+                source: None,
                 argument: None,
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @file main.rs", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @file main.rs", 0),
             Some(MentionCompletion {
                 source_range: 6..19,
                 mode: Some(ContextPickerMode::File),
+                source: None,
                 argument: Some("main.rs".to_string()),
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @file main.rs ", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @file main.rs ", 0),
             Some(MentionCompletion {
                 source_range: 6..19,
                 mode: Some(ContextPickerMode::File),
+                source: None,
                 argument: Some("main.rs".to_string()),
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @file main.rs Ipsum", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @file main.rs Ipsum", 0),
             Some(MentionCompletion {
                 source_range: 6..19,
                 mode: Some(ContextPickerMode::File),
+                source: None,
                 argument: Some("main.rs".to_string()),
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @main", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @main", 0),
             Some(MentionCompletion {
                 source_range: 6..11,
                 mode: None,
+                source: None,
                 argument: Some("main".to_string()),
             })
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @main ", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @main ", 0),
             Some(MentionCompletion {
                 source_range: 6..12,
                 mode: None,
+                source: None,
                 argument: Some("main".to_string()),
             })
         );
 
-        assert_eq!(MentionCompletion::try_parse(true, "Lorem @main m", 0), None);
+        assert_eq!(
+            MentionCompletion::try_parse(true, &[], "Lorem @main m", 0),
+            None
+        );
 
-        assert_eq!(MentionCompletion::try_parse(true, "test@", 0), None);
+        assert_eq!(MentionCompletion::try_parse(true, &[], "test@", 0), None);
 
         // Allowed non-file mentions
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @symbol main", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @symbol main", 0),
             Some(MentionCompletion {
                 source_range: 6..18,
                 mode: Some(ContextPickerMode::Symbol),
+                source: None,
                 argument: Some("main".to_string()),
             })
         );
 
         // Disallowed non-file mentions
         assert_eq!(
-            MentionCompletion::try_parse(false, "Lorem @symbol main", 0),
+            MentionCompletion::try_parse(false, &[], "Lorem @symbol main", 0),
             None
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem@symbol", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem@symbol", 0),
             None,
             "Should not parse mention inside word"
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "Lorem @ file", 0),
+            MentionCompletion::try_parse(true, &[], "Lorem @ file", 0),
             None,
             "Should not parse with a space after @"
         );
 
         assert_eq!(
-            MentionCompletion::try_parse(true, "@ file", 0),
+            MentionCompletion::try_parse(true, &[], "@ file", 0),
             None,
             "Should not parse with a space after @ at the start of the line"
         );
+
+        // Registered mention sources
+        let sources = [SharedString::from("issue")];
+        assert_eq!(
+            MentionCompletion::try_parse(true, &sources, "Lorem @issue crash", 0),
+            Some(MentionCompletion {
+                source_range: 6..18,
+                mode: None,
+                source: Some("issue".into()),
+                argument: Some("crash".to_string()),
+            })
+        );
+        assert_eq!(
+            MentionCompletion::try_parse(false, &sources, "Lorem @issue crash", 0),
+            None
+        );
     }
 }
@@ -0,0 +1,204 @@
+use std::rc::Rc;
+
+use gpui::{App, Context, Global, SharedString, Window};
+
+use crate::NewExternalAgentThread;
+use crate::acp::message_editor::{MessageEditor, MessageEditorEvent};
+use crate::context_picker::ContextPickerMode;
+
+/// A command typed as `/name` at the start of a message, which the message editor runs
+/// instead of sending the message, e.g. to add context to it or to act on the thread.
+///
+/// Commands of the agent take precedence over the ones with the same name.
+pub trait ComposerCommand: 'static {
+    fn name(&self) -> SharedString;
+
+    fn description(&self) -> SharedString;
+
+    /// Runs the command, after its name was removed from the message.
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    );
+}
+
+#[derive(Default)]
+struct GlobalComposerCommands(Vec<Rc<dyn ComposerCommand>>);
+
+impl Global for GlobalComposerCommands {}
+
+/// Registers a command for the message editor of every agent. Commands registered later
+/// take precedence over the earlier and built-in ones with the same name.
+pub fn register_composer_command(command: impl ComposerCommand, cx: &mut App) {
+    cx.default_global::<GlobalComposerCommands>()
+        .0
+        .push(Rc::new(command));
+}
+
+/// The registered and built-in commands, without the ones whose name is taken by a
+/// command registered later.
+pub(crate) fn composer_commands(cx: &App) -> Vec<Rc<dyn ComposerCommand>> {
+    let registered = cx
+        .try_global::<GlobalComposerCommands>()
+        .map(|commands| commands.0.as_slice())
+        .unwrap_or_default();
+    let mut commands: Vec<Rc<dyn ComposerCommand>> = Vec::new();
+    for command in registered.iter().rev().cloned().chain(built_in_commands()) {
+        if !commands
+            .iter()
+            .any(|existing| existing.name() == command.name())
+        {
+            commands.push(command);
+        }
+    }
+    commands
+}
+
+pub(crate) fn composer_command(name: &str, cx: &App) -> Option<Rc<dyn ComposerCommand>> {
+    composer_commands(cx)
+        .into_iter()
+        .find(|command| command.name() == name)
+}
+
+fn built_in_commands() -> [Rc<dyn ComposerCommand>; 6] {
+    [
+        Rc::new(FileCommand),
+        Rc::new(TabCommand),
+        Rc::new(DiagnosticsCommand),
+        Rc::new(JsonCommand),
+        Rc::new(ClearCommand),
+        Rc::new(NewThreadCommand),
+    ]
+}
+
+struct FileCommand;
+
+impl ComposerCommand for FileCommand {
+    fn name(&self) -> SharedString {
+        "file".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Mention a file of the project".into()
+    }
+
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        message_editor.start_mention(ContextPickerMode::File, window, cx);
+    }
+}
+
+struct TabCommand;
+
+impl ComposerCommand for TabCommand {
+    fn name(&self) -> SharedString {
+        "tab".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Mention the files open in tabs".into()
+    }
+
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        message_editor.insert_open_tabs(window, cx);
+    }
+}
+
+struct DiagnosticsCommand;
+
+impl ComposerCommand for DiagnosticsCommand {
+    fn name(&self) -> SharedString {
+        "diagnostics".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Attach the errors and warnings of the project".into()
+    }
+
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        message_editor.insert_diagnostics(window, cx);
+    }
+}
+
+struct JsonCommand;
+
+impl ComposerCommand for JsonCommand {
+    fn name(&self) -> SharedString {
+        "json".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Ask for a structured response in JSON".into()
+    }
+
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        _window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        message_editor.set_structured_response(true, cx);
+        // Send the rest of the message, if any, as `/json` is usually typed before it.
+        if !message_editor.is_empty(cx) {
+            cx.emit(MessageEditorEvent::Send);
+        }
+    }
+}
+
+struct ClearCommand;
+
+impl ComposerCommand for ClearCommand {
+    fn name(&self) -> SharedString {
+        "clear".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Clear the message and its context".into()
+    }
+
+    fn run(
+        &self,
+        message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        message_editor.clear(window, cx);
+    }
+}
+
+struct NewThreadCommand;
+
+impl ComposerCommand for NewThreadCommand {
+    fn name(&self) -> SharedString {
+        "new".into()
+    }
+
+    fn description(&self) -> SharedString {
+        "Start a new thread with the agent".into()
+    }
+
+    fn run(
+        &self,
+        _message_editor: &mut MessageEditor,
+        window: &mut Window,
+        cx: &mut Context<MessageEditor>,
+    ) {
+        window.dispatch_action(Box::new(NewExternalAgentThread::default()), cx);
+    }
+}
@@ -1,15 +1,16 @@
-use std::{cell::RefCell, ops::Range, rc::Rc};
+use std::{cell::RefCell, ops::Range, path::PathBuf, rc::Rc};
 
-use acp_thread::{AcpThread, AgentThreadEntry};
+use acp_thread::{AcpThread, AgentThreadEntry, ToolCallContent};
 use agent::HistoryStore;
 use agent_client_protocol::{self as acp, ToolCallId};
 use collections::HashMap;
-use editor::{Editor, EditorMode, MinimapVisibility};
+use editor::{Editor, EditorMode, MinimapVisibility, MultiBuffer};
 use gpui::{
     AnyEntity, App, AppContext as _, Entity, EntityId, EventEmitter, FocusHandle, Focusable,
     ScrollHandle, SharedString, TextStyleRefinement, WeakEntity, Window,
 };
-use language::language_settings::SoftWrap;
+use language::{Buffer, language_settings::SoftWrap};
+use markdown::Markdown;
 use project::Project;
 use prompt_store::PromptStore;
 use settings::Settings as _;
@@ -118,6 +119,20 @@ impl EntryViewState {
                 let id = tool_call.id.clone();
                 let terminals = tool_call.terminals().cloned().collect::<Vec<_>>();
                 let diffs = tool_call.diffs().cloned().collect::<Vec<_>>();
+                let read_location = match tool_call.locations.as_slice() {
+                    [location] if tool_call.kind == acp::ToolKind::Read => {
+                        Some(location.path.clone())
+                    }
+                    _ => None,
+                };
+                let read_contents = tool_call
+                    .content
+                    .iter()
+                    .filter_map(|content| match content {
+                        ToolCallContent::ContentBlock(block) => block.markdown().cloned(),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
 
                 let views = if let Some(Entry::Content(views)) = self.entries.get_mut(index) {
                     views
@@ -170,6 +185,20 @@ impl EntryViewState {
                         element
                     });
                 }
+
+                // The content of a read is final once the tool call completes, so
+                // it can be shown in an editor with the file's own highlighting.
+                if let Some(path) = read_location
+                    && is_tool_call_completed
+                {
+                    for markdown in read_contents {
+                        views.entry(markdown.entity_id()).or_insert_with(|| {
+                            let text = unfenced(markdown.read(cx).source()).to_string();
+                            create_file_excerpt(text, path.clone(), &self.project, window, cx)
+                                .into_any()
+                        });
+                    }
+                }
             }
             AgentThreadEntry::AssistantMessage(message) => {
                 let entry = if let Some(Entry::AssistantMessage(entry)) =
@@ -203,7 +232,17 @@ impl EntryViewState {
         self.entries.drain(range);
     }
 
-    pub fn agent_ui_font_size_changed(&mut self, cx: &mut App) {
+    /// Drops the views of the entries in `range`. They are created again when the
+    /// entries are synced.
+    pub fn release(&mut self, range: Range<usize>) {
+        for entry in self.entries.iter_mut().take(range.end).skip(range.start) {
+            *entry = Entry::empty();
+        }
+    }
+
+    /// Applies the current theme settings to the diff editors, which keep the text style
+    /// they were created with.
+    pub fn refresh_styles(&mut self, cx: &mut App) {
         for entry in self.entries.iter() {
             match entry {
                 Entry::UserMessage { .. } | Entry::AssistantMessage { .. } => {}
@@ -286,6 +325,14 @@ impl Entry {
             .map(|entity| entity.downcast::<Editor>().unwrap())
     }
 
+    /// The read-only editor showing the file content of a read tool call.
+    pub fn file_excerpt(&self, markdown: &Entity<Markdown>) -> Option<Entity<Editor>> {
+        self.content_map()?
+            .get(&markdown.entity_id())
+            .cloned()
+            .and_then(|entity| entity.downcast::<Editor>().ok())
+    }
+
     pub fn terminal(
         &self,
         terminal: &Entity<acp_thread::Terminal>,
@@ -376,12 +423,74 @@ fn create_editor_diff(
         editor.set_show_breakpoints(false, cx);
         editor.set_show_code_actions(false, cx);
         editor.set_show_git_diff_gutter(false, cx);
-        editor.set_expand_all_diff_hunks(cx);
+        // A created file is previewed as plain content rather than as a single added hunk.
+        if !diff.read(cx).creates_file() {
+            editor.set_expand_all_diff_hunks(cx);
+        }
         editor.set_text_style_refinement(diff_editor_text_style_refinement(cx));
         editor
     })
 }
 
+fn create_file_excerpt(
+    text: String,
+    path: PathBuf,
+    project: &Entity<Project>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Entity<Editor> {
+    let buffer = cx.new(|cx| Buffer::local(text, cx));
+    let languages = project.read(cx).languages().clone();
+    cx.spawn({
+        let buffer = buffer.downgrade();
+        async move |cx| {
+            let language = languages.load_language_for_file_path(&path).await?;
+            buffer.update(cx, |buffer, cx| buffer.set_language(Some(language), cx))
+        }
+    })
+    .detach_and_log_err(cx);
+
+    let multibuffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx));
+    cx.new(|cx| {
+        let mut editor = Editor::new(
+            EditorMode::Full {
+                scale_ui_elements_with_buffer_font_size: false,
+                show_active_line_background: false,
+                sized_by_content: true,
+            },
+            multibuffer,
+            None,
+            window,
+            cx,
+        );
+        editor.set_show_gutter(false, cx);
+        editor.disable_inline_diagnostics();
+        editor.set_show_vertical_scrollbar(false, cx);
+        editor.set_minimap_visibility(MinimapVisibility::Disabled, window, cx);
+        editor.set_soft_wrap_mode(SoftWrap::None, cx);
+        editor.scroll_manager.set_forbid_vertical_scroll(true);
+        editor.set_show_indent_guides(false, cx);
+        editor.set_read_only(true);
+        editor.set_show_breakpoints(false, cx);
+        editor.set_show_code_actions(false, cx);
+        editor.set_text_style_refinement(diff_editor_text_style_refinement(cx));
+        editor
+    })
+}
+
+/// The text inside a markdown code fence, or the text itself when it isn't fenced.
+fn unfenced(text: &str) -> &str {
+    let fence_len = text.chars().take_while(|c| *c == '`').count();
+    if fence_len < 3 {
+        return text;
+    }
+    let fence = &text[..fence_len];
+    text.split_once('\n')
+        .and_then(|(_, body)| body.trim_end().strip_suffix(fence))
+        .map(|body| body.strip_suffix('\n').unwrap_or(body))
+        .unwrap_or(text)
+}
+
 fn diff_editor_text_style_refinement(cx: &mut App) -> TextStyleRefinement {
     TextStyleRefinement {
         font_size: Some(
@@ -417,6 +526,17 @@ mod tests {
     use util::path;
     use workspace::Workspace;
 
+    #[test]
+    fn test_unfenced() {
+        assert_eq!(
+            super::unfenced("```src/main.rs\nfn main() {}\n```\n"),
+            "fn main() {}"
+        );
+        assert_eq!(super::unfenced("````\nlet a = ```;\n````"), "let a = ```;");
+        assert_eq!(super::unfenced("plain text"), "plain text");
+        assert_eq!(super::unfenced("```\nunterminated"), "```\nunterminated");
+    }
+
     #[gpui::test]
     async fn test_diff_sync(cx: &mut TestAppContext) {
         init_test(cx);
@@ -0,0 +1,109 @@
+use std::{
+    iter,
+    ops::Range,
+    sync::{Arc, LazyLock},
+};
+
+use agent_settings::IssueLinkSettings;
+use gpui::{App, Entity, SharedString};
+use markdown::LinkDetector;
+use project::Project;
+use regex::Regex;
+use settings::{Settings as _, SettingsLocation};
+use util::{ResultExt as _, rel_path::RelPath};
+
+/// References like `#1234` and `GH-1234`.
+static BUILT_IN_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:#|\bGH-)(\d+)\b").unwrap());
+
+/// The settings of the project's first worktree, as threads aren't tied to a file.
+pub(crate) fn issue_link_settings(project: &Entity<Project>, cx: &App) -> IssueLinkSettings {
+    let worktree_id = project
+        .read(cx)
+        .visible_worktrees(cx)
+        .next()
+        .map(|worktree| worktree.read(cx).id());
+    IssueLinkSettings::get(
+        worktree_id.map(|worktree_id| SettingsLocation {
+            worktree_id,
+            path: RelPath::empty(),
+        }),
+        cx,
+    )
+    .clone()
+}
+
+/// Links the references to issues in markdown, or `None` when there is no URL to link
+/// them to.
+pub(crate) fn issue_link_detector(settings: &IssueLinkSettings) -> Option<LinkDetector> {
+    let url_template = settings.url_template.clone()?;
+    let patterns = iter::once(BUILT_IN_PATTERN.clone())
+        .chain(
+            settings
+                .patterns
+                .iter()
+                .filter_map(|pattern| Regex::new(pattern).log_err()),
+        )
+        .collect::<Vec<_>>();
+    Some(Arc::new(move |text: &str| {
+        find_issue_links(text, &patterns, &url_template)
+    }))
+}
+
+/// The references that the patterns match in the text, with the URL of each. Matches
+/// right after a word character, like in `a#1`, aren't references.
+fn find_issue_links(
+    text: &str,
+    patterns: &[Regex],
+    url_template: &str,
+) -> Vec<(Range<usize>, SharedString)> {
+    let mut links: Vec<(Range<usize>, SharedString)> = Vec::new();
+    for pattern in patterns {
+        for captures in pattern.captures_iter(text) {
+            let Some(reference) = captures.get(0) else {
+                continue;
+            };
+            let id = captures.get(1).unwrap_or(reference);
+            let follows_word = text[..reference.start()]
+                .chars()
+                .next_back()
+                .is_some_and(|char| char.is_alphanumeric() || char == '_');
+            let overlaps = links
+                .iter()
+                .any(|(range, _)| range.start < reference.end() && reference.start() < range.end);
+            if reference.is_empty() || follows_word || overlaps {
+                continue;
+            }
+            links.push((
+                reference.range(),
+                url_template.replace("{id}", id.as_str()).into(),
+            ));
+        }
+    }
+    links.sort_by_key(|(range, _)| range.start);
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_issue_links() {
+        let patterns = [
+            BUILT_IN_PATTERN.clone(),
+            Regex::new(r"\b[A-Z]+-\d+\b").unwrap(),
+        ];
+        let links = |text| find_issue_links(text, &patterns, "https://issues/{id}");
+
+        assert_eq!(
+            links("Fixes #12 and GH-34, see ZED-5"),
+            vec![
+                (6..9, "https://issues/12".into()),
+                (14..19, "https://issues/34".into()),
+                (25..30, "https://issues/ZED-5".into()),
+            ]
+        );
+        assert_eq!(links("a#1 foo_#2 #x"), vec![]);
+    }
+}
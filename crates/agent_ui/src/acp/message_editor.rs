@@ -1,19 +1,26 @@
 use crate::{
-    acp::completion_provider::{ContextPickerCompletionProvider, SlashCommandCompletion},
-    context_picker::{ContextPickerAction, fetch_context_picker::fetch_url_content},
+    acp::{
+        completion_provider::{ContextPickerCompletionProvider, SlashCommandCompletion},
+        composer_commands::{ComposerCommand, composer_command},
+    },
+    context_picker::{
+        ContextPickerAction, ContextPickerMode, fetch_context_picker::fetch_url_content,
+    },
 };
-use acp_thread::{MentionUri, selection_name};
-use agent::{HistoryStore, outline};
+use acp_thread::{AgentSessions, MentionUri, selection_name};
+use agent::{HistoryStore, ProjectMemory, outline};
 use agent_client_protocol as acp;
 use agent_servers::{AgentServer, AgentServerDelegate};
-use anyhow::{Result, anyhow};
-use assistant_slash_commands::codeblock_fence_for_path;
+use anyhow::{Context as _, Result, anyhow};
+use assistant_slash_command::SlashCommandOutput;
+use assistant_slash_commands::{codeblock_fence_for_path, collect_buffer_diagnostics};
+use base64::Engine as _;
 use collections::{HashMap, HashSet};
 use editor::{
     Addon, Anchor, AnchorRangeExt, ContextMenuOptions, ContextMenuPlacement, Editor, EditorElement,
     EditorEvent, EditorMode, EditorSnapshot, EditorStyle, ExcerptId, FoldPlaceholder, Inlay,
-    MultiBuffer, ToOffset,
-    actions::Paste,
+    MultiBuffer, SymbolReferenceCounts, ToOffset,
+    actions::{MoveToEnd, Paste, ShowCompletions},
     display_map::{Crease, CreaseId, FoldId},
 };
 use futures::{
@@ -22,10 +29,11 @@ use futures::{
 };
 use gpui::{
     Animation, AnimationExt as _, AppContext, ClipboardEntry, Context, Entity, EntityId,
-    EventEmitter, FocusHandle, Focusable, Image, ImageFormat, Img, KeyContext, SharedString,
-    Subscription, Task, TextStyle, WeakEntity, pulsating_between,
+    EventEmitter, FocusHandle, Focusable, FontWeight, HighlightStyle, Image, ImageFormat, Img,
+    KeyContext, PathPromptOptions, SharedString, Subscription, Task, TextStyle, WeakEntity,
+    pulsating_between,
 };
-use language::{Buffer, Language, language_settings::InlayHintKind};
+use language::{Buffer, BufferSnapshot, Language, language_settings::InlayHintKind};
 use language_model::LanguageModelImage;
 use postage::stream::Stream as _;
 use project::{
@@ -46,10 +54,13 @@ use std::{
     time::Duration,
 };
 use text::OffsetRangeExt;
-use theme::ThemeSettings;
+use theme::{GlobalTheme, ThemeSettings};
 use ui::{ButtonLike, TintColor, Toggleable, prelude::*};
 use util::{ResultExt, debug_panic, rel_path::RelPath};
-use workspace::{Workspace, notifications::NotifyResultExt as _};
+use workspace::{
+    Workspace,
+    notifications::{NotifyResultExt as _, NotifyTaskExt as _},
+};
 use zed_actions::agent::Chat;
 
 pub struct MessageEditor {
@@ -62,6 +73,8 @@ pub struct MessageEditor {
     prompt_capabilities: Rc<RefCell<acp::PromptCapabilities>>,
     available_commands: Rc<RefCell<Vec<acp::AvailableCommand>>>,
     agent_name: SharedString,
+    /// Whether the message asks the agent for a JSON response, until it's sent.
+    structured_response: bool,
     _subscriptions: Vec<Subscription>,
     _parse_slash_command_task: Task<()>,
 }
@@ -78,6 +91,11 @@ impl EventEmitter<MessageEditorEvent> for MessageEditor {}
 
 const COMMAND_HINT_INLAY_ID: InlayId = InlayId::Hint(0);
 
+/// Highlights mentions and code fences of the prompt.
+const AGENT_PROMPT_LANGUAGE: &str = "Agent Prompt";
+
+enum SlashCommandHighlight {}
+
 impl MessageEditor {
     pub fn new(
         workspace: WeakEntity<Workspace>,
@@ -92,6 +110,7 @@ impl MessageEditor {
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
+        // Used until the registered language, which highlights the prompt, has loaded.
         let language = Language::new(
             language::LanguageConfig {
                 completion_query_characters: HashSet::from_iter(['.', '-', '_', '@']),
@@ -127,6 +146,22 @@ impl MessageEditor {
             editor
         });
 
+        if let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() {
+            let language = project
+                .read(cx)
+                .languages()
+                .language_for_name(AGENT_PROMPT_LANGUAGE);
+            cx.spawn(async move |_, cx| {
+                // Without the registered language, e.g. in tests, the fallback stays.
+                if let Ok(language) = language.await {
+                    buffer
+                        .update(cx, |buffer, cx| buffer.set_language(Some(language), cx))
+                        .ok();
+                }
+            })
+            .detach();
+        }
+
         cx.on_focus_in(&editor.focus_handle(cx), window, |_, _, cx| {
             cx.emit(MessageEditorEvent::Focus)
         })
@@ -161,6 +196,7 @@ impl MessageEditor {
                         );
                         has_hint = has_new_hint;
 
+                        this.highlight_command_name(editor, cx);
                         editor.snapshot(window, cx)
                     });
                     this.mention_set.remove_invalid(snapshot);
@@ -169,6 +205,14 @@ impl MessageEditor {
                 }
             }
         }));
+        // The highlight keeps the accent color of the theme it was made with.
+        subscriptions.push(cx.observe_global::<GlobalTheme>(|this, cx| {
+            this.editor.update(cx, |editor, cx| {
+                if !editor.read_only(cx) {
+                    this.highlight_command_name(editor, cx);
+                }
+            });
+        }));
 
         Self {
             editor,
@@ -180,11 +224,82 @@ impl MessageEditor {
             prompt_capabilities,
             available_commands,
             agent_name,
+            structured_response: false,
             _subscriptions: subscriptions,
             _parse_slash_command_task: Task::ready(()),
         }
     }
 
+    fn highlight_command_name(&self, editor: &mut Editor, cx: &mut Context<Editor>) {
+        match self.command_name_range(editor.buffer(), cx) {
+            Some(range) => editor.highlight_text::<SlashCommandHighlight>(
+                vec![range],
+                HighlightStyle {
+                    color: Some(cx.theme().colors().text_accent),
+                    font_weight: Some(FontWeight::SEMIBOLD),
+                    ..Default::default()
+                },
+                cx,
+            ),
+            None => editor.clear_highlights::<SlashCommandHighlight>(cx),
+        }
+    }
+
+    /// The range of the name of a known command at the start of the prompt.
+    fn command_name_range(&self, buffer: &Entity<MultiBuffer>, cx: &App) -> Option<Range<Anchor>> {
+        let available_commands = self.available_commands.borrow();
+        let snapshot = buffer.read(cx).snapshot(cx);
+        let command_name = SlashCommandCompletion::try_parse(&snapshot.text(), 0)?.command?;
+        if !available_commands
+            .iter()
+            .any(|command| command.name == command_name)
+            && self.composer_command(&command_name, cx).is_none()
+        {
+            return None;
+        }
+        Some(snapshot.anchor_before(0)..snapshot.anchor_after(1 + command_name.len()))
+    }
+
+    /// The command run by the message editor for the name, unless the agent has a command
+    /// with the same name.
+    fn composer_command(&self, name: &str, cx: &App) -> Option<Rc<dyn ComposerCommand>> {
+        if self
+            .available_commands
+            .borrow()
+            .iter()
+            .any(|command| command.name == name)
+        {
+            return None;
+        }
+        composer_command(name, cx)
+    }
+
+    /// Runs the command at the start of the message if it's one of the message editor,
+    /// returning whether it was run instead of sending the message.
+    pub fn run_composer_command(&mut self, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let text = self.editor.read(cx).text(cx);
+        let Some(command_name) =
+            SlashCommandCompletion::try_parse(&text, 0).and_then(|parsed| parsed.command)
+        else {
+            return false;
+        };
+        let Some(command) = self.composer_command(&command_name, cx) else {
+            return false;
+        };
+
+        // Remove the command, keeping the rest of the message.
+        let name_end = 1 + command_name.len();
+        let command_end = text.len() - text[name_end..].trim_start().len();
+        self.editor.update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let range = snapshot.anchor_before(0)..snapshot.anchor_after(command_end);
+            editor.edit([(range, "")], cx);
+            editor.clear_inlay_hints(cx);
+        });
+        command.run(self, window, cx);
+        true
+    }
+
     fn command_hint(&self, buffer: &Entity<MultiBuffer>, cx: &App) -> Option<Inlay> {
         let available_commands = self.available_commands.borrow();
         if available_commands.is_empty() {
@@ -272,6 +387,15 @@ impl MessageEditor {
         self.editor.read(cx).is_empty(cx)
     }
 
+    pub fn structured_response(&self) -> bool {
+        self.structured_response
+    }
+
+    pub fn set_structured_response(&mut self, structured_response: bool, cx: &mut Context<Self>) {
+        self.structured_response = structured_response;
+        cx.notify();
+    }
+
     pub fn mentions(&self) -> HashSet<MentionUri> {
         self.mention_set
             .mentions
@@ -377,6 +501,17 @@ impl MessageEditor {
                 debug_panic!("unexpected selection URI");
                 Task::ready(Err(anyhow!("unexpected selection URI")))
             }
+            MentionUri::WorkspaceState => {
+                debug_panic!("workspace state URI should not be included in completions");
+                Task::ready(Err(anyhow!(
+                    "workspace state URI should not be included in completions"
+                )))
+            }
+            MentionUri::ProjectMemory => self.confirm_mention_for_project_memory(cx),
+            MentionUri::Diagnostics => self.confirm_mention_for_diagnostics(cx),
+            MentionUri::Source { source, id, .. } => {
+                self.confirm_mention_for_source(&source, id, cx)
+            }
         };
         let task = cx
             .spawn(async move |_, _| task.await.map_err(|e| e.to_string()))
@@ -429,14 +564,14 @@ impl MessageEditor {
             return cx.spawn(async move |_, cx| {
                 let image = task.await?;
                 let image = image.update(cx, |image, _| image.image.clone())?;
-                let format = image.format;
                 let image = cx
                     .update(|cx| LanguageModelImage::from_image(image, cx))?
                     .await;
                 if let Some(image) = image {
+                    // Images are converted to PNG, whatever their original format.
                     Ok(Mention::Image(MentionImage {
                         data: image.source,
-                        format,
+                        format: ImageFormat::Png,
                     }))
                 } else {
                     Err(anyhow!("Failed to convert image"))
@@ -456,8 +591,20 @@ impl MessageEditor {
             )
             .await?;
 
+            let mut content = buffer_content.text;
+            let include_reference_counts = cx.update(|cx| {
+                agent_settings::AgentSettings::get_global(cx).include_symbol_reference_counts
+            })?;
+            if include_reference_counts
+                && let Some(table) = buffer.read_with(cx, |buffer, cx| {
+                    symbol_reference_table(&buffer.snapshot(), cx)
+                })?
+            {
+                content.push_str(&table);
+            }
+
             Ok(Mention::Text {
-                content: buffer_content.text,
+                content,
                 tracked_buffers: vec![buffer],
             })
         })
@@ -533,6 +680,73 @@ impl MessageEditor {
         })
     }
 
+    fn confirm_mention_for_project_memory(
+        &mut self,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Mention>> {
+        let memory = ProjectMemory::load(&self.project, cx);
+        cx.spawn(async move |_, cx| {
+            let content = memory
+                .await
+                .read_with(cx, |memory, _| memory.to_markdown())?
+                .ok_or_else(|| anyhow!("the project memory is empty"))?;
+            Ok(Mention::Text {
+                content,
+                tracked_buffers: Vec::new(),
+            })
+        })
+    }
+
+    fn confirm_mention_for_diagnostics(&mut self, cx: &mut Context<Self>) -> Task<Result<Mention>> {
+        let project = self.project.clone();
+        let path_style = project.read(cx).path_style(cx);
+        let mut paths = project
+            .read(cx)
+            .diagnostic_summaries(false, cx)
+            .filter(|(_, _, summary)| summary.error_count > 0 || summary.warning_count > 0)
+            .map(|(path, _, _)| path)
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+
+        cx.spawn(async move |_, cx| {
+            let mut output = SlashCommandOutput::default();
+            for path in paths {
+                let buffer = project
+                    .update(cx, |project, cx| project.open_buffer(path.clone(), cx))?
+                    .await?;
+                let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot())?;
+                writeln!(output.text, "{}", path.path.display(path_style)).ok();
+                collect_buffer_diagnostics(&mut output, &snapshot, true);
+            }
+            if output.text.is_empty() {
+                return Err(anyhow!("the project has no errors or warnings"));
+            }
+            Ok(Mention::Text {
+                content: output.text,
+                tracked_buffers: Vec::new(),
+            })
+        })
+    }
+
+    fn confirm_mention_for_source(
+        &mut self,
+        keyword: &str,
+        id: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Mention>> {
+        let Some(source) = AgentSessions::global(cx).read(cx).mention_source(keyword) else {
+            return Task::ready(Err(anyhow!("no mention source for @{keyword}")));
+        };
+        let content = source.load(id, cx);
+        cx.background_spawn(async move {
+            Ok(Mention::Text {
+                content: content.await?,
+                tracked_buffers: Vec::new(),
+            })
+        })
+    }
+
     pub fn confirm_mention_for_selection(
         &mut self,
         source_range: Range<text::Anchor>,
@@ -805,6 +1019,7 @@ impl MessageEditor {
     }
 
     pub fn clear(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.structured_response = false;
         self.editor.update(cx, |editor, cx| {
             editor.clear(window, cx);
             editor.remove_creases(
@@ -859,7 +1074,40 @@ impl MessageEditor {
             return;
         }
         cx.stop_propagation();
+        self.insert_images(images, window, cx);
+    }
 
+    /// Asks for image files to attach to the message, from inside or outside of the project.
+    pub fn attach_images(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.prompt_capabilities.borrow().image {
+            return;
+        }
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: true,
+            prompt: Some("Attach Images".into()),
+        });
+        let fs = self.project.read(cx).fs().clone();
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(paths) = paths.await?? else {
+                return Ok(());
+            };
+            let mut images = Vec::new();
+            for path in paths {
+                let format = image_format_for_path(&path)
+                    .with_context(|| format!("{} is not a supported image", path.display()))?;
+                images.push(Image::from_bytes(format, fs.load_bytes(&path).await?));
+            }
+            this.update_in(cx, |this, window, cx| {
+                this.insert_images(images, window, cx)
+            })
+        })
+        .detach_and_notify_err(window, cx);
+    }
+
+    /// Inserts the images at the end of the message, converted for sending to the agent.
+    fn insert_images(&mut self, images: Vec<Image>, window: &mut Window, cx: &mut Context<Self>) {
         let replacement_text = MentionUri::PastedImage.as_link().to_string();
         for image in images {
             let (excerpt_id, text_anchor, multibuffer_anchor) =
@@ -907,7 +1155,6 @@ impl MessageEditor {
             let task = cx
                 .spawn_in(window, {
                     async move |_, cx| {
-                        let format = image.format;
                         let image = cx
                             .update(|_, cx| LanguageModelImage::from_image(image, cx))
                             .map_err(|e| e.to_string())?
@@ -916,7 +1163,7 @@ impl MessageEditor {
                         if let Some(image) = image {
                             Ok(Mention::Image(MentionImage {
                                 data: image.source,
-                                format,
+                                format: ImageFormat::Png,
                             }))
                         } else {
                             Err("Failed to convert image".into())
@@ -1008,6 +1255,66 @@ impl MessageEditor {
         .detach();
     }
 
+    /// Mentions the files open in the tabs of the workspace.
+    pub fn insert_open_tabs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let mut paths = Vec::new();
+        for item in workspace.read(cx).items(cx) {
+            if let Some(path) = item.project_path(cx)
+                && !paths.contains(&path)
+            {
+                paths.push(path);
+            }
+        }
+        self.insert_dragged_files(paths, Vec::new(), window, cx);
+    }
+
+    /// Mentions the errors and warnings of the project.
+    pub fn insert_diagnostics(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let buffer = self.editor.read(cx).buffer().clone();
+        let Some(buffer) = buffer.read(cx).as_singleton() else {
+            return;
+        };
+        let uri = MentionUri::Diagnostics;
+        let new_text = format!("{} ", uri.as_link());
+        let content_len = new_text.len() - 1;
+        let anchor = buffer.update(cx, |buffer, _cx| buffer.anchor_before(buffer.len()));
+        self.editor.update(cx, |message_editor, cx| {
+            message_editor.edit(
+                [(
+                    multi_buffer::Anchor::max()..multi_buffer::Anchor::max(),
+                    new_text,
+                )],
+                cx,
+            );
+        });
+        self.confirm_mention_completion(uri.name().into(), anchor, content_len, uri, window, cx)
+            .detach();
+    }
+
+    /// Starts a mention of the given kind at the end of the message, showing the
+    /// completions to pick it from.
+    pub(crate) fn start_mention(
+        &mut self,
+        mode: ContextPickerMode,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.editor.update(cx, |editor, cx| {
+            editor.move_to_end(&MoveToEnd, window, cx);
+            let text = editor.text(cx);
+            let separator = if text.is_empty() || text.ends_with(char::is_whitespace) {
+                ""
+            } else {
+                " "
+            };
+            editor.insert(&format!("{separator}@{} ", mode.keyword()), window, cx);
+            editor.show_completions(&ShowCompletions::default(), window, cx);
+        });
+    }
+
     pub fn insert_selections(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let editor = self.editor.read(cx);
         let editor_buffer = editor.buffer().read(cx);
@@ -1139,13 +1446,19 @@ impl MessageEditor {
 
         for (range, mention_uri, mention) in mentions {
             let anchor = snapshot.anchor_before(range.start);
+            let image = match &mention {
+                Mention::Image(image) => image
+                    .to_image()
+                    .map(|image| Task::ready(Ok(Arc::new(image))).shared()),
+                _ => None,
+            };
             let Some((crease_id, tx)) = insert_crease_for_mention(
                 anchor.excerpt_id,
                 anchor.text_anchor,
                 range.end - range.start,
                 mention_uri.name().into(),
                 mention_uri.icon_path(cx),
-                None,
+                image,
                 self.editor.clone(),
                 window,
                 cx,
@@ -1166,6 +1479,26 @@ impl MessageEditor {
         self.editor.read(cx).text(cx)
     }
 
+    /// The number of lines of the message, not counting soft wraps.
+    pub fn line_count(&self, cx: &App) -> u32 {
+        self.editor
+            .read(cx)
+            .buffer()
+            .read(cx)
+            .snapshot(cx)
+            .max_point()
+            .row
+            + 1
+    }
+
+    /// Inserts a quote before the message, leaving the cursor at the end of it.
+    pub fn insert_quote(&mut self, quote: &str, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor.update(cx, |editor, cx| {
+            editor.edit([(0..0, format!("{quote}\n\n"))], cx);
+            editor.move_to_end(&editor::actions::MoveToEnd, window, cx);
+        });
+    }
+
     #[cfg(test)]
     pub fn set_text(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
         self.editor.update(cx, |editor, cx| {
@@ -1392,6 +1725,17 @@ fn render_mention_fold_button(
             })
             .ok();
         });
+        let load_thumbnail = image_task.clone().map(|image_task| {
+            cx.spawn(async move |this, cx| {
+                if let Ok(image) = image_task.await {
+                    this.update(cx, |this: &mut LoadingContext, cx| {
+                        this.thumbnail = Some(image);
+                        cx.notify();
+                    })
+                    .ok();
+                }
+            })
+        });
         LoadingContext {
             id: cx.entity_id(),
             label,
@@ -1400,6 +1744,8 @@ fn render_mention_fold_button(
             editor,
             loading: Some(loading),
             image: image_task.clone(),
+            thumbnail: None,
+            _load_thumbnail: load_thumbnail,
         }
     });
     Arc::new(move |_fold_id, _fold_range, _cx| loading.clone().into_any_element())
@@ -1413,6 +1759,9 @@ struct LoadingContext {
     editor: WeakEntity<Editor>,
     loading: Option<Task<()>>,
     image: Option<Shared<Task<Result<Arc<Image>, String>>>>,
+    /// Shown in place of the icon once the image is loaded.
+    thumbnail: Option<Arc<Image>>,
+    _load_thumbnail: Option<Task<()>>,
 }
 
 impl Render for LoadingContext {
@@ -1448,11 +1797,14 @@ impl Render for LoadingContext {
             .child(
                 h_flex()
                     .gap_1()
-                    .child(
-                        Icon::from_path(self.icon.clone())
-                            .size(IconSize::XSmall)
-                            .color(Color::Muted),
-                    )
+                    .map(|this| match self.thumbnail.clone() {
+                        Some(thumbnail) => this.child(gpui::img(thumbnail).size_3p5().rounded_xs()),
+                        None => this.child(
+                            Icon::from_path(self.icon.clone())
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                        ),
+                    })
                     .child(
                         Label::new(self.label.clone())
                             .size(LabelSize::Small)
@@ -1492,6 +1844,23 @@ impl Render for ImageHover {
     }
 }
 
+/// Formats the reference counts known for the buffer's outline symbols, so the agent
+/// can tell heavily used symbols apart from unreferenced ones.
+fn symbol_reference_table(buffer: &BufferSnapshot, cx: &App) -> Option<String> {
+    let mut table = String::new();
+    for item in buffer.outline(None).items {
+        if let Some(count) = SymbolReferenceCounts::count_at(buffer, item.range.start, cx) {
+            writeln!(table, "{}\t{count}", item.text).ok();
+        }
+    }
+    if table.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "\n\nReference counts (symbol\treferences):\n{table}"
+    ))
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Mention {
     Text {
@@ -1508,6 +1877,22 @@ pub struct MentionImage {
     pub format: ImageFormat,
 }
 
+impl MentionImage {
+    /// Decodes the image, to be rendered.
+    fn to_image(&self) -> Option<Image> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(self.data.as_bytes())
+            .log_err()?;
+        Some(Image::from_bytes(self.format, bytes))
+    }
+}
+
+/// The format of an image file, judging by its extension.
+fn image_format_for_path(path: &Path) -> Option<ImageFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    ImageFormat::from_mime_type(&format!("image/{extension}"))
+}
+
 #[derive(Default)]
 pub struct MentionSet {
     mentions: HashMap<CreaseId, (MentionUri, Shared<Task<Result<Mention, String>>>)>,
@@ -1607,6 +1992,7 @@ mod tests {
     use workspace::{AppState, Item, Workspace};
 
     use crate::acp::{
+        composer_commands::{ComposerCommand, register_composer_command},
         message_editor::{Mention, MessageEditor},
         thread_view::tests::init_test,
     };
@@ -1844,6 +2230,108 @@ mod tests {
         }
     }
 
+    #[gpui::test]
+    async fn test_composer_commands(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        struct GreetCommand;
+
+        impl ComposerCommand for GreetCommand {
+            fn name(&self) -> SharedString {
+                "greet".into()
+            }
+
+            fn description(&self) -> SharedString {
+                "Greet the agent".into()
+            }
+
+            fn run(
+                &self,
+                message_editor: &mut MessageEditor,
+                window: &mut Window,
+                cx: &mut Context<MessageEditor>,
+            ) {
+                let text = message_editor.text(cx);
+                message_editor.set_text(&format!("Hello! {text}"), window, cx);
+            }
+        }
+
+        cx.update(|cx| register_composer_command(GreetCommand, cx));
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/test", json!({})).await;
+        let project = Project::test(fs.clone(), ["/test".as_ref()], cx).await;
+        let text_thread_store = cx.new(|cx| TextThreadStore::fake(project.clone(), cx));
+        let history_store = cx.new(|cx| HistoryStore::new(text_thread_store, cx));
+        let available_commands = Rc::new(RefCell::new(vec![]));
+
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        let message_editor = workspace.update_in(cx, |workspace, window, cx| {
+            let workspace_handle = workspace.weak_handle();
+            cx.new(|cx| {
+                MessageEditor::new(
+                    workspace_handle,
+                    project.clone(),
+                    history_store.clone(),
+                    None,
+                    Rc::default(),
+                    available_commands.clone(),
+                    "Test Agent".into(),
+                    "Test",
+                    EditorMode::AutoHeight {
+                        min_lines: 1,
+                        max_lines: None,
+                    },
+                    window,
+                    cx,
+                )
+            })
+        });
+
+        // The command is replaced by what it expands into.
+        message_editor.update_in(cx, |message_editor, window, cx| {
+            message_editor.set_text("/greet how are you?", window, cx);
+            assert!(message_editor.run_composer_command(window, cx));
+            assert_eq!(message_editor.text(cx), "Hello! how are you?");
+        });
+
+        message_editor.update_in(cx, |message_editor, window, cx| {
+            message_editor.set_text("/clear some text", window, cx);
+            assert!(message_editor.run_composer_command(window, cx));
+            assert_eq!(message_editor.text(cx), "");
+        });
+
+        message_editor.update_in(cx, |message_editor, window, cx| {
+            message_editor.set_text("/json list the crates", window, cx);
+            assert!(message_editor.run_composer_command(window, cx));
+            assert_eq!(message_editor.text(cx), "list the crates");
+            assert!(message_editor.structured_response());
+            message_editor.clear(window, cx);
+            assert!(!message_editor.structured_response());
+        });
+
+        // Messages without a command are sent as they are.
+        message_editor.update_in(cx, |message_editor, window, cx| {
+            message_editor.set_text("clear the cache", window, cx);
+            assert!(!message_editor.run_composer_command(window, cx));
+            assert_eq!(message_editor.text(cx), "clear the cache");
+        });
+
+        // The commands of the agent take precedence.
+        available_commands.replace(vec![acp::AvailableCommand {
+            name: "clear".to_string(),
+            description: "Clear the conversation".to_string(),
+            input: None,
+            meta: None,
+        }]);
+        message_editor.update_in(cx, |message_editor, window, cx| {
+            message_editor.set_text("/clear", window, cx);
+            assert!(!message_editor.run_composer_command(window, cx));
+            assert_eq!(message_editor.text(cx), "/clear");
+        });
+    }
+
     struct MessageEditorItem(Entity<MessageEditor>);
 
     impl Item for MessageEditorItem {
@@ -1955,7 +2443,15 @@ mod tests {
                 current_completion_labels_with_documentation(editor),
                 &[
                     ("quick-math".into(), "2 + 2 = 4 - 1 = 3".into()),
-                    ("say-hello".into(), "Say hello to whoever you want".into())
+                    ("say-hello".into(), "Say hello to whoever you want".into()),
+                    ("file".into(), "Mention a file of the project".into()),
+                    ("tab".into(), "Mention the files open in tabs".into()),
+                    (
+                        "diagnostics".into(),
+                        "Attach the errors and warnings of the project".into()
+                    ),
+                    ("clear".into(), "Clear the message and its context".into()),
+                    ("new".into(), "Start a new thread with the agent".into()),
                 ]
             );
             editor.set_text("", window, cx);
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result, bail};
+use futures::AsyncReadExt as _;
+use http_client::{AsyncBody, HttpClient, Method, Request};
+use serde::Deserialize;
+
+const GISTS_URL: &str = "https://api.github.com/gists";
+
+#[derive(Deserialize)]
+struct Gist {
+    html_url: String,
+}
+
+/// Uploads a thread as a secret GitHub gist and returns its URL. Anyone with the URL can
+/// read the gist, which is created with the `GITHUB_TOKEN` of the environment.
+pub(crate) async fn share_as_gist(
+    title: String,
+    markdown: String,
+    http_client: Arc<dyn HttpClient>,
+) -> Result<String> {
+    let token = std::env::var("GITHUB_TOKEN")
+        .context("set GITHUB_TOKEN to a token that can create gists")?;
+    let mut files = serde_json::Map::new();
+    files.insert(
+        gist_file_name(&title),
+        serde_json::json!({ "content": markdown }),
+    );
+    let body = serde_json::json!({
+        "description": title,
+        "public": false,
+        "files": files,
+    });
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(GISTS_URL)
+        .header("Accept", "application/vnd.github+json")
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(&body)?))?;
+    let mut response = http_client.send(request).await?;
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+    if !response.status().is_success() {
+        bail!("GitHub responded with {}: {body}", response.status());
+    }
+    let gist: Gist = serde_json::from_str(&body).context("failed to parse the created gist")?;
+    Ok(gist.html_url)
+}
+
+/// A markdown file name made of the words of the thread's title.
+fn gist_file_name(title: &str) -> String {
+    let name = title
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if name.is_empty() {
+        "thread.md".to_string()
+    } else {
+        format!("{name}.md")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gist_file_name() {
+        assert_eq!(gist_file_name("Fix the build"), "Fix-the-build.md");
+        assert_eq!(gist_file_name("What's `foo`?"), "What-s-foo.md");
+        assert_eq!(gist_file_name("—"), "thread.md");
+    }
+}
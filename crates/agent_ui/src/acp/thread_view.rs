@@ -1,14 +1,16 @@
 use acp_thread::{
     AcpThread, AcpThreadEvent, AgentThreadEntry, AssistantMessage, AssistantMessageChunk,
-    AuthRequired, LoadError, MentionUri, RetryStatus, ThreadStatus, ToolCall, ToolCallContent,
-    ToolCallStatus, UserMessageId,
+    AuthRequired, LoadError, MentionUri, RetryStatus, ThreadStatus, ToolCall, ToolCallConfirmation,
+    ToolCallContent, ToolCallStatus, UserMessageId,
 };
-use acp_thread::{AgentConnection, Plan};
+use acp_thread::{AgentConnection, AgentSessionFileChanges, Plan};
 use action_log::ActionLog;
-use agent::{DbThreadMetadata, HistoryEntry, HistoryEntryId, HistoryStore, NativeAgentServer};
+use agent::{
+    DbThreadMetadata, HistoryEntry, HistoryEntryId, HistoryStore, NativeAgentServer, ProjectMemory,
+};
 use agent_client_protocol::{self as acp, PromptCapabilities};
 use agent_servers::{AgentServer, AgentServerDelegate};
-use agent_settings::{AgentProfileId, AgentSettings, CompletionMode};
+use agent_settings::{AgentProfileId, AgentSettings, CompletionMode, IssueLinkSettings};
 use anyhow::{Result, anyhow, bail};
 use arrayvec::ArrayVec;
 use audio::{Audio, Sound};
@@ -16,6 +18,8 @@ use buffer_diff::BufferDiff;
 use client::zed_urls;
 use cloud_llm_client::PlanV1;
 use collections::{HashMap, HashSet};
+use db::kvp::KEY_VALUE_STORE;
+use editor::actions::{MoveDown, MoveUp};
 use editor::scroll::Autoscroll;
 use editor::{Editor, EditorEvent, EditorMode, MultiBuffer, PathKey, SelectionEffects};
 use file_icons::FileIcons;
@@ -23,54 +27,91 @@ use fs::Fs;
 use futures::FutureExt as _;
 use gpui::{
     Action, Animation, AnimationExt, AnyView, App, BorderStyle, ClickEvent, ClipboardItem,
-    CursorStyle, EdgesRefinement, ElementId, Empty, Entity, FocusHandle, Focusable, Hsla, Length,
-    ListOffset, ListState, PlatformDisplay, SharedString, StyleRefinement, Subscription, Task,
-    TextStyle, TextStyleRefinement, UnderlineStyle, WeakEntity, Window, WindowHandle, div,
-    ease_in_out, linear_color_stop, linear_gradient, list, point, pulsating_between,
+    CursorStyle, EdgesRefinement, ElementId, Empty, Entity, EntityId, FocusHandle, Focusable,
+    Global, Hsla, Length, ListOffset, ListState, PlatformDisplay, SharedString, StyleRefinement,
+    Subscription, Task, TextStyle, TextStyleRefinement, UnderlineStyle, WeakEntity, WhiteSpace,
+    Window, WindowHandle, div, ease_in_out, linear_color_stop, linear_gradient, list, point,
+    pulsating_between,
 };
-use language::Buffer;
+use language::{Buffer, Language};
 
 use language_model::LanguageModelRegistry;
-use markdown::{HeadingLevelStyles, Markdown, MarkdownElement, MarkdownStyle};
-use project::{Project, ProjectEntryId};
+use markdown::{
+    HeadingLevelStyles, LinkDetector, Markdown, MarkdownElement, MarkdownStyle, PathWithRange,
+};
+use project::{
+    Project, ProjectEntryId, ProjectItem as _, ProjectPath, UpdatedEntriesSet, WorktreeId,
+    git_store::GitStoreEvent,
+};
 use prompt_store::{PromptId, PromptStore};
 use rope::Point;
-use settings::{NotifyWhenAgentWaiting, Settings as _, SettingsStore};
+use settings::{AgentNotificationKind, NotifyWhenAgentWaiting, Settings as _, SettingsStore};
 use std::cell::RefCell;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
-use std::{collections::BTreeMap, rc::Rc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+    ops::Range,
+    rc::Rc,
+    time::Duration,
+};
 use terminal_view::terminal_panel::TerminalPanel;
 use text::Anchor;
-use theme::{AgentFontSize, ThemeSettings};
+use theme::{AgentFontSize, GlobalTheme, ThemeSettings};
 use ui::{
-    Callout, CommonAnimationExt, Disclosure, Divider, DividerColor, ElevationIndex, KeyBinding,
-    PopoverMenuHandle, SpinnerLabel, TintColor, Tooltip, WithScrollbar, prelude::*,
+    ButtonLike, Callout, CommonAnimationExt, Disclosure, Divider, DividerColor, ElevationIndex,
+    KeyBinding, PopoverMenuHandle, SpinnerLabel, TintColor, Tooltip, WithScrollbar, prelude::*,
 };
-use util::{ResultExt, size::format_file_size, time::duration_alt_display};
-use workspace::{CollaboratorId, Workspace};
-use zed_actions::agent::{Chat, ToggleModelSelector};
+use util::{ResultExt, paths::PathExt, size::format_file_size, time::duration_alt_display};
+use workspace::{CollaboratorId, SaveIntent, Toast, Workspace, notifications::NotificationId};
+use zed_actions::agent::{AddSelectionToThread, Chat, ToggleModelSelector};
 use zed_actions::assistant::OpenRulesLibrary;
 
 use super::entry_view_state::EntryViewState;
 use crate::acp::AcpModelSelectorPopover;
 use crate::acp::ModeSelector;
 use crate::acp::entry_view_state::{EntryViewEvent, ViewEvent};
+use crate::acp::issue_links::{issue_link_detector, issue_link_settings};
 use crate::acp::message_editor::{MessageEditor, MessageEditorEvent};
+use crate::acp::thread_share::share_as_gist;
 use crate::agent_diff::AgentDiff;
 use crate::profile_selector::{ProfileProvider, ProfileSelector};
 
 use crate::ui::{
-    AgentNotification, AgentNotificationEvent, BurnModeTooltip, UnavailableEditingTooltip,
-    UsageCallout,
+    AgentNotification, AgentNotificationEvent, BurnModeTooltip, ProjectMemoryModal,
+    UnavailableEditingTooltip, UsageCallout,
 };
 use crate::{
-    AgentDiffPane, AgentPanel, AllowAlways, AllowOnce, ContinueThread, ContinueWithBurnMode,
-    CycleModeSelector, ExpandMessageEditor, Follow, KeepAll, OpenAgentDiff, OpenHistory, RejectAll,
-    RejectOnce, ToggleBurnMode, ToggleProfileSelector,
+    AgentDiffPane, AgentPanel, AllowAlways, AllowOnce, CancelGeneration, CollapseAllEntries,
+    ContinueThread, ContinueWithBurnMode, CycleModeSelector, ExpandAllEntries, ExpandMessageEditor,
+    Follow, KeepAll, OpenAgentDiff, OpenHistory, RejectAll, RejectOnce, SelectNextEntry,
+    SelectPermissionOption, SelectPreviousEntry, ToggleAutoAcceptEdits, ToggleBurnMode,
+    TogglePlanMode, ToggleProfileSelector, ToggleThinking,
 };
 
+/// How often the memory use of an external agent's process is sampled.
+const AGENT_PROCESS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long files have to stop changing before the agent is told about them, so that a
+/// checkout or a formatter run is sent as one notification.
+const FILE_CHANGES_DEBOUNCE: Duration = Duration::from_millis(500);
+/// How long typing has to pause before the unsent message is saved.
+const DRAFT_SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+const DRAFT_KEY_PREFIX: &str = "agent_thread_draft";
+const BRANCHES_KEY_PREFIX: &str = "agent_thread_branches";
+
+/// User messages longer than this are collapsed until expanded.
+const COLLAPSED_USER_MESSAGE_LINES: u32 = 12;
+
+/// How many lines of an entry are quoted when asking a follow-up about it.
+const FOLLOW_UP_QUOTE_LINES: usize = 8;
+
+/// How many members of a JSON object or array in a tool call's output are shown.
+const JSON_TREE_MAX_CHILDREN: usize = 100;
+/// How many characters of a JSON string in a tool call's output are shown.
+const JSON_TREE_MAX_VALUE_LEN: usize = 200;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum ThreadFeedback {
     Positive,
@@ -269,6 +310,8 @@ pub struct AcpThreadView {
     entry_view_state: Entity<EntryViewState>,
     message_editor: Entity<MessageEditor>,
     focus_handle: FocusHandle,
+    /// Focuses the first tool call waiting for confirmation, to pick an option by its number.
+    confirmation_focus_handle: FocusHandle,
     model_selector: Option<Entity<AcpModelSelectorPopover>>,
     profile_selector: Option<Entity<ProfileSelector>>,
     notifications: Vec<WindowHandle<AgentNotification>>,
@@ -279,28 +322,606 @@ pub struct AcpThreadView {
     list_state: ListState,
     auth_task: Option<Task<()>>,
     expanded_tool_calls: HashSet<acp::ToolCallId>,
+    /// Tool calls whose command scrolls horizontally instead of soft wrapping.
+    unwrapped_commands: HashSet<acp::ToolCallId>,
+    /// Countdowns of tool calls that are allowed once they run out, or `None` when canceled.
+    auto_approve_countdowns: HashMap<acp::ToolCallId, Option<AutoApproveCountdown>>,
+    /// Earlier continuations of each edited user message, by its id.
+    message_branches: HashMap<UserMessageId, MessageBranches>,
+    /// The branches of a message being edited, until the message replacing it is added.
+    rewound_branches: Option<MessageBranches>,
+    show_branch_tree: bool,
+    /// While set, the transcript is shown as text in this read-only editor, so that
+    /// any span across entries can be selected and copied.
+    transcript_selection: Option<Entity<Editor>>,
+    /// Entries before this index have their views released to bound memory use. They stay
+    /// in the thread, and aren't rendered until they're loaded again.
+    first_live_entry: usize,
+    /// Whether earlier entries were loaded, after which the views are kept as new entries
+    /// arrive, rather than releasing the loaded ones again.
+    loaded_earlier_entries: bool,
+    agent_process_usage: Option<AgentProcessUsage>,
+    dismissed_memory_warning: bool,
+    _agent_process_poll_task: Option<Task<()>>,
     expanded_thinking_blocks: HashSet<(usize, usize)>,
+    /// Long user messages that were expanded, by entry index.
+    expanded_user_messages: HashSet<usize>,
+    /// Attachments of user messages whose content is shown, by entry and attachment index.
+    expanded_attachments: HashSet<(usize, usize)>,
+    /// Turns whose stats are shown in detail, by the entry index of their user message.
+    expanded_turn_stats: HashSet<usize>,
+    /// Tool call outputs that are JSON, parsed once the tool call finished, by the entity
+    /// of their markdown. `None` for outputs that aren't JSON.
+    json_outputs: RefCell<HashMap<EntityId, Option<Rc<serde_json::Value>>>>,
+    /// Expanded nodes of JSON outputs, by the entity of their markdown and their path.
+    expanded_json_nodes: HashSet<(EntityId, SharedString)>,
+    issue_link_settings: IssueLinkSettings,
+    /// Links the references to issues in the thread's markdown, if configured.
+    issue_link_detector: Option<LinkDetector>,
+    context_summary_expanded: bool,
+    /// The git branch checked out in the agent's working directory when last checked.
+    branch: Option<SharedString>,
+    /// Branch switches that happened while the thread was open, shown in the transcript.
+    branch_changes: Vec<BranchChange>,
+    /// The branch switch to tell the agent about with the next message, as the branch it
+    /// was last on and the current one.
+    unsent_branch_change: Option<(SharedString, SharedString)>,
+    /// Files changed on disk that the agent hasn't been told about yet.
+    unsent_file_changes: BTreeSet<PathBuf>,
+    /// Completed tool calls that deleted or moved paths, whose tabs were closed or reopened.
+    synced_path_changes: HashSet<acp::ToolCallId>,
+    _file_changes_task: Option<Task<()>>,
+    _save_draft_task: Option<Task<()>>,
     edits_expanded: bool,
+    file_changes_expanded: bool,
     plan_expanded: bool,
     editor_expanded: bool,
     should_be_following: bool,
     editing_message: Option<usize>,
+    /// The messages sent in this thread, to recall them into the message editor.
+    sent_messages: Vec<Vec<acp::ContentBlock>>,
+    /// The sent message shown in the message editor, with its text to tell whether it was
+    /// edited since.
+    recalled_message: Option<(usize, String)>,
     prompt_capabilities: Rc<RefCell<PromptCapabilities>>,
     available_commands: Rc<RefCell<Vec<acp::AvailableCommand>>>,
     is_loading_contents: bool,
+    /// Whether to send the message editor's contents once the thread has loaded.
+    send_when_ready: bool,
+    /// A prompt to send once the thread has loaded, leaving the message editor as it is.
+    pending_prompt: Option<String>,
     new_server_version_available: Option<SharedString>,
     resume_thread_metadata: Option<DbThreadMetadata>,
     _cancel_task: Option<Task<()>>,
-    _subscriptions: [Subscription; 5],
+    _subscriptions: [Subscription; 8],
     #[cfg(target_os = "windows")]
     show_codex_windows_warning: bool,
 }
 
+/// How a thread was shown when its view was closed, to show it the same way when it's
+/// opened again.
+#[derive(Clone, Default)]
+struct SavedViewState {
+    /// `None` when the thread was scrolled to the bottom.
+    scroll_top: Option<ListOffset>,
+    expanded_tool_calls: HashSet<acp::ToolCallId>,
+    expanded_thinking_blocks: HashSet<(usize, usize)>,
+    expanded_user_messages: HashSet<usize>,
+}
+
+#[derive(Default)]
+struct SavedViewStates(HashMap<acp::SessionId, SavedViewState>);
+
+impl Global for SavedViewStates {}
+
+/// A switch of the git branch in the agent's working directory.
+/// The resource use of the agent process, as last sampled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgentProcessUsage {
+    /// Resident memory, in bytes.
+    pub memory: u64,
+    /// CPU use since the previous sample, in percent of one core.
+    pub cpu: f32,
+}
+
+struct BranchChange {
+    anchor: BranchChangeAnchor,
+    from: SharedString,
+    to: SharedString,
+}
+
+/// Where the notice of a branch switch is shown in the transcript.
+#[derive(Debug, PartialEq)]
+enum BranchChangeAnchor {
+    /// At the end of the turn of this message, the last one when the branch was switched,
+    /// so that the notice stays there when the thread is rewound.
+    Turn(UserMessageId),
+    /// After this entry, the last one when the branch was switched, for agents whose
+    /// messages have no id as their threads can't be rewound.
+    Entry(usize),
+}
+
+/// What happened during the most recent turn, for reviewing it at a glance.
+#[derive(Default)]
+struct TurnSummary {
+    /// Entry index and label of each command that ran.
+    commands: Vec<(usize, SharedString)>,
+    /// Entry and location index of the first tool call that changed each file.
+    changed_files: Vec<(usize, usize, PathBuf)>,
+    /// Entry index and label of each tool call allowed from a confirmation.
+    approvals: Vec<(usize, SharedString)>,
+}
+
+impl TurnSummary {
+    fn for_last_turn(entries: &[AgentThreadEntry], cx: &App) -> Self {
+        let turn_start = entries
+            .iter()
+            .rposition(|entry| matches!(entry, AgentThreadEntry::UserMessage(_)))
+            .map_or(0, |ix| ix + 1);
+        let mut summary = Self::default();
+        for (entry_ix, entry) in entries.iter().enumerate().skip(turn_start) {
+            let AgentThreadEntry::ToolCall(tool_call) = entry else {
+                continue;
+            };
+            let label = SharedString::from(tool_call.label.read(cx).source().to_string());
+            let ran = !matches!(
+                tool_call.status,
+                ToolCallStatus::Rejected
+                    | ToolCallStatus::Canceled
+                    | ToolCallStatus::WaitingForConfirmation { .. }
+            );
+            if ran && tool_call.kind == acp::ToolKind::Execute {
+                summary.commands.push((entry_ix, label.clone()));
+            }
+            let changes_files = matches!(
+                tool_call.kind,
+                acp::ToolKind::Edit | acp::ToolKind::Delete | acp::ToolKind::Move
+            ) || tool_call.diffs().next().is_some();
+            if changes_files && matches!(tool_call.status, ToolCallStatus::Completed) {
+                for (location_ix, location) in tool_call.locations.iter().enumerate() {
+                    if !summary
+                        .changed_files
+                        .iter()
+                        .any(|(_, _, path)| *path == location.path)
+                    {
+                        summary
+                            .changed_files
+                            .push((entry_ix, location_ix, location.path.clone()));
+                    }
+                }
+            }
+            if tool_call.approved {
+                summary.approvals.push((entry_ix, label));
+            }
+        }
+        summary
+    }
+
+    fn is_empty(&self) -> bool {
+        self.commands.is_empty() && self.changed_files.is_empty() && self.approvals.is_empty()
+    }
+}
+
+/// What the agent is doing at some point of a turn, as told by its thoughts and the
+/// kinds of its tool calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TurnPhase {
+    Thinking,
+    Searching,
+    Editing,
+    Verifying,
+}
+
+impl TurnPhase {
+    /// The most phases shown in the timeline, the earlier ones being elided.
+    const MAX_SHOWN: usize = 5;
+
+    fn for_entry(entry: &AgentThreadEntry) -> Option<Self> {
+        match entry {
+            AgentThreadEntry::UserMessage(_) => None,
+            AgentThreadEntry::AssistantMessage(message) => message
+                .chunks
+                .iter()
+                .any(|chunk| matches!(chunk, AssistantMessageChunk::Thought { .. }))
+                .then_some(Self::Thinking),
+            AgentThreadEntry::ToolCall(tool_call) => match tool_call.kind {
+                acp::ToolKind::Think => Some(Self::Thinking),
+                acp::ToolKind::Read | acp::ToolKind::Search | acp::ToolKind::Fetch => {
+                    Some(Self::Searching)
+                }
+                acp::ToolKind::Edit | acp::ToolKind::Delete | acp::ToolKind::Move => {
+                    Some(Self::Editing)
+                }
+                acp::ToolKind::Execute => Some(Self::Verifying),
+                _ => None,
+            },
+        }
+    }
+
+    /// The phases of the last turn in order, with consecutive repeats merged.
+    fn for_last_turn(entries: &[AgentThreadEntry]) -> Vec<Self> {
+        let turn_start = entries
+            .iter()
+            .rposition(|entry| matches!(entry, AgentThreadEntry::UserMessage(_)))
+            .map_or(0, |ix| ix + 1);
+        let mut phases = entries[turn_start..]
+            .iter()
+            .filter_map(Self::for_entry)
+            .collect::<Vec<_>>();
+        phases.dedup();
+        phases
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Thinking => "Thinking",
+            Self::Searching => "Searching",
+            Self::Editing => "Editing",
+            Self::Verifying => "Verifying",
+        }
+    }
+}
+
+/// The mentions a user message was sent with, in order and without duplicates.
+fn user_message_attachments(chunks: &[acp::ContentBlock]) -> Vec<MentionUri> {
+    let mut attachments = Vec::new();
+    for chunk in chunks {
+        let mention = match chunk {
+            acp::ContentBlock::Resource(acp::EmbeddedResource {
+                resource: acp::EmbeddedResourceResource::TextResourceContents(resource),
+                ..
+            }) => MentionUri::parse(&resource.uri).ok(),
+            acp::ContentBlock::Resource(acp::EmbeddedResource {
+                resource: acp::EmbeddedResourceResource::BlobResourceContents(resource),
+                ..
+            }) => MentionUri::parse(&resource.uri).ok(),
+            acp::ContentBlock::ResourceLink(resource) => MentionUri::parse(&resource.uri).ok(),
+            acp::ContentBlock::Image(image) => match &image.uri {
+                Some(uri) => MentionUri::parse(uri).ok(),
+                None => Some(MentionUri::PastedImage),
+            },
+            _ => None,
+        };
+        // Pasted images have no uri to tell them apart.
+        if let Some(mention) = mention
+            && (mention == MentionUri::PastedImage || !attachments.contains(&mention))
+        {
+            attachments.push(mention);
+        }
+    }
+    attachments
+}
+
+/// The text the attachment was sent with, if it was embedded in the message.
+fn attachment_content<'a>(
+    chunks: &'a [acp::ContentBlock],
+    mention: &MentionUri,
+) -> Option<&'a str> {
+    chunks.iter().find_map(|chunk| match chunk {
+        acp::ContentBlock::Resource(acp::EmbeddedResource {
+            resource: acp::EmbeddedResourceResource::TextResourceContents(resource),
+            ..
+        }) if MentionUri::parse(&resource.uri).ok().as_ref() == Some(mention) => {
+            Some(resource.text.as_str())
+        }
+        _ => None,
+    })
+}
+
+/// Context attached to a message without being mentioned in it.
+fn context_resource(uri: MentionUri, text: String) -> acp::ContentBlock {
+    acp::ContentBlock::Resource(acp::EmbeddedResource {
+        annotations: None,
+        resource: acp::EmbeddedResourceResource::TextResourceContents(acp::TextResourceContents {
+            mime_type: None,
+            text,
+            uri: uri.to_uri().to_string(),
+            meta: None,
+        }),
+        meta: None,
+    })
+}
+
+/// A quote of an assistant message or tool call, to refer to it in a follow-up message.
+fn follow_up_quote(entry: &AgentThreadEntry, cx: &App) -> Option<String> {
+    let text = match entry {
+        AgentThreadEntry::UserMessage(_) => return None,
+        AgentThreadEntry::AssistantMessage(message) => assistant_message_text(message, cx),
+        AgentThreadEntry::ToolCall(tool_call) => {
+            format!("Tool call: {}", tool_call.label.read(cx).source())
+        }
+    };
+    quote_lines(&text)
+}
+
+/// The markdown of an assistant message, without its thinking.
+fn assistant_message_text(message: &AssistantMessage, cx: &App) -> String {
+    message
+        .chunks
+        .iter()
+        .filter_map(|chunk| match chunk {
+            AssistantMessageChunk::Message { block } => Some(block.to_markdown(cx)),
+            AssistantMessageChunk::Thought { .. } => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// What to open an assistant message or the output of a tool call as, in a scratch buffer:
+/// its title, the name of its language and its text.
+fn scratch_buffer_contents(
+    entry: &AgentThreadEntry,
+    cx: &App,
+) -> Option<(String, Option<&'static str>, String)> {
+    match entry {
+        AgentThreadEntry::UserMessage(_) => None,
+        AgentThreadEntry::AssistantMessage(message) => Some((
+            "Agent Response".to_string(),
+            Some("Markdown"),
+            assistant_message_text(message, cx),
+        )),
+        AgentThreadEntry::ToolCall(tool_call) => {
+            let title = tool_call.label.read(cx).source().to_string();
+            let terminal_output = tool_call
+                .terminals()
+                .map(|terminal| terminal.read(cx).inner().read(cx).get_content())
+                .collect::<Vec<_>>();
+            if !terminal_output.is_empty() {
+                return Some((title, None, terminal_output.join("\n")));
+            }
+            if let Some(output) = tool_call
+                .raw_output
+                .as_ref()
+                .filter(|output| output.is_object() || output.is_array())
+            {
+                return Some((
+                    title,
+                    Some("JSON"),
+                    serde_json::to_string_pretty(output).ok()?,
+                ));
+            }
+            let text = tool_call
+                .content
+                .iter()
+                .map(|content| content.to_markdown(cx))
+                .collect::<Vec<_>>()
+                .join("\n\n");
+            if text.trim().is_empty() {
+                return None;
+            }
+            Some((title, Some("Markdown"), text))
+        }
+    }
+}
+
+/// Prefixes the leading non-empty lines of the text with `> `, eliding the rest.
+fn quote_lines(text: &str) -> Option<String> {
+    let lines = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return None;
+    }
+    let mut quote = lines
+        .iter()
+        .take(FOLLOW_UP_QUOTE_LINES)
+        .map(|line| format!("> {line}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if lines.len() > FOLLOW_UP_QUOTE_LINES {
+        quote.push_str("\n> …");
+    }
+    Some(quote)
+}
+
+/// Explains a canceled tool call, including whether its commands were stopped or are
+/// still running, since canceling the turn leaves them to the agent.
+fn canceled_tool_call_note(tool_call: &ToolCall, cx: &App) -> &'static str {
+    let mut terminals = tool_call.terminals().peekable();
+    if terminals.peek().is_none() {
+        "Canceled when the generation was stopped"
+    } else if terminals.any(|terminal| terminal.read(cx).output().is_none()) {
+        "Canceled when the generation was stopped, but the command was left running"
+    } else {
+        "Canceled when the generation was stopped; the command is no longer running"
+    }
+}
+
+/// Parses a tool call's output or a structured response as JSON, optionally in a fenced code
+/// block, if it is a non-empty object or array; other values read fine as markdown.
+fn parse_json_output(source: &str) -> Option<serde_json::Value> {
+    let source = source.trim();
+    let source = source
+        .strip_prefix("```json")
+        .and_then(|source| source.strip_suffix("```"))
+        .unwrap_or(source)
+        .trim();
+    if !source.starts_with(['{', '[']) {
+        return None;
+    }
+    let value = serde_json::from_str::<serde_json::Value>(source).ok()?;
+    match &value {
+        serde_json::Value::Object(object) if !object.is_empty() => Some(value),
+        serde_json::Value::Array(array) if !array.is_empty() => Some(value),
+        _ => None,
+    }
+}
+
+/// The JSONPath of a member of an object, with bracket notation for keys that aren't
+/// identifiers.
+fn json_member_path(parent: &str, key: &str) -> SharedString {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && key
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_');
+    if is_identifier {
+        format!("{parent}.{key}").into()
+    } else {
+        format!(
+            "{parent}[{}]",
+            serde_json::to_string(key).unwrap_or_else(|_| key.to_string())
+        )
+        .into()
+    }
+}
+
+fn format_turn_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{:.1}s", duration.as_secs_f32())
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl FileChangeKind {
+    /// The net change of a file changed as `self` and then as `next`, or `None` when
+    /// the changes cancel out.
+    fn then(self, next: Self) -> Option<Self> {
+        match (self, next) {
+            (Self::Created, Self::Deleted) => None,
+            (Self::Created, _) => Some(Self::Created),
+            (Self::Deleted, Self::Created | Self::Modified) => Some(Self::Modified),
+            (_, next) => Some(next),
+        }
+    }
+}
+
+/// A file changed by the thread's tool calls.
+struct FileChange {
+    path: PathBuf,
+    kind: FileChangeKind,
+    /// Entry and location index of the latest tool call that changed the file.
+    entry_ix: usize,
+    location_ix: usize,
+}
+
+/// The net changes the thread's completed tool calls made to each file, in the order
+/// the files were first changed.
+fn thread_file_changes(entries: &[AgentThreadEntry], cx: &App) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+    for (entry_ix, entry) in entries.iter().enumerate() {
+        let AgentThreadEntry::ToolCall(tool_call) = entry else {
+            continue;
+        };
+        let changes_files = matches!(
+            tool_call.kind,
+            acp::ToolKind::Edit | acp::ToolKind::Delete | acp::ToolKind::Move
+        ) || tool_call.diffs().next().is_some();
+        if !changes_files || !matches!(tool_call.status, ToolCallStatus::Completed) {
+            continue;
+        }
+        let kind = if tool_call.kind == acp::ToolKind::Delete {
+            FileChangeKind::Deleted
+        } else if tool_call.diffs().any(|diff| diff.read(cx).creates_file()) {
+            FileChangeKind::Created
+        } else {
+            FileChangeKind::Modified
+        };
+        for (location_ix, location) in tool_call.locations.iter().enumerate() {
+            match changes
+                .iter()
+                .position(|change| change.path == location.path)
+            {
+                Some(ix) => match changes[ix].kind.then(kind) {
+                    Some(kind) => {
+                        let change = &mut changes[ix];
+                        change.kind = kind;
+                        change.entry_ix = entry_ix;
+                        change.location_ix = location_ix;
+                    }
+                    None => {
+                        changes.remove(ix);
+                    }
+                },
+                None => changes.push(FileChange {
+                    path: location.path.clone(),
+                    kind,
+                    entry_ix,
+                    location_ix,
+                }),
+            }
+        }
+    }
+    changes
+}
+
+/// Labels for the allow and reject options of tool calls that create, delete or move a
+/// file, naming what allowing them does.
+fn confirmation_labels(tool_call: &ToolCall, cx: &App) -> Option<(&'static str, &'static str)> {
+    match tool_call.kind {
+        acp::ToolKind::Delete => Some(("Delete", "Keep")),
+        acp::ToolKind::Move => match tool_call.locations.as_slice() {
+            [source, destination] if source.path.parent() == destination.path.parent() => {
+                Some(("Rename", "Skip"))
+            }
+            _ => Some(("Move", "Skip")),
+        },
+        _ if tool_call.diffs().any(|diff| diff.read(cx).creates_file()) => {
+            Some(("Create File", "Skip"))
+        }
+        _ => None,
+    }
+}
+
+/// Continuations that were replaced by editing a user message, kept so they can still be read.
+#[derive(Default)]
+struct MessageBranches {
+    /// Transcripts of the earlier continuations, starting at the message, oldest first.
+    archived: Vec<Entity<Markdown>>,
+    /// The archived continuation being viewed, or `None` for the live one.
+    viewing: Option<usize>,
+}
+
+impl MessageBranches {
+    fn len(&self) -> usize {
+        self.archived.len() + 1
+    }
+
+    /// The one-based position of the viewed continuation, the live one being last.
+    fn position(&self) -> usize {
+        self.viewing.map_or(self.len(), |ix| ix + 1)
+    }
+
+    fn select(&mut self, position: usize) {
+        self.viewing = (position < self.len()).then(|| position.saturating_sub(1));
+    }
+}
+
+/// Where the divider marking the summarized messages of a native thread is rendered.
+enum ContextSummaryPosition {
+    /// Before the first user message sent after summarizing.
+    Before(UserMessageId),
+    /// After the last entry, when no message has been sent since.
+    End,
+}
+
+struct AutoApproveCountdown {
+    deadline: Instant,
+    _task: Task<()>,
+}
+
 enum ThreadState {
+    /// The agent process hasn't been started yet; it is when the first message is sent.
+    NotConnected,
     Loading(Entity<LoadingView>),
     Ready {
         thread: Entity<AcpThread>,
-        title_editor: Option<Entity<Editor>>,
+        /// The working directory the agent was started in.
+        root_dir: Arc<Path>,
+        title_editor: Entity<Editor>,
         mode_selector: Option<Entity<ModeSelector>>,
         _subscriptions: Vec<Subscription>,
     },
@@ -335,19 +956,7 @@ impl AcpThreadView {
         let prompt_capabilities = Rc::new(RefCell::new(acp::PromptCapabilities::default()));
         let available_commands = Rc::new(RefCell::new(vec![]));
 
-        let placeholder = if agent.name() == "Zed Agent" {
-            format!("Message the {} — @ to include context", agent.name())
-        } else if agent.name() == "Claude Code"
-            || agent.name() == "Codex"
-            || !available_commands.borrow().is_empty()
-        {
-            format!(
-                "Message {} — @ to include context, / for commands",
-                agent.name()
-            )
-        } else {
-            format!("Message {} — @ to include context", agent.name())
-        };
+        let placeholder = agent.placeholder();
 
         let message_editor = cx.new(|cx| {
             let mut editor = MessageEditor::new(
@@ -386,11 +995,24 @@ impl AcpThreadView {
             )
         });
 
+        let issue_link_settings = issue_link_settings(&project, cx);
+
         let agent_server_store = project.read(cx).agent_server_store().clone();
         let subscriptions = [
-            cx.observe_global_in::<SettingsStore>(window, Self::agent_ui_font_size_changed),
-            cx.observe_global_in::<AgentFontSize>(window, Self::agent_ui_font_size_changed),
+            cx.observe_global_in::<SettingsStore>(window, Self::refresh_styles),
+            cx.observe_global_in::<AgentFontSize>(window, Self::refresh_styles),
+            cx.observe_global_in::<GlobalTheme>(window, Self::refresh_styles),
+            cx.observe_global_in::<SettingsStore>(window, Self::issue_link_settings_changed),
             cx.subscribe_in(&message_editor, window, Self::handle_message_editor_event),
+            cx.subscribe_in(
+                &message_editor.read(cx).editor().clone(),
+                window,
+                |this, _, event, _, cx| {
+                    if let EditorEvent::Edited { .. } = event {
+                        this.save_draft(cx);
+                    }
+                },
+            ),
             cx.subscribe_in(&entry_view_state, window, Self::handle_entry_view_event),
             cx.subscribe_in(
                 &agent_server_store,
@@ -399,23 +1021,30 @@ impl AcpThreadView {
             ),
         ];
 
+        cx.on_release(|this, cx| this.save_view_state(cx)).detach();
+
         #[cfg(target_os = "windows")]
         let show_codex_windows_warning = crate::ExternalAgent::parse_built_in(agent.as_ref())
             == Some(crate::ExternalAgent::Codex);
 
-        Self {
+        let restore_draft = resume_thread.is_none();
+        let mut this = Self {
             agent: agent.clone(),
             workspace: workspace.clone(),
             project: project.clone(),
             entry_view_state,
-            thread_state: Self::initial_state(
-                agent.clone(),
-                resume_thread.clone(),
-                workspace.clone(),
-                project.clone(),
-                window,
-                cx,
-            ),
+            thread_state: if resume_thread.is_none() && agent.spawns_process() {
+                ThreadState::NotConnected
+            } else {
+                Self::initial_state(
+                    agent.clone(),
+                    resume_thread.clone(),
+                    workspace.clone(),
+                    project.clone(),
+                    window,
+                    cx,
+                )
+            },
             login: None,
             message_editor,
             model_selector: None,
@@ -429,9 +1058,38 @@ impl AcpThreadView {
             thread_feedback: Default::default(),
             auth_task: None,
             expanded_tool_calls: HashSet::default(),
+            unwrapped_commands: HashSet::default(),
+            auto_approve_countdowns: HashMap::default(),
+            message_branches: HashMap::default(),
+            rewound_branches: None,
+            show_branch_tree: false,
+            transcript_selection: None,
+            first_live_entry: 0,
+            loaded_earlier_entries: false,
+            agent_process_usage: None,
+            dismissed_memory_warning: false,
+            _agent_process_poll_task: None,
             expanded_thinking_blocks: HashSet::default(),
+            expanded_user_messages: HashSet::default(),
+            expanded_attachments: HashSet::default(),
+            expanded_turn_stats: HashSet::default(),
+            json_outputs: RefCell::default(),
+            expanded_json_nodes: HashSet::default(),
+            issue_link_detector: issue_link_detector(&issue_link_settings),
+            issue_link_settings,
+            context_summary_expanded: false,
+            branch: None,
+            branch_changes: Vec::new(),
+            synced_path_changes: HashSet::default(),
+            unsent_branch_change: None,
+            unsent_file_changes: BTreeSet::new(),
+            _file_changes_task: None,
+            _save_draft_task: None,
             editing_message: None,
+            sent_messages: Vec::new(),
+            recalled_message: None,
             edits_expanded: false,
+            file_changes_expanded: false,
             plan_expanded: false,
             prompt_capabilities,
             available_commands,
@@ -440,14 +1098,21 @@ impl AcpThreadView {
             history_store,
             hovered_recent_history_item: None,
             is_loading_contents: false,
+            send_when_ready: false,
+            pending_prompt: None,
             _subscriptions: subscriptions,
             _cancel_task: None,
             focus_handle: cx.focus_handle(),
+            confirmation_focus_handle: cx.focus_handle(),
             new_server_version_available: None,
             resume_thread_metadata: resume_thread,
             #[cfg(target_os = "windows")]
             show_codex_windows_warning,
+        };
+        if restore_draft {
+            this.restore_draft(window, cx);
         }
+        this
     }
 
     fn reset(&mut self, window: &mut Window, cx: &mut Context<Self>) {
@@ -464,6 +1129,13 @@ impl AcpThreadView {
         cx.notify();
     }
 
+    /// Starts the agent process if it was deferred.
+    fn connect(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.thread_state, ThreadState::NotConnected) {
+            self.reset(window, cx);
+        }
+    }
+
     fn initial_state(
         agent: Rc<dyn AgentServer>,
         resume_thread: Option<DbThreadMetadata>,
@@ -527,6 +1199,14 @@ impl AcpThreadView {
                 }
             };
 
+            let root_dir: Arc<Path> = if let Some(acp_agent) = connection
+                .clone()
+                .downcast::<agent_servers::AcpConnection>(
+            ) {
+                acp_agent.root_dir().into()
+            } else {
+                root_dir.unwrap_or(paths::home_dir().as_path().into())
+            };
             let result = if let Some(native_agent) = connection
                 .clone()
                 .downcast::<agent::NativeAgentConnection>()
@@ -539,14 +1219,6 @@ impl AcpThreadView {
                 })
                 .log_err()
             } else {
-                let root_dir = if let Some(acp_agent) = connection
-                    .clone()
-                    .downcast::<agent_servers::AcpConnection>()
-                {
-                    acp_agent.root_dir().into()
-                } else {
-                    root_dir.unwrap_or(paths::home_dir().as_path().into())
-                };
                 cx.update(|_, cx| {
                     connection
                         .clone()
@@ -591,6 +1263,17 @@ impl AcpThreadView {
                                 (0..count).map(|ix| view_state.entry(ix)?.focus_handle(cx)),
                             );
                         });
+                        this.first_live_entry = 0;
+                        this.loaded_earlier_entries = false;
+                        this.link_issues(&thread, 0..count, cx);
+                        this.release_old_entry_views(&thread, cx);
+                        this.restore_view_state(&thread, cx);
+                        this.message_branches.clear();
+                        this.rewound_branches = None;
+                        this.restore_branches(&thread, cx);
+                        if resume_thread.is_some() {
+                            this.restore_draft(window, cx);
+                        }
 
                         if let Some(resume) = resume_thread {
                             this.history_store.update(cx, |history, cx| {
@@ -636,35 +1319,80 @@ impl AcpThreadView {
                                 })
                             });
 
+                        let git_store = this.project.read(cx).git_store().clone();
                         let mut subscriptions = vec![
                             cx.subscribe_in(&thread, window, Self::handle_thread_event),
                             cx.observe(&action_log, |_, _, cx| cx.notify()),
+                            // Keeps the branch shown in the header up to date.
+                            cx.subscribe(&git_store, |this, _, event: &GitStoreEvent, cx| {
+                                if matches!(
+                                    event,
+                                    GitStoreEvent::RepositoryUpdated(..)
+                                        | GitStoreEvent::RepositoryAdded
+                                        | GitStoreEvent::RepositoryRemoved(_)
+                                ) {
+                                    this.detect_branch_change(cx);
+                                    cx.notify();
+                                }
+                            }),
                         ];
 
-                        let title_editor =
-                            if thread.update(cx, |thread, cx| thread.can_set_title(cx)) {
-                                let editor = cx.new(|cx| {
-                                    let mut editor = Editor::single_line(window, cx);
-                                    editor.set_text(thread.read(cx).title(), window, cx);
-                                    editor
-                                });
-                                subscriptions.push(cx.subscribe_in(
-                                    &editor,
-                                    window,
-                                    Self::handle_title_editor_event,
-                                ));
-                                Some(editor)
-                            } else {
-                                None
-                            };
+                        if let Some(file_changes) = thread
+                            .read(cx)
+                            .connection()
+                            .file_changes(thread.read(cx).session_id(), cx)
+                        {
+                            subscriptions.push(cx.subscribe(
+                                &this.project,
+                                move |this, _, event: &project::Event, cx| {
+                                    if let project::Event::WorktreeUpdatedEntries(
+                                        worktree_id,
+                                        changes,
+                                    ) = event
+                                    {
+                                        this.queue_file_changes(
+                                            *worktree_id,
+                                            changes,
+                                            file_changes.clone(),
+                                            cx,
+                                        );
+                                    }
+                                },
+                            ));
+                        }
+
+                        let title_editor = cx.new(|cx| {
+                            let mut editor = Editor::single_line(window, cx);
+                            editor.set_text(thread.read(cx).title(), window, cx);
+                            editor
+                        });
+                        subscriptions.push(cx.subscribe_in(
+                            &title_editor,
+                            window,
+                            Self::handle_title_editor_event,
+                        ));
+
+                        this.poll_agent_process(&thread, cx);
 
                         this.thread_state = ThreadState::Ready {
                             thread,
+                            root_dir,
                             title_editor,
                             mode_selector,
                             _subscriptions: subscriptions,
                         };
+                        this.branch = this.current_branch(cx);
+                        this.branch_changes.clear();
+                        this.unsent_branch_change = None;
+                        this.unsent_file_changes.clear();
+                        this._file_changes_task = None;
                         this.message_editor.focus_handle(cx).focus(window);
+                        if std::mem::take(&mut this.send_when_ready) {
+                            this.send(window, cx);
+                        }
+                        if let Some(prompt) = this.pending_prompt.take() {
+                            this.send_prompt(&prompt, window, cx);
+                        }
 
                         this.profile_selector = this.as_native_thread(cx).map(|thread| {
                             cx.new(|cx| {
@@ -827,7 +1555,8 @@ impl AcpThreadView {
     pub fn thread(&self) -> Option<&Entity<AcpThread>> {
         match &self.thread_state {
             ThreadState::Ready { thread, .. } => Some(thread),
-            ThreadState::Unauthenticated { .. }
+            ThreadState::NotConnected
+            | ThreadState::Unauthenticated { .. }
             | ThreadState::Loading { .. }
             | ThreadState::LoadError { .. } => None,
         }
@@ -836,7 +1565,8 @@ impl AcpThreadView {
     pub fn mode_selector(&self) -> Option<&Entity<ModeSelector>> {
         match &self.thread_state {
             ThreadState::Ready { mode_selector, .. } => mode_selector.as_ref(),
-            ThreadState::Unauthenticated { .. }
+            ThreadState::NotConnected
+            | ThreadState::Unauthenticated { .. }
             | ThreadState::Loading { .. }
             | ThreadState::LoadError { .. } => None,
         }
@@ -844,7 +1574,15 @@ impl AcpThreadView {
 
     pub fn title(&self, cx: &App) -> SharedString {
         match &self.thread_state {
-            ThreadState::Ready { .. } | ThreadState::Unauthenticated { .. } => "New Thread".into(),
+            ThreadState::Ready { thread, .. } => {
+                let title = thread.read(cx).title();
+                if title.is_empty() {
+                    "New Thread".into()
+                } else {
+                    title
+                }
+            }
+            ThreadState::NotConnected | ThreadState::Unauthenticated { .. } => "New Thread".into(),
             ThreadState::Loading(loading_view) => loading_view.read(cx).title.clone(),
             ThreadState::LoadError(error) => match error {
                 LoadError::Unsupported { .. } => format!("Upgrade {}", self.agent.name()).into(),
@@ -859,7 +1597,7 @@ impl AcpThreadView {
 
     pub fn title_editor(&self) -> Option<Entity<Editor>> {
         if let ThreadState::Ready { title_editor, .. } = &self.thread_state {
-            title_editor.clone()
+            Some(title_editor.clone())
         } else {
             None
         }
@@ -973,6 +1711,15 @@ impl AcpThreadView {
                 if AgentSettings::get_global(cx).expand_terminal_card {
                     self.expanded_tool_calls.insert(tool_call_id.clone());
                 }
+                if AgentSettings::get_global(cx).show_commands_in_terminal_panel
+                    && let Some(thread) = self.thread()
+                    && let Some(AgentThreadEntry::ToolCall(tool_call)) =
+                        thread.read(cx).entries().get(event.entry_index)
+                {
+                    for terminal in tool_call.terminals().cloned().collect::<Vec<_>>() {
+                        self.show_terminal_in_panel(&terminal, false, window, cx);
+                    }
+                }
             }
             ViewEvent::TerminalMovedToBackground(tool_call_id) => {
                 self.expanded_tool_calls.remove(tool_call_id);
@@ -1000,7 +1747,9 @@ impl AcpThreadView {
                 }
             }
             ViewEvent::MessageEditorEvent(editor, MessageEditorEvent::Send) => {
-                self.regenerate(event.entry_index, editor.clone(), window, cx);
+                if !editor.update(cx, |editor, cx| editor.run_composer_command(window, cx)) {
+                    self.regenerate(event.entry_index, editor.clone(), window, cx);
+                }
             }
             ViewEvent::MessageEditorEvent(_editor, MessageEditorEvent::Cancel) => {
                 self.cancel_editing(&Default::default(), window, cx);
@@ -1030,13 +1779,41 @@ impl AcpThreadView {
         .detach();
     }
 
+    /// Sends `text` as a new message, as soon as the thread has loaded. The message editor
+    /// keeps what was typed in it.
+    pub fn send_prompt(&mut self, text: &str, window: &mut Window, cx: &mut Context<Self>) {
+        if self.thread().is_some() {
+            let contents = Task::ready(Ok((vec![text.into()], Vec::new())));
+            self.send_contents(contents, false, None, window, cx);
+        } else {
+            if matches!(
+                self.thread_state,
+                ThreadState::NotConnected | ThreadState::Loading(_)
+            ) {
+                self.pending_prompt = Some(text.to_string());
+            }
+            self.connect(window, cx);
+        }
+    }
+
     fn send(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if matches!(self.thread_state, ThreadState::NotConnected) {
+            self.send_when_ready = true;
+            self.connect(window, cx);
+            return;
+        }
         let Some(thread) = self.thread() else { return };
 
         if self.is_loading_contents {
             return;
         }
 
+        if self.message_editor.update(cx, |message_editor, cx| {
+            message_editor.run_composer_command(window, cx)
+        }) {
+            return;
+        }
+
         self.history_store.update(cx, |history, cx| {
             history.push_recently_opened_entry(
                 HistoryEntryId::AcpThread(thread.read(cx).session_id().clone()),
@@ -1092,18 +1869,123 @@ impl AcpThreadView {
         self.send_impl(self.message_editor.clone(), window, cx)
     }
 
-    fn stop_current_and_send_new_message(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let Some(thread) = self.thread().cloned() else {
-            return;
+    /// Describes where the user is in the active editor, so that a message can refer to
+    /// "this function" without mentioning it explicitly. It's attached as context, which
+    /// agents that don't accept embedded context don't get.
+    fn active_editor_context(&self, cx: &mut Context<Self>) -> Option<acp::ContentBlock> {
+        if !self.prompt_capabilities.borrow().embedded_context {
+            return None;
+        }
+        let item = self.workspace.upgrade()?.read(cx).active_item(cx)?;
+        let project_path = item.project_path(cx)?;
+        let abs_path = self.project.read(cx).absolute_path(&project_path, cx)?;
+        let editor = item.act_as::<Editor>(cx)?;
+        let selection = editor.update(cx, |editor, cx| {
+            editor
+                .selections
+                .newest::<Point>(&editor.display_snapshot(cx))
+        });
+        let location = if selection.is_empty() {
+            format!(
+                "the cursor at line {}, column {}",
+                selection.head().row + 1,
+                selection.head().column + 1
+            )
+        } else {
+            // A selection of whole lines ends at the start of the next one.
+            let end_row = if selection.end.column == 0 && selection.end.row > selection.start.row {
+                selection.end.row - 1
+            } else {
+                selection.end.row
+            };
+            format!("lines {}-{} selected", selection.start.row + 1, end_row + 1)
         };
+        Some(context_resource(
+            MentionUri::WorkspaceState,
+            format!(
+                "The active editor is {}, with {location}.",
+                abs_path.display()
+            ),
+        ))
+    }
 
-        let cancelled = thread.update(cx, |thread, cx| thread.cancel(cx));
+    /// The project memory, attached as context to the first message of a thread. Agents that
+    /// don't accept embedded context don't get it, rather than adding it to the prompt text.
+    fn project_memory_context(&self, cx: &mut Context<Self>) -> Task<Option<acp::ContentBlock>> {
+        let has_user_message = self.thread().is_none_or(|thread| {
+            thread
+                .read(cx)
+                .entries()
+                .iter()
+                .any(|entry| matches!(entry, AgentThreadEntry::UserMessage(_)))
+        });
+        if has_user_message || !self.prompt_capabilities.borrow().embedded_context {
+            return Task::ready(None);
+        }
+        let memory = ProjectMemory::load(&self.project, cx);
+        cx.spawn(async move |_, cx| {
+            let facts = memory
+                .await
+                .read_with(cx, |memory, _| memory.to_markdown())
+                .ok()??;
+            Some(context_resource(MentionUri::ProjectMemory, facts))
+        })
+    }
 
-        cx.spawn_in(window, async move |this, cx| {
-            cancelled.await;
+    fn recall_previous_message(&mut self, _: &MoveUp, window: &mut Window, cx: &mut Context<Self>) {
+        if self.recall_message(true, window, cx) {
+            cx.stop_propagation();
+        }
+    }
 
-            this.update_in(cx, |this, window, cx| {
-                this.send_impl(this.message_editor.clone(), window, cx);
+    fn recall_next_message(&mut self, _: &MoveDown, window: &mut Window, cx: &mut Context<Self>) {
+        if self.recall_message(false, window, cx) {
+            cx.stop_propagation();
+        }
+    }
+
+    /// Shows an older or newer sent message in the message editor, like the history of a
+    /// shell, when it's empty or shows a recalled message that wasn't edited.
+    fn recall_message(&mut self, older: bool, window: &mut Window, cx: &mut Context<Self>) -> bool {
+        let text = self.message_editor.read(cx).text(cx);
+        let current_ix = match &self.recalled_message {
+            Some((ix, recalled_text)) if *recalled_text == text => Some(*ix),
+            _ if text.is_empty() => None,
+            _ => return false,
+        };
+        let ix = match current_ix {
+            None if older => self.sent_messages.len().checked_sub(1),
+            None => return false,
+            Some(ix) if older => Some(ix.saturating_sub(1)),
+            Some(ix) => Some(ix + 1).filter(|ix| *ix < self.sent_messages.len()),
+        };
+
+        self.message_editor
+            .update(cx, |message_editor, cx| match ix {
+                Some(ix) => {
+                    message_editor.set_message(self.sent_messages[ix].clone(), window, cx);
+                    self.recalled_message = Some((ix, message_editor.text(cx)));
+                }
+                None => {
+                    message_editor.clear(window, cx);
+                    self.recalled_message = None;
+                }
+            });
+        ix.is_some() || current_ix.is_some()
+    }
+
+    fn stop_current_and_send_new_message(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread().cloned() else {
+            return;
+        };
+
+        let cancelled = thread.update(cx, |thread, cx| thread.cancel(cx));
+
+        cx.spawn_in(window, async move |this, cx| {
+            cancelled.await;
+
+            this.update_in(cx, |this, window, cx| {
+                this.send_impl(this.message_editor.clone(), window, cx);
             })
             .ok();
         })
@@ -1128,6 +2010,51 @@ impl AcpThreadView {
         let contents = message_editor.update(cx, |message_editor, cx| {
             message_editor.contents(full_mention_content, cx)
         });
+        let structured_response = message_editor.read(cx).structured_response();
+        self.send_contents(
+            contents,
+            structured_response,
+            Some(message_editor),
+            window,
+            cx,
+        );
+    }
+
+    /// Sends the contents loaded from `message_editor`, clearing the message editor once
+    /// they're loaded. Prompts sent without an editor leave the message editor as it is.
+    fn send_contents(
+        &mut self,
+        contents: Task<Result<(Vec<acp::ContentBlock>, Vec<Entity<Buffer>>)>>,
+        structured_response: bool,
+        message_editor: Option<Entity<MessageEditor>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let is_main_editor = message_editor.as_ref() == Some(&self.message_editor);
+        let clears_editor = message_editor.is_some();
+        let active_editor_context = if AgentSettings::get_global(cx).include_active_editor_context {
+            self.active_editor_context(cx)
+        } else {
+            None
+        };
+        let project_memory_context = self.project_memory_context(cx);
+        // The branch switch is only forgotten once the agent was told about it.
+        let branch_change = self.unsent_branch_change.clone();
+        let branch_change_context = branch_change
+            .as_ref()
+            .filter(|_| {
+                AgentSettings::get_global(cx).notify_agent_of_branch_changes
+                    && self.prompt_capabilities.borrow().embedded_context
+            })
+            .map(|(from, to)| {
+                context_resource(
+                    MentionUri::WorkspaceState,
+                    format!(
+                        "The git branch was switched from {from} to {to} since your last turn. \
+                        Files you have read may have changed."
+                    ),
+                )
+            });
 
         let agent_telemetry_id = self.agent.telemetry_id();
 
@@ -1156,18 +2083,36 @@ impl AcpThreadView {
         .detach();
 
         let task = cx.spawn_in(window, async move |this, cx| {
-            let (contents, tracked_buffers) = contents.await?;
+            let (mut contents, tracked_buffers) = contents.await?;
 
             if contents.is_empty() {
                 return Ok(());
             }
+            let unsent_message = is_main_editor.then(|| contents.clone());
+            if is_main_editor {
+                this.update(cx, |this, _| {
+                    this.sent_messages.push(contents.clone());
+                    this.recalled_message = None;
+                })?;
+            }
+            if let Some(context) = active_editor_context {
+                contents.push(context);
+            }
+            if let Some(context) = project_memory_context.await {
+                contents.push(context);
+            }
+            if let Some(context) = branch_change_context {
+                contents.push(context);
+            }
 
             this.update_in(cx, |this, window, cx| {
                 this.set_editor_is_expanded(false, cx);
                 this.scroll_to_bottom(cx);
-                this.message_editor.update(cx, |message_editor, cx| {
-                    message_editor.clear(window, cx);
-                });
+                if clears_editor {
+                    this.message_editor.update(cx, |message_editor, cx| {
+                        message_editor.clear(window, cx);
+                    });
+                }
             })?;
             let send = thread.update(cx, |thread, cx| {
                 thread.action_log().update(cx, |action_log, cx| {
@@ -1179,9 +2124,39 @@ impl AcpThreadView {
 
                 telemetry::event!("Agent Message Sent", agent = agent_telemetry_id);
 
-                thread.send(contents, cx)
+                if structured_response {
+                    thread.send_for_structured_response(contents, cx)
+                } else {
+                    thread.send(contents, cx)
+                }
             })?;
-            send.await
+            let result = send.await;
+            if result.is_ok()
+                && let Some((_, sent_to)) = branch_change
+            {
+                // Only a switch made since sending is left to tell the agent about.
+                this.update(cx, |this, _| {
+                    this.unsent_branch_change = this
+                        .unsent_branch_change
+                        .take()
+                        .and_then(|(_, to)| (to != sent_to).then_some((sent_to, to)));
+                })
+                .ok();
+            }
+            // The editor was cleared when sending, so give the message back to send it again.
+            if result.is_err()
+                && let Some(message) = unsent_message
+            {
+                this.update_in(cx, |this, window, cx| {
+                    this.message_editor.update(cx, |message_editor, cx| {
+                        if message_editor.is_empty(cx) {
+                            message_editor.set_message(message, window, cx);
+                        }
+                    });
+                })
+                .ok();
+            }
+            result
         });
 
         cx.spawn(async move |this, cx| {
@@ -1233,6 +2208,28 @@ impl AcpThreadView {
         cx.notify();
     }
 
+    /// Puts the cursor at the end of the user message, so that it can be edited and sent
+    /// again, restarting the thread from there.
+    fn edit_user_message(&mut self, entry_ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(editor) = self
+            .entry_view_state
+            .read(cx)
+            .entry(entry_ix)
+            .and_then(|entry| entry.message_editor())
+            .cloned()
+        else {
+            return;
+        };
+        self.expanded_user_messages.insert(entry_ix);
+        editor.update(cx, |editor, cx| {
+            editor.editor().update(cx, |editor, cx| {
+                editor.move_to_end(&editor::actions::MoveToEnd, window, cx);
+            });
+        });
+        window.focus(&editor.focus_handle(cx));
+        cx.notify();
+    }
+
     fn regenerate(
         &mut self,
         entry_ix: usize,
@@ -1252,11 +2249,20 @@ impl AcpThreadView {
         }) else {
             return;
         };
+        self.archive_branch(entry_ix, &user_message_id, &thread, cx);
 
         cx.spawn_in(window, async move |this, cx| {
-            thread
-                .update(cx, |thread, cx| thread.rewind(user_message_id, cx))?
-                .await?;
+            let rewind =
+                thread.update(cx, |thread, cx| thread.rewind(user_message_id.clone(), cx))?;
+            if let Err(error) = rewind.await {
+                // The message stays, and so do its branches.
+                this.update(cx, |this, _| {
+                    if let Some(branches) = this.rewound_branches.take() {
+                        this.message_branches.insert(user_message_id, branches);
+                    }
+                })?;
+                return Err(error);
+            }
             this.update_in(cx, |this, window, cx| {
                 this.send_impl(message_editor, window, cx);
                 this.focus_handle(cx).focus(window);
@@ -1266,6 +2272,257 @@ impl AcpThreadView {
         .detach();
     }
 
+    /// Keeps the continuation from the message at `entry_ix` on, before it is replaced by a
+    /// regenerated one. The branches move to the message replacing it once that's added.
+    fn archive_branch(
+        &mut self,
+        entry_ix: usize,
+        user_message_id: &UserMessageId,
+        thread: &Entity<AcpThread>,
+        cx: &mut Context<Self>,
+    ) {
+        let entries = thread
+            .read(cx)
+            .entries()
+            .get(entry_ix..)
+            .unwrap_or_default();
+        let transcript = entries
+            .iter()
+            .map(|entry| entry.to_markdown(cx))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        // Branches of later messages are part of the archived transcript.
+        for entry in entries.iter().skip(1) {
+            if let Some(id) = entry.user_message().and_then(|message| message.id.as_ref()) {
+                self.message_branches.remove(id);
+            }
+        }
+        let mut branches = self
+            .message_branches
+            .remove(user_message_id)
+            .unwrap_or_default();
+        branches
+            .archived
+            .push(self.branch_transcript(transcript, cx));
+        branches.viewing = None;
+        self.rewound_branches = Some(branches);
+    }
+
+    fn branch_transcript(&self, transcript: String, cx: &mut Context<Self>) -> Entity<Markdown> {
+        let language_registry = self.project.read(cx).languages().clone();
+        cx.new(|cx| Markdown::new(transcript.into(), Some(language_registry), None, cx))
+    }
+
+    fn select_branch(
+        &mut self,
+        message_id: &UserMessageId,
+        position: usize,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(branches) = self.message_branches.get_mut(message_id) {
+            branches.select(position);
+            cx.notify();
+        }
+    }
+
+    /// Saves the earlier continuations with the thread, so that they're kept when it's
+    /// opened again.
+    fn save_branches(&self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        let key = format!("{BRANCHES_KEY_PREFIX}-{}", thread.read(cx).session_id());
+        let branches = self
+            .message_branches
+            .iter()
+            .map(|(message_id, branches)| {
+                let transcripts = branches
+                    .archived
+                    .iter()
+                    .map(|transcript| transcript.read(cx).source().to_string())
+                    .collect::<Vec<_>>();
+                (message_id.clone(), transcripts)
+            })
+            .collect::<HashMap<_, _>>();
+        cx.background_spawn(async move {
+            if branches.is_empty() {
+                KEY_VALUE_STORE.delete_kvp(key).await
+            } else {
+                KEY_VALUE_STORE
+                    .write_kvp(key, serde_json::to_string(&branches)?)
+                    .await
+            }
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Brings back the earlier continuations saved with the thread.
+    fn restore_branches(&mut self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        let key = format!("{BRANCHES_KEY_PREFIX}-{}", thread.read(cx).session_id());
+        cx.spawn(async move |this, cx| {
+            let Some(branches) = cx
+                .background_spawn(async move { KEY_VALUE_STORE.read_kvp(&key) })
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|branches| {
+                    serde_json::from_str::<HashMap<UserMessageId, Vec<String>>>(&branches).log_err()
+                })
+            else {
+                return;
+            };
+            this.update(cx, |this, cx| {
+                for (message_id, transcripts) in branches {
+                    let archived = transcripts
+                        .into_iter()
+                        .map(|transcript| this.branch_transcript(transcript, cx))
+                        .collect();
+                    this.message_branches.insert(
+                        message_id,
+                        MessageBranches {
+                            archived,
+                            viewing: None,
+                        },
+                    );
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn render_branch_switcher(
+        &self,
+        entry_ix: usize,
+        message_id: Option<&UserMessageId>,
+        window: &Window,
+        cx: &Context<Self>,
+    ) -> Option<AnyElement> {
+        let message_id = message_id?;
+        let branches = self.message_branches.get(message_id)?;
+        let position = branches.position();
+        let len = branches.len();
+        let archived = branches
+            .viewing
+            .and_then(|ix| branches.archived.get(ix))
+            .cloned();
+
+        Some(
+            v_flex()
+                .gap_1()
+                .child(
+                    h_flex()
+                        .px_2()
+                        .gap_0p5()
+                        .justify_end()
+                        .child(
+                            IconButton::new(("previous-branch", entry_ix), IconName::ChevronLeft)
+                                .icon_size(IconSize::XSmall)
+                                .disabled(position == 1)
+                                .tooltip(Tooltip::text("Previous Branch"))
+                                .on_click(cx.listener({
+                                    let message_id = message_id.clone();
+                                    move |this, _, _, cx| {
+                                        this.select_branch(&message_id, position - 1, cx)
+                                    }
+                                })),
+                        )
+                        .child(
+                            Label::new(format!("{position}/{len}"))
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            IconButton::new(("next-branch", entry_ix), IconName::ChevronRight)
+                                .icon_size(IconSize::XSmall)
+                                .disabled(position == len)
+                                .tooltip(Tooltip::text("Next Branch"))
+                                .on_click(cx.listener({
+                                    let message_id = message_id.clone();
+                                    move |this, _, _, cx| {
+                                        this.select_branch(&message_id, position + 1, cx)
+                                    }
+                                })),
+                        ),
+                )
+                .children(archived.map(|transcript| {
+                    v_flex()
+                        .p_2()
+                        .gap_1()
+                        .rounded_md()
+                        .border_1()
+                        .border_dashed()
+                        .border_color(self.tool_card_border_color(cx))
+                        .child(
+                            Label::new("Earlier continuation (read-only)")
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(self.render_markdown(
+                            transcript,
+                            default_markdown_style(false, false, window, cx),
+                        ))
+                }))
+                .into_any_element(),
+        )
+    }
+
+    fn render_branch_tree(&self, thread: &Entity<AcpThread>, cx: &Context<Self>) -> Option<Div> {
+        if !self.show_branch_tree || self.message_branches.is_empty() {
+            return None;
+        }
+        let entries = thread.read(cx).entries();
+        Some(
+            v_flex()
+                .mx_5()
+                .mt_2()
+                .p_2()
+                .gap_1()
+                .rounded_md()
+                .border_1()
+                .border_color(self.tool_card_border_color(cx))
+                .child(
+                    Label::new("Conversation Branches")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .children(entries.iter().enumerate().filter_map(|(entry_ix, entry)| {
+                    let message_id = entry.user_message()?.id.clone()?;
+                    let branches = self.message_branches.get(&message_id)?;
+                    let message = entry.to_markdown(cx);
+                    let title = message
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty() && !line.starts_with('#'))
+                        .unwrap_or("Message")
+                        .to_string();
+                    let row = h_flex()
+                        .gap_1()
+                        .child(
+                            div()
+                                .flex_1()
+                                .min_w_0()
+                                .child(Label::new(title).size(LabelSize::Small).truncate()),
+                        )
+                        .children((1..=branches.len()).map(|position| {
+                            Button::new(
+                                SharedString::from(format!("branch-{entry_ix}-{position}")),
+                                position.to_string(),
+                            )
+                            .label_size(LabelSize::Small)
+                            .toggle_state(position == branches.position())
+                            .on_click(cx.listener({
+                                let message_id = message_id.clone();
+                                move |this, _, _, cx| {
+                                    this.select_branch(&message_id, position, cx);
+                                    this.scroll_to_entry(entry_ix, cx);
+                                }
+                            }))
+                        }));
+                    Some(row)
+                })),
+        )
+    }
+
     fn open_edited_buffer(
         &mut self,
         buffer: &Entity<Buffer>,
@@ -1349,27 +2606,75 @@ impl AcpThreadView {
                             .and_then(|entry| entry.focus_handle(cx))],
                     );
                 });
+                self.link_issues(thread, index..index + 1, cx);
+                if AgentSettings::get_global(cx).expand_entries {
+                    self.expand_entries(thread, index..index + 1, cx);
+                }
+                self.sync_tabs_with_path_change(thread, index, window, cx);
+                self.release_old_entry_views(thread, cx);
+                if let Some(message_id) = thread
+                    .read(cx)
+                    .entries()
+                    .get(index)
+                    .and_then(|entry| entry.user_message()?.id.clone())
+                    && let Some(branches) = self.rewound_branches.take()
+                {
+                    self.message_branches.insert(message_id, branches);
+                    self.save_branches(thread, cx);
+                }
             }
-            AcpThreadEvent::EntryUpdated(index) => {
+            // Released entries get their views again once they're loaded.
+            AcpThreadEvent::EntryUpdated(index) if *index >= self.first_live_entry => {
                 self.entry_view_state.update(cx, |view_state, cx| {
                     view_state.sync_entry(*index, thread, window, cx)
                 });
+                self.link_issues(thread, *index..*index + 1, cx);
+                self.sync_tabs_with_path_change(thread, *index, window, cx);
             }
+            AcpThreadEvent::EntryUpdated(_) => {}
             AcpThreadEvent::EntriesRemoved(range) => {
                 self.entry_view_state
                     .update(cx, |view_state, _cx| view_state.remove(range.clone()));
                 self.list_state.splice(range.clone(), 0);
+                self.first_live_entry = self.first_live_entry.min(range.start);
             }
             AcpThreadEvent::ToolAuthorizationRequired => {
-                self.notify_with_sound("Waiting for tool confirmation", IconName::Info, window, cx);
+                self.start_auto_approve_countdowns(thread, window, cx);
+                // Only take the focus from an empty message editor, so that no typing is lost.
+                let message_editor = self.message_editor.focus_handle(cx);
+                if message_editor.is_focused(window) && self.message_editor.read(cx).is_empty(cx) {
+                    self.confirmation_focus_handle.focus(window);
+                }
+                let kind = AgentSettings::get_global(cx).notifications.approval_needed;
+                self.notify(
+                    kind,
+                    "Waiting for tool confirmation",
+                    IconName::Info,
+                    window,
+                    cx,
+                );
             }
             AcpThreadEvent::Retry(retry) => {
                 self.thread_retry_status = Some(retry.clone());
             }
             AcpThreadEvent::Stopped => {
                 self.thread_retry_status.take();
+                if self.as_native_connection(cx).is_none() {
+                    self.history_store
+                        .update(cx, |history, cx| history.save_external_thread(thread, cx))
+                        .detach_and_log_err(cx);
+                }
+                if AgentSettings::get_global(cx).auto_summarize_context
+                    && thread
+                        .read(cx)
+                        .token_usage()
+                        .is_some_and(|usage| usage.ratio() != acp_thread::TokenUsageRatio::Normal)
+                {
+                    self.summarize_context(cx);
+                }
                 let used_tools = thread.read(cx).used_tools_since_last_user_message();
-                self.notify_with_sound(
+                self.notify(
+                    AgentSettings::get_global(cx).notifications.done,
                     if used_tools {
                         "Finished running tools"
                     } else {
@@ -1386,11 +2691,13 @@ impl AcpThreadView {
                 let model_or_agent_name = self.get_current_model_name(cx);
                 let notification_message =
                     format!("{} refused to respond to this request", model_or_agent_name);
-                self.notify_with_sound(&notification_message, IconName::Warning, window, cx);
+                let kind = AgentSettings::get_global(cx).notifications.error;
+                self.notify(kind, &notification_message, IconName::Warning, window, cx);
             }
             AcpThreadEvent::Error => {
                 self.thread_retry_status.take();
-                self.notify_with_sound(
+                self.notify(
+                    AgentSettings::get_global(cx).notifications.error,
                     "Agent stopped due to an error",
                     IconName::Warning,
                     window,
@@ -1697,9 +3004,13 @@ impl AcpThreadView {
         let Some(thread) = self.thread() else {
             return;
         };
-        thread.update(cx, |thread, cx| {
+        let has_pending_confirmation = thread.update(cx, |thread, cx| {
             thread.authorize_tool_call(tool_call_id, option_id, option_kind, cx);
+            thread.first_tool_awaiting_confirmation().is_some()
         });
+        if !has_pending_confirmation && self.confirmation_focus_handle.is_focused(window) {
+            self.message_editor.focus_handle(cx).focus(window);
+        }
         if self.should_be_following {
             self.workspace
                 .update(cx, |workspace, cx| {
@@ -1710,26 +3021,122 @@ impl AcpThreadView {
         cx.notify();
     }
 
-    fn restore_checkpoint(&mut self, message_id: &UserMessageId, cx: &mut Context<Self>) {
+    fn start_auto_approve_countdowns(
+        &mut self,
+        thread: &Entity<AcpThread>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let countdowns = AgentSettings::get_global(cx).auto_approve_countdown;
+        let pending = thread
+            .read(cx)
+            .entries()
+            .iter()
+            .filter_map(|entry| match entry {
+                AgentThreadEntry::ToolCall(tool_call)
+                    if matches!(
+                        tool_call.status,
+                        ToolCallStatus::WaitingForConfirmation { .. }
+                    ) && !self.auto_approve_countdowns.contains_key(&tool_call.id) =>
+                {
+                    let countdown = auto_approve_countdown(tool_call.kind, &countdowns);
+                    (!countdown.is_zero()).then(|| (tool_call.id.clone(), countdown))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (tool_call_id, countdown) in pending {
+            let task = cx.spawn_in(window, {
+                let tool_call_id = tool_call_id.clone();
+                async move |this, cx| {
+                    let mut remaining = countdown;
+                    while !remaining.is_zero() {
+                        let tick = remaining.min(Duration::from_secs(1));
+                        cx.background_executor().timer(tick).await;
+                        remaining -= tick;
+                        if this.update(cx, |_, cx| cx.notify()).is_err() {
+                            return;
+                        }
+                    }
+                    this.update_in(cx, |this, window, cx| {
+                        this.auto_approve_tool_call(tool_call_id, window, cx)
+                    })
+                    .ok();
+                }
+            });
+            self.auto_approve_countdowns.insert(
+                tool_call_id,
+                Some(AutoApproveCountdown {
+                    deadline: Instant::now() + countdown,
+                    _task: task,
+                }),
+            );
+        }
+    }
+
+    fn auto_approve_tool_call(
+        &mut self,
+        tool_call_id: acp::ToolCallId,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Keep the id, so that the countdown doesn't start over if the call is still waiting.
+        self.auto_approve_countdowns
+            .insert(tool_call_id.clone(), None);
         let Some(thread) = self.thread() else {
             return;
         };
-
-        thread
-            .update(cx, |thread, cx| {
-                thread.restore_checkpoint(message_id.clone(), cx)
-            })
-            .detach_and_log_err(cx);
+        let allow_once = thread
+            .read(cx)
+            .entries()
+            .iter()
+            .find_map(|entry| match entry {
+                AgentThreadEntry::ToolCall(ToolCall {
+                    id,
+                    status: ToolCallStatus::WaitingForConfirmation { options, .. },
+                    ..
+                }) if *id == tool_call_id => options
+                    .iter()
+                    .find(|option| option.kind == acp::PermissionOptionKind::AllowOnce)
+                    .cloned(),
+                _ => None,
+            });
+        if let Some(option) = allow_once {
+            self.authorize_tool_call(tool_call_id, option.id, option.kind, window, cx);
+        }
     }
 
-    fn render_entry(
-        &self,
-        entry_ix: usize,
+    fn cancel_auto_approve_countdown(
+        &mut self,
+        tool_call_id: acp::ToolCallId,
+        cx: &mut Context<Self>,
+    ) {
+        self.auto_approve_countdowns.insert(tool_call_id, None);
+        cx.notify();
+    }
+
+    fn restore_checkpoint(&mut self, message_id: &UserMessageId, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+
+        thread
+            .update(cx, |thread, cx| {
+                thread.restore_checkpoint(message_id.clone(), cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
+    fn render_entry(
+        &self,
+        entry_ix: usize,
         total_entries: usize,
         entry: &AgentThreadEntry,
         window: &mut Window,
         cx: &Context<Self>,
     ) -> AnyElement {
+        let minimal = AgentSettings::get_global(cx).minimal_transcript;
         let primary = match &entry {
             AgentThreadEntry::UserMessage(message) => {
                 let Some(editor) = self
@@ -1739,7 +3146,8 @@ impl AcpThreadView {
                     .and_then(|entry| entry.message_editor())
                     .cloned()
                 else {
-                    return Empty.into_any_element();
+                    return self
+                        .render_entry_error("The editor of this message is missing.".into());
                 };
 
                 let editing = self.editing_message == Some(entry_ix);
@@ -1759,6 +3167,12 @@ impl AcpThreadView {
 
                 let agent_name = self.agent.name();
 
+                let collapsible =
+                    !editing && editor.read(cx).line_count(cx) > COLLAPSED_USER_MESSAGE_LINES;
+                let expanded = self.expanded_user_messages.contains(&entry_ix);
+                let collapsed_height = window.line_height() * COLLAPSED_USER_MESSAGE_LINES as f32;
+                let message_group = SharedString::from(format!("user-message-{entry_ix}"));
+
                 v_flex()
                     .id(("user_message", entry_ix))
                     .map(|this| {
@@ -1800,6 +3214,7 @@ impl AcpThreadView {
                     .child(
                         div()
                             .relative()
+                            .group(&message_group)
                             .child(
                                 div()
                                     .py_3()
@@ -1820,7 +3235,46 @@ impl AcpThreadView {
                                         }
                                     })
                                     .text_xs()
-                                    .child(editor.clone().into_any_element()),
+                                    .map(|this| {
+                                        if collapsible && !expanded {
+                                            this.child(
+                                                div()
+                                                    .max_h(collapsed_height)
+                                                    .overflow_hidden()
+                                                    .child(editor.clone().into_any_element()),
+                                            )
+                                        } else {
+                                            this.child(editor.clone().into_any_element())
+                                        }
+                                    })
+                                    .when(collapsible, |this| {
+                                        this.child(
+                                            h_flex().pt_1().justify_center().child(
+                                                Button::new(
+                                                    ("toggle-user-message", entry_ix),
+                                                    if expanded { "Show Less" } else { "Show More" },
+                                                )
+                                                .icon(if expanded {
+                                                    IconName::ChevronUp
+                                                } else {
+                                                    IconName::ChevronDown
+                                                })
+                                                .icon_size(IconSize::XSmall)
+                                                .icon_color(Color::Muted)
+                                                .icon_position(IconPosition::End)
+                                                .label_size(LabelSize::XSmall)
+                                                .color(Color::Muted)
+                                                .on_click(cx.listener(move |this, _, _, cx| {
+                                                    if expanded {
+                                                        this.expanded_user_messages.remove(&entry_ix);
+                                                    } else {
+                                                        this.expanded_user_messages.insert(entry_ix);
+                                                    }
+                                                    cx.notify();
+                                                })),
+                                            ),
+                                        )
+                                    }),
                             )
                             .when(editor_focus, |this| {
                                 let base_container = h_flex()
@@ -1885,25 +3339,64 @@ impl AcpThreadView {
                                             )
                                     )
                                 }
+                            })
+                            .when(!editor_focus && message.id.is_some(), |this| {
+                                this.child(
+                                    h_flex()
+                                        .absolute()
+                                        .top_neg_3p5()
+                                        .right_3()
+                                        .rounded_sm()
+                                        .border_1()
+                                        .border_color(cx.theme().colors().border)
+                                        .bg(cx.theme().colors().editor_background)
+                                        .visible_on_hover(&message_group)
+                                        .child(
+                                            IconButton::new(("edit-user-message", entry_ix), IconName::Pencil)
+                                                .icon_color(Color::Muted)
+                                                .icon_size(IconSize::XSmall)
+                                                .tooltip(Tooltip::text("Edit Message"))
+                                                .on_click(cx.listener(move |this, _, window, cx| {
+                                                    this.edit_user_message(entry_ix, window, cx);
+                                                })),
+                                        ),
+                                )
                             }),
                     )
+                    .children(self.render_user_message_attachments(entry_ix, &message.chunks, cx))
+                    .children(self.render_branch_switcher(
+                        entry_ix,
+                        message.id.as_ref(),
+                        window,
+                        cx,
+                    ))
                     .into_any()
             }
-            AgentThreadEntry::AssistantMessage(AssistantMessage { chunks }) => {
+            AgentThreadEntry::AssistantMessage(message @ AssistantMessage { chunks }) => {
                 let is_last = entry_ix + 1 == total_entries;
+                let structured_response = self.structured_response(entry_ix, message, cx);
 
                 let style = default_markdown_style(false, false, window, cx);
                 let message_body = v_flex()
                     .w_full()
                     .gap_3()
-                    .children(chunks.iter().enumerate().filter_map(
-                        |(chunk_ix, chunk)| match chunk {
-                            AssistantMessageChunk::Message { block } => {
-                                block.markdown().map(|md| {
+                    .children(chunks.iter().enumerate().filter_map(|(chunk_ix, chunk)| {
+                        match chunk {
+                            // A structured response is shown as a tree instead.
+                            AssistantMessageChunk::Message { block } => block
+                                .markdown()
+                                .filter(|_| structured_response.is_none())
+                                .map(|md| {
+                                    let workspace = self.workspace.clone();
                                     self.render_markdown(md.clone(), style.clone())
+                                        .code_block_actions(move |id, code, language, _, cx| {
+                                            Self::render_code_block_actions(
+                                                &workspace, id, code, language, cx,
+                                            )
+                                        })
                                         .into_any_element()
-                                })
-                            }
+                                }),
+                            AssistantMessageChunk::Thought { .. } if minimal => None,
                             AssistantMessageChunk::Thought { block } => {
                                 block.markdown().map(|md| {
                                     self.render_thinking_block(
@@ -1916,33 +3409,53 @@ impl AcpThreadView {
                                     .into_any_element()
                                 })
                             }
-                        },
-                    ))
+                        }
+                    }))
+                    .when_some(structured_response, |this, (markdown_id, value)| {
+                        this.child(self.render_structured_response(markdown_id, value, cx))
+                    })
                     .into_any();
 
                 v_flex()
+                    .group("thread-entry")
+                    .relative()
                     .px_5()
                     .py_1p5()
                     .when(is_last, |this| this.pb_4())
                     .w_full()
                     .text_ui(cx)
                     .child(message_body)
+                    .child(self.render_entry_hover_actions(entry_ix, cx))
                     .into_any()
             }
+            AgentThreadEntry::ToolCall(tool_call)
+                if minimal
+                    && !matches!(
+                        tool_call.status,
+                        ToolCallStatus::WaitingForConfirmation { .. }
+                    ) =>
+            {
+                Empty.into_any()
+            }
             AgentThreadEntry::ToolCall(tool_call) => {
                 let has_terminals = tool_call.terminals().next().is_some();
 
-                div().w_full().map(|this| {
-                    if has_terminals {
-                        this.children(tool_call.terminals().map(|terminal| {
-                            self.render_terminal_tool_call(
-                                entry_ix, terminal, tool_call, window, cx,
-                            )
-                        }))
-                    } else {
-                        this.child(self.render_tool_call(entry_ix, tool_call, window, cx))
-                    }
-                })
+                div()
+                    .group("thread-entry")
+                    .relative()
+                    .w_full()
+                    .map(|this| {
+                        if has_terminals {
+                            this.children(tool_call.terminals().map(|terminal| {
+                                self.render_terminal_tool_call(
+                                    entry_ix, terminal, tool_call, window, cx,
+                                )
+                            }))
+                        } else {
+                            this.child(self.render_tool_call(entry_ix, tool_call, window, cx))
+                        }
+                    })
+                    .child(self.render_entry_hover_actions(entry_ix, cx))
             }
             .into_any(),
         };
@@ -1951,10 +3464,78 @@ impl AcpThreadView {
             return primary;
         };
 
+        let primary = match self.context_summary_position(cx) {
+            Some(ContextSummaryPosition::Before(message_id)) if matches!(entry, AgentThreadEntry::UserMessage(message) if message.id.as_ref() == Some(&message_id)) => {
+                v_flex()
+                    .w_full()
+                    .children(self.render_context_summary(cx))
+                    .child(primary)
+                    .into_any_element()
+            }
+            Some(ContextSummaryPosition::End) if entry_ix == total_entries - 1 => v_flex()
+                .w_full()
+                .child(primary)
+                .children(self.render_context_summary(cx))
+                .into_any_element(),
+            _ => primary,
+        };
+
+        let turn_stats = if minimal {
+            None
+        } else {
+            self.render_turn_stats(entry_ix, &thread, cx)
+        };
+        let primary = match turn_stats {
+            Some(turn_stats) => v_flex()
+                .w_full()
+                .child(primary)
+                .child(turn_stats)
+                .into_any_element(),
+            None => primary,
+        };
+
+        let entries = thread.read(cx).entries();
+        let turn_message_id = entries
+            .get(entry_ix + 1)
+            .is_none_or(|entry| matches!(entry, AgentThreadEntry::UserMessage(_)))
+            .then(|| {
+                entries[..=entry_ix]
+                    .iter()
+                    .rev()
+                    .find_map(|entry| entry.user_message())?
+                    .id
+                    .clone()
+            })
+            .flatten();
+        let branch_changes = self
+            .branch_changes
+            .iter()
+            .filter(|change| match &change.anchor {
+                BranchChangeAnchor::Turn(message_id) => {
+                    turn_message_id.as_ref() == Some(message_id)
+                }
+                BranchChangeAnchor::Entry(ix) => *ix == entry_ix,
+            })
+            .map(Self::render_branch_change)
+            .collect::<Vec<_>>();
+        let primary = if branch_changes.is_empty() {
+            primary
+        } else {
+            v_flex()
+                .w_full()
+                .child(primary)
+                .children(branch_changes)
+                .into_any_element()
+        };
+
         let primary = if entry_ix == total_entries - 1 {
             v_flex()
                 .w_full()
                 .child(primary)
+                .when(!minimal, |this| {
+                    this.children(self.render_turn_summary(&thread, cx))
+                })
+                .children(self.render_branch_tree(&thread, cx))
                 .child(self.render_thread_controls(&thread, cx))
                 .when_some(
                     self.thread_feedback.comments_editor.clone(),
@@ -2127,9 +3708,18 @@ impl AcpThreadView {
         let is_edit =
             matches!(tool_call.kind, acp::ToolKind::Edit) || tool_call.diffs().next().is_some();
 
-        let use_card_layout = needs_confirmation || is_edit || is_terminal_tool;
+        let path_change = self.render_path_change(entry_ix, tool_call, cx);
+
+        let use_card_layout =
+            needs_confirmation || is_edit || is_terminal_tool || path_change.is_some();
 
         let is_collapsible = !tool_call.content.is_empty() && !needs_confirmation;
+        let can_open_in_search = tool_call.kind == acp::ToolKind::Search
+            && tool_call
+                .raw_input
+                .as_ref()
+                .and_then(SearchToolQuery::from_raw_input)
+                .is_some();
 
         let is_open = needs_confirmation || self.expanded_tool_calls.contains(&tool_call.id);
 
@@ -2153,12 +3743,8 @@ impl AcpThreadView {
                                     .into_any_element()
                             },
                         ))
-                        .child(self.render_permission_buttons(
-                            tool_call.kind,
-                            options,
-                            entry_ix,
-                            tool_call.id.clone(),
-                            cx,
+                        .child(self.render_tool_call_confirmation(
+                            tool_call, options, entry_ix, window, cx,
                         ))
                         .into_any(),
                     ToolCallStatus::Pending | ToolCallStatus::InProgress
@@ -2230,19 +3816,12 @@ impl AcpThreadView {
                                     .size(LabelSize::XSmall)
                                     .color(Color::Muted),
                             )
-                            .child(
-                                MarkdownElement::new(
-                                    tool_call.label.clone(),
-                                    terminal_command_markdown_style(window, cx),
-                                )
-                                .code_block_renderer(
-                                    markdown::CodeBlockRenderer::Default {
-                                        copy_button: false,
-                                        copy_button_on_hover: false,
-                                        border: false,
-                                    },
-                                )
-                            ),
+                            .child(self.render_command(
+                                &tool_call.id,
+                                tool_call.label.clone(),
+                                window,
+                                cx,
+                            )),
                     )
                 } else {
                    this.child(
@@ -2265,6 +3844,21 @@ impl AcpThreadView {
                                 window,
                                 cx,
                             ))
+                            .when(can_open_in_search, |this| {
+                                this.child(
+                                    IconButton::new(
+                                        ("open-in-search", entry_ix),
+                                        IconName::MagnifyingGlass,
+                                    )
+                                    .icon_size(IconSize::Small)
+                                    .icon_color(Color::Muted)
+                                    .visible_on_hover(&card_header_id)
+                                    .tooltip(Tooltip::text("Open in Search"))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.open_tool_call_in_search(entry_ix, window, cx);
+                                    })),
+                                )
+                            })
                             .when(is_collapsible || failed_or_canceled, |this| {
                                 this.child(
                                     h_flex()
@@ -2290,20 +3884,57 @@ impl AcpThreadView {
                                         )
                                         })
                                         .when(failed_or_canceled, |this| {
-                                            this.child(
-                                                Icon::new(IconName::Close)
-                                                    .color(Color::Error)
-                                                    .size(IconSize::Small),
-                                            )
+                                            if matches!(
+                                                tool_call.status,
+                                                ToolCallStatus::Canceled
+                                            ) {
+                                                this.child(self.render_canceled_indicator(
+                                                    ("tool-call-canceled", entry_ix),
+                                                    tool_call,
+                                                    cx,
+                                                ))
+                                            } else {
+                                                this.child(
+                                                    Icon::new(IconName::Close)
+                                                        .color(Color::Error)
+                                                        .size(IconSize::Small),
+                                                )
+                                            }
                                         }),
                                 )
                             }),
                     )
                 }
             })
+            .children(path_change)
             .children(tool_output_display)
     }
 
+    /// Tells canceled tool calls apart from failed ones, and whether their command is still
+    /// running, since canceling doesn't stop it.
+    fn render_canceled_indicator(
+        &self,
+        id: impl Into<ElementId>,
+        tool_call: &ToolCall,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        h_flex()
+            .id(id)
+            .gap_1()
+            .child(
+                Icon::new(IconName::Stop)
+                    .size(IconSize::XSmall)
+                    .color(Color::Warning),
+            )
+            .child(
+                Label::new("Canceled")
+                    .color(Color::Muted)
+                    .size(LabelSize::XSmall),
+            )
+            .tooltip(Tooltip::text(canceled_tool_call_note(tool_call, cx)))
+            .into_any_element()
+    }
+
     fn render_tool_call_label(
         &self,
         entry_ix: usize,
@@ -2427,6 +4058,23 @@ impl AcpThreadView {
                 if let Some(resource_link) = content.resource_link() {
                     self.render_resource_link(resource_link, cx)
                 } else if let Some(markdown) = content.markdown() {
+                    if let Some(editor) = self
+                        .entry_view_state
+                        .read(cx)
+                        .entry(entry_ix)
+                        .and_then(|entry| entry.file_excerpt(markdown))
+                    {
+                        return self.render_file_excerpt(entry_ix, editor, tool_call, cx);
+                    }
+                    if let Some(value) = self.json_output(markdown, tool_call, cx) {
+                        return self.render_json_output(
+                            markdown.entity_id(),
+                            &value,
+                            context_ix,
+                            card_layout,
+                            cx,
+                        );
+                    }
                     self.render_markdown_output(
                         markdown.clone(),
                         tool_call.id.clone(),
@@ -2446,20 +4094,157 @@ impl AcpThreadView {
         }
     }
 
-    fn render_markdown_output(
+    fn render_file_excerpt(
         &self,
-        markdown: Entity<Markdown>,
-        tool_call_id: acp::ToolCallId,
+        entry_ix: usize,
+        editor: Entity<Editor>,
+        tool_call: &ToolCall,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let Some(location) = tool_call.locations.first() else {
+            return editor.into_any_element();
+        };
+        let path = self.display_path(&location.path, cx);
+        let first_line = location.line.unwrap_or(0) + 1;
+        let line_count = editor.read(cx).buffer().read(cx).read(cx).max_point().row + 1;
+        let label = format!("{path}:{first_line}-{}", first_line + line_count - 1);
+
+        v_flex()
+            .border_t_1()
+            .border_color(self.tool_card_border_color(cx))
+            .child(
+                h_flex()
+                    .id(("open-file-excerpt", entry_ix))
+                    .px_2()
+                    .py_1()
+                    .gap_1p5()
+                    .cursor_pointer()
+                    .hover(|s| s.bg(cx.theme().colors().element_hover.opacity(0.5)))
+                    .child(
+                        Icon::new(IconName::File)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(label)
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted)
+                            .truncate(),
+                    )
+                    .tooltip(Tooltip::text("Jump to File"))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.open_tool_call_location(entry_ix, 0, window, cx);
+                    })),
+            )
+            .child(editor)
+            .into_any()
+    }
+
+    /// The output parsed as JSON, if it is an object or array, such as the result of an
+    /// MCP tool. Outputs are only parsed once the tool call finished streaming them.
+    fn json_output(
+        &self,
+        markdown: &Entity<Markdown>,
+        tool_call: &ToolCall,
+        cx: &App,
+    ) -> Option<Rc<serde_json::Value>> {
+        if !matches!(
+            tool_call.status,
+            ToolCallStatus::Completed | ToolCallStatus::Failed
+        ) {
+            return None;
+        }
+        self.json_outputs
+            .borrow_mut()
+            .entry(markdown.entity_id())
+            .or_insert_with(|| parse_json_output(markdown.read(cx).source()).map(Rc::new))
+            .clone()
+    }
+
+    /// The response parsed as JSON, if the message it answers asked for a structured
+    /// response. Like tool outputs, responses are only parsed once they finished streaming.
+    fn structured_response(
+        &self,
+        entry_ix: usize,
+        message: &AssistantMessage,
+        cx: &App,
+    ) -> Option<(EntityId, Rc<serde_json::Value>)> {
+        let thread = self.thread()?.read(cx);
+        let entries = thread.entries();
+        if entry_ix + 1 == entries.len() && thread.status() != ThreadStatus::Idle {
+            return None;
+        }
+        let user_message = entries[..entry_ix]
+            .iter()
+            .rev()
+            .find_map(|entry| entry.user_message())?;
+        if !user_message.structured_response {
+            return None;
+        }
+        let markdown_id = message
+            .chunks
+            .iter()
+            .find_map(|chunk| match chunk {
+                AssistantMessageChunk::Message { block } => block.markdown(),
+                AssistantMessageChunk::Thought { .. } => None,
+            })?
+            .entity_id();
+        let value = self
+            .json_outputs
+            .borrow_mut()
+            .entry(markdown_id)
+            .or_insert_with(|| parse_json_output(&assistant_message_text(message, cx)).map(Rc::new))
+            .clone()?;
+        Some((markdown_id, value))
+    }
+
+    fn render_structured_response(
+        &self,
+        markdown_id: EntityId,
+        value: Rc<serde_json::Value>,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let json = serde_json::to_string_pretty(value.as_ref()).unwrap_or_default();
+
+        v_flex()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(
+                        Label::new("Structured Response")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new(("copy-structured-response", markdown_id), "Copy JSON")
+                            .icon(IconName::Copy)
+                            .icon_position(IconPosition::Start)
+                            .icon_size(IconSize::XSmall)
+                            .icon_color(Color::Muted)
+                            .label_size(LabelSize::Small)
+                            .on_click(move |_, _window, cx| {
+                                cx.write_to_clipboard(ClipboardItem::new_string(json.clone()))
+                            }),
+                    ),
+            )
+            .child(self.render_json_output(markdown_id, &value, 0, false, cx))
+            .into_any_element()
+    }
+
+    fn render_json_output(
+        &self,
+        markdown_id: EntityId,
+        value: &serde_json::Value,
         context_ix: usize,
         card_layout: bool,
-        window: &Window,
         cx: &Context<Self>,
     ) -> AnyElement {
-        let button_id = SharedString::from(format!("tool_output-{:?}", tool_call_id));
+        let mut rows = Vec::new();
+        self.render_json_node(markdown_id, None, value, "$".into(), 0, &mut rows, cx);
 
         v_flex()
+            .id(("json-output", markdown_id))
             .mt_1p5()
-            .gap_2()
             .when(!card_layout, |this| {
                 this.ml(rems(0.4))
                     .px_3p5()
@@ -2473,44 +4258,222 @@ impl AcpThreadView {
                         .border_color(self.tool_card_border_color(cx))
                 })
             })
+            .font_buffer(cx)
             .text_xs()
             .text_color(cx.theme().colors().text_muted)
-            .child(self.render_markdown(markdown, default_markdown_style(false, false, window, cx)))
-            .when(!card_layout, |this| {
-                this.child(
-                    IconButton::new(button_id, IconName::ChevronUp)
-                        .full_width()
-                        .style(ButtonStyle::Outlined)
-                        .icon_color(Color::Muted)
-                        .on_click(cx.listener({
-                            move |this: &mut Self, _, _, cx: &mut Context<Self>| {
-                                this.expanded_tool_calls.remove(&tool_call_id);
-                                cx.notify();
-                            }
-                        })),
-                )
-            })
+            .children(rows)
             .into_any_element()
     }
 
-    fn render_resource_link(
+    /// Adds the rows of the node and, if it is expanded, of its members. The root is
+    /// always expanded.
+    fn render_json_node(
         &self,
-        resource_link: &acp::ResourceLink,
+        markdown_id: EntityId,
+        key: Option<String>,
+        value: &serde_json::Value,
+        path: SharedString,
+        depth: usize,
+        rows: &mut Vec<AnyElement>,
         cx: &Context<Self>,
-    ) -> AnyElement {
-        let uri: SharedString = resource_link.uri.clone().into();
-        let is_file = resource_link.uri.strip_prefix("file://");
+    ) {
+        let syntax = cx.theme().syntax();
+        let member_count = match value {
+            serde_json::Value::Object(object) => Some(object.len()),
+            serde_json::Value::Array(array) => Some(array.len()),
+            _ => None,
+        };
+        let node = (markdown_id, path.clone());
+        let is_open =
+            member_count.is_some() && (depth == 0 || self.expanded_json_nodes.contains(&node));
+        let row_group = SharedString::from(format!("json-node-{markdown_id}-{path}"));
 
-        let label: SharedString = if let Some(abs_path) = is_file {
-            if let Some(project_path) = self
-                .project
-                .read(cx)
-                .project_path_for_absolute_path(&Path::new(abs_path), cx)
-                && let Some(worktree) = self
-                    .project
-                    .read(cx)
-                    .worktree_for_id(project_path.worktree_id, cx)
-            {
+        let (preview, preview_color) = match value {
+            serde_json::Value::Object(object) => (
+                format!(
+                    "{{…}} {} key{}",
+                    object.len(),
+                    if object.len() == 1 { "" } else { "s" }
+                ),
+                cx.theme().colors().text_muted,
+            ),
+            serde_json::Value::Array(array) => (
+                format!(
+                    "[…] {} item{}",
+                    array.len(),
+                    if array.len() == 1 { "" } else { "s" }
+                ),
+                cx.theme().colors().text_muted,
+            ),
+            serde_json::Value::String(string) => {
+                let truncated = util::truncate_and_trailoff(string, JSON_TREE_MAX_VALUE_LEN);
+                (
+                    serde_json::to_string(&truncated).unwrap_or(truncated),
+                    syntax.color("string"),
+                )
+            }
+            serde_json::Value::Number(number) => (number.to_string(), syntax.color("number")),
+            serde_json::Value::Bool(bool) => (bool.to_string(), syntax.color("boolean")),
+            serde_json::Value::Null => ("null".to_string(), syntax.color("constant")),
+        };
+
+        rows.push(
+            h_flex()
+                .id(row_group.clone())
+                .group(&row_group)
+                .gap_1()
+                .pl(rems(depth as f32 * 0.75))
+                .child(if member_count.is_some() {
+                    Disclosure::new(
+                        SharedString::from(format!("toggle-json-node-{markdown_id}-{path}")),
+                        is_open,
+                    )
+                    .opened_icon(IconName::ChevronDown)
+                    .closed_icon(IconName::ChevronRight)
+                    .disabled(depth == 0)
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        if !this.expanded_json_nodes.remove(&node) {
+                            this.expanded_json_nodes.insert(node.clone());
+                        }
+                        cx.notify();
+                    }))
+                    .into_any_element()
+                } else {
+                    div().w(IconSize::Small.rems()).into_any_element()
+                })
+                .children(key.map(|key| {
+                    div()
+                        .flex_none()
+                        .text_color(syntax.color("property"))
+                        .child(format!("{key}:"))
+                }))
+                .child(
+                    div()
+                        .min_w_0()
+                        .truncate()
+                        .text_color(preview_color)
+                        .child(preview),
+                )
+                .child(
+                    IconButton::new(
+                        SharedString::from(format!("copy-json-path-{markdown_id}-{path}")),
+                        IconName::Copy,
+                    )
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(Color::Muted)
+                    .visible_on_hover(&row_group)
+                    .tooltip(Tooltip::text(format!("Copy Path {path}")))
+                    .on_click({
+                        let path = path.clone();
+                        move |_, _, cx| {
+                            cx.write_to_clipboard(ClipboardItem::new_string(path.to_string()))
+                        }
+                    }),
+                )
+                .into_any_element(),
+        );
+
+        if !is_open {
+            return;
+        }
+        let members: Box<dyn Iterator<Item = (String, SharedString, &serde_json::Value)>> =
+            match value {
+                serde_json::Value::Object(object) => Box::new(
+                    object
+                        .iter()
+                        .map(|(key, value)| (key.clone(), json_member_path(&path, key), value)),
+                ),
+                serde_json::Value::Array(array) => Box::new(
+                    array
+                        .iter()
+                        .enumerate()
+                        .map(|(ix, value)| (ix.to_string(), format!("{path}[{ix}]").into(), value)),
+                ),
+                _ => return,
+            };
+        for (key, path, value) in members.take(JSON_TREE_MAX_CHILDREN) {
+            self.render_json_node(markdown_id, Some(key), value, path, depth + 1, rows, cx);
+        }
+        let hidden = member_count
+            .unwrap_or(0)
+            .saturating_sub(JSON_TREE_MAX_CHILDREN);
+        if hidden > 0 {
+            rows.push(
+                h_flex()
+                    .gap_1()
+                    .pl(rems((depth + 1) as f32 * 0.75))
+                    .child(div().w(IconSize::Small.rems()))
+                    .child(format!("… {hidden} more"))
+                    .into_any_element(),
+            );
+        }
+    }
+
+    fn render_markdown_output(
+        &self,
+        markdown: Entity<Markdown>,
+        tool_call_id: acp::ToolCallId,
+        context_ix: usize,
+        card_layout: bool,
+        window: &Window,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let button_id = SharedString::from(format!("tool_output-{:?}", tool_call_id));
+
+        v_flex()
+            .mt_1p5()
+            .gap_2()
+            .when(!card_layout, |this| {
+                this.ml(rems(0.4))
+                    .px_3p5()
+                    .border_l_1()
+                    .border_color(self.tool_card_border_color(cx))
+            })
+            .when(card_layout, |this| {
+                this.px_2().pb_2().when(context_ix > 0, |this| {
+                    this.border_t_1()
+                        .pt_2()
+                        .border_color(self.tool_card_border_color(cx))
+                })
+            })
+            .text_xs()
+            .text_color(cx.theme().colors().text_muted)
+            .child(self.render_markdown(markdown, default_markdown_style(false, false, window, cx)))
+            .when(!card_layout, |this| {
+                this.child(
+                    IconButton::new(button_id, IconName::ChevronUp)
+                        .full_width()
+                        .style(ButtonStyle::Outlined)
+                        .icon_color(Color::Muted)
+                        .on_click(cx.listener({
+                            move |this: &mut Self, _, _, cx: &mut Context<Self>| {
+                                this.expanded_tool_calls.remove(&tool_call_id);
+                                cx.notify();
+                            }
+                        })),
+                )
+            })
+            .into_any_element()
+    }
+
+    fn render_resource_link(
+        &self,
+        resource_link: &acp::ResourceLink,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let uri: SharedString = resource_link.uri.clone().into();
+        let is_file = resource_link.uri.strip_prefix("file://");
+
+        let label: SharedString = if let Some(abs_path) = is_file {
+            if let Some(project_path) = self
+                .project
+                .read(cx)
+                .project_path_for_absolute_path(&Path::new(abs_path), cx)
+                && let Some(worktree) = self
+                    .project
+                    .read(cx)
+                    .worktree_for_id(project_path.worktree_id, cx)
+            {
                 worktree
                     .read(cx)
                     .full_path(&project_path.path)
@@ -2552,12 +4515,48 @@ impl AcpThreadView {
             .into_any_element()
     }
 
+    /// The UI of the confirmation handler registered for the tool call, or the built-in buttons.
+    fn render_tool_call_confirmation(
+        &self,
+        tool_call: &ToolCall,
+        options: &[acp::PermissionOption],
+        entry_ix: usize,
+        window: &Window,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        if let Some(thread) = self.thread()
+            && let Some(handler) = acp_thread::tool_call_confirmation_handler(tool_call, cx)
+        {
+            let confirmation = ToolCallConfirmation {
+                thread: thread.downgrade(),
+                tool_call_id: tool_call.id.clone(),
+                options: options.to_vec(),
+            };
+            if let Some(element) = handler.render(tool_call, confirmation, window, cx) {
+                return element;
+            }
+        }
+
+        self.render_permission_buttons(
+            tool_call.kind,
+            confirmation_labels(tool_call, cx),
+            options,
+            entry_ix,
+            tool_call.id.clone(),
+            window,
+            cx,
+        )
+        .into_any_element()
+    }
+
     fn render_permission_buttons(
         &self,
         kind: acp::ToolKind,
+        labels: Option<(&'static str, &'static str)>,
         options: &[acp::PermissionOption],
         entry_ix: usize,
         tool_call_id: acp::ToolCallId,
+        window: &Window,
         cx: &Context<Self>,
     ) -> Div {
         let is_first = self.thread().is_some_and(|thread| {
@@ -2566,13 +4565,29 @@ impl AcpThreadView {
                 .first_tool_awaiting_confirmation()
                 .is_some_and(|call| call.id == tool_call_id)
         });
+        let is_focused = is_first && self.confirmation_focus_handle.is_focused(window);
         let mut seen_kinds: ArrayVec<acp::PermissionOptionKind, 3> = ArrayVec::new();
+        let countdown_remaining = self
+            .auto_approve_countdowns
+            .get(&tool_call_id)
+            .and_then(Option::as_ref)
+            .map(|countdown| {
+                countdown
+                    .deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_secs_f32()
+                    .ceil() as u64
+            });
 
         div()
             .p_1()
             .border_t_1()
             .border_color(self.tool_card_border_color(cx))
             .w_full()
+            .when(is_first, |this| {
+                this.key_context("ToolCallConfirmation")
+                    .track_focus(&self.confirmation_focus_handle)
+            })
             .map(|this| {
                 if kind == acp::ToolKind::SwitchMode {
                     this.v_flex()
@@ -2581,9 +4596,34 @@ impl AcpThreadView {
                 }
             })
             .gap_0p5()
-            .children(options.iter().map(move |option| {
+            .children(countdown_remaining.map(|seconds| {
+                h_flex()
+                    .mr_auto()
+                    .gap_1()
+                    .child(
+                        Label::new(format!("Auto-allowing in {seconds}s…"))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new(("cancel-auto-approve", entry_ix), "Cancel")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener({
+                                let tool_call_id = tool_call_id.clone();
+                                move |this, _, _, cx| {
+                                    this.cancel_auto_approve_countdown(tool_call_id.clone(), cx);
+                                }
+                            })),
+                    )
+            }))
+            .children(options.iter().enumerate().map(move |(option_ix, option)| {
                 let option_id = SharedString::from(option.id.0.clone());
-                Button::new((option_id, entry_ix), option.name.clone())
+                let label = match (option.kind, labels) {
+                    (acp::PermissionOptionKind::AllowOnce, Some((allow, _))) => allow.into(),
+                    (acp::PermissionOptionKind::RejectOnce, Some((_, reject))) => reject.into(),
+                    _ => option.name.clone(),
+                };
+                Button::new((option_id, entry_ix), label)
                     .map(|this| {
                         let (this, action) = match option.kind {
                             acp::PermissionOptionKind::AllowOnce => (
@@ -2603,6 +4643,18 @@ impl AcpThreadView {
                             }
                         };
 
+                        // While the confirmation is focused, options are picked by their number.
+                        if is_focused {
+                            return this.key_binding(
+                                KeyBinding::for_action_in(
+                                    &SelectPermissionOption(option_ix),
+                                    &self.confirmation_focus_handle,
+                                    cx,
+                                )
+                                .map(|kb| kb.size(rems_from_px(10.))),
+                            );
+                        }
+
                         let Some(action) = action else {
                             return this;
                         };
@@ -2695,6 +4747,9 @@ impl AcpThreadView {
             .h_full()
             .border_t_1()
             .border_color(self.tool_card_border_color(cx))
+            .when(diff.read(cx).creates_file(), |this| {
+                this.child(self.render_created_file_header(entry_ix, diff, tool_call, cx))
+            })
             .child(
                 if let Some(entry) = self.entry_view_state.read(cx).entry(entry_ix)
                     && let Some(editor) = entry.editor_for_diff(diff)
@@ -2710,64 +4765,301 @@ impl AcpThreadView {
             .into_any()
     }
 
-    fn render_terminal_tool_call(
+    /// The path relative to its worktree, or as is if it's outside the project.
+    fn display_path(&self, path: &Path, cx: &App) -> String {
+        let project = self.project.read(cx);
+        match project.find_project_path(path, cx) {
+            Some(project_path) => project_path
+                .path
+                .display(project.path_style(cx))
+                .to_string(),
+            None => path.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Shows what a tool call deletes, or where it moves a path from and to.
+    fn render_path_change(
         &self,
         entry_ix: usize,
-        terminal: &Entity<acp_thread::Terminal>,
         tool_call: &ToolCall,
-        window: &Window,
         cx: &Context<Self>,
-    ) -> AnyElement {
-        let terminal_data = terminal.read(cx);
-        let working_dir = terminal_data.working_dir();
-        let command = terminal_data.command();
-        let started_at = terminal_data.started_at();
-
-        let tool_failed = matches!(
-            &tool_call.status,
-            ToolCallStatus::Rejected | ToolCallStatus::Canceled | ToolCallStatus::Failed
-        );
-
-        let output = terminal_data.output();
-        let command_finished = output.is_some();
-        let truncated_output =
-            output.is_some_and(|output| output.original_content_len > output.content.len());
-        let output_line_count = output.map(|output| output.content_line_count).unwrap_or(0);
+    ) -> Option<AnyElement> {
+        let done = matches!(tool_call.status, ToolCallStatus::Completed);
+        let row = h_flex()
+            .px_2()
+            .py_1()
+            .gap_1p5()
+            .border_t_1()
+            .border_color(self.tool_card_border_color(cx));
+        let path_label = |path: &Path| {
+            Label::new(self.display_path(path, cx))
+                .size(LabelSize::XSmall)
+                .buffer_font(cx)
+                .truncate()
+        };
+        let status_label = |label: &'static str, color: Color| {
+            Label::new(label).size(LabelSize::XSmall).color(color)
+        };
 
-        let command_failed = command_finished
-            && output.is_some_and(|o| o.exit_status.is_some_and(|status| !status.success()));
+        match tool_call.kind {
+            acp::ToolKind::Delete => {
+                let path = &tool_call.locations.first()?.path;
+                Some(
+                    row.child(
+                        Icon::new(IconName::Trash)
+                            .size(IconSize::XSmall)
+                            .color(Color::Error),
+                    )
+                    .child(path_label(path).when(done, |label| label.strikethrough()))
+                    .when(!done, |this| {
+                        this.children(self.deleted_path_details(path, cx).map(|details| {
+                            Label::new(details)
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted)
+                        }))
+                    })
+                    .child(div().flex_1())
+                    .child(if done {
+                        status_label("Deleted", Color::Error)
+                    } else {
+                        status_label("Will Be Deleted", Color::Muted)
+                    })
+                    .into_any_element(),
+                )
+            }
+            acp::ToolKind::Move => {
+                let [source, destination] = tool_call.locations.as_slice() else {
+                    return None;
+                };
+                let destination_path = destination.path.clone();
+                Some(
+                    row.child(
+                        Icon::new(IconName::ArrowRightLeft)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(path_label(&source.path).color(Color::Muted))
+                    .child(
+                        Icon::new(IconName::ArrowRight)
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        h_flex()
+                            .id(("open-moved-path", entry_ix))
+                            .child(path_label(&destination.path))
+                            .when(done, |this| {
+                                this.cursor_pointer()
+                                    .tooltip(Tooltip::text("Open File"))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.open_file_at_path(&destination_path, window, cx);
+                                    }))
+                            }),
+                    )
+                    .child(div().flex_1())
+                    .child(if done {
+                        status_label("Moved", Color::Success)
+                    } else {
+                        status_label("Will Be Moved", Color::Muted)
+                    })
+                    .into_any_element(),
+                )
+            }
+            _ => None,
+        }
+    }
 
-        let time_elapsed = if let Some(output) = output {
-            output.ended_at.duration_since(started_at)
+    /// How much a deletion removes: the size of a file or the number of entries in a directory.
+    fn deleted_path_details(&self, path: &Path, cx: &App) -> Option<String> {
+        let project = self.project.read(cx);
+        let project_path = project.find_project_path(path, cx)?;
+        let worktree = project
+            .worktree_for_id(project_path.worktree_id, cx)?
+            .read(cx);
+        let entry = worktree.entry_for_path(&project_path.path)?;
+        if entry.is_dir() {
+            let count = worktree
+                .traverse_from_path(true, true, false, &project_path.path)
+                .skip(1)
+                .take_while(|entry| entry.path.starts_with(&project_path.path))
+                .count();
+            Some(format!(
+                "Directory with {count} {}",
+                if count == 1 { "entry" } else { "entries" }
+            ))
         } else {
-            started_at.elapsed()
-        };
-
-        let header_id =
-            SharedString::from(format!("terminal-tool-header-{}", terminal.entity_id()));
-        let header_group = SharedString::from(format!(
-            "terminal-tool-header-group-{}",
-            terminal.entity_id()
-        ));
-        let header_bg = cx
-            .theme()
-            .colors()
-            .element_background
-            .blend(cx.theme().colors().editor_foreground.opacity(0.025));
-        let border_color = cx.theme().colors().border.opacity(0.6);
+            Some(format_file_size(entry.size, false))
+        }
+    }
 
-        let working_dir = working_dir
-            .as_ref()
-            .map(|path| path.display().to_string())
-            .unwrap_or_else(|| "current directory".to_string());
+    /// Describes the file a tool call creates, since its preview isn't shown as a diff,
+    /// and links to the file once it was created.
+    fn render_created_file_header(
+        &self,
+        entry_ix: usize,
+        diff: &Entity<acp_thread::Diff>,
+        tool_call: &ToolCall,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let Some(path) = tool_call
+            .locations
+            .first()
+            .map(|location| location.path.clone())
+            .or_else(|| diff.read(cx).path(cx).map(PathBuf::from))
+        else {
+            return Empty.into_any_element();
+        };
+        let display_path = self.display_path(&path, cx);
 
-        let is_expanded = self.expanded_tool_calls.contains(&tool_call.id);
+        let buffer = diff.read(cx).new_buffer().read(cx);
+        let line_count = buffer.max_point().row + 1;
+        let mut details = vec![
+            format!(
+                "{line_count} {}",
+                if line_count == 1 { "line" } else { "lines" }
+            ),
+            format_file_size(buffer.len() as u64, false),
+        ];
+        if let Some(language) = buffer.language() {
+            details.push(language.name().to_string());
+        }
+        let created = matches!(tool_call.status, ToolCallStatus::Completed);
 
-        let header = h_flex()
-            .id(header_id)
-            .flex_none()
-            .gap_1()
-            .justify_between()
+        h_flex()
+            .id(("open-created-file", entry_ix))
+            .px_2()
+            .py_1()
+            .gap_1p5()
+            .border_b_1()
+            .border_color(self.tool_card_border_color(cx))
+            .child(
+                FileIcons::get_icon(&path, cx)
+                    .map(Icon::from_path)
+                    .unwrap_or(Icon::new(IconName::File))
+                    .size(IconSize::XSmall)
+                    .color(Color::Muted),
+            )
+            .child(
+                Label::new(display_path)
+                    .size(LabelSize::XSmall)
+                    .buffer_font(cx)
+                    .truncate(),
+            )
+            .child(
+                Label::new(details.join(" · "))
+                    .size(LabelSize::XSmall)
+                    .color(Color::Muted),
+            )
+            .child(div().flex_1())
+            .child(
+                Label::new(if created { "Created" } else { "New File" })
+                    .size(LabelSize::XSmall)
+                    .color(if created {
+                        Color::Success
+                    } else {
+                        Color::Muted
+                    }),
+            )
+            .when(created, |this| {
+                this.cursor_pointer()
+                    .hover(|s| s.bg(cx.theme().colors().element_hover.opacity(0.5)))
+                    .tooltip(Tooltip::text("Open File"))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.open_file_at_path(&path, window, cx);
+                    }))
+            })
+            .into_any_element()
+    }
+
+    fn open_file_at_path(&self, path: &Path, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(project_path) = self.project.read(cx).find_project_path(path, cx) else {
+            return;
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_path(project_path, None, true, window, cx)
+                    .detach_and_log_err(cx);
+            })
+            .log_err();
+    }
+
+    /// Shows the terminal running a command of the agent in the terminal panel, with full
+    /// terminal emulation and scrollback, where it keeps running along with the thread's view.
+    fn show_terminal_in_panel(
+        &self,
+        terminal: &Entity<acp_thread::Terminal>,
+        focus: bool,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let terminal = terminal.read(cx).inner().clone();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                TerminalPanel::show_terminal(workspace, terminal, focus, window, cx)
+            })
+            .ok();
+    }
+
+    fn render_terminal_tool_call(
+        &self,
+        entry_ix: usize,
+        terminal: &Entity<acp_thread::Terminal>,
+        tool_call: &ToolCall,
+        window: &Window,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        let terminal_data = terminal.read(cx);
+        let working_dir = terminal_data.working_dir();
+        let command = terminal_data.command();
+        let started_at = terminal_data.started_at();
+
+        let tool_failed = matches!(
+            &tool_call.status,
+            ToolCallStatus::Rejected | ToolCallStatus::Canceled | ToolCallStatus::Failed
+        );
+        let tool_canceled = matches!(tool_call.status, ToolCallStatus::Canceled);
+
+        let output = terminal_data.output();
+        let command_finished = output.is_some();
+        let truncated_output =
+            output.is_some_and(|output| output.original_content_len > output.content.len());
+        let output_line_count = output.map(|output| output.content_line_count).unwrap_or(0);
+
+        let command_failed = command_finished
+            && output.is_some_and(|o| o.exit_status.is_some_and(|status| !status.success()));
+
+        let time_elapsed = if let Some(output) = output {
+            output.ended_at.duration_since(started_at)
+        } else {
+            started_at.elapsed()
+        };
+
+        let header_id =
+            SharedString::from(format!("terminal-tool-header-{}", terminal.entity_id()));
+        let header_group = SharedString::from(format!(
+            "terminal-tool-header-group-{}",
+            terminal.entity_id()
+        ));
+        let header_bg = cx
+            .theme()
+            .colors()
+            .element_background
+            .blend(cx.theme().colors().editor_foreground.opacity(0.025));
+        let border_color = cx.theme().colors().border.opacity(0.6);
+
+        let working_dir = working_dir
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "current directory".to_string());
+
+        let is_expanded = self.expanded_tool_calls.contains(&tool_call.id);
+
+        let header = h_flex()
+            .id(header_id)
+            .flex_none()
+            .gap_1()
+            .justify_between()
             .rounded_t_md()
             .child(
                 div()
@@ -2863,7 +5155,16 @@ impl AcpThreadView {
                         .size(LabelSize::XSmall),
                 )
             })
-            .when(tool_failed || command_failed, |header| {
+            .when(tool_canceled, |header| {
+                header.child(self.render_canceled_indicator(
+                    ("terminal-tool-canceled", terminal.entity_id()),
+                    tool_call,
+                    cx,
+                ))
+            })
+            .when(
+                (tool_failed || command_failed) && !tool_canceled,
+                |header| {
                 header.child(
                     div()
                         .id(("terminal-tool-error-code-indicator", terminal.entity_id()))
@@ -2880,6 +5181,22 @@ impl AcpThreadView {
                         }),
                 )
             })
+            .child(
+                IconButton::new(
+                    ("open-terminal-in-panel", terminal.entity_id()),
+                    IconName::ArrowUpRight,
+                )
+                .icon_size(IconSize::Small)
+                .icon_color(Color::Muted)
+                .visible_on_hover(&header_group)
+                .tooltip(Tooltip::text("Open in Terminal Panel"))
+                .on_click(cx.listener({
+                    let terminal = terminal.clone();
+                    move |this, _event, window, cx| {
+                        this.show_terminal_in_panel(&terminal, true, window, cx);
+                    }
+                })),
+            )
             .child(
                 Disclosure::new(
                     SharedString::from(format!(
@@ -2928,19 +5245,7 @@ impl AcpThreadView {
                     .bg(header_bg)
                     .text_xs()
                     .child(header)
-                    .child(
-                        MarkdownElement::new(
-                            command.clone(),
-                            terminal_command_markdown_style(window, cx),
-                        )
-                        .code_block_renderer(
-                            markdown::CodeBlockRenderer::Default {
-                                copy_button: false,
-                                copy_button_on_hover: true,
-                                border: false,
-                            },
-                        ),
-                    ),
+                    .child(self.render_command(&tool_call.id, command.clone(), window, cx)),
             )
             .when(show_output, |this| {
                 this.child(
@@ -2969,6 +5274,60 @@ impl AcpThreadView {
             .into_any()
     }
 
+    /// Renders a command as selectable monospace text that soft wraps, unless wrapping was
+    /// turned off for it, in which case it scrolls horizontally.
+    fn render_command(
+        &self,
+        tool_call_id: &acp::ToolCallId,
+        command: Entity<Markdown>,
+        window: &Window,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let soft_wrap = !self.unwrapped_commands.contains(tool_call_id);
+        h_flex()
+            .w_full()
+            .items_start()
+            .gap_1()
+            .child(
+                div().flex_1().min_w_0().child(
+                    MarkdownElement::new(
+                        command,
+                        terminal_command_markdown_style(soft_wrap, window, cx),
+                    )
+                    .code_block_renderer(
+                        markdown::CodeBlockRenderer::Default {
+                            copy_button: true,
+                            copy_button_on_hover: false,
+                            border: false,
+                        },
+                    ),
+                ),
+            )
+            .child(
+                IconButton::new(
+                    SharedString::from(format!("toggle-command-soft-wrap-{}", tool_call_id.0)),
+                    IconName::ArrowRightLeft,
+                )
+                .icon_size(IconSize::XSmall)
+                .icon_color(Color::Muted)
+                .toggle_state(!soft_wrap)
+                .tooltip(Tooltip::text(if soft_wrap {
+                    "Disable Soft Wrap"
+                } else {
+                    "Enable Soft Wrap"
+                }))
+                .on_click(cx.listener({
+                    let tool_call_id = tool_call_id.clone();
+                    move |this, _, _, cx| {
+                        if !this.unwrapped_commands.remove(&tool_call_id) {
+                            this.unwrapped_commands.insert(tool_call_id.clone());
+                        }
+                        cx.notify();
+                    }
+                })),
+            )
+    }
+
     fn render_rules_item(&self, cx: &Context<Self>) -> Option<AnyElement> {
         let project_context = self
             .as_native_thread(cx)?
@@ -3416,8 +5775,9 @@ impl AcpThreadView {
         let action_log = thread.action_log();
         let changed_buffers = action_log.read(cx).changed_buffers(cx);
         let plan = thread.plan();
+        let file_changes = thread_file_changes(thread.entries(), cx);
 
-        if changed_buffers.is_empty() && plan.is_empty() {
+        if changed_buffers.is_empty() && plan.is_empty() && file_changes.is_empty() {
             return None;
         }
 
@@ -3467,10 +5827,177 @@ impl AcpThreadView {
                     ))
                 })
             })
+            .when(
+                !file_changes.is_empty() && (!plan.is_empty() || !changed_buffers.is_empty()),
+                |this| this.child(Divider::horizontal().color(DividerColor::Border)),
+            )
+            .when(!file_changes.is_empty(), |this| {
+                this.child(self.render_file_changes_summary(&file_changes, cx))
+                    .when(self.file_changes_expanded, |parent| {
+                        parent.child(self.render_file_changes(
+                            action_log,
+                            &changed_buffers,
+                            &file_changes,
+                            cx,
+                        ))
+                    })
+            })
             .into_any()
             .into()
     }
 
+    fn render_file_changes_summary(
+        &self,
+        file_changes: &[FileChange],
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let count = |kind| {
+            file_changes
+                .iter()
+                .filter(|change| change.kind == kind)
+                .count()
+        };
+        let counts = [
+            (FileChangeKind::Created, "+", Color::Created),
+            (FileChangeKind::Modified, "~", Color::Modified),
+            (FileChangeKind::Deleted, "-", Color::Deleted),
+        ];
+
+        h_flex()
+            .id("file-changes-container")
+            .p_1()
+            .gap_1()
+            .when(self.file_changes_expanded, |this| {
+                this.border_b_1().border_color(cx.theme().colors().border)
+            })
+            .child(Disclosure::new(
+                "file-changes-disclosure",
+                self.file_changes_expanded,
+            ))
+            .child(
+                Label::new("Files Changed in This Thread")
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .children(counts.into_iter().filter_map(|(kind, sign, color)| {
+                let count = count(kind);
+                (count > 0).then(|| {
+                    Label::new(format!("{sign}{count}"))
+                        .size(LabelSize::Small)
+                        .color(color)
+                })
+            }))
+            .on_click(cx.listener(|this, _, _, cx| {
+                this.file_changes_expanded = !this.file_changes_expanded;
+                cx.notify();
+            }))
+    }
+
+    fn render_file_changes(
+        &self,
+        action_log: &Entity<ActionLog>,
+        changed_buffers: &BTreeMap<Entity<Buffer>, Entity<BufferDiff>>,
+        file_changes: &[FileChange],
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let editor_bg_color = cx.theme().colors().editor_background;
+
+        v_flex().children(file_changes.iter().enumerate().map(|(index, change)| {
+            // Unreviewed changes can still be shown in the agent diff and rejected.
+            let unreviewed_buffer = changed_buffers
+                .keys()
+                .find(|buffer| {
+                    buffer
+                        .read(cx)
+                        .file()
+                        .and_then(|file| file.as_local())
+                        .is_some_and(|file| file.abs_path(cx) == change.path)
+                })
+                .cloned();
+            let (sign, color) = match change.kind {
+                FileChangeKind::Created => ("A", Color::Created),
+                FileChangeKind::Modified => ("M", Color::Modified),
+                FileChangeKind::Deleted => ("D", Color::Deleted),
+            };
+            let entry_ix = change.entry_ix;
+            let location_ix = change.location_ix;
+
+            h_flex()
+                .group("changed-file")
+                .id(("changed-file", index))
+                .py_1()
+                .pl_2()
+                .pr_1()
+                .gap_2()
+                .justify_between()
+                .bg(editor_bg_color)
+                .when(index < file_changes.len() - 1, |parent| {
+                    parent.border_color(cx.theme().colors().border).border_b_1()
+                })
+                .child(
+                    h_flex()
+                        .min_w_0()
+                        .gap_1p5()
+                        .child(Label::new(sign).size(LabelSize::XSmall).color(color))
+                        .child(
+                            Label::new(change.path.to_string_lossy().into_owned())
+                                .size(LabelSize::XSmall)
+                                .buffer_font(cx)
+                                .truncate(),
+                        ),
+                )
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .visible_on_hover("changed-file")
+                        .child(
+                            Button::new(("diff-changed-file", index), "Diff")
+                                .label_size(LabelSize::Small)
+                                .on_click({
+                                    let buffer = unreviewed_buffer.clone();
+                                    cx.listener(move |this, _, window, cx| match &buffer {
+                                        Some(buffer) => this.open_edited_buffer(buffer, window, cx),
+                                        None => this.scroll_to_entry(entry_ix, cx),
+                                    })
+                                }),
+                        )
+                        .child(
+                            Button::new(("open-changed-file", index), "Open")
+                                .label_size(LabelSize::Small)
+                                .disabled(change.kind == FileChangeKind::Deleted)
+                                .on_click(cx.listener(move |this, _, window, cx| {
+                                    this.open_tool_call_location(entry_ix, location_ix, window, cx);
+                                })),
+                        )
+                        .child(
+                            Button::new(("revert-changed-file", index), "Revert")
+                                .label_size(LabelSize::Small)
+                                .disabled(unreviewed_buffer.is_none())
+                                .when(unreviewed_buffer.is_none(), |this| {
+                                    this.tooltip(Tooltip::text("Kept changes can't be reverted"))
+                                })
+                                .on_click({
+                                    let action_log = action_log.clone();
+                                    move |_, _, cx| {
+                                        let Some(buffer) = unreviewed_buffer.clone() else {
+                                            return;
+                                        };
+                                        action_log.update(cx, |action_log, cx| {
+                                            action_log
+                                                .reject_edits_in_ranges(
+                                                    buffer,
+                                                    vec![Anchor::MIN..Anchor::MAX],
+                                                    cx,
+                                                )
+                                                .detach_and_log_err(cx);
+                                        })
+                                    }
+                                }),
+                        ),
+                )
+        }))
+    }
+
     fn render_plan_summary(
         &self,
         plan: &Plan,
@@ -3498,7 +6025,7 @@ impl AcpThreadView {
                         .text_xs()
                         .text_color(cx.theme().colors().text_muted)
                         .line_clamp(1)
-                        .child(MarkdownElement::new(
+                        .child(self.render_markdown(
                             entry.content.clone(),
                             plan_label_markdown_style(&entry.status, window, cx),
                         )),
@@ -3600,7 +6127,7 @@ impl AcpThreadView {
                                 .color(Color::Success)
                                 .into_any_element(),
                         })
-                        .child(MarkdownElement::new(
+                        .child(self.render_markdown(
                             entry.content.clone(),
                             plan_label_markdown_style(&entry.status, window, cx),
                         )),
@@ -3904,11 +6431,15 @@ impl AcpThreadView {
             .block_mouse_except_scroll();
 
         let enable_editor = match self.thread_state {
-            ThreadState::Loading { .. } | ThreadState::Ready { .. } => true,
+            ThreadState::NotConnected | ThreadState::Loading { .. } | ThreadState::Ready { .. } => {
+                true
+            }
             ThreadState::Unauthenticated { .. } | ThreadState::LoadError(..) => false,
         };
 
         v_flex()
+            .capture_action(cx.listener(Self::recall_previous_message))
+            .capture_action(cx.listener(Self::recall_next_message))
             .on_action(cx.listener(Self::expand_message_editor))
             .on_action(cx.listener(|this, _: &ToggleProfileSelector, window, cx| {
                 if let Some(profile_selector) = this.profile_selector.as_ref() {
@@ -3980,6 +6511,11 @@ impl AcpThreadView {
                     .child(
                         h_flex()
                             .child(self.render_follow_toggle(cx))
+                            .children(self.render_plan_mode_toggle(cx))
+                            .children(self.render_auto_accept_edits_toggle(cx))
+                            .child(self.render_structured_response_toggle(cx))
+                            .child(self.render_add_selection_button(cx))
+                            .children(self.render_attach_images_button(cx))
                             .children(self.render_burn_mode_toggle(cx)),
                     )
                     .child(
@@ -4080,6 +6616,36 @@ impl AcpThreadView {
         });
     }
 
+    fn toggle_plan_mode(
+        &mut self,
+        _: &TogglePlanMode,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        thread.update(cx, |thread, cx| {
+            let plan_mode = thread.plan_mode();
+            thread.set_plan_mode(!plan_mode, cx);
+        });
+    }
+
+    fn toggle_auto_accept_edits(
+        &mut self,
+        _: &ToggleAutoAcceptEdits,
+        _window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        thread.update(cx, |thread, cx| {
+            let auto_accept_edits = thread.auto_accept_edits();
+            thread.set_auto_accept_edits(!auto_accept_edits, cx);
+        });
+    }
+
     fn keep_all(&mut self, _: &KeepAll, _window: &mut Window, cx: &mut Context<Self>) {
         let Some(thread) = self.thread() else {
             return;
@@ -4099,20 +6665,145 @@ impl AcpThreadView {
     }
 
     fn allow_always(&mut self, _: &AllowAlways, window: &mut Window, cx: &mut Context<Self>) {
-        self.authorize_pending_tool_call(acp::PermissionOptionKind::AllowAlways, window, cx);
+        self.authorize_pending_tool_call(
+            |options| {
+                options
+                    .iter()
+                    .find(|o| o.kind == acp::PermissionOptionKind::AllowAlways)
+            },
+            window,
+            cx,
+        );
     }
 
     fn allow_once(&mut self, _: &AllowOnce, window: &mut Window, cx: &mut Context<Self>) {
-        self.authorize_pending_tool_call(acp::PermissionOptionKind::AllowOnce, window, cx);
+        self.authorize_pending_tool_call(
+            |options| {
+                options
+                    .iter()
+                    .find(|o| o.kind == acp::PermissionOptionKind::AllowOnce)
+            },
+            window,
+            cx,
+        );
     }
 
     fn reject_once(&mut self, _: &RejectOnce, window: &mut Window, cx: &mut Context<Self>) {
-        self.authorize_pending_tool_call(acp::PermissionOptionKind::RejectOnce, window, cx);
+        self.authorize_pending_tool_call(
+            |options| {
+                options
+                    .iter()
+                    .find(|o| o.kind == acp::PermissionOptionKind::RejectOnce)
+            },
+            window,
+            cx,
+        );
+    }
+
+    fn select_next_entry(&mut self, _: &SelectNextEntry, _: &mut Window, cx: &mut Context<Self>) {
+        let scroll_top = self.list_state.logical_scroll_top();
+        let last_ix = self.list_state.item_count().saturating_sub(1);
+        self.list_state.scroll_to(ListOffset {
+            item_ix: (scroll_top.item_ix + 1).min(last_ix),
+            offset_in_item: px(0.),
+        });
+        cx.notify();
+    }
+
+    fn select_previous_entry(
+        &mut self,
+        _: &SelectPreviousEntry,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let scroll_top = self.list_state.logical_scroll_top();
+        // Scrolled into an entry, go back to its start first.
+        let item_ix = if scroll_top.offset_in_item > px(0.) {
+            scroll_top.item_ix
+        } else {
+            scroll_top.item_ix.saturating_sub(1)
+        };
+        self.list_state.scroll_to(ListOffset {
+            item_ix,
+            offset_in_item: px(0.),
+        });
+        cx.notify();
+    }
+
+    fn toggle_thinking(&mut self, _: &ToggleThinking, _: &mut Window, cx: &mut Context<Self>) {
+        if !self.expanded_thinking_blocks.is_empty() {
+            self.expanded_thinking_blocks.clear();
+        } else if let Some(thread) = self.thread() {
+            for (entry_ix, entry) in thread.read(cx).entries().iter().enumerate() {
+                let AgentThreadEntry::AssistantMessage(message) = entry else {
+                    continue;
+                };
+                for (chunk_ix, chunk) in message.chunks.iter().enumerate() {
+                    if matches!(chunk, AssistantMessageChunk::Thought { .. }) {
+                        self.expanded_thinking_blocks.insert((entry_ix, chunk_ix));
+                    }
+                }
+            }
+        }
+        cx.notify();
+    }
+
+    fn expand_all_entries(&mut self, _: &ExpandAllEntries, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(thread) = self.thread().cloned() {
+            let len = thread.read(cx).entries().len();
+            self.expand_entries(&thread, 0..len, cx);
+        }
+        cx.notify();
+    }
+
+    fn collapse_all_entries(
+        &mut self,
+        _: &CollapseAllEntries,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.expanded_tool_calls.clear();
+        self.expanded_thinking_blocks.clear();
+        cx.notify();
+    }
+
+    /// Expands the tool calls and thinking blocks of the entries in the range.
+    fn expand_entries(
+        &mut self,
+        thread: &Entity<AcpThread>,
+        range: Range<usize>,
+        cx: &mut Context<Self>,
+    ) {
+        let entries = &thread.read(cx).entries()[range.clone()];
+        for (entry_ix, entry) in range.zip(entries) {
+            match entry {
+                AgentThreadEntry::ToolCall(tool_call) => {
+                    self.expanded_tool_calls.insert(tool_call.id.clone());
+                }
+                AgentThreadEntry::AssistantMessage(message) => {
+                    for (chunk_ix, chunk) in message.chunks.iter().enumerate() {
+                        if matches!(chunk, AssistantMessageChunk::Thought { .. }) {
+                            self.expanded_thinking_blocks.insert((entry_ix, chunk_ix));
+                        }
+                    }
+                }
+                AgentThreadEntry::UserMessage(_) => {}
+            }
+        }
+    }
+
+    fn select_permission_option(
+        &mut self,
+        action: &SelectPermissionOption,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.authorize_pending_tool_call(|options| options.get(action.0), window, cx);
     }
 
     fn authorize_pending_tool_call(
         &mut self,
-        kind: acp::PermissionOptionKind,
+        select_option: impl FnOnce(&[acp::PermissionOption]) -> Option<&acp::PermissionOption>,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Option<()> {
@@ -4121,7 +6812,7 @@ impl AcpThreadView {
         let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
             return None;
         };
-        let option = options.iter().find(|o| o.kind == kind)?;
+        let option = select_option(options)?;
 
         self.authorize_tool_call(
             tool_call.id.clone(),
@@ -4169,6 +6860,112 @@ impl AcpThreadView {
         )
     }
 
+    fn render_plan_mode_toggle(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let plan_mode = self.thread()?.read(cx).plan_mode();
+
+        Some(
+            IconButton::new("plan-mode", IconName::ListTodo)
+                .icon_size(IconSize::Small)
+                .icon_color(Color::Muted)
+                .toggle_state(plan_mode)
+                .selected_icon_color(Color::Accent)
+                .on_click(cx.listener(|this, _event, window, cx| {
+                    this.toggle_plan_mode(&TogglePlanMode, window, cx);
+                }))
+                .tooltip(move |_window, cx| {
+                    Tooltip::with_meta(
+                        if plan_mode {
+                            "Turn Off Plan Mode"
+                        } else {
+                            "Turn On Plan Mode"
+                        },
+                        Some(&TogglePlanMode),
+                        "The agent only proposes changes, and its edits and commands are rejected.",
+                        cx,
+                    )
+                })
+                .into_any_element(),
+        )
+    }
+
+    fn render_auto_accept_edits_toggle(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let auto_accept_edits = self.thread()?.read(cx).auto_accept_edits();
+
+        Some(
+            IconButton::new("auto-accept-edits", IconName::CheckDouble)
+                .icon_size(IconSize::Small)
+                .icon_color(Color::Muted)
+                .toggle_state(auto_accept_edits)
+                .selected_icon_color(Color::Warning)
+                .on_click(cx.listener(|this, _event, window, cx| {
+                    this.toggle_auto_accept_edits(&ToggleAutoAcceptEdits, window, cx);
+                }))
+                .tooltip(move |_window, cx| {
+                    Tooltip::with_meta(
+                        if auto_accept_edits {
+                            "Stop Accepting Edits Automatically"
+                        } else {
+                            "Accept Edits Automatically"
+                        },
+                        Some(&ToggleAutoAcceptEdits),
+                        "Allows the agent's edits without confirmation, in this thread only.",
+                        cx,
+                    )
+                })
+                .into_any_element(),
+        )
+    }
+
+    fn render_structured_response_toggle(&self, cx: &mut Context<Self>) -> AnyElement {
+        let structured_response = self.message_editor.read(cx).structured_response();
+
+        IconButton::new("structured-response", IconName::Json)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Muted)
+            .toggle_state(structured_response)
+            .selected_icon_color(Color::Accent)
+            .on_click(cx.listener(move |this, _event, _window, cx| {
+                this.message_editor.update(cx, |message_editor, cx| {
+                    message_editor.set_structured_response(!structured_response, cx)
+                });
+            }))
+            .tooltip(move |_window, cx| {
+                Tooltip::with_meta(
+                    if structured_response {
+                        "Don't Ask for JSON"
+                    } else {
+                        "Ask for JSON"
+                    },
+                    None,
+                    "The agent responds to this message with JSON, shown as a tree. Also available as /json.",
+                    cx,
+                )
+            })
+            .into_any_element()
+    }
+
+    /// Stands out while edits are accepted automatically, so that it isn't left on unnoticed.
+    fn render_auto_accept_edits_callout(&self, cx: &mut Context<Self>) -> Option<Callout> {
+        if !self.thread()?.read(cx).auto_accept_edits() {
+            return None;
+        }
+
+        Some(
+            Callout::new()
+                .severity(Severity::Warning)
+                .icon(IconName::CheckDouble)
+                .title("Accepting edits automatically")
+                .description("The agent's edits in this thread are allowed without confirmation.")
+                .actions_slot(
+                    Button::new("turn-off-auto-accept-edits", "Turn Off")
+                        .label_size(LabelSize::Small)
+                        .on_click(cx.listener(|this, _event, window, cx| {
+                            this.toggle_auto_accept_edits(&ToggleAutoAcceptEdits, window, cx);
+                        })),
+                ),
+        )
+    }
+
     fn render_send_button(&self, cx: &mut Context<Self>) -> AnyElement {
         let is_editor_empty = self.message_editor.read(cx).is_empty(cx);
         let is_generating = self
@@ -4287,1608 +7084,4420 @@ impl AcpThreadView {
             }))
     }
 
-    fn render_markdown(&self, markdown: Entity<Markdown>, style: MarkdownStyle) -> MarkdownElement {
-        let workspace = self.workspace.clone();
-        MarkdownElement::new(markdown, style).on_url_click(move |text, window, cx| {
-            Self::open_link(text, &workspace, window, cx);
-        })
+    /// The git branch checked out in the agent's working directory.
+    fn current_branch(&self, cx: &App) -> Option<SharedString> {
+        let ThreadState::Ready { root_dir, .. } = &self.thread_state else {
+            return None;
+        };
+        let git_store = self.project.read(cx).git_store().read(cx);
+        git_store
+            .repositories()
+            .values()
+            .map(|repository| repository.read(cx))
+            .filter(|repository| root_dir.starts_with(&repository.work_directory_abs_path))
+            .max_by_key(|repository| repository.work_directory_abs_path.as_os_str().len())
+            .and_then(|repository| Some(SharedString::from(repository.branch.as_ref()?.name())))
+    }
+
+    /// Notes a switch of the branch in the transcript, and for the agent, once the thread
+    /// has started, since the agent's understanding of the files may be stale.
+    fn detect_branch_change(&mut self, cx: &mut Context<Self>) {
+        let branch = self.current_branch(cx);
+        if branch == self.branch {
+            return;
+        }
+        let from = std::mem::replace(&mut self.branch, branch.clone());
+        // The branch is unknown while the repository is being scanned.
+        let (Some(from), Some(to)) = (from, branch) else {
+            return;
+        };
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        let entries = thread.read(cx).entries();
+        if entries.is_empty() {
+            return;
+        }
+        let last_message_id = entries
+            .iter()
+            .rev()
+            .find_map(|entry| entry.user_message())
+            .and_then(|message| message.id.clone());
+        let anchor = match last_message_id {
+            Some(message_id) => BranchChangeAnchor::Turn(message_id),
+            None => BranchChangeAnchor::Entry(entries.len() - 1),
+        };
+
+        self.branch_changes.push(BranchChange {
+            anchor,
+            from: from.clone(),
+            to: to.clone(),
+        });
+        // Switching back before the next message leaves nothing to tell the agent.
+        let agent_branch = self
+            .unsent_branch_change
+            .take()
+            .map_or(from, |(agent_branch, _)| agent_branch);
+        if agent_branch != to {
+            self.unsent_branch_change = Some((agent_branch, to));
+        }
     }
 
-    fn open_link(
-        url: SharedString,
-        workspace: &WeakEntity<Workspace>,
-        window: &mut Window,
-        cx: &mut App,
+    /// Collects the files changed on disk outside of the agent's edits, to tell the agent
+    /// about them once they stop changing.
+    fn queue_file_changes(
+        &mut self,
+        worktree_id: WorktreeId,
+        changes: &UpdatedEntriesSet,
+        file_changes: Rc<dyn AgentSessionFileChanges>,
+        cx: &mut Context<Self>,
     ) {
-        let Some(workspace) = workspace.upgrade() else {
-            cx.open_url(&url);
+        if !AgentSettings::get_global(cx).notify_agent_of_file_changes {
+            return;
+        }
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        let Some(worktree) = self.project.read(cx).worktree_for_id(worktree_id, cx) else {
             return;
         };
 
-        if let Some(mention) = MentionUri::parse(&url).log_err() {
-            workspace.update(cx, |workspace, cx| match mention {
-                MentionUri::File { abs_path } => {
-                    let project = workspace.project();
-                    let Some(path) =
-                        project.update(cx, |project, cx| project.find_project_path(abs_path, cx))
-                    else {
-                        return;
-                    };
-
-                    workspace
-                        .open_path(path, None, true, window, cx)
-                        .detach_and_log_err(cx);
-                }
-                MentionUri::PastedImage => {}
-                MentionUri::Directory { abs_path } => {
-                    let project = workspace.project();
-                    let Some(entry_id) = project.update(cx, |project, cx| {
-                        let path = project.find_project_path(abs_path, cx)?;
-                        project.entry_for_path(&path, cx).map(|entry| entry.id)
-                    }) else {
-                        return;
-                    };
-
-                    project.update(cx, |_, cx| {
-                        cx.emit(project::Event::RevealInProjectPanel(entry_id));
-                    });
-                }
-                MentionUri::Symbol {
-                    abs_path: path,
-                    line_range,
-                    ..
-                }
-                | MentionUri::Selection {
-                    abs_path: Some(path),
-                    line_range,
-                } => {
-                    let project = workspace.project();
-                    let Some(path) =
-                        project.update(cx, |project, cx| project.find_project_path(path, cx))
-                    else {
-                        return;
-                    };
-
-                    let item = workspace.open_path(path, None, true, window, cx);
-                    window
-                        .spawn(cx, async move |cx| {
-                            let Some(editor) = item.await?.downcast::<Editor>() else {
-                                return Ok(());
-                            };
-                            let range = Point::new(*line_range.start(), 0)
-                                ..Point::new(*line_range.start(), 0);
-                            editor
-                                .update_in(cx, |editor, window, cx| {
-                                    editor.change_selections(
-                                        SelectionEffects::scroll(Autoscroll::center()),
-                                        window,
-                                        cx,
-                                        |s| s.select_ranges(vec![range]),
-                                    );
-                                })
-                                .ok();
-                            anyhow::Ok(())
-                        })
-                        .detach_and_log_err(cx);
-                }
-                MentionUri::Selection { abs_path: None, .. } => {}
-                MentionUri::Thread { id, name } => {
-                    if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
-                        panel.update(cx, |panel, cx| {
-                            panel.load_agent_thread(
-                                DbThreadMetadata {
-                                    id,
-                                    title: name.into(),
-                                    updated_at: Default::default(),
-                                },
-                                window,
-                                cx,
-                            )
-                        });
-                    }
-                }
-                MentionUri::TextThread { path, .. } => {
-                    if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
-                        panel.update(cx, |panel, cx| {
-                            panel
-                                .open_saved_text_thread(path.as_path().into(), window, cx)
-                                .detach_and_log_err(cx);
-                        });
-                    }
-                }
-                MentionUri::Rule { id, .. } => {
-                    let PromptId::User { uuid } = id else {
-                        return;
-                    };
-                    window.dispatch_action(
-                        Box::new(OpenRulesLibrary {
-                            prompt_to_select: Some(uuid.0),
-                        }),
-                        cx,
-                    )
-                }
-                MentionUri::Fetch { url } => {
-                    cx.open_url(url.as_str());
-                }
-            })
+        let thread = thread.read(cx);
+        // The agent knows about the files it's editing in the current turn.
+        let agent_edits = if thread.status() == ThreadStatus::Idle {
+            HashSet::default()
         } else {
-            cx.open_url(&url);
+            thread
+                .action_log()
+                .read(cx)
+                .changed_buffers(cx)
+                .into_keys()
+                .filter_map(|buffer| buffer.read(cx).project_path(cx))
+                .collect::<HashSet<_>>()
+        };
+        let worktree = worktree.read(cx);
+        let paths = changes
+            .iter()
+            .filter(|(path, entry_id, _)| {
+                worktree
+                    .entry_for_id(*entry_id)
+                    .is_none_or(|entry| entry.is_file() && !entry.is_ignored)
+                    && !agent_edits.contains(&ProjectPath {
+                        worktree_id,
+                        path: path.clone(),
+                    })
+            })
+            .map(|(path, _, _)| worktree.absolutize(path))
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return;
         }
+
+        self.unsent_file_changes.extend(paths);
+        self._file_changes_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(FILE_CHANGES_DEBOUNCE).await;
+            let Ok(paths) =
+                this.update(cx, |this, _| std::mem::take(&mut this.unsent_file_changes))
+            else {
+                return;
+            };
+            let Ok(task) = cx.update(|cx| file_changes.notify(paths.into_iter().collect(), cx))
+            else {
+                return;
+            };
+            task.await.log_err();
+        }));
     }
 
-    fn open_tool_call_location(
-        &self,
-        entry_ix: usize,
-        location_ix: usize,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) -> Option<()> {
-        let (tool_call_location, agent_location) = self
-            .thread()?
-            .read(cx)
-            .entries()
-            .get(entry_ix)?
-            .location(location_ix)?;
+    /// The directory the agent works in and the git branch checked out there.
+    fn render_working_directory(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let ThreadState::Ready { root_dir, .. } = &self.thread_state else {
+            return None;
+        };
+        let branch = self.current_branch(cx);
 
-        let project_path = self
-            .project
-            .read(cx)
-            .find_project_path(&tool_call_location.path, cx)?;
+        Some(
+            h_flex()
+                .px_2()
+                .py_1()
+                .gap_1()
+                .border_b_1()
+                .border_color(cx.theme().colors().border_variant)
+                .child(
+                    Icon::new(IconName::Folder)
+                        .size(IconSize::XSmall)
+                        .color(Color::Muted),
+                )
+                .child(
+                    Label::new(root_dir.compact().to_string_lossy().into_owned())
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted)
+                        .truncate(),
+                )
+                .children(branch.map(|branch| {
+                    h_flex()
+                        .flex_none()
+                        .ml_1()
+                        .gap_1()
+                        .child(
+                            Icon::new(IconName::GitBranch)
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Label::new(branch)
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                })),
+        )
+    }
 
-        let open_task = self
-            .workspace
-            .update(cx, |workspace, cx| {
-                workspace.open_path(project_path, None, true, window, cx)
+    fn render_add_selection_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        IconButton::new("add-selection", IconName::TextSnippet)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Muted)
+            .tooltip(|_window, cx| {
+                Tooltip::with_meta(
+                    "Add Selection",
+                    Some(&AddSelectionToThread),
+                    "Attach the text selected in the active editor to the message.",
+                    cx,
+                )
             })
-            .log_err()?;
-        window
-            .spawn(cx, async move |cx| {
-                let item = open_task.await?;
-
-                let Some(active_editor) = item.downcast::<Editor>() else {
-                    return anyhow::Ok(());
-                };
-
-                active_editor.update_in(cx, |editor, window, cx| {
-                    let multibuffer = editor.buffer().read(cx);
-                    let buffer = multibuffer.as_singleton();
-                    if agent_location.buffer.upgrade() == buffer {
-                        let excerpt_id = multibuffer.excerpt_ids().first().cloned();
-                        let anchor = editor::Anchor::in_buffer(
-                            excerpt_id.unwrap(),
-                            buffer.unwrap().read(cx).remote_id(),
-                            agent_location.position,
-                        );
-                        editor.change_selections(Default::default(), window, cx, |selections| {
-                            selections.select_anchor_ranges([anchor..anchor]);
-                        })
-                    } else {
-                        let row = tool_call_location.line.unwrap_or_default();
-                        editor.change_selections(Default::default(), window, cx, |selections| {
-                            selections.select_ranges([Point::new(row, 0)..Point::new(row, 0)]);
-                        })
-                    }
-                })?;
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.insert_selections(window, cx);
+            }))
+    }
 
-                anyhow::Ok(())
-            })
-            .detach_and_log_err(cx);
+    fn render_attach_images_button(&self, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        if !self.prompt_capabilities.borrow().image {
+            return None;
+        }
+        Some(
+            IconButton::new("attach-images", IconName::Image)
+                .icon_size(IconSize::Small)
+                .icon_color(Color::Muted)
+                .tooltip(Tooltip::text("Attach Images"))
+                .on_click(cx.listener(|this, _, window, cx| {
+                    this.message_editor.update(cx, |message_editor, cx| {
+                        message_editor.attach_images(window, cx);
+                    });
+                })),
+        )
+    }
 
-        None
+    fn render_markdown(&self, markdown: Entity<Markdown>, style: MarkdownStyle) -> MarkdownElement {
+        let workspace = self.workspace.clone();
+        MarkdownElement::new(markdown, style).on_url_click(move |text, window, cx| {
+            Self::open_link(text, &workspace, window, cx);
+        })
     }
 
-    pub fn open_thread_as_markdown(
+    fn render_user_message_attachments(
         &self,
-        workspace: Entity<Workspace>,
-        window: &mut Window,
-        cx: &mut App,
-    ) -> Task<Result<()>> {
-        let markdown_language_task = workspace
-            .read(cx)
-            .app_state()
-            .languages
-            .language_for_name("Markdown");
-
-        let (thread_summary, markdown) = if let Some(thread) = self.thread() {
-            let thread = thread.read(cx);
-            (thread.title().to_string(), thread.to_markdown(cx))
-        } else {
-            return Task::ready(Ok(()));
-        };
-
-        window.spawn(cx, async move |cx| {
-            let markdown_language = markdown_language_task.await?;
-
-            workspace.update_in(cx, |workspace, window, cx| {
-                let project = workspace.project().clone();
+        entry_ix: usize,
+        chunks: &[acp::ContentBlock],
+        cx: &Context<Self>,
+    ) -> Option<AnyElement> {
+        let attachments = user_message_attachments(chunks);
+        if attachments.is_empty() {
+            return None;
+        }
 
-                if !project.read(cx).is_local() {
-                    bail!("failed to open active thread as markdown in remote project");
+        let mut expanded_contents = Vec::new();
+        let chips = attachments
+            .into_iter()
+            .enumerate()
+            .map(|(ix, mention)| {
+                let uri = SharedString::from(mention.to_uri().to_string());
+                let content = attachment_content(chunks, &mention);
+                let is_expanded = self.expanded_attachments.contains(&(entry_ix, ix));
+                if is_expanded && let Some(content) = content {
+                    expanded_contents
+                        .push(self.render_attachment_content(ix, &mention, content, cx));
                 }
 
-                let buffer = project.update(cx, |project, cx| {
-                    project.create_local_buffer(&markdown, Some(markdown_language), true, cx)
-                });
-                let buffer = cx.new(|cx| {
-                    MultiBuffer::singleton(buffer, cx).with_title(thread_summary.clone())
-                });
-
-                workspace.add_item_to_active_pane(
-                    Box::new(cx.new(|cx| {
-                        let mut editor =
-                            Editor::for_multibuffer(buffer, Some(project.clone()), window, cx);
-                        editor.set_breadcrumb_header(thread_summary);
-                        editor
-                    })),
-                    None,
-                    true,
-                    window,
-                    cx,
-                );
-
-                anyhow::Ok(())
-            })??;
-            anyhow::Ok(())
-        })
-    }
-
-    fn scroll_to_top(&mut self, cx: &mut Context<Self>) {
-        self.list_state.scroll_to(ListOffset::default());
-        cx.notify();
-    }
-
-    pub fn scroll_to_bottom(&mut self, cx: &mut Context<Self>) {
-        if let Some(thread) = self.thread() {
-            let entry_count = thread.read(cx).entries().len();
-            self.list_state.reset(entry_count);
-            cx.notify();
-        }
-    }
+                h_flex()
+                    .child(
+                        ButtonLike::new(("user-message-attachment", ix))
+                            .style(ButtonStyle::Filled)
+                            .child(
+                                h_flex()
+                                    .gap_1()
+                                    .child(
+                                        Icon::from_path(mention.icon_path(cx))
+                                            .size(IconSize::XSmall)
+                                            .color(Color::Muted),
+                                    )
+                                    .child(
+                                        Label::new(mention.name())
+                                            .size(LabelSize::XSmall)
+                                            .buffer_font(cx)
+                                            .single_line(),
+                                    ),
+                            )
+                            .tooltip(Tooltip::text(uri.clone()))
+                            .on_click(cx.listener({
+                                let workspace = self.workspace.clone();
+                                move |_, _, window, cx: &mut Context<Self>| {
+                                    Self::open_link(uri.clone(), &workspace, window, cx);
+                                }
+                            })),
+                    )
+                    .when(content.is_some(), |this| {
+                        this.child(
+                            Disclosure::new(("toggle-user-message-attachment", ix), is_expanded)
+                                .opened_icon(IconName::ChevronUp)
+                                .closed_icon(IconName::ChevronDown)
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    if !this.expanded_attachments.remove(&(entry_ix, ix)) {
+                                        this.expanded_attachments.insert((entry_ix, ix));
+                                    }
+                                    cx.notify();
+                                })),
+                        )
+                    })
+            })
+            .collect::<Vec<_>>();
 
-    fn notify_with_sound(
-        &mut self,
-        caption: impl Into<SharedString>,
-        icon: IconName,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        self.play_notification_sound(window, cx);
-        self.show_notification(caption, icon, window, cx);
+        Some(
+            v_flex()
+                .px_2()
+                .gap_1()
+                .child(h_flex().gap_1().flex_wrap().children(chips))
+                .children(expanded_contents)
+                .into_any_element(),
+        )
     }
 
-    fn play_notification_sound(&self, window: &Window, cx: &mut App) {
-        let settings = AgentSettings::get_global(cx);
-        if settings.play_sound_when_agent_done && !window.is_window_active() {
-            Audio::play_sound(Sound::AgentDone, cx);
-        }
+    /// The text an attachment was sent with, as the agent saw it.
+    fn render_attachment_content(
+        &self,
+        ix: usize,
+        mention: &MentionUri,
+        content: &str,
+        cx: &Context<Self>,
+    ) -> AnyElement {
+        v_flex()
+            .rounded_md()
+            .border_1()
+            .border_color(self.tool_card_border_color(cx))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .bg(self.tool_card_header_bg(cx))
+                    .child(
+                        Icon::from_path(mention.icon_path(cx))
+                            .size(IconSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(mention.name())
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                div()
+                    .id(("user-message-attachment-content", ix))
+                    .max_h_64()
+                    .overflow_y_scroll()
+                    .px_2()
+                    .py_1()
+                    .font_buffer(cx)
+                    .text_xs()
+                    .text_color(cx.theme().colors().text_muted)
+                    .child(content.to_string()),
+            )
+            .into_any_element()
     }
 
-    fn show_notification(
-        &mut self,
-        caption: impl Into<SharedString>,
-        icon: IconName,
+    fn open_link(
+        url: SharedString,
+        workspace: &WeakEntity<Workspace>,
         window: &mut Window,
-        cx: &mut Context<Self>,
+        cx: &mut App,
     ) {
-        if window.is_window_active() || !self.notifications.is_empty() {
+        let Some(workspace) = workspace.upgrade() else {
+            cx.open_url(&url);
             return;
-        }
+        };
 
-        // TODO: Change this once we have title summarization for external agents.
-        let title = self.agent.name();
+        if let Some(mention) = MentionUri::parse(&url).log_err() {
+            workspace.update(cx, |workspace, cx| match mention {
+                MentionUri::File { abs_path } => {
+                    let project = workspace.project();
+                    let Some(path) =
+                        project.update(cx, |project, cx| project.find_project_path(abs_path, cx))
+                    else {
+                        return;
+                    };
 
-        match AgentSettings::get_global(cx).notify_when_agent_waiting {
-            NotifyWhenAgentWaiting::PrimaryScreen => {
-                if let Some(primary) = cx.primary_display() {
-                    self.pop_up(icon, caption.into(), title, window, primary, cx);
+                    workspace
+                        .open_path(path, None, true, window, cx)
+                        .detach_and_log_err(cx);
                 }
-            }
-            NotifyWhenAgentWaiting::AllScreens => {
-                let caption = caption.into();
-                for screen in cx.displays() {
-                    self.pop_up(icon, caption.clone(), title.clone(), window, screen, cx);
+                MentionUri::PastedImage => {}
+                MentionUri::Directory { abs_path } => {
+                    let project = workspace.project();
+                    let Some(entry_id) = project.update(cx, |project, cx| {
+                        let path = project.find_project_path(abs_path, cx)?;
+                        project.entry_for_path(&path, cx).map(|entry| entry.id)
+                    }) else {
+                        return;
+                    };
+
+                    project.update(cx, |_, cx| {
+                        cx.emit(project::Event::RevealInProjectPanel(entry_id));
+                    });
                 }
+                MentionUri::Symbol {
+                    abs_path: path,
+                    line_range,
+                    ..
+                }
+                | MentionUri::Selection {
+                    abs_path: Some(path),
+                    line_range,
+                } => {
+                    let project = workspace.project();
+                    let Some(path) =
+                        project.update(cx, |project, cx| project.find_project_path(path, cx))
+                    else {
+                        return;
+                    };
+                    Self::open_path_at_line(workspace, path, Some(*line_range.start()), window, cx);
+                }
+                MentionUri::Selection { abs_path: None, .. } => {}
+                MentionUri::Thread { id, name } => {
+                    if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
+                        panel.update(cx, |panel, cx| {
+                            panel.load_agent_thread(
+                                DbThreadMetadata {
+                                    id,
+                                    title: name.into(),
+                                    updated_at: Default::default(),
+                                },
+                                window,
+                                cx,
+                            )
+                        });
+                    }
+                }
+                MentionUri::TextThread { path, .. } => {
+                    if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
+                        panel.update(cx, |panel, cx| {
+                            panel
+                                .open_saved_text_thread(path.as_path().into(), window, cx)
+                                .detach_and_log_err(cx);
+                        });
+                    }
+                }
+                MentionUri::Rule { id, .. } => {
+                    let PromptId::User { uuid } = id else {
+                        return;
+                    };
+                    window.dispatch_action(
+                        Box::new(OpenRulesLibrary {
+                            prompt_to_select: Some(uuid.0),
+                        }),
+                        cx,
+                    )
+                }
+                MentionUri::Fetch { url } => {
+                    cx.open_url(url.as_str());
+                }
+                MentionUri::ProjectMemory => {
+                    ProjectMemoryModal::toggle(workspace, window, cx);
+                }
+                MentionUri::Diagnostics
+                | MentionUri::WorkspaceState
+                | MentionUri::Source { .. } => {}
+            })
+        } else if !url.contains("://") && !url.starts_with("mailto:") {
+            // Links to paths in the project, like `src/main.rs#L10`.
+            let link = PathWithRange::new(&url);
+            if link.path.is_empty() {
+                return;
             }
-            NotifyWhenAgentWaiting::Never => {
-                // Don't show anything
-            }
+            workspace.update(cx, |workspace, cx| {
+                let Some(path) = workspace
+                    .project()
+                    .read(cx)
+                    .find_project_path(Path::new(link.path.as_ref()), cx)
+                else {
+                    return;
+                };
+                let line = link.range.map(|range| range.start.line.saturating_sub(1));
+                Self::open_path_at_line(workspace, path, line, window, cx);
+            });
+        } else {
+            cx.open_url(&url);
         }
     }
 
-    fn pop_up(
-        &mut self,
-        icon: IconName,
-        caption: SharedString,
-        title: SharedString,
+    /// Opens the file, moving the cursor to the start of the zero-based line.
+    fn open_path_at_line(
+        workspace: &mut Workspace,
+        path: ProjectPath,
+        line: Option<u32>,
         window: &mut Window,
-        screen: Rc<dyn PlatformDisplay>,
-        cx: &mut Context<Self>,
+        cx: &mut Context<Workspace>,
     ) {
-        let options = AgentNotification::window_options(screen, cx);
+        let item = workspace.open_path(path, None, true, window, cx);
+        let Some(line) = line else {
+            item.detach_and_log_err(cx);
+            return;
+        };
+        window
+            .spawn(cx, async move |cx| {
+                let Some(editor) = item.await?.downcast::<Editor>() else {
+                    return Ok(());
+                };
+                let range = Point::new(line, 0)..Point::new(line, 0);
+                editor
+                    .update_in(cx, |editor, window, cx| {
+                        editor.change_selections(
+                            SelectionEffects::scroll(Autoscroll::center()),
+                            window,
+                            cx,
+                            |s| s.select_ranges(vec![range]),
+                        );
+                    })
+                    .ok();
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
+    }
 
-        let project_name = self.workspace.upgrade().and_then(|workspace| {
+    /// Buttons for a code block of an assistant message, to insert it into the active
+    /// editor or to open it in a new buffer.
+    fn render_code_block_actions(
+        workspace: &WeakEntity<Workspace>,
+        id: usize,
+        code: &str,
+        language: Option<&Arc<Language>>,
+        cx: &App,
+    ) -> AnyElement {
+        let active_editor = workspace.upgrade().and_then(|workspace| {
             workspace
                 .read(cx)
-                .project()
-                .read(cx)
-                .visible_worktrees(cx)
-                .next()
-                .map(|worktree| worktree.read(cx).root_name_str().to_string())
+                .active_item(cx)
+                .and_then(|item| item.act_as::<Editor>(cx))
         });
+        let replaces_selection = active_editor.as_ref().is_some_and(|editor| {
+            let selection = editor.read(cx).selections.newest_anchor();
+            selection.start != selection.end
+        });
+        let code: SharedString = code.to_string().into();
 
-        if let Some(screen_window) = cx
-            .open_window(options, |_, cx| {
-                cx.new(|_| {
-                    AgentNotification::new(title.clone(), caption.clone(), icon, project_name)
-                })
-            })
-            .log_err()
-            && let Some(pop_up) = screen_window.entity(cx).log_err()
-        {
-            self.notification_subscriptions
-                .entry(screen_window)
-                .or_insert_with(Vec::new)
-                .push(cx.subscribe_in(&pop_up, window, {
-                    |this, _, event, window, cx| match event {
-                        AgentNotificationEvent::Accepted => {
-                            let handle = window.window_handle();
-                            cx.activate(true);
-
-                            let workspace_handle = this.workspace.clone();
-
-                            // If there are multiple Zed windows, activate the correct one.
-                            cx.defer(move |cx| {
-                                handle
-                                    .update(cx, |_view, window, _cx| {
-                                        window.activate_window();
-
-                                        if let Some(workspace) = workspace_handle.upgrade() {
-                                            workspace.update(_cx, |workspace, cx| {
-                                                workspace.focus_panel::<AgentPanel>(window, cx);
-                                            });
-                                        }
-                                    })
-                                    .log_err();
+        h_flex()
+            .gap_1()
+            .child(
+                IconButton::new(
+                    ElementId::named_usize("insert-code-block", id),
+                    if replaces_selection {
+                        IconName::Replace
+                    } else {
+                        IconName::CursorIBeam
+                    },
+                )
+                .icon_color(Color::Muted)
+                .icon_size(IconSize::Small)
+                .style(ButtonStyle::Filled)
+                .shape(ui::IconButtonShape::Square)
+                .disabled(active_editor.is_none())
+                .tooltip(Tooltip::text(if replaces_selection {
+                    "Replace Selection"
+                } else {
+                    "Insert at Cursor"
+                }))
+                .on_click({
+                    let code = code.clone();
+                    move |_, window, cx| {
+                        if let Some(editor) = &active_editor {
+                            editor.update(cx, |editor, cx| {
+                                editor.insert(&code, window, cx);
+                                window.focus(&editor.focus_handle(cx));
                             });
-
-                            this.dismiss_notifications(cx);
-                        }
-                        AgentNotificationEvent::Dismissed => {
-                            this.dismiss_notifications(cx);
                         }
                     }
-                }));
-
-            self.notifications.push(screen_window);
-
-            // If the user manually refocuses the original window, dismiss the popup.
-            self.notification_subscriptions
-                .entry(screen_window)
-                .or_insert_with(Vec::new)
-                .push({
-                    let pop_up_weak = pop_up.downgrade();
-
-                    cx.observe_window_activation(window, move |_, window, cx| {
-                        if window.is_window_active()
-                            && let Some(pop_up) = pop_up_weak.upgrade()
-                        {
-                            pop_up.update(cx, |_, cx| {
-                                cx.emit(AgentNotificationEvent::Dismissed);
-                            });
-                        }
-                    })
-                });
-        }
+                }),
+            )
+            .child(
+                IconButton::new(
+                    ElementId::named_usize("open-code-block", id),
+                    IconName::FileTextOutlined,
+                )
+                .icon_color(Color::Muted)
+                .icon_size(IconSize::Small)
+                .style(ButtonStyle::Filled)
+                .shape(ui::IconButtonShape::Square)
+                .tooltip(Tooltip::text("Open in New Buffer"))
+                .on_click({
+                    let workspace = workspace.clone();
+                    let language = language.cloned();
+                    move |_, window, cx| {
+                        Self::open_code_in_new_buffer(
+                            &workspace,
+                            code.clone(),
+                            language.clone(),
+                            window,
+                            cx,
+                        );
+                    }
+                }),
+            )
+            .into_any_element()
     }
 
-    fn dismiss_notifications(&mut self, cx: &mut Context<Self>) {
-        for window in self.notifications.drain(..) {
-            window
-                .update(cx, |_, window, _| {
-                    window.remove_window();
+    fn open_code_in_new_buffer(
+        workspace: &WeakEntity<Workspace>,
+        code: SharedString,
+        language: Option<Arc<Language>>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let Some(workspace) = workspace.upgrade() else {
+            return;
+        };
+        let editor = workspace.update(cx, |workspace, cx| {
+            Editor::new_in_workspace(workspace, window, cx)
+        });
+        window
+            .spawn(cx, async move |cx| {
+                let editor = editor.await?;
+                editor.update_in(cx, |editor, window, cx| {
+                    if let Some(buffer) = editor.buffer().read(cx).as_singleton() {
+                        buffer.update(cx, |buffer, cx| {
+                            buffer.set_text(code.as_ref(), cx);
+                            buffer.set_language(language, cx);
+                        });
+                    }
+                    window.focus(&editor.focus_handle(cx));
                 })
-                .ok();
-
-            self.notification_subscriptions.remove(&window);
-        }
+            })
+            .detach_and_log_err(cx);
     }
 
-    fn render_thread_controls(
+    /// Reruns a search tool call through the project search, to browse all of its results.
+    fn open_tool_call_in_search(
         &self,
-        thread: &Entity<AcpThread>,
-        cx: &Context<Self>,
-    ) -> impl IntoElement {
-        let is_generating = matches!(thread.read(cx).status(), ThreadStatus::Generating);
-        if is_generating {
-            return h_flex().id("thread-controls-container").child(
-                div()
-                    .py_2()
-                    .px(rems_from_px(22.))
-                    .child(SpinnerLabel::new().size(LabelSize::Small)),
-            );
-        }
+        entry_ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<()> {
+        let AgentThreadEntry::ToolCall(tool_call) =
+            self.thread()?.read(cx).entries().get(entry_ix)?
+        else {
+            return None;
+        };
+        let query = SearchToolQuery::from_raw_input(tool_call.raw_input.as_ref()?)?;
+        self.workspace
+            .update(cx, |workspace, cx| {
+                search::ProjectSearchView::new_search_with_query(
+                    workspace,
+                    &query.query,
+                    query.regex,
+                    query.included_files.as_deref(),
+                    window,
+                    cx,
+                )
+            })
+            .log_err()
+    }
 
-        let open_as_markdown = IconButton::new("open-as-markdown", IconName::FileMarkdown)
-            .shape(ui::IconButtonShape::Square)
-            .icon_size(IconSize::Small)
-            .icon_color(Color::Ignored)
-            .tooltip(Tooltip::text("Open Thread as Markdown"))
-            .on_click(cx.listener(move |this, _, window, cx| {
-                if let Some(workspace) = this.workspace.upgrade() {
-                    this.open_thread_as_markdown(workspace, window, cx)
-                        .detach_and_log_err(cx);
-                }
-            }));
+    fn open_tool_call_location(
+        &self,
+        entry_ix: usize,
+        location_ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<()> {
+        let (tool_call_location, agent_location) = self
+            .thread()?
+            .read(cx)
+            .entries()
+            .get(entry_ix)?
+            .location(location_ix)?;
 
-        let scroll_to_top = IconButton::new("scroll_to_top", IconName::ArrowUp)
-            .shape(ui::IconButtonShape::Square)
-            .icon_size(IconSize::Small)
-            .icon_color(Color::Ignored)
-            .tooltip(Tooltip::text("Scroll To Top"))
-            .on_click(cx.listener(move |this, _, _, cx| {
-                this.scroll_to_top(cx);
-            }));
+        let project_path = self
+            .project
+            .read(cx)
+            .find_project_path(&tool_call_location.path, cx)?;
 
-        let mut container = h_flex()
-            .id("thread-controls-container")
-            .group("thread-controls-container")
-            .w_full()
-            .py_2()
-            .px_5()
-            .gap_px()
-            .opacity(0.6)
-            .hover(|style| style.opacity(1.))
-            .flex_wrap()
-            .justify_end();
+        let open_task = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path(project_path, None, true, window, cx)
+            })
+            .log_err()?;
+        window
+            .spawn(cx, async move |cx| {
+                let item = open_task.await?;
 
-        if AgentSettings::get_global(cx).enable_feedback
-            && self
-                .thread()
-                .is_some_and(|thread| thread.read(cx).connection().telemetry().is_some())
-        {
-            let feedback = self.thread_feedback.feedback;
+                let Some(active_editor) = item.downcast::<Editor>() else {
+                    return anyhow::Ok(());
+                };
 
-            container = container
-                .child(
-                    div().visible_on_hover("thread-controls-container").child(
-                        Label::new(match feedback {
-                            Some(ThreadFeedback::Positive) => "Thanks for your feedback!",
-                            Some(ThreadFeedback::Negative) => {
-                                "We appreciate your feedback and will use it to improve."
-                            }
-                            None => {
-                                "Rating the thread sends all of your current conversation to the Zed team."
-                            }
-                        })
-                        .color(Color::Muted)
-                        .size(LabelSize::XSmall)
-                        .truncate(),
-                    ),
-                )
-                .child(
-                    IconButton::new("feedback-thumbs-up", IconName::ThumbsUp)
-                        .shape(ui::IconButtonShape::Square)
-                        .icon_size(IconSize::Small)
-                        .icon_color(match feedback {
-                            Some(ThreadFeedback::Positive) => Color::Accent,
-                            _ => Color::Ignored,
+                active_editor.update_in(cx, |editor, window, cx| {
+                    let multibuffer = editor.buffer().read(cx);
+                    let buffer = multibuffer.as_singleton();
+                    if agent_location.buffer.upgrade() == buffer {
+                        let excerpt_id = multibuffer.excerpt_ids().first().cloned();
+                        let anchor = editor::Anchor::in_buffer(
+                            excerpt_id.unwrap(),
+                            buffer.unwrap().read(cx).remote_id(),
+                            agent_location.position,
+                        );
+                        editor.change_selections(Default::default(), window, cx, |selections| {
+                            selections.select_anchor_ranges([anchor..anchor]);
                         })
-                        .tooltip(Tooltip::text("Helpful Response"))
-                        .on_click(cx.listener(move |this, _, window, cx| {
-                            this.handle_feedback_click(ThreadFeedback::Positive, window, cx);
-                        })),
-                )
-                .child(
-                    IconButton::new("feedback-thumbs-down", IconName::ThumbsDown)
-                        .shape(ui::IconButtonShape::Square)
-                        .icon_size(IconSize::Small)
-                        .icon_color(match feedback {
-                            Some(ThreadFeedback::Negative) => Color::Accent,
-                            _ => Color::Ignored,
+                    } else {
+                        let row = tool_call_location.line.unwrap_or_default();
+                        editor.change_selections(Default::default(), window, cx, |selections| {
+                            selections.select_ranges([Point::new(row, 0)..Point::new(row, 0)]);
                         })
-                        .tooltip(Tooltip::text("Not Helpful"))
-                        .on_click(cx.listener(move |this, _, window, cx| {
-                            this.handle_feedback_click(ThreadFeedback::Negative, window, cx);
-                        })),
-                );
-        }
+                    }
+                })?;
 
-        container.child(open_as_markdown).child(scroll_to_top)
-    }
+                anyhow::Ok(())
+            })
+            .detach_and_log_err(cx);
 
-    fn render_feedback_feedback_editor(editor: Entity<Editor>, cx: &Context<Self>) -> Div {
-        h_flex()
-            .key_context("AgentFeedbackMessageEditor")
-            .on_action(cx.listener(move |this, _: &menu::Cancel, _, cx| {
-                this.thread_feedback.dismiss_comments();
-                cx.notify();
-            }))
-            .on_action(cx.listener(move |this, _: &menu::Confirm, _window, cx| {
-                this.submit_feedback_message(cx);
-            }))
-            .p_2()
-            .mb_2()
-            .mx_5()
-            .gap_1()
-            .rounded_md()
-            .border_1()
-            .border_color(cx.theme().colors().border)
-            .bg(cx.theme().colors().editor_background)
-            .child(div().w_full().child(editor))
-            .child(
-                h_flex()
-                    .child(
-                        IconButton::new("dismiss-feedback-message", IconName::Close)
-                            .icon_color(Color::Error)
-                            .icon_size(IconSize::XSmall)
-                            .shape(ui::IconButtonShape::Square)
-                            .on_click(cx.listener(move |this, _, _window, cx| {
-                                this.thread_feedback.dismiss_comments();
-                                cx.notify();
-                            })),
-                    )
-                    .child(
-                        IconButton::new("submit-feedback-message", IconName::Return)
-                            .icon_size(IconSize::XSmall)
-                            .shape(ui::IconButtonShape::Square)
-                            .on_click(cx.listener(move |this, _, _window, cx| {
-                                this.submit_feedback_message(cx);
-                            })),
-                    ),
-            )
+        None
     }
 
-    fn handle_feedback_click(
-        &mut self,
-        feedback: ThreadFeedback,
+    pub fn open_thread_as_markdown(
+        &self,
+        workspace: Entity<Workspace>,
         window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        let Some(thread) = self.thread().cloned() else {
-            return;
+        cx: &mut App,
+    ) -> Task<Result<()>> {
+        let (thread_summary, markdown) = if let Some(thread) = self.thread() {
+            let thread = thread.read(cx);
+            (thread.title().to_string(), thread.to_markdown(cx))
+        } else {
+            return Task::ready(Ok(()));
         };
 
-        self.thread_feedback.submit(thread, feedback, window, cx);
-        cx.notify();
+        Self::open_scratch_buffer(
+            workspace,
+            thread_summary,
+            Some("Markdown"),
+            markdown,
+            window,
+            cx,
+        )
     }
 
-    fn submit_feedback_message(&mut self, cx: &mut Context<Self>) {
-        let Some(thread) = self.thread().cloned() else {
+    /// Opens an assistant message or the output of a tool call in an unsaved editor, to use
+    /// the editor's tools on it.
+    fn open_entry_as_buffer(&self, entry_ix: usize, window: &mut Window, cx: &mut App) {
+        let Some(workspace) = self.workspace.upgrade() else {
             return;
         };
-
-        self.thread_feedback.submit_comments(thread, cx);
-        cx.notify();
+        let Some((title, language_name, text)) = self.thread().and_then(|thread| {
+            scratch_buffer_contents(thread.read(cx).entries().get(entry_ix)?, cx)
+        }) else {
+            return;
+        };
+        Self::open_scratch_buffer(workspace, title, language_name, text, window, cx)
+            .detach_and_log_err(cx);
     }
 
-    fn render_token_limit_callout(
-        &self,
-        line_height: Pixels,
-        cx: &mut Context<Self>,
-    ) -> Option<Callout> {
-        let token_usage = self.thread()?.read(cx).token_usage()?;
-        let ratio = token_usage.ratio();
+    fn open_scratch_buffer(
+        workspace: Entity<Workspace>,
+        title: String,
+        language_name: Option<&'static str>,
+        text: String,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<()>> {
+        let language_task = language_name.map(|language_name| {
+            workspace
+                .read(cx)
+                .app_state()
+                .languages
+                .language_for_name(language_name)
+        });
 
-        let (severity, title) = match ratio {
-            acp_thread::TokenUsageRatio::Normal => return None,
-            acp_thread::TokenUsageRatio::Warning => {
-                (Severity::Warning, "Thread reaching the token limit soon")
-            }
-            acp_thread::TokenUsageRatio::Exceeded => {
-                (Severity::Error, "Thread reached the token limit")
-            }
-        };
+        window.spawn(cx, async move |cx| {
+            let language = match language_task {
+                Some(language_task) => Some(language_task.await?),
+                None => None,
+            };
 
-        let burn_mode_available = self.as_native_thread(cx).is_some_and(|thread| {
-            thread.read(cx).completion_mode() == CompletionMode::Normal
-                && thread
-                    .read(cx)
-                    .model()
-                    .is_some_and(|model| model.supports_burn_mode())
-        });
+            workspace.update_in(cx, |workspace, window, cx| {
+                let project = workspace.project().clone();
 
-        let description = if burn_mode_available {
-            "To continue, start a new thread from a summary or turn Burn Mode on."
-        } else {
-            "To continue, start a new thread from a summary."
-        };
+                if !project.read(cx).is_local() {
+                    bail!("failed to open {title} in remote project");
+                }
 
-        Some(
-            Callout::new()
-                .severity(severity)
-                .line_height(line_height)
-                .title(title)
-                .description(description)
-                .actions_slot(
-                    h_flex()
-                        .gap_0p5()
-                        .child(
-                            Button::new("start-new-thread", "Start New Thread")
-                                .label_size(LabelSize::Small)
-                                .on_click(cx.listener(|this, _, window, cx| {
-                                    let Some(thread) = this.thread() else {
-                                        return;
-                                    };
-                                    let session_id = thread.read(cx).session_id().clone();
-                                    window.dispatch_action(
-                                        crate::NewNativeAgentThreadFromSummary {
-                                            from_session_id: session_id,
-                                        }
-                                        .boxed_clone(),
-                                        cx,
-                                    );
-                                })),
-                        )
-                        .when(burn_mode_available, |this| {
-                            this.child(
-                                IconButton::new("burn-mode-callout", IconName::ZedBurnMode)
-                                    .icon_size(IconSize::XSmall)
-                                    .on_click(cx.listener(|this, _event, window, cx| {
-                                        this.toggle_burn_mode(&ToggleBurnMode, window, cx);
-                                    })),
-                            )
-                        }),
-                ),
-        )
-    }
+                let buffer = project.update(cx, |project, cx| {
+                    project.create_local_buffer(&text, language, true, cx)
+                });
+                let buffer =
+                    cx.new(|cx| MultiBuffer::singleton(buffer, cx).with_title(title.clone()));
 
-    fn render_usage_callout(&self, line_height: Pixels, cx: &mut Context<Self>) -> Option<Div> {
-        if !self.is_using_zed_ai_models(cx) {
-            return None;
-        }
+                workspace.add_item_to_active_pane(
+                    Box::new(cx.new(|cx| {
+                        let mut editor =
+                            Editor::for_multibuffer(buffer, Some(project.clone()), window, cx);
+                        editor.set_breadcrumb_header(title);
+                        editor
+                    })),
+                    None,
+                    true,
+                    window,
+                    cx,
+                );
 
-        let user_store = self.project.read(cx).user_store().read(cx);
-        if user_store.is_usage_based_billing_enabled() {
-            return None;
-        }
+                anyhow::Ok(())
+            })??;
+            anyhow::Ok(())
+        })
+    }
 
-        let plan = user_store
-            .plan()
-            .unwrap_or(cloud_llm_client::Plan::V1(PlanV1::ZedFree));
+    /// Uploads the thread as a secret gist, once confirmed, and copies its link.
+    fn share_thread(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        let (title, markdown) = {
+            let thread = thread.read(cx);
+            (thread.title().to_string(), thread.to_markdown(cx))
+        };
+        let http_client = cx.http_client();
+        cx.spawn_in(window, async move |this, cx| {
+            let answer = cx
+                .prompt(
+                    gpui::PromptLevel::Info,
+                    "Share this thread as a secret GitHub gist?",
+                    Some("Anyone with the link will be able to read the whole conversation."),
+                    &["Share", "Cancel"],
+                )
+                .await?;
+            if answer != 0 {
+                return anyhow::Ok(());
+            }
+            let message = match share_as_gist(title, markdown, http_client).await {
+                Ok(url) => {
+                    cx.update(|_, cx| cx.write_to_clipboard(ClipboardItem::new_string(url)))?;
+                    "Copied the link to the shared thread".to_string()
+                }
+                Err(error) => format!("Failed to share the thread: {error:#}"),
+            };
+            this.update(cx, |this, cx| {
+                struct ThreadShareToast;
+                this.workspace
+                    .update(cx, |workspace, cx| {
+                        workspace.show_toast(
+                            Toast::new(NotificationId::unique::<ThreadShareToast>(), message)
+                                .autohide(),
+                            cx,
+                        );
+                    })
+                    .ok();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
 
-        let usage = user_store.model_request_usage()?;
+    /// Releases the views of the entries beyond the `max_live_entries` most recent ones,
+    /// unless earlier entries were loaded.
+    fn release_old_entry_views(&mut self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        let max_live_entries = AgentSettings::get_global(cx).max_live_entries;
+        if max_live_entries == 0 || self.loaded_earlier_entries {
+            return;
+        }
+        let len = thread.read(cx).entries().len();
+        let mut first_live_entry = len.saturating_sub(max_live_entries);
+        if let Some(editing_message) = self.editing_message {
+            first_live_entry = first_live_entry.min(editing_message);
+        }
+        if first_live_entry <= self.first_live_entry {
+            return;
+        }
 
-        Some(
-            div()
-                .child(UsageCallout::new(plan, usage))
-                .line_height(line_height),
-        )
+        let released = self.first_live_entry..first_live_entry;
+        self.entry_view_state.update(cx, |view_state, _| {
+            view_state.release(released.clone());
+        });
+        self.list_state
+            .splice_focusable(released.clone(), released.map(|_| None));
+        self.first_live_entry = first_live_entry;
+        cx.notify();
     }
 
-    fn agent_ui_font_size_changed(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
-        self.entry_view_state.update(cx, |entry_view_state, cx| {
-            entry_view_state.agent_ui_font_size_changed(cx);
+    /// Brings back up to `max_live_entries` released entries, or all of them when there's no
+    /// limit. The loaded entries are kept as new entries arrive.
+    fn load_earlier_entries(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread().cloned() else {
+            return;
+        };
+        let max_live_entries = AgentSettings::get_global(cx).max_live_entries;
+        let first_live_entry = if max_live_entries == 0 {
+            0
+        } else {
+            self.first_live_entry.saturating_sub(max_live_entries)
+        };
+
+        let loaded = first_live_entry..self.first_live_entry;
+        self.entry_view_state.update(cx, |view_state, cx| {
+            for ix in loaded.clone() {
+                view_state.sync_entry(ix, &thread, window, cx);
+            }
+            self.list_state.splice_focusable(
+                loaded.clone(),
+                loaded
+                    .clone()
+                    .map(|ix| view_state.entry(ix)?.focus_handle(cx)),
+            );
         });
+        self.first_live_entry = first_live_entry;
+        self.loaded_earlier_entries = true;
+        cx.notify();
     }
 
-    pub(crate) fn insert_dragged_files(
-        &self,
-        paths: Vec<project::ProjectPath>,
-        added_worktrees: Vec<Entity<project::Worktree>>,
-        window: &mut Window,
-        cx: &mut Context<Self>,
-    ) {
-        self.message_editor.update(cx, |message_editor, cx| {
-            message_editor.insert_dragged_files(paths, added_worktrees, window, cx);
-        })
+    fn render_not_connected(&self, cx: &Context<Self>) -> impl IntoElement {
+        h_flex()
+            .px_5()
+            .py_1()
+            .gap_2()
+            .justify_between()
+            .border_t_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                Label::new(format!(
+                    "Not connected: {} starts when you send a message",
+                    self.agent.name()
+                ))
+                .size(LabelSize::Small)
+                .color(Color::Muted)
+                .truncate(),
+            )
+            .child(
+                Button::new("connect-agent", "Connect")
+                    .label_size(LabelSize::Small)
+                    .on_click(cx.listener(|this, _, window, cx| this.connect(window, cx))),
+            )
     }
 
-    /// Inserts the selected text into the message editor or the message being
-    /// edited, if any.
-    pub(crate) fn insert_selections(&self, window: &mut Window, cx: &mut Context<Self>) {
-        self.active_editor(cx).update(cx, |editor, cx| {
-            editor.insert_selections(window, cx);
-        });
+    fn render_load_earlier_entries(&self, cx: &Context<Self>) -> impl IntoElement {
+        let label = if self.first_live_entry == 1 {
+            "Load 1 Earlier Entry".to_string()
+        } else {
+            format!("Load {} Earlier Entries", self.first_live_entry)
+        };
+        h_flex().w_full().justify_center().py_2().child(
+            Button::new("load-earlier-entries", label)
+                .label_size(LabelSize::Small)
+                .icon(IconName::ArrowUp)
+                .icon_size(IconSize::XSmall)
+                .icon_position(IconPosition::Start)
+                .icon_color(Color::Muted)
+                .on_click(cx.listener(|this, _, window, cx| {
+                    this.load_earlier_entries(window, cx);
+                })),
+        )
     }
 
-    fn render_thread_retry_status_callout(
-        &self,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
-    ) -> Option<Callout> {
-        let state = self.thread_retry_status.as_ref()?;
-
-        let next_attempt_in = state
-            .duration
-            .saturating_sub(Instant::now().saturating_duration_since(state.started_at));
-        if next_attempt_in.is_zero() {
-            return None;
+    fn toggle_transcript_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.transcript_selection.take().is_some() {
+            window.focus(&self.focus_handle);
+            cx.notify();
+            return;
         }
+        let Some(thread) = self.thread() else {
+            return;
+        };
 
-        let next_attempt_in_secs = next_attempt_in.as_secs() + 1;
-
-        let retry_message = if state.max_attempts == 1 {
-            if next_attempt_in_secs == 1 {
-                "Retrying. Next attempt in 1 second.".to_string()
-            } else {
-                format!("Retrying. Next attempt in {next_attempt_in_secs} seconds.")
+        let buffer = cx.new(|cx| Buffer::local(thread.read(cx).to_markdown(cx), cx));
+        let markdown_language = self
+            .project
+            .read(cx)
+            .languages()
+            .language_for_name("Markdown");
+        cx.spawn({
+            let buffer = buffer.downgrade();
+            async move |_, cx| {
+                let language = markdown_language.await?;
+                buffer.update(cx, |buffer, cx| buffer.set_language(Some(language), cx))
             }
-        } else if next_attempt_in_secs == 1 {
-            format!(
-                "Retrying. Next attempt in 1 second (Attempt {} of {}).",
-                state.attempt, state.max_attempts,
-            )
-        } else {
-            format!(
-                "Retrying. Next attempt in {next_attempt_in_secs} seconds (Attempt {} of {}).",
-                state.attempt, state.max_attempts,
-            )
-        };
+        })
+        .detach_and_log_err(cx);
 
-        Some(
-            Callout::new()
-                .severity(Severity::Warning)
-                .title(state.last_error.clone())
-                .description(retry_message),
-        )
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::for_buffer(buffer, None, window, cx);
+            editor.set_read_only(true);
+            editor.set_show_gutter(false, cx);
+            editor.set_soft_wrap_mode(language::language_settings::SoftWrap::EditorWidth, cx);
+            editor
+        });
+        window.focus(&editor.focus_handle(cx));
+        self.transcript_selection = Some(editor);
+        cx.notify();
     }
 
-    #[cfg(target_os = "windows")]
-    fn render_codex_windows_warning(&self, cx: &mut Context<Self>) -> Option<Callout> {
-        if self.show_codex_windows_warning {
-            Some(
-                Callout::new()
-                    .icon(IconName::Warning)
-                    .severity(Severity::Warning)
-                    .title("Codex on Windows")
-                    .description(
-                        "For best performance, run Codex in Windows Subsystem for Linux (WSL2)",
-                    )
-                    .actions_slot(
-                        Button::new("open-wsl-modal", "Open in WSL")
-                            .icon_size(IconSize::Small)
-                            .icon_color(Color::Muted)
-                            .on_click(cx.listener({
-                                move |_, _, window, cx| {
-                                    window.dispatch_action(
-                                        zed_actions::wsl_actions::OpenWsl::default().boxed_clone(),
-                                        cx,
-                                    );
-                                    cx.notify();
-                                }
-                            })),
-                    )
-                    .dismiss_action(
-                        IconButton::new("dismiss", IconName::Close)
-                            .icon_size(IconSize::Small)
-                            .icon_color(Color::Muted)
-                            .tooltip(Tooltip::text("Dismiss Warning"))
-                            .on_click(cx.listener({
-                                move |this, _, _, cx| {
-                                    this.show_codex_windows_warning = false;
-                                    cx.notify();
-                                }
-                            })),
-                    ),
-            )
-        } else {
-            None
-        }
+    fn scroll_to_top(&mut self, cx: &mut Context<Self>) {
+        self.list_state.scroll_to(ListOffset::default());
+        cx.notify();
     }
 
-    fn render_thread_error(&self, cx: &mut Context<Self>) -> Option<Div> {
-        let content = match self.thread_error.as_ref()? {
-            ThreadError::Other(error) => self.render_any_thread_error(error.clone(), cx),
-            ThreadError::Refusal => self.render_refusal_error(cx),
-            ThreadError::AuthenticationRequired(error) => {
-                self.render_authentication_required_error(error.clone(), cx)
-            }
-            ThreadError::PaymentRequired => self.render_payment_required_error(cx),
-            ThreadError::ModelRequestLimitReached(plan) => {
-                self.render_model_request_limit_reached_error(*plan, cx)
-            }
-            ThreadError::ToolUseLimitReached => self.render_tool_use_limit_reached_error(cx)?,
+    fn save_view_state(&self, cx: &mut App) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        let session_id = thread.read(cx).session_id().clone();
+        let scroll_top = self.list_state.logical_scroll_top();
+        let state = SavedViewState {
+            scroll_top: (scroll_top.item_ix < self.list_state.item_count()).then_some(scroll_top),
+            expanded_tool_calls: self.expanded_tool_calls.clone(),
+            expanded_thinking_blocks: self.expanded_thinking_blocks.clone(),
+            expanded_user_messages: self.expanded_user_messages.clone(),
         };
+        cx.default_global::<SavedViewStates>()
+            .0
+            .insert(session_id, state);
+    }
 
-        Some(div().child(content))
+    /// The key the unsent message is saved under: per thread once it has entries, and per
+    /// agent and project for a new thread, which is what the panel opens after a restart.
+    fn draft_key(&self, cx: &App) -> String {
+        if let Some(thread) = self.thread().map(|thread| thread.read(cx))
+            && !thread.entries().is_empty()
+        {
+            return format!("{DRAFT_KEY_PREFIX}-{}", thread.session_id());
+        }
+        let mut paths = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).abs_path().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        paths.sort();
+        format!(
+            "{DRAFT_KEY_PREFIX}-new-{}-{}",
+            self.agent.telemetry_id(),
+            paths.join(":")
+        )
     }
 
-    fn render_new_version_callout(&self, version: &SharedString, cx: &mut Context<Self>) -> Div {
-        v_flex().w_full().justify_end().child(
-            h_flex()
-                .p_2()
-                .pr_3()
-                .w_full()
-                .gap_1p5()
-                .border_t_1()
-                .border_color(cx.theme().colors().border)
-                .bg(cx.theme().colors().element_background)
-                .child(
-                    h_flex()
-                        .flex_1()
-                        .gap_1p5()
-                        .child(
-                            Icon::new(IconName::Download)
-                                .color(Color::Accent)
-                                .size(IconSize::Small),
-                        )
-                        .child(Label::new("New version available").size(LabelSize::Small)),
+    /// Saves the unsent message once typing pauses, so that it survives closing the panel
+    /// or a crash. The key is picked right away, so that clearing the editor on send
+    /// deletes the draft of a new thread rather than of the thread it becomes.
+    fn save_draft(&mut self, cx: &mut Context<Self>) {
+        let key = self.draft_key(cx);
+        let message_editor = self.message_editor.clone();
+        self._save_draft_task = Some(cx.spawn(async move |_, cx| {
+            cx.background_executor().timer(DRAFT_SAVE_DEBOUNCE).await;
+            let Ok((is_empty, contents, text)) = message_editor.update(cx, |editor, cx| {
+                (
+                    editor.is_empty(cx),
+                    editor.contents(false, cx),
+                    editor.text(cx),
                 )
-                .child(
-                    Button::new("update-button", format!("Update to v{}", version))
-                        .label_size(LabelSize::Small)
-                        .style(ButtonStyle::Tinted(TintColor::Accent))
-                        .on_click(cx.listener(|this, _, window, cx| {
-                            this.reset(window, cx);
-                        })),
-                ),
-        )
+            }) else {
+                return;
+            };
+            let draft = if is_empty {
+                None
+            } else {
+                // Messages that can't be sent as they are, e.g. because of an unknown
+                // command, are still kept as text.
+                let blocks = match contents.await {
+                    Ok((blocks, _)) => blocks,
+                    Err(_) => vec![text.into()],
+                };
+                serde_json::to_string(&blocks).log_err()
+            };
+            cx.background_spawn(async move {
+                match draft {
+                    Some(draft) => KEY_VALUE_STORE.write_kvp(key, draft).await,
+                    None => KEY_VALUE_STORE.delete_kvp(key).await,
+                }
+            })
+            .await
+            .log_err();
+        }));
     }
 
-    fn get_current_model_name(&self, cx: &App) -> SharedString {
-        // For native agent (Zed Agent), use the specific model name (e.g., "Claude 3.5 Sonnet")
-        // For ACP agents, use the agent name (e.g., "Claude Code", "Gemini CLI")
-        // This provides better clarity about what refused the request
-        if self
-            .agent
-            .clone()
-            .downcast::<agent::NativeAgentServer>()
-            .is_some()
-        {
-            // Native agent - use the model name
-            self.model_selector
-                .as_ref()
-                .and_then(|selector| selector.read(cx).active_model_name(cx))
-                .unwrap_or_else(|| SharedString::from("The model"))
-        } else {
-            // ACP agent - use the agent name (e.g., "Claude Code", "Gemini CLI")
-            self.agent.name()
-        }
+    /// Puts the message that was left unsent back into the empty message editor.
+    fn restore_draft(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let key = self.draft_key(cx);
+        cx.spawn_in(window, async move |this, cx| {
+            let Some(blocks) = cx
+                .background_spawn(async move { KEY_VALUE_STORE.read_kvp(&key) })
+                .await
+                .log_err()
+                .flatten()
+                .and_then(|draft| serde_json::from_str::<Vec<acp::ContentBlock>>(&draft).log_err())
+            else {
+                return;
+            };
+            this.update_in(cx, |this, window, cx| {
+                this.message_editor.update(cx, |editor, cx| {
+                    if editor.is_empty(cx) {
+                        editor.set_message(blocks, window, cx);
+                    }
+                });
+            })
+            .ok();
+        })
+        .detach();
     }
 
-    fn render_refusal_error(&self, cx: &mut Context<'_, Self>) -> Callout {
-        let model_or_agent_name = self.get_current_model_name(cx);
-        let refusal_message = format!(
-            "{} refused to respond to this prompt. This can happen when a model believes the prompt violates its content policy or safety guidelines, so rephrasing it can sometimes address the issue.",
-            model_or_agent_name
-        );
-
-        Callout::new()
-            .severity(Severity::Error)
-            .title("Request Refused")
-            .icon(IconName::XCircle)
-            .description(refusal_message.clone())
-            .actions_slot(self.create_copy_button(&refusal_message))
-            .dismiss_action(self.dismiss_error_button(cx))
+    /// Shows the thread as it was when it was last closed, instead of at the bottom.
+    fn restore_view_state(&mut self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        let Some(state) = cx
+            .try_global::<SavedViewStates>()
+            .and_then(|states| states.0.get(thread.read(cx).session_id()))
+            .cloned()
+        else {
+            return;
+        };
+        self.expanded_tool_calls = state.expanded_tool_calls;
+        self.expanded_thinking_blocks = state.expanded_thinking_blocks;
+        self.expanded_user_messages = state.expanded_user_messages;
+        if let Some(scroll_top) = state.scroll_top {
+            self.list_state.scroll_to(scroll_top);
+        }
+        cx.notify();
     }
 
-    fn render_any_thread_error(&self, error: SharedString, cx: &mut Context<'_, Self>) -> Callout {
-        let can_resume = self
-            .thread()
-            .map_or(false, |thread| thread.read(cx).can_resume(cx));
-
-        let can_enable_burn_mode = self.as_native_thread(cx).map_or(false, |thread| {
-            let thread = thread.read(cx);
-            let supports_burn_mode = thread
-                .model()
-                .map_or(false, |model| model.supports_burn_mode());
-            supports_burn_mode && thread.completion_mode() == CompletionMode::Normal
-        });
-
-        Callout::new()
-            .severity(Severity::Error)
-            .title("Error")
-            .icon(IconName::XCircle)
-            .description(error.clone())
-            .actions_slot(
-                h_flex()
-                    .gap_0p5()
-                    .when(can_resume && can_enable_burn_mode, |this| {
-                        this.child(
-                            Button::new("enable-burn-mode-and-retry", "Enable Burn Mode and Retry")
-                                .icon(IconName::ZedBurnMode)
-                                .icon_position(IconPosition::Start)
-                                .icon_size(IconSize::Small)
-                                .label_size(LabelSize::Small)
-                                .on_click(cx.listener(|this, _, window, cx| {
-                                    this.toggle_burn_mode(&ToggleBurnMode, window, cx);
-                                    this.resume_chat(cx);
-                                })),
-                        )
-                    })
-                    .when(can_resume, |this| {
-                        this.child(
-                            Button::new("retry", "Retry")
-                                .icon(IconName::RotateCw)
-                                .icon_position(IconPosition::Start)
-                                .icon_size(IconSize::Small)
-                                .label_size(LabelSize::Small)
-                                .on_click(cx.listener(|this, _, _window, cx| {
-                                    this.resume_chat(cx);
-                                })),
-                        )
-                    })
-                    .child(self.create_copy_button(error.to_string())),
-            )
-            .dismiss_action(self.dismiss_error_button(cx))
+    pub fn scroll_to_bottom(&mut self, cx: &mut Context<Self>) {
+        if let Some(thread) = self.thread() {
+            let entry_count = thread.read(cx).entries().len();
+            self.list_state.reset(entry_count);
+            cx.notify();
+        }
     }
 
-    fn render_payment_required_error(&self, cx: &mut Context<Self>) -> Callout {
-        const ERROR_MESSAGE: &str =
-            "You reached your free usage limit. Upgrade to Zed Pro for more prompts.";
-
-        Callout::new()
-            .severity(Severity::Error)
-            .icon(IconName::XCircle)
-            .title("Free Usage Exceeded")
-            .description(ERROR_MESSAGE)
-            .actions_slot(
-                h_flex()
-                    .gap_0p5()
-                    .child(self.upgrade_button(cx))
-                    .child(self.create_copy_button(ERROR_MESSAGE)),
-            )
-            .dismiss_action(self.dismiss_error_button(cx))
+    fn notify(
+        &mut self,
+        kind: AgentNotificationKind,
+        caption: impl Into<SharedString>,
+        icon: IconName,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match kind {
+            AgentNotificationKind::None => {}
+            AgentNotificationKind::Visual => {
+                self.play_notification_sound(window, cx);
+                self.show_toast(caption, window, cx);
+            }
+            AgentNotificationKind::Sound => {
+                if !window.is_window_active() {
+                    Audio::play_sound(Sound::AgentDone, cx);
+                }
+            }
+            AgentNotificationKind::SystemNotification => {
+                self.play_notification_sound(window, cx);
+                self.show_notification(caption, icon, window, cx);
+            }
+        }
     }
 
-    fn render_authentication_required_error(
-        &self,
-        error: SharedString,
-        cx: &mut Context<Self>,
-    ) -> Callout {
-        Callout::new()
-            .severity(Severity::Error)
-            .title("Authentication Required")
-            .icon(IconName::XCircle)
-            .description(error.clone())
-            .actions_slot(
-                h_flex()
-                    .gap_0p5()
-                    .child(self.authenticate_button(cx))
-                    .child(self.create_copy_button(error)),
-            )
-            .dismiss_action(self.dismiss_error_button(cx))
+    fn play_notification_sound(&self, window: &Window, cx: &mut App) {
+        let settings = AgentSettings::get_global(cx);
+        if settings.play_sound_when_agent_done && !window.is_window_active() {
+            Audio::play_sound(Sound::AgentDone, cx);
+        }
     }
 
-    fn render_model_request_limit_reached_error(
-        &self,
-        plan: cloud_llm_client::Plan,
+    fn show_toast(
+        &mut self,
+        caption: impl Into<SharedString>,
+        window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> Callout {
-        let error_message = match plan {
-            cloud_llm_client::Plan::V1(PlanV1::ZedPro) => {
-                "Upgrade to usage-based billing for more prompts."
-            }
-            cloud_llm_client::Plan::V1(PlanV1::ZedProTrial)
-            | cloud_llm_client::Plan::V1(PlanV1::ZedFree) => "Upgrade to Zed Pro for more prompts.",
-            cloud_llm_client::Plan::V2(_) => "",
+    ) {
+        if self.focus_handle.contains_focused(window, cx) {
+            return;
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
         };
 
-        Callout::new()
-            .severity(Severity::Error)
-            .title("Model Prompt Limit Reached")
-            .icon(IconName::XCircle)
-            .description(error_message)
-            .actions_slot(
-                h_flex()
-                    .gap_0p5()
-                    .child(self.upgrade_button(cx))
-                    .child(self.create_copy_button(error_message)),
-            )
-            .dismiss_action(self.dismiss_error_button(cx))
+        struct AgentStatusToast;
+        let message = format!("{}: {}", self.agent.name(), caption.into());
+        let workspace_handle = self.workspace.clone();
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_toast(
+                Toast::new(NotificationId::unique::<AgentStatusToast>(), message)
+                    .on_click("Open Agent", move |window, cx| {
+                        workspace_handle
+                            .update(cx, |workspace, cx| {
+                                workspace.focus_panel::<AgentPanel>(window, cx);
+                            })
+                            .ok();
+                    })
+                    .autohide(),
+                cx,
+            );
+        });
     }
 
-    fn render_tool_use_limit_reached_error(&self, cx: &mut Context<Self>) -> Option<Callout> {
-        let thread = self.as_native_thread(cx)?;
-        let supports_burn_mode = thread
-            .read(cx)
-            .model()
-            .is_some_and(|model| model.supports_burn_mode());
+    fn show_notification(
+        &mut self,
+        caption: impl Into<SharedString>,
+        icon: IconName,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if window.is_window_active() || !self.notifications.is_empty() {
+            return;
+        }
 
-        let focus_handle = self.focus_handle(cx);
+        // TODO: Change this once we have title summarization for external agents.
+        let title = self.agent.name();
 
-        Some(
-            Callout::new()
-                .icon(IconName::Info)
-                .title("Consecutive tool use limit reached.")
-                .actions_slot(
-                    h_flex()
-                        .gap_0p5()
-                        .when(supports_burn_mode, |this| {
-                            this.child(
-                                Button::new("continue-burn-mode", "Continue with Burn Mode")
-                                    .style(ButtonStyle::Filled)
-                                    .style(ButtonStyle::Tinted(ui::TintColor::Accent))
-                                    .layer(ElevationIndex::ModalSurface)
-                                    .label_size(LabelSize::Small)
-                                    .key_binding(
-                                        KeyBinding::for_action_in(
-                                            &ContinueWithBurnMode,
-                                            &focus_handle,
-                                            cx,
-                                        )
-                                        .map(|kb| kb.size(rems_from_px(10.))),
-                                    )
-                                    .tooltip(Tooltip::text(
-                                        "Enable Burn Mode for unlimited tool use.",
-                                    ))
-                                    .on_click({
-                                        cx.listener(move |this, _, _window, cx| {
-                                            thread.update(cx, |thread, cx| {
-                                                thread
-                                                    .set_completion_mode(CompletionMode::Burn, cx);
-                                            });
-                                            this.resume_chat(cx);
-                                        })
-                                    }),
-                            )
-                        })
-                        .child(
-                            Button::new("continue-conversation", "Continue")
-                                .layer(ElevationIndex::ModalSurface)
-                                .label_size(LabelSize::Small)
-                                .key_binding(
-                                    KeyBinding::for_action_in(&ContinueThread, &focus_handle, cx)
-                                        .map(|kb| kb.size(rems_from_px(10.))),
-                                )
-                                .on_click(cx.listener(|this, _, _window, cx| {
-                                    this.resume_chat(cx);
-                                })),
-                        ),
-                ),
-        )
+        match AgentSettings::get_global(cx).notify_when_agent_waiting {
+            NotifyWhenAgentWaiting::PrimaryScreen => {
+                if let Some(primary) = cx.primary_display() {
+                    self.pop_up(icon, caption.into(), title, window, primary, cx);
+                }
+            }
+            NotifyWhenAgentWaiting::AllScreens => {
+                let caption = caption.into();
+                for screen in cx.displays() {
+                    self.pop_up(icon, caption.clone(), title.clone(), window, screen, cx);
+                }
+            }
+            NotifyWhenAgentWaiting::Never => {
+                // Don't show anything
+            }
+        }
     }
 
-    fn create_copy_button(&self, message: impl Into<String>) -> impl IntoElement {
-        let message = message.into();
+    fn pop_up(
+        &mut self,
+        icon: IconName,
+        caption: SharedString,
+        title: SharedString,
+        window: &mut Window,
+        screen: Rc<dyn PlatformDisplay>,
+        cx: &mut Context<Self>,
+    ) {
+        let options = AgentNotification::window_options(screen, cx);
 
-        IconButton::new("copy", IconName::Copy)
-            .icon_size(IconSize::Small)
-            .icon_color(Color::Muted)
-            .tooltip(Tooltip::text("Copy Error Message"))
-            .on_click(move |_, _, cx| {
-                cx.write_to_clipboard(ClipboardItem::new_string(message.clone()))
+        let project_name = self.workspace.upgrade().and_then(|workspace| {
+            workspace
+                .read(cx)
+                .project()
+                .read(cx)
+                .visible_worktrees(cx)
+                .next()
+                .map(|worktree| worktree.read(cx).root_name_str().to_string())
+        });
+
+        if let Some(screen_window) = cx
+            .open_window(options, |_, cx| {
+                cx.new(|_| {
+                    AgentNotification::new(title.clone(), caption.clone(), icon, project_name)
+                })
             })
-    }
+            .log_err()
+            && let Some(pop_up) = screen_window.entity(cx).log_err()
+        {
+            self.notification_subscriptions
+                .entry(screen_window)
+                .or_insert_with(Vec::new)
+                .push(cx.subscribe_in(&pop_up, window, {
+                    |this, _, event, window, cx| match event {
+                        AgentNotificationEvent::Accepted => {
+                            let handle = window.window_handle();
+                            cx.activate(true);
 
-    fn dismiss_error_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        IconButton::new("dismiss", IconName::Close)
-            .icon_size(IconSize::Small)
-            .icon_color(Color::Muted)
-            .tooltip(Tooltip::text("Dismiss Error"))
-            .on_click(cx.listener({
-                move |this, _, _, cx| {
-                    this.clear_thread_error(cx);
-                    cx.notify();
-                }
-            }))
-    }
+                            let workspace_handle = this.workspace.clone();
 
-    fn authenticate_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        Button::new("authenticate", "Authenticate")
-            .label_size(LabelSize::Small)
-            .style(ButtonStyle::Filled)
-            .on_click(cx.listener({
-                move |this, _, window, cx| {
-                    let agent = this.agent.clone();
-                    let ThreadState::Ready { thread, .. } = &this.thread_state else {
-                        return;
-                    };
+                            // If there are multiple Zed windows, activate the correct one.
+                            cx.defer(move |cx| {
+                                handle
+                                    .update(cx, |_view, window, _cx| {
+                                        window.activate_window();
 
-                    let connection = thread.read(cx).connection().clone();
-                    let err = AuthRequired {
-                        description: None,
-                        provider_id: None,
-                    };
-                    this.clear_thread_error(cx);
-                    let this = cx.weak_entity();
-                    window.defer(cx, |window, cx| {
-                        Self::handle_auth_required(this, err, agent, connection, window, cx);
-                    })
-                }
-            }))
-    }
+                                        if let Some(workspace) = workspace_handle.upgrade() {
+                                            workspace.update(_cx, |workspace, cx| {
+                                                workspace.focus_panel::<AgentPanel>(window, cx);
+                                            });
+                                        }
+                                    })
+                                    .log_err();
+                            });
 
-    pub(crate) fn reauthenticate(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        let agent = self.agent.clone();
-        let ThreadState::Ready { thread, .. } = &self.thread_state else {
-            return;
-        };
+                            this.dismiss_notifications(cx);
+                        }
+                        AgentNotificationEvent::Dismissed => {
+                            this.dismiss_notifications(cx);
+                        }
+                    }
+                }));
 
-        let connection = thread.read(cx).connection().clone();
-        let err = AuthRequired {
-            description: None,
-            provider_id: None,
-        };
-        self.clear_thread_error(cx);
-        let this = cx.weak_entity();
-        window.defer(cx, |window, cx| {
-            Self::handle_auth_required(this, err, agent, connection, window, cx);
-        })
-    }
+            self.notifications.push(screen_window);
 
-    fn upgrade_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
-        Button::new("upgrade", "Upgrade")
-            .label_size(LabelSize::Small)
-            .style(ButtonStyle::Tinted(ui::TintColor::Accent))
-            .on_click(cx.listener({
-                move |this, _, _, cx| {
-                    this.clear_thread_error(cx);
-                    cx.open_url(&zed_urls::upgrade_to_zed_pro_url(cx));
-                }
-            }))
+            // If the user manually refocuses the original window, dismiss the popup.
+            self.notification_subscriptions
+                .entry(screen_window)
+                .or_insert_with(Vec::new)
+                .push({
+                    let pop_up_weak = pop_up.downgrade();
+
+                    cx.observe_window_activation(window, move |_, window, cx| {
+                        if window.is_window_active()
+                            && let Some(pop_up) = pop_up_weak.upgrade()
+                        {
+                            pop_up.update(cx, |_, cx| {
+                                cx.emit(AgentNotificationEvent::Dismissed);
+                            });
+                        }
+                    })
+                });
+        }
     }
 
-    pub fn delete_history_entry(&mut self, entry: HistoryEntry, cx: &mut Context<Self>) {
-        let task = match entry {
-            HistoryEntry::AcpThread(thread) => self.history_store.update(cx, |history, cx| {
-                history.delete_thread(thread.id.clone(), cx)
-            }),
-            HistoryEntry::TextThread(text_thread) => {
-                self.history_store.update(cx, |history, cx| {
-                    history.delete_text_thread(text_thread.path.clone(), cx)
+    fn dismiss_notifications(&mut self, cx: &mut Context<Self>) {
+        for window in self.notifications.drain(..) {
+            window
+                .update(cx, |_, window, _| {
+                    window.remove_window();
                 })
-            }
-        };
-        task.detach_and_log_err(cx);
-    }
+                .ok();
 
-    /// Returns the currently active editor, either for a message that is being
-    /// edited or the editor for a new message.
-    fn active_editor(&self, cx: &App) -> Entity<MessageEditor> {
-        if let Some(index) = self.editing_message
-            && let Some(editor) = self
-                .entry_view_state
-                .read(cx)
-                .entry(index)
-                .and_then(|e| e.message_editor())
-                .cloned()
-        {
-            editor
-        } else {
-            self.message_editor.clone()
+            self.notification_subscriptions.remove(&window);
         }
     }
-}
-
-fn loading_contents_spinner(size: IconSize) -> AnyElement {
-    Icon::new(IconName::LoadCircle)
-        .size(size)
-        .color(Color::Accent)
-        .with_rotate_animation(3)
-        .into_any_element()
-}
 
-impl Focusable for AcpThreadView {
-    fn focus_handle(&self, cx: &App) -> FocusHandle {
-        match self.thread_state {
-            ThreadState::Loading { .. } | ThreadState::Ready { .. } => {
-                self.active_editor(cx).focus_handle(cx)
-            }
-            ThreadState::LoadError(_) | ThreadState::Unauthenticated { .. } => {
-                self.focus_handle.clone()
-            }
+    fn render_turn_summary(
+        &self,
+        thread: &Entity<AcpThread>,
+        cx: &Context<Self>,
+    ) -> Option<AnyElement> {
+        let thread = thread.read(cx);
+        if thread.status() == ThreadStatus::Generating {
+            return None;
+        }
+        let summary = TurnSummary::for_last_turn(thread.entries(), cx);
+        if summary.is_empty() {
+            return None;
         }
-    }
-}
 
-impl Render for AcpThreadView {
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        let has_messages = self.list_state.item_count() > 0;
-        let line_height = TextSize::Small.rems(cx).to_pixels(window.rem_size()) * 1.5;
+        let count = |count: usize, noun: &str| {
+            format!("{count} {noun}{}", if count == 1 { "" } else { "s" })
+        };
+        let title = format!(
+            "{} ran, {} changed, {} granted",
+            count(summary.commands.len(), "command"),
+            count(summary.changed_files.len(), "file"),
+            count(summary.approvals.len(), "approval"),
+        );
+        let path_style = self.project.read(cx).path_style(cx);
+        let link = |id: (&'static str, usize), icon: IconName, label: SharedString| {
+            Button::new(id, label)
+                .icon(icon)
+                .icon_position(IconPosition::Start)
+                .icon_size(IconSize::XSmall)
+                .icon_color(Color::Muted)
+                .label_size(LabelSize::Small)
+                .truncate(true)
+        };
 
-        v_flex()
-            .size_full()
-            .key_context("AcpThread")
-            .on_action(cx.listener(Self::toggle_burn_mode))
-            .on_action(cx.listener(Self::keep_all))
-            .on_action(cx.listener(Self::reject_all))
-            .on_action(cx.listener(Self::allow_always))
-            .on_action(cx.listener(Self::allow_once))
-            .on_action(cx.listener(Self::reject_once))
-            .track_focus(&self.focus_handle)
-            .bg(cx.theme().colors().panel_background)
-            .child(match &self.thread_state {
-                ThreadState::Unauthenticated {
-                    connection,
-                    description,
-                    configuration_view,
-                    pending_auth_method,
-                    ..
-                } => self
-                    .render_auth_required_state(
-                        connection,
-                        description.as_ref(),
-                        configuration_view.as_ref(),
-                        pending_auth_method.as_ref(),
-                        window,
-                        cx,
+        let commands = summary.commands.into_iter().map(|(entry_ix, label)| {
+            link(
+                ("turn-summary-command", entry_ix),
+                IconName::ToolTerminal,
+                label,
+            )
+            .on_click(cx.listener(move |this, _, _, cx| this.scroll_to_entry(entry_ix, cx)))
+        });
+        let changed_files = summary.changed_files.into_iter().enumerate().map(
+            |(ix, (entry_ix, location_ix, path))| {
+                let label = self
+                    .project
+                    .read(cx)
+                    .find_project_path(&path, cx)
+                    .map(|project_path| project_path.path.display(path_style).to_string())
+                    .unwrap_or_else(|| path.to_string_lossy().to_string());
+                link(
+                    ("turn-summary-file", ix),
+                    IconName::ToolPencil,
+                    label.into(),
+                )
+                .on_click(cx.listener(move |this, _, window, cx| {
+                    this.open_tool_call_location(entry_ix, location_ix, window, cx);
+                }))
+            },
+        );
+        let approvals = summary.approvals.into_iter().map(|(entry_ix, label)| {
+            link(("turn-summary-approval", entry_ix), IconName::Check, label)
+                .on_click(cx.listener(move |this, _, _, cx| this.scroll_to_entry(entry_ix, cx)))
+        });
+
+        Some(
+            v_flex()
+                .mx_5()
+                .mt_2()
+                .p_2()
+                .gap_1()
+                .rounded_md()
+                .border_1()
+                .border_color(self.tool_card_border_color(cx))
+                .bg(cx.theme().colors().editor_background.opacity(0.2))
+                .child(Label::new(title).size(LabelSize::Small).color(Color::Muted))
+                .child(
+                    h_flex()
+                        .flex_wrap()
+                        .gap_1()
+                        .children(commands)
+                        .children(changed_files)
+                        .children(approvals),
+                )
+                .into_any_element(),
+        )
+    }
+
+    /// A footer after the last entry of a finished turn, with how long it took and, when
+    /// the agent reported them, its model and tokens. Clicking it shows the details.
+    fn render_turn_stats(
+        &self,
+        entry_ix: usize,
+        thread: &Entity<AcpThread>,
+        cx: &Context<Self>,
+    ) -> Option<AnyElement> {
+        let thread = thread.read(cx);
+        let entries = thread.entries();
+        if entries.get(entry_ix)?.user_message().is_some()
+            || entries
+                .get(entry_ix + 1)
+                .is_some_and(|next| next.user_message().is_none())
+        {
+            return None;
+        }
+        let (message_ix, message) = entries[..entry_ix]
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(ix, entry)| entry.user_message().map(|message| (ix, message)))?;
+        let stats = message.turn_stats.as_ref()?;
+        let is_expanded = self.expanded_turn_stats.contains(&message_ix);
+
+        let tokens = crate::text_thread_editor::humanize_token_count;
+        let mut summary = Vec::new();
+        summary.extend(stats.model.as_ref().map(|model| model.to_string()));
+        summary.push(format_turn_duration(stats.duration));
+        summary.extend(
+            stats
+                .input_tokens
+                .map(|count| format!("{} in", tokens(count))),
+        );
+        summary.extend(
+            stats
+                .output_tokens
+                .map(|count| format!("{} out", tokens(count))),
+        );
+
+        let detail = |label: &'static str, value: String| {
+            h_flex()
+                .gap_2()
+                .child(
+                    div().w_24().child(
+                        Label::new(label)
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    ),
+                )
+                .child(Label::new(value).size(LabelSize::XSmall))
+        };
+
+        Some(
+            v_flex()
+                .px_5()
+                .pb_1()
+                .gap_1()
+                .child(
+                    h_flex()
+                        .id(("turn-stats", message_ix))
+                        .gap_1()
+                        .cursor_pointer()
+                        .child(
+                            Disclosure::new(("turn-stats-disclosure", message_ix), is_expanded)
+                                .opened_icon(IconName::ChevronUp)
+                                .closed_icon(IconName::ChevronDown),
+                        )
+                        .child(
+                            Label::new(summary.join(" · "))
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            if !this.expanded_turn_stats.remove(&message_ix) {
+                                this.expanded_turn_stats.insert(message_ix);
+                            }
+                            cx.notify();
+                        })),
+                )
+                .when(is_expanded, |this| {
+                    this.child(
+                        v_flex()
+                            .ml_5()
+                            .gap_0p5()
+                            .when_some(stats.model.clone(), |this, model| {
+                                this.child(detail("Model", model.to_string()))
+                            })
+                            .child(detail("Duration", format_turn_duration(stats.duration)))
+                            .when_some(stats.first_response, |this, first_response| {
+                                this.child(detail(
+                                    "First response",
+                                    format_turn_duration(first_response),
+                                ))
+                            })
+                            .when_some(stats.input_tokens, |this, count| {
+                                this.child(detail("Input tokens", count.to_string()))
+                            })
+                            .when_some(stats.output_tokens, |this, count| {
+                                this.child(detail("Output tokens", count.to_string()))
+                            }),
                     )
-                    .into_any(),
-                ThreadState::Loading { .. } => v_flex()
-                    .flex_1()
-                    .child(self.render_recent_history(cx))
-                    .into_any(),
-                ThreadState::LoadError(e) => v_flex()
-                    .flex_1()
-                    .size_full()
-                    .items_center()
-                    .justify_end()
-                    .child(self.render_load_error(e, window, cx))
-                    .into_any(),
-                ThreadState::Ready { .. } => v_flex().flex_1().map(|this| {
-                    if has_messages {
-                        this.child(
-                            list(
-                                self.list_state.clone(),
-                                cx.processor(|this, index: usize, window, cx| {
-                                    let Some((entry, len)) = this.thread().and_then(|thread| {
-                                        let entries = &thread.read(cx).entries();
-                                        Some((entries.get(index)?, entries.len()))
-                                    }) else {
-                                        return Empty.into_any();
-                                    };
-                                    this.render_entry(index, len, entry, window, cx)
-                                }),
-                            )
-                            .with_sizing_behavior(gpui::ListSizingBehavior::Auto)
-                            .flex_grow()
-                            .into_any(),
+                })
+                .into_any_element(),
+        )
+    }
+
+    fn scroll_to_entry(&mut self, entry_ix: usize, cx: &mut Context<Self>) {
+        self.list_state.scroll_to(ListOffset {
+            item_ix: entry_ix,
+            offset_in_item: px(0.),
+        });
+        cx.notify();
+    }
+
+    /// The phases the current turn went through, so that long silent turns feel less opaque.
+    fn render_turn_timeline(thread: &Entity<AcpThread>, cx: &Context<Self>) -> Option<Div> {
+        let phases = TurnPhase::for_last_turn(thread.read(cx).entries());
+        let (current, earlier) = phases.split_last()?;
+        let elided = earlier.len() >= TurnPhase::MAX_SHOWN;
+        let earlier = &earlier[earlier.len().saturating_sub(TurnPhase::MAX_SHOWN - 1)..];
+        let separator = || {
+            Icon::new(IconName::ChevronRight)
+                .size(IconSize::XSmall)
+                .color(Color::Disabled)
+        };
+
+        Some(
+            h_flex()
+                .gap_1()
+                .when(elided, |this| {
+                    this.child(
+                        Label::new("…")
+                            .size(LabelSize::XSmall)
+                            .color(Color::Disabled),
+                    )
+                    .child(separator())
+                })
+                .children(earlier.iter().map(|phase| {
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Label::new(phase.label())
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
                         )
-                        .vertical_scrollbar_for(self.list_state.clone(), window, cx)
-                        .into_any()
-                    } else {
-                        this.child(self.render_recent_history(cx)).into_any()
-                    }
-                }),
-            })
-            // The activity bar is intentionally rendered outside of the ThreadState::Ready match
-            // above so that the scrollbar doesn't render behind it. The current setup allows
-            // the scrollbar to stop exactly at the activity bar start.
-            .when(has_messages, |this| match &self.thread_state {
-                ThreadState::Ready { thread, .. } => {
-                    this.children(self.render_activity_bar(thread, window, cx))
-                }
-                _ => this,
-            })
-            .children(self.render_thread_retry_status_callout(window, cx))
-            .children({
-                #[cfg(target_os = "windows")]
-                {
-                    self.render_codex_windows_warning(cx)
-                }
-                #[cfg(not(target_os = "windows"))]
-                {
-                    Vec::<Empty>::new()
+                        .child(separator())
+                }))
+                .child(Label::new(current.label()).size(LabelSize::XSmall)),
+        )
+    }
+
+    fn render_thread_controls(
+        &self,
+        thread: &Entity<AcpThread>,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        let is_generating = matches!(thread.read(cx).status(), ThreadStatus::Generating);
+        if is_generating {
+            return h_flex()
+                .id("thread-controls-container")
+                .py_2()
+                .px(rems_from_px(22.))
+                .gap_2()
+                .child(SpinnerLabel::new().size(LabelSize::Small))
+                .children(Self::render_turn_timeline(thread, cx));
+        }
+
+        let open_as_markdown = IconButton::new("open-as-markdown", IconName::FileMarkdown)
+            .shape(ui::IconButtonShape::Square)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Ignored)
+            .tooltip(Tooltip::text("Open Thread as Markdown"))
+            .on_click(cx.listener(move |this, _, window, cx| {
+                if let Some(workspace) = this.workspace.upgrade() {
+                    this.open_thread_as_markdown(workspace, window, cx)
+                        .detach_and_log_err(cx);
                 }
-            })
-            .children(self.render_thread_error(cx))
-            .when_some(
-                self.new_server_version_available.as_ref().filter(|_| {
-                    !has_messages || !matches!(self.thread_state, ThreadState::Ready { .. })
-                }),
-                |this, version| this.child(self.render_new_version_callout(&version, cx)),
-            )
-            .children(
-                if let Some(usage_callout) = self.render_usage_callout(line_height, cx) {
-                    Some(usage_callout.into_any_element())
-                } else {
-                    self.render_token_limit_callout(line_height, cx)
-                        .map(|token_limit_callout| token_limit_callout.into_any_element())
-                },
-            )
-            .child(self.render_message_editor(window, cx))
+            }));
+
+        let scroll_to_top = IconButton::new("scroll_to_top", IconName::ArrowUp)
+            .shape(ui::IconButtonShape::Square)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Ignored)
+            .tooltip(Tooltip::text("Scroll To Top"))
+            .on_click(cx.listener(move |this, _, _, cx| {
+                this.scroll_to_top(cx);
+            }));
+
+        let mut container = h_flex()
+            .id("thread-controls-container")
+            .group("thread-controls-container")
+            .w_full()
+            .py_2()
+            .px_5()
+            .gap_px()
+            .opacity(0.6)
+            .hover(|style| style.opacity(1.))
+            .flex_wrap()
+            .justify_end();
+
+        if AgentSettings::get_global(cx).enable_feedback
+            && self
+                .thread()
+                .is_some_and(|thread| thread.read(cx).connection().telemetry().is_some())
+        {
+            let feedback = self.thread_feedback.feedback;
+
+            container = container
+                .child(
+                    div().visible_on_hover("thread-controls-container").child(
+                        Label::new(match feedback {
+                            Some(ThreadFeedback::Positive) => "Thanks for your feedback!",
+                            Some(ThreadFeedback::Negative) => {
+                                "We appreciate your feedback and will use it to improve."
+                            }
+                            None => {
+                                "Rating the thread sends all of your current conversation to the Zed team."
+                            }
+                        })
+                        .color(Color::Muted)
+                        .size(LabelSize::XSmall)
+                        .truncate(),
+                    ),
+                )
+                .child(
+                    IconButton::new("feedback-thumbs-up", IconName::ThumbsUp)
+                        .shape(ui::IconButtonShape::Square)
+                        .icon_size(IconSize::Small)
+                        .icon_color(match feedback {
+                            Some(ThreadFeedback::Positive) => Color::Accent,
+                            _ => Color::Ignored,
+                        })
+                        .tooltip(Tooltip::text("Helpful Response"))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.handle_feedback_click(ThreadFeedback::Positive, window, cx);
+                        })),
+                )
+                .child(
+                    IconButton::new("feedback-thumbs-down", IconName::ThumbsDown)
+                        .shape(ui::IconButtonShape::Square)
+                        .icon_size(IconSize::Small)
+                        .icon_color(match feedback {
+                            Some(ThreadFeedback::Negative) => Color::Accent,
+                            _ => Color::Ignored,
+                        })
+                        .tooltip(Tooltip::text("Not Helpful"))
+                        .on_click(cx.listener(move |this, _, window, cx| {
+                            this.handle_feedback_click(ThreadFeedback::Negative, window, cx);
+                        })),
+                );
+        }
+
+        if !self.message_branches.is_empty() {
+            container = container.child(
+                IconButton::new("toggle-branch-tree", IconName::GitBranch)
+                    .shape(ui::IconButtonShape::Square)
+                    .icon_size(IconSize::Small)
+                    .icon_color(if self.show_branch_tree {
+                        Color::Accent
+                    } else {
+                        Color::Ignored
+                    })
+                    .tooltip(Tooltip::text("Conversation Branches"))
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.show_branch_tree = !this.show_branch_tree;
+                        cx.notify();
+                    })),
+            );
+        }
+
+        let select_text = IconButton::new("toggle-transcript-selection", IconName::EditorCursor)
+            .shape(ui::IconButtonShape::Square)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Ignored)
+            .tooltip(Tooltip::text("Select Text"))
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.toggle_transcript_selection(window, cx);
+            }));
+
+        let any_expanded =
+            !self.expanded_tool_calls.is_empty() || !self.expanded_thinking_blocks.is_empty();
+        let toggle_entries = if any_expanded {
+            IconButton::new("collapse-all-entries", IconName::ListCollapse)
+                .tooltip(Tooltip::text("Collapse All"))
+                .on_click(cx.listener(|this, _, window, cx| {
+                    this.collapse_all_entries(&CollapseAllEntries, window, cx);
+                }))
+        } else {
+            IconButton::new("expand-all-entries", IconName::ListTree)
+                .tooltip(Tooltip::text("Expand All"))
+                .on_click(cx.listener(|this, _, window, cx| {
+                    this.expand_all_entries(&ExpandAllEntries, window, cx);
+                }))
+        }
+        .shape(ui::IconButtonShape::Square)
+        .icon_size(IconSize::Small)
+        .icon_color(Color::Ignored);
+
+        if AgentSettings::get_global(cx).enable_thread_sharing {
+            container = container.child(
+                IconButton::new("share-thread", IconName::ArrowUpRight)
+                    .shape(ui::IconButtonShape::Square)
+                    .icon_size(IconSize::Small)
+                    .icon_color(Color::Ignored)
+                    .tooltip(Tooltip::text("Share Thread as Gist"))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.share_thread(window, cx);
+                    })),
+            );
+        }
+
+        container
+            .child(toggle_entries)
+            .child(select_text)
+            .child(open_as_markdown)
+            .child(scroll_to_top)
+    }
+
+    fn render_transcript_selection(&self, editor: &Entity<Editor>, cx: &Context<Self>) -> Div {
+        v_flex()
+            .flex_1()
+            .min_h_0()
+            .on_action(cx.listener(|this, _: &menu::Cancel, window, cx| {
+                this.toggle_transcript_selection(window, cx);
+            }))
+            .child(
+                h_flex()
+                    .px_5()
+                    .py_1()
+                    .gap_2()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        Label::new("Select any part of the conversation to copy it")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new("exit-transcript-selection", "Done")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.toggle_transcript_selection(window, cx);
+                            })),
+                    ),
+            )
+            .child(div().flex_1().min_h_0().px_5().py_2().child(editor.clone()))
+    }
+
+    fn render_feedback_feedback_editor(editor: Entity<Editor>, cx: &Context<Self>) -> Div {
+        h_flex()
+            .key_context("AgentFeedbackMessageEditor")
+            .on_action(cx.listener(move |this, _: &menu::Cancel, _, cx| {
+                this.thread_feedback.dismiss_comments();
+                cx.notify();
+            }))
+            .on_action(cx.listener(move |this, _: &menu::Confirm, _window, cx| {
+                this.submit_feedback_message(cx);
+            }))
+            .p_2()
+            .mb_2()
+            .mx_5()
+            .gap_1()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().colors().border)
+            .bg(cx.theme().colors().editor_background)
+            .child(div().w_full().child(editor))
+            .child(
+                h_flex()
+                    .child(
+                        IconButton::new("dismiss-feedback-message", IconName::Close)
+                            .icon_color(Color::Error)
+                            .icon_size(IconSize::XSmall)
+                            .shape(ui::IconButtonShape::Square)
+                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                this.thread_feedback.dismiss_comments();
+                                cx.notify();
+                            })),
+                    )
+                    .child(
+                        IconButton::new("submit-feedback-message", IconName::Return)
+                            .icon_size(IconSize::XSmall)
+                            .shape(ui::IconButtonShape::Square)
+                            .on_click(cx.listener(move |this, _, _window, cx| {
+                                this.submit_feedback_message(cx);
+                            })),
+                    ),
+            )
+    }
+
+    fn handle_feedback_click(
+        &mut self,
+        feedback: ThreadFeedback,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(thread) = self.thread().cloned() else {
+            return;
+        };
+
+        self.thread_feedback.submit(thread, feedback, window, cx);
+        cx.notify();
+    }
+
+    fn submit_feedback_message(&mut self, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread().cloned() else {
+            return;
+        };
+
+        self.thread_feedback.submit_comments(thread, cx);
+        cx.notify();
+    }
+
+    /// Condenses the earlier messages of a native thread so that it can continue once
+    /// the context window fills up.
+    fn summarize_context(&mut self, cx: &mut Context<Self>) {
+        let Some(thread) = self.as_native_thread(cx) else {
+            return;
+        };
+        if thread.read(cx).is_summarizing_context() {
+            return;
+        }
+        let task = thread.update(cx, |thread, cx| thread.summarize_context(cx));
+        self.context_summary_expanded = false;
+        cx.notify();
+        cx.spawn(async move |this, cx| {
+            let result = task.await;
+            this.update(cx, |this, cx| match result {
+                Ok(()) => cx.notify(),
+                Err(error) => this.handle_thread_error(error, cx),
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn context_summary_position(&self, cx: &App) -> Option<ContextSummaryPosition> {
+        let thread = self.as_native_thread(cx)?;
+        let thread = thread.read(cx);
+        thread.context_summary()?;
+        Some(match thread.first_message_after_context_summary() {
+            Some(message_id) => ContextSummaryPosition::Before(message_id.clone()),
+            None => ContextSummaryPosition::End,
+        })
+    }
+
+    fn render_context_summary(&self, cx: &Context<Self>) -> Option<AnyElement> {
+        let thread = self.as_native_thread(cx)?;
+        let summary = thread.read(cx).context_summary()?.text.clone();
+        let expanded = self.context_summary_expanded;
+
+        Some(
+            v_flex()
+                .px_5()
+                .py_2()
+                .gap_2()
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(Divider::horizontal())
+                        .child(
+                            Icon::new(IconName::ListCollapse)
+                                .size(IconSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Label::new("Earlier context was summarized")
+                                .size(LabelSize::XSmall)
+                                .color(Color::Muted),
+                        )
+                        .child(
+                            Button::new(
+                                "toggle-context-summary",
+                                if expanded {
+                                    "Hide Summary"
+                                } else {
+                                    "Show Summary"
+                                },
+                            )
+                            .label_size(LabelSize::XSmall)
+                            .color(Color::Muted)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.context_summary_expanded = !this.context_summary_expanded;
+                                cx.notify();
+                            })),
+                        )
+                        .child(Divider::horizontal()),
+                )
+                .when(expanded, |this| {
+                    this.child(
+                        div()
+                            .p_2()
+                            .rounded_md()
+                            .border_1()
+                            .border_color(cx.theme().colors().border_variant)
+                            .bg(cx.theme().colors().editor_background)
+                            .child(
+                                Label::new(summary)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                })
+                .into_any_element(),
+        )
+    }
+
+    fn render_branch_change(change: &BranchChange) -> AnyElement {
+        h_flex()
+            .px_5()
+            .py_2()
+            .gap_2()
+            .child(Divider::horizontal())
+            .child(
+                Icon::new(IconName::GitBranch)
+                    .size(IconSize::XSmall)
+                    .color(Color::Warning),
+            )
+            .child(
+                Label::new(format!(
+                    "Branch changed from {} to {}",
+                    change.from, change.to
+                ))
+                .size(LabelSize::XSmall)
+                .color(Color::Muted),
+            )
+            .child(Divider::horizontal())
+            .into_any_element()
+    }
+
+    fn render_token_limit_callout(
+        &self,
+        line_height: Pixels,
+        cx: &mut Context<Self>,
+    ) -> Option<Callout> {
+        let token_usage = self.thread()?.read(cx).token_usage()?;
+        let ratio = token_usage.ratio();
+
+        let (severity, title) = match ratio {
+            acp_thread::TokenUsageRatio::Normal => return None,
+            acp_thread::TokenUsageRatio::Warning => {
+                (Severity::Warning, "Thread reaching the token limit soon")
+            }
+            acp_thread::TokenUsageRatio::Exceeded => {
+                (Severity::Error, "Thread reached the token limit")
+            }
+        };
+
+        let burn_mode_available = self.as_native_thread(cx).is_some_and(|thread| {
+            thread.read(cx).completion_mode() == CompletionMode::Normal
+                && thread
+                    .read(cx)
+                    .model()
+                    .is_some_and(|model| model.supports_burn_mode())
+        });
+
+        let summarizing = self
+            .as_native_thread(cx)
+            .map(|thread| thread.read(cx).is_summarizing_context());
+
+        let description = match (summarizing.is_some(), burn_mode_available) {
+            (true, true) => {
+                "To continue, summarize the earlier messages, start a new thread from a summary or turn Burn Mode on."
+            }
+            (true, false) => {
+                "To continue, summarize the earlier messages or start a new thread from a summary."
+            }
+            (false, true) => "To continue, start a new thread from a summary or turn Burn Mode on.",
+            (false, false) => "To continue, start a new thread from a summary.",
+        };
+
+        Some(
+            Callout::new()
+                .severity(severity)
+                .line_height(line_height)
+                .title(title)
+                .description(description)
+                .actions_slot(
+                    h_flex()
+                        .gap_0p5()
+                        .when_some(summarizing, |this, summarizing| {
+                            this.child(
+                                Button::new(
+                                    "summarize-context",
+                                    if summarizing {
+                                        "Summarizing…"
+                                    } else {
+                                        "Summarize and Continue"
+                                    },
+                                )
+                                .label_size(LabelSize::Small)
+                                .disabled(summarizing)
+                                .tooltip(Tooltip::text(
+                                    "Replace the earlier messages with a summary in what is sent to the model",
+                                ))
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.summarize_context(cx);
+                                })),
+                            )
+                        })
+                        .child(
+                            Button::new("start-new-thread", "Start New Thread")
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    let Some(thread) = this.thread() else {
+                                        return;
+                                    };
+                                    let session_id = thread.read(cx).session_id().clone();
+                                    window.dispatch_action(
+                                        crate::NewNativeAgentThreadFromSummary {
+                                            from_session_id: session_id,
+                                        }
+                                        .boxed_clone(),
+                                        cx,
+                                    );
+                                })),
+                        )
+                        .when(burn_mode_available, |this| {
+                            this.child(
+                                IconButton::new("burn-mode-callout", IconName::ZedBurnMode)
+                                    .icon_size(IconSize::XSmall)
+                                    .on_click(cx.listener(|this, _event, window, cx| {
+                                        this.toggle_burn_mode(&ToggleBurnMode, window, cx);
+                                    })),
+                            )
+                        }),
+                ),
+        )
+    }
+
+    fn render_usage_callout(&self, line_height: Pixels, cx: &mut Context<Self>) -> Option<Div> {
+        if !self.is_using_zed_ai_models(cx) {
+            return None;
+        }
+
+        let user_store = self.project.read(cx).user_store().read(cx);
+        if user_store.is_usage_based_billing_enabled() {
+            return None;
+        }
+
+        let plan = user_store
+            .plan()
+            .unwrap_or(cloud_llm_client::Plan::V1(PlanV1::ZedFree));
+
+        let usage = user_store.model_request_usage()?;
+
+        Some(
+            div()
+                .child(UsageCallout::new(plan, usage))
+                .line_height(line_height),
+        )
+    }
+
+    fn refresh_styles(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        self.entry_view_state.update(cx, |entry_view_state, cx| {
+            entry_view_state.refresh_styles(cx);
+        });
+        cx.notify();
+    }
+
+    fn issue_link_settings_changed(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        let settings = issue_link_settings(&self.project, cx);
+        if settings == self.issue_link_settings {
+            return;
+        }
+        self.issue_link_detector = issue_link_detector(&settings);
+        self.issue_link_settings = settings;
+        if let Some(thread) = self.thread().cloned() {
+            let count = thread.read(cx).entries().len();
+            self.link_issues(&thread, 0..count, cx);
+        }
+    }
+
+    /// Links the references to issues in the assistant messages and tool calls of the
+    /// entries.
+    /// Closes the tabs of paths a completed tool call deleted, and reopens the ones of
+    /// paths it moved at their destination, unless the project already followed the move.
+    fn sync_tabs_with_path_change(
+        &mut self,
+        thread: &Entity<AcpThread>,
+        entry_ix: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(AgentThreadEntry::ToolCall(tool_call)) = thread.read(cx).entries().get(entry_ix)
+        else {
+            return;
+        };
+        if !matches!(tool_call.kind, acp::ToolKind::Delete | acp::ToolKind::Move)
+            || !matches!(tool_call.status, ToolCallStatus::Completed)
+            || !self.synced_path_changes.insert(tool_call.id.clone())
+        {
+            return;
+        }
+        let kind = tool_call.kind;
+        let project = self.project.read(cx);
+        let paths = tool_call
+            .locations
+            .iter()
+            .filter_map(|location| project.find_project_path(&location.path, cx))
+            .collect::<Vec<_>>();
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let is_within = |path: &ProjectPath, ancestor: &ProjectPath| {
+            path.worktree_id == ancestor.worktree_id && path.path.starts_with(&ancestor.path)
+        };
+
+        let panes = workspace.read(cx).panes().to_vec();
+        match kind {
+            acp::ToolKind::Delete => {
+                for pane in panes {
+                    let item_ids = pane
+                        .read(cx)
+                        .items()
+                        .filter(|item| {
+                            item.project_path(cx).is_some_and(|path| {
+                                paths.iter().any(|deleted| is_within(&path, deleted))
+                            })
+                        })
+                        .map(|item| item.item_id())
+                        .collect::<Vec<_>>();
+                    if !item_ids.is_empty() {
+                        pane.update(cx, |pane, cx| {
+                            pane.close_items(window, cx, SaveIntent::Skip, move |id| {
+                                item_ids.contains(&id)
+                            })
+                            .detach_and_log_err(cx);
+                        });
+                    }
+                }
+            }
+            _ => {
+                let [source, destination] = paths.as_slice() else {
+                    return;
+                };
+                for pane in panes {
+                    let moved_items = pane
+                        .read(cx)
+                        .items()
+                        .filter_map(|item| {
+                            let path = item.project_path(cx)?;
+                            let relative = path.path.strip_prefix(&source.path).ok()?;
+                            (path.worktree_id == source.worktree_id).then(|| {
+                                let moved_path = ProjectPath {
+                                    worktree_id: destination.worktree_id,
+                                    path: destination.path.join(relative),
+                                };
+                                (item.item_id(), moved_path)
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    for (item_id, moved_path) in moved_items {
+                        let open = workspace.update(cx, |workspace, cx| {
+                            workspace.open_path(
+                                moved_path,
+                                Some(pane.downgrade()),
+                                false,
+                                window,
+                                cx,
+                            )
+                        });
+                        let pane = pane.downgrade();
+                        window
+                            .spawn(cx, async move |cx| {
+                                let opened = open.await?;
+                                // The item follows the move itself when the project moved the path.
+                                if opened.item_id() == item_id {
+                                    return anyhow::Ok(());
+                                }
+                                pane.update_in(cx, |pane, window, cx| {
+                                    pane.close_item_by_id(item_id, SaveIntent::Skip, window, cx)
+                                })?
+                                .await
+                            })
+                            .detach_and_log_err(cx);
+                    }
+                }
+            }
+        }
+    }
+
+    fn link_issues(&self, thread: &Entity<AcpThread>, range: Range<usize>, cx: &mut Context<Self>) {
+        let markdowns = thread.read(cx).entries()[range]
+            .iter()
+            .flat_map(|entry| match entry {
+                AgentThreadEntry::UserMessage(_) => Vec::new(),
+                AgentThreadEntry::AssistantMessage(message) => message
+                    .chunks
+                    .iter()
+                    .filter_map(|chunk| match chunk {
+                        AssistantMessageChunk::Message { block }
+                        | AssistantMessageChunk::Thought { block } => block.markdown().cloned(),
+                    })
+                    .collect(),
+                AgentThreadEntry::ToolCall(tool_call) => iter::once(tool_call.label.clone())
+                    .chain(
+                        tool_call
+                            .content
+                            .iter()
+                            .filter_map(|content| match content {
+                                ToolCallContent::ContentBlock(block) => block.markdown().cloned(),
+                                ToolCallContent::Diff(_) | ToolCallContent::Terminal(_) => None,
+                            }),
+                    )
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        for markdown in markdowns {
+            markdown.update(cx, |markdown, cx| {
+                markdown.set_link_detector(self.issue_link_detector.clone(), cx);
+            });
+        }
+    }
+
+    pub(crate) fn insert_dragged_files(
+        &self,
+        paths: Vec<project::ProjectPath>,
+        added_worktrees: Vec<Entity<project::Worktree>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.message_editor.update(cx, |message_editor, cx| {
+            message_editor.insert_dragged_files(paths, added_worktrees, window, cx);
+        })
+    }
+
+    fn render_entry_hover_actions(&self, entry_ix: usize, cx: &Context<Self>) -> impl IntoElement {
+        h_flex()
+            .absolute()
+            .top_0()
+            .right_2()
+            .visible_on_hover("thread-entry")
+            .child(
+                IconButton::new(("open-as-buffer", entry_ix), IconName::FileTextOutlined)
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(Color::Muted)
+                    .tooltip(Tooltip::text("Open as Buffer"))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.open_entry_as_buffer(entry_ix, window, cx);
+                    })),
+            )
+            .child(
+                IconButton::new(("ask-follow-up", entry_ix), IconName::ReplyArrowRight)
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(Color::Muted)
+                    .tooltip(Tooltip::text("Ask a Follow-Up About This"))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.ask_follow_up(entry_ix, window, cx);
+                    })),
+            )
+    }
+
+    /// Quotes the entry in the message editor, so that the next message refers to it.
+    fn ask_follow_up(&mut self, entry_ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(thread) = self.thread() else {
+            return;
+        };
+        let Some(quote) = thread
+            .read(cx)
+            .entries()
+            .get(entry_ix)
+            .and_then(|entry| follow_up_quote(entry, cx))
+        else {
+            return;
+        };
+        self.message_editor.update(cx, |editor, cx| {
+            editor.insert_quote(&quote, window, cx);
+        });
+        self.message_editor.focus_handle(cx).focus(window);
+    }
+
+    /// Inserts the selected text into the message editor or the message being
+    /// edited, if any.
+    pub(crate) fn insert_selections(&self, window: &mut Window, cx: &mut Context<Self>) {
+        self.active_editor(cx).update(cx, |editor, cx| {
+            editor.insert_selections(window, cx);
+        });
+    }
+
+    fn render_thread_retry_status_callout(
+        &self,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Option<Callout> {
+        let state = self.thread_retry_status.as_ref()?;
+
+        let next_attempt_in = state
+            .duration
+            .saturating_sub(Instant::now().saturating_duration_since(state.started_at));
+        if next_attempt_in.is_zero() {
+            return None;
+        }
+
+        let next_attempt_in_secs = next_attempt_in.as_secs() + 1;
+
+        let retry_message = if state.max_attempts == 1 {
+            if next_attempt_in_secs == 1 {
+                "Retrying. Next attempt in 1 second.".to_string()
+            } else {
+                format!("Retrying. Next attempt in {next_attempt_in_secs} seconds.")
+            }
+        } else if next_attempt_in_secs == 1 {
+            format!(
+                "Retrying. Next attempt in 1 second (Attempt {} of {}).",
+                state.attempt, state.max_attempts,
+            )
+        } else {
+            format!(
+                "Retrying. Next attempt in {next_attempt_in_secs} seconds (Attempt {} of {}).",
+                state.attempt, state.max_attempts,
+            )
+        };
+
+        Some(
+            Callout::new()
+                .severity(Severity::Warning)
+                .title(state.last_error.clone())
+                .description(retry_message),
+        )
+    }
+
+    /// Samples the memory and CPU use of the thread's agent process, if it runs in one, for
+    /// as long as the process lives.
+    fn poll_agent_process(&mut self, thread: &Entity<AcpThread>, cx: &mut Context<Self>) {
+        self.agent_process_usage = None;
+        let Some(pid) = thread
+            .read(cx)
+            .connection()
+            .clone()
+            .downcast::<agent_servers::AcpConnection>()
+            .and_then(|connection| connection.pid())
+        else {
+            self._agent_process_poll_task = None;
+            return;
+        };
+
+        self._agent_process_poll_task = Some(cx.spawn(async move |this, cx| {
+            let mut system = sysinfo::System::new();
+            loop {
+                let (returned_system, usage) = cx
+                    .background_spawn(async move {
+                        let pid = sysinfo::Pid::from_u32(pid);
+                        // The CPU use is measured since the previous refresh, so the first
+                        // sample reports none.
+                        system.refresh_processes_specifics(
+                            sysinfo::ProcessesToUpdate::Some(&[pid]),
+                            true,
+                            sysinfo::ProcessRefreshKind::nothing()
+                                .with_memory()
+                                .with_cpu(),
+                        );
+                        let usage = system.process(pid).map(|process| AgentProcessUsage {
+                            memory: process.memory(),
+                            cpu: process.cpu_usage(),
+                        });
+                        (system, usage)
+                    })
+                    .await;
+                system = returned_system;
+                let updated = this.update(cx, |this, cx| {
+                    if this.agent_process_usage != usage {
+                        this.agent_process_usage = usage;
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() || usage.is_none() {
+                    break;
+                }
+                cx.background_executor()
+                    .timer(AGENT_PROCESS_POLL_INTERVAL)
+                    .await;
+            }
+        }));
+    }
+
+    pub fn agent_process_usage(&self) -> Option<AgentProcessUsage> {
+        self.agent_process_usage
+    }
+
+    fn render_agent_memory_warning(&self, cx: &mut Context<Self>) -> Option<Callout> {
+        let memory = self.agent_process_usage?.memory;
+        let threshold_mb = AgentSettings::get_global(cx).process_memory_warning_mb;
+        if self.dismissed_memory_warning || threshold_mb == 0 || memory < threshold_mb * 1024 * 1024
+        {
+            return None;
+        }
+
+        Some(
+            Callout::new()
+                .icon(IconName::Warning)
+                .severity(Severity::Warning)
+                .title(format!(
+                    "{} is using {} of memory",
+                    self.agent.name(),
+                    format_file_size(memory, false)
+                ))
+                .description("Starting a new thread restarts the agent process.")
+                .dismiss_action(
+                    IconButton::new("dismiss-memory-warning", IconName::Close)
+                        .icon_size(IconSize::Small)
+                        .icon_color(Color::Muted)
+                        .tooltip(Tooltip::text("Dismiss Warning"))
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.dismissed_memory_warning = true;
+                            cx.notify();
+                        })),
+                ),
+        )
+    }
+
+    #[cfg(target_os = "windows")]
+    fn render_codex_windows_warning(&self, cx: &mut Context<Self>) -> Option<Callout> {
+        if self.show_codex_windows_warning {
+            Some(
+                Callout::new()
+                    .icon(IconName::Warning)
+                    .severity(Severity::Warning)
+                    .title("Codex on Windows")
+                    .description(
+                        "For best performance, run Codex in Windows Subsystem for Linux (WSL2)",
+                    )
+                    .actions_slot(
+                        Button::new("open-wsl-modal", "Open in WSL")
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .on_click(cx.listener({
+                                move |_, _, window, cx| {
+                                    window.dispatch_action(
+                                        zed_actions::wsl_actions::OpenWsl::default().boxed_clone(),
+                                        cx,
+                                    );
+                                    cx.notify();
+                                }
+                            })),
+                    )
+                    .dismiss_action(
+                        IconButton::new("dismiss", IconName::Close)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .tooltip(Tooltip::text("Dismiss Warning"))
+                            .on_click(cx.listener({
+                                move |this, _, _, cx| {
+                                    this.show_codex_windows_warning = false;
+                                    cx.notify();
+                                }
+                            })),
+                    ),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn render_thread_error(&self, cx: &mut Context<Self>) -> Option<Div> {
+        let content = match self.thread_error.as_ref()? {
+            ThreadError::Other(error) => self.render_any_thread_error(error.clone(), cx),
+            ThreadError::Refusal => self.render_refusal_error(cx),
+            ThreadError::AuthenticationRequired(error) => {
+                self.render_authentication_required_error(error.clone(), cx)
+            }
+            ThreadError::PaymentRequired => self.render_payment_required_error(cx),
+            ThreadError::ModelRequestLimitReached(plan) => {
+                self.render_model_request_limit_reached_error(*plan, cx)
+            }
+            ThreadError::ToolUseLimitReached => self.render_tool_use_limit_reached_error(cx)?,
+        };
+
+        Some(div().child(content))
+    }
+
+    fn render_new_version_callout(&self, version: &SharedString, cx: &mut Context<Self>) -> Div {
+        v_flex().w_full().justify_end().child(
+            h_flex()
+                .p_2()
+                .pr_3()
+                .w_full()
+                .gap_1p5()
+                .border_t_1()
+                .border_color(cx.theme().colors().border)
+                .bg(cx.theme().colors().element_background)
+                .child(
+                    h_flex()
+                        .flex_1()
+                        .gap_1p5()
+                        .child(
+                            Icon::new(IconName::Download)
+                                .color(Color::Accent)
+                                .size(IconSize::Small),
+                        )
+                        .child(Label::new("New version available").size(LabelSize::Small)),
+                )
+                .child(
+                    Button::new("update-button", format!("Update to v{}", version))
+                        .label_size(LabelSize::Small)
+                        .style(ButtonStyle::Tinted(TintColor::Accent))
+                        .on_click(cx.listener(|this, _, window, cx| {
+                            this.reset(window, cx);
+                        })),
+                ),
+        )
+    }
+
+    fn get_current_model_name(&self, cx: &App) -> SharedString {
+        // For native agent (Zed Agent), use the specific model name (e.g., "Claude 3.5 Sonnet")
+        // For ACP agents, use the agent name (e.g., "Claude Code", "Gemini CLI")
+        // This provides better clarity about what refused the request
+        if self
+            .agent
+            .clone()
+            .downcast::<agent::NativeAgentServer>()
+            .is_some()
+        {
+            // Native agent - use the model name
+            self.model_selector
+                .as_ref()
+                .and_then(|selector| selector.read(cx).active_model_name(cx))
+                .unwrap_or_else(|| SharedString::from("The model"))
+        } else {
+            // ACP agent - use the agent name (e.g., "Claude Code", "Gemini CLI")
+            self.agent.name()
+        }
+    }
+
+    fn render_refusal_error(&self, cx: &mut Context<'_, Self>) -> Callout {
+        let model_or_agent_name = self.get_current_model_name(cx);
+        let refusal_message = format!(
+            "{} refused to respond to this prompt. This can happen when a model believes the prompt violates its content policy or safety guidelines, so rephrasing it can sometimes address the issue.",
+            model_or_agent_name
+        );
+
+        Callout::new()
+            .severity(Severity::Error)
+            .title("Request Refused")
+            .icon(IconName::XCircle)
+            .description(refusal_message.clone())
+            .actions_slot(self.create_copy_button(&refusal_message))
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    /// Stands in for an entry that can't be rendered, keeping the rest of the thread usable.
+    fn render_entry_error(&self, details: SharedString) -> AnyElement {
+        div()
+            .px_5()
+            .py_1p5()
+            .child(
+                Callout::new()
+                    .severity(Severity::Error)
+                    .title("Failed to Render Entry")
+                    .icon(IconName::XCircle)
+                    .description(details),
+            )
+            .into_any_element()
+    }
+
+    fn render_any_thread_error(&self, error: SharedString, cx: &mut Context<'_, Self>) -> Callout {
+        let can_resume = self
+            .thread()
+            .map_or(false, |thread| thread.read(cx).can_resume(cx));
+
+        let can_enable_burn_mode = self.as_native_thread(cx).map_or(false, |thread| {
+            let thread = thread.read(cx);
+            let supports_burn_mode = thread
+                .model()
+                .map_or(false, |model| model.supports_burn_mode());
+            supports_burn_mode && thread.completion_mode() == CompletionMode::Normal
+        });
+
+        Callout::new()
+            .severity(Severity::Error)
+            .title("Error")
+            .icon(IconName::XCircle)
+            .description(error.clone())
+            .actions_slot(
+                h_flex()
+                    .gap_0p5()
+                    .when(can_resume && can_enable_burn_mode, |this| {
+                        this.child(
+                            Button::new("enable-burn-mode-and-retry", "Enable Burn Mode and Retry")
+                                .icon(IconName::ZedBurnMode)
+                                .icon_position(IconPosition::Start)
+                                .icon_size(IconSize::Small)
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.toggle_burn_mode(&ToggleBurnMode, window, cx);
+                                    this.resume_chat(cx);
+                                })),
+                        )
+                    })
+                    .when(can_resume, |this| {
+                        this.child(
+                            Button::new("retry", "Retry")
+                                .icon(IconName::RotateCw)
+                                .icon_position(IconPosition::Start)
+                                .icon_size(IconSize::Small)
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.resume_chat(cx);
+                                })),
+                        )
+                    })
+                    .child(self.create_copy_button(error.to_string())),
+            )
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    fn render_payment_required_error(&self, cx: &mut Context<Self>) -> Callout {
+        const ERROR_MESSAGE: &str =
+            "You reached your free usage limit. Upgrade to Zed Pro for more prompts.";
+
+        Callout::new()
+            .severity(Severity::Error)
+            .icon(IconName::XCircle)
+            .title("Free Usage Exceeded")
+            .description(ERROR_MESSAGE)
+            .actions_slot(
+                h_flex()
+                    .gap_0p5()
+                    .child(self.upgrade_button(cx))
+                    .child(self.create_copy_button(ERROR_MESSAGE)),
+            )
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    fn render_authentication_required_error(
+        &self,
+        error: SharedString,
+        cx: &mut Context<Self>,
+    ) -> Callout {
+        Callout::new()
+            .severity(Severity::Error)
+            .title("Authentication Required")
+            .icon(IconName::XCircle)
+            .description(error.clone())
+            .actions_slot(
+                h_flex()
+                    .gap_0p5()
+                    .child(self.authenticate_button(cx))
+                    .child(self.create_copy_button(error)),
+            )
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    fn render_model_request_limit_reached_error(
+        &self,
+        plan: cloud_llm_client::Plan,
+        cx: &mut Context<Self>,
+    ) -> Callout {
+        let error_message = match plan {
+            cloud_llm_client::Plan::V1(PlanV1::ZedPro) => {
+                "Upgrade to usage-based billing for more prompts."
+            }
+            cloud_llm_client::Plan::V1(PlanV1::ZedProTrial)
+            | cloud_llm_client::Plan::V1(PlanV1::ZedFree) => "Upgrade to Zed Pro for more prompts.",
+            cloud_llm_client::Plan::V2(_) => "",
+        };
+
+        Callout::new()
+            .severity(Severity::Error)
+            .title("Model Prompt Limit Reached")
+            .icon(IconName::XCircle)
+            .description(error_message)
+            .actions_slot(
+                h_flex()
+                    .gap_0p5()
+                    .child(self.upgrade_button(cx))
+                    .child(self.create_copy_button(error_message)),
+            )
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    fn render_tool_use_limit_reached_error(&self, cx: &mut Context<Self>) -> Option<Callout> {
+        let thread = self.as_native_thread(cx)?;
+        let supports_burn_mode = thread
+            .read(cx)
+            .model()
+            .is_some_and(|model| model.supports_burn_mode());
+
+        let focus_handle = self.focus_handle(cx);
+
+        Some(
+            Callout::new()
+                .icon(IconName::Info)
+                .title("Consecutive tool use limit reached.")
+                .actions_slot(
+                    h_flex()
+                        .gap_0p5()
+                        .when(supports_burn_mode, |this| {
+                            this.child(
+                                Button::new("continue-burn-mode", "Continue with Burn Mode")
+                                    .style(ButtonStyle::Filled)
+                                    .style(ButtonStyle::Tinted(ui::TintColor::Accent))
+                                    .layer(ElevationIndex::ModalSurface)
+                                    .label_size(LabelSize::Small)
+                                    .key_binding(
+                                        KeyBinding::for_action_in(
+                                            &ContinueWithBurnMode,
+                                            &focus_handle,
+                                            cx,
+                                        )
+                                        .map(|kb| kb.size(rems_from_px(10.))),
+                                    )
+                                    .tooltip(Tooltip::text(
+                                        "Enable Burn Mode for unlimited tool use.",
+                                    ))
+                                    .on_click({
+                                        cx.listener(move |this, _, _window, cx| {
+                                            thread.update(cx, |thread, cx| {
+                                                thread
+                                                    .set_completion_mode(CompletionMode::Burn, cx);
+                                            });
+                                            this.resume_chat(cx);
+                                        })
+                                    }),
+                            )
+                        })
+                        .child(
+                            Button::new("continue-conversation", "Continue")
+                                .layer(ElevationIndex::ModalSurface)
+                                .label_size(LabelSize::Small)
+                                .key_binding(
+                                    KeyBinding::for_action_in(&ContinueThread, &focus_handle, cx)
+                                        .map(|kb| kb.size(rems_from_px(10.))),
+                                )
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.resume_chat(cx);
+                                })),
+                        ),
+                ),
+        )
+    }
+
+    fn create_copy_button(&self, message: impl Into<String>) -> impl IntoElement {
+        let message = message.into();
+
+        IconButton::new("copy", IconName::Copy)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Muted)
+            .tooltip(Tooltip::text("Copy Error Message"))
+            .on_click(move |_, _, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(message.clone()))
+            })
+    }
+
+    fn dismiss_error_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        IconButton::new("dismiss", IconName::Close)
+            .icon_size(IconSize::Small)
+            .icon_color(Color::Muted)
+            .tooltip(Tooltip::text("Dismiss Error"))
+            .on_click(cx.listener({
+                move |this, _, _, cx| {
+                    this.clear_thread_error(cx);
+                    cx.notify();
+                }
+            }))
+    }
+
+    fn authenticate_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("authenticate", "Authenticate")
+            .label_size(LabelSize::Small)
+            .style(ButtonStyle::Filled)
+            .on_click(cx.listener({
+                move |this, _, window, cx| {
+                    let agent = this.agent.clone();
+                    let ThreadState::Ready { thread, .. } = &this.thread_state else {
+                        return;
+                    };
+
+                    let connection = thread.read(cx).connection().clone();
+                    let err = AuthRequired {
+                        description: None,
+                        provider_id: None,
+                    };
+                    this.clear_thread_error(cx);
+                    let this = cx.weak_entity();
+                    window.defer(cx, |window, cx| {
+                        Self::handle_auth_required(this, err, agent, connection, window, cx);
+                    })
+                }
+            }))
+    }
+
+    pub(crate) fn reauthenticate(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let agent = self.agent.clone();
+        let ThreadState::Ready { thread, .. } = &self.thread_state else {
+            return;
+        };
+
+        let connection = thread.read(cx).connection().clone();
+        let err = AuthRequired {
+            description: None,
+            provider_id: None,
+        };
+        self.clear_thread_error(cx);
+        let this = cx.weak_entity();
+        window.defer(cx, |window, cx| {
+            Self::handle_auth_required(this, err, agent, connection, window, cx);
+        })
+    }
+
+    fn upgrade_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("upgrade", "Upgrade")
+            .label_size(LabelSize::Small)
+            .style(ButtonStyle::Tinted(ui::TintColor::Accent))
+            .on_click(cx.listener({
+                move |this, _, _, cx| {
+                    this.clear_thread_error(cx);
+                    cx.open_url(&zed_urls::upgrade_to_zed_pro_url(cx));
+                }
+            }))
+    }
+
+    pub fn delete_history_entry(&mut self, entry: HistoryEntry, cx: &mut Context<Self>) {
+        let task = match entry {
+            HistoryEntry::AcpThread(thread) => self.history_store.update(cx, |history, cx| {
+                history.delete_thread(thread.id.clone(), cx)
+            }),
+            HistoryEntry::TextThread(text_thread) => {
+                self.history_store.update(cx, |history, cx| {
+                    history.delete_text_thread(text_thread.path.clone(), cx)
+                })
+            }
+        };
+        task.detach_and_log_err(cx);
+    }
+
+    /// Returns the currently active editor, either for a message that is being
+    /// edited or the editor for a new message.
+    fn active_editor(&self, cx: &App) -> Entity<MessageEditor> {
+        if let Some(index) = self.editing_message
+            && let Some(editor) = self
+                .entry_view_state
+                .read(cx)
+                .entry(index)
+                .and_then(|e| e.message_editor())
+                .cloned()
+        {
+            editor
+        } else {
+            self.message_editor.clone()
+        }
+    }
+}
+
+/// The query of a search tool call, as far as it can be read from the tool's raw input.
+#[derive(Debug, PartialEq)]
+struct SearchToolQuery {
+    query: String,
+    regex: bool,
+    included_files: Option<String>,
+}
+
+impl SearchToolQuery {
+    fn from_raw_input(raw_input: &serde_json::Value) -> Option<Self> {
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| raw_input.get(name)?.as_str())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        // Glob tools also name their argument `pattern`, but a glob is never a valid regex.
+        let (query, regex) = match field(&["regex", "pattern"]) {
+            Some(pattern) if !pattern.starts_with('*') => (pattern, true),
+            _ => (field(&["query"])?, false),
+        };
+        Some(Self {
+            query,
+            regex,
+            included_files: field(&["include_pattern", "include", "glob"]),
+        })
+    }
+}
+
+fn loading_contents_spinner(size: IconSize) -> AnyElement {
+    Icon::new(IconName::LoadCircle)
+        .size(size)
+        .color(Color::Accent)
+        .with_rotate_animation(3)
+        .into_any_element()
+}
+
+impl Focusable for AcpThreadView {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        match self.thread_state {
+            ThreadState::NotConnected | ThreadState::Loading { .. } | ThreadState::Ready { .. } => {
+                self.active_editor(cx).focus_handle(cx)
+            }
+            ThreadState::LoadError(_) | ThreadState::Unauthenticated { .. } => {
+                self.focus_handle.clone()
+            }
+        }
+    }
+}
+
+impl Render for AcpThreadView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_messages = self.list_state.item_count() > 0;
+        let line_height = TextSize::Small.rems(cx).to_pixels(window.rem_size()) * 1.5;
+
+        v_flex()
+            .size_full()
+            .key_context("AcpThread")
+            .on_action(cx.listener(Self::toggle_burn_mode))
+            .on_action(cx.listener(Self::toggle_plan_mode))
+            .on_action(cx.listener(Self::toggle_auto_accept_edits))
+            .on_action(cx.listener(Self::keep_all))
+            .on_action(cx.listener(Self::reject_all))
+            .on_action(cx.listener(Self::allow_always))
+            .on_action(cx.listener(Self::allow_once))
+            .on_action(cx.listener(Self::reject_once))
+            .on_action(cx.listener(Self::select_permission_option))
+            .on_action(cx.listener(|this, _: &CancelGeneration, _, cx| {
+                this.cancel_generation(cx);
+            }))
+            .on_action(cx.listener(Self::select_next_entry))
+            .on_action(cx.listener(Self::select_previous_entry))
+            .on_action(cx.listener(Self::toggle_thinking))
+            .on_action(cx.listener(Self::expand_all_entries))
+            .on_action(cx.listener(Self::collapse_all_entries))
+            .track_focus(&self.focus_handle)
+            .bg(cx.theme().colors().panel_background)
+            .children(self.render_working_directory(cx))
+            .child(match &self.thread_state {
+                ThreadState::Unauthenticated {
+                    connection,
+                    description,
+                    configuration_view,
+                    pending_auth_method,
+                    ..
+                } => self
+                    .render_auth_required_state(
+                        connection,
+                        description.as_ref(),
+                        configuration_view.as_ref(),
+                        pending_auth_method.as_ref(),
+                        window,
+                        cx,
+                    )
+                    .into_any(),
+                ThreadState::NotConnected => v_flex()
+                    .flex_1()
+                    .child(self.render_recent_history(cx))
+                    .child(self.render_not_connected(cx))
+                    .into_any(),
+                ThreadState::Loading { .. } => v_flex()
+                    .flex_1()
+                    .child(self.render_recent_history(cx))
+                    .into_any(),
+                ThreadState::LoadError(e) => v_flex()
+                    .flex_1()
+                    .size_full()
+                    .items_center()
+                    .justify_end()
+                    .child(self.render_load_error(e, window, cx))
+                    .into_any(),
+                ThreadState::Ready { .. } => v_flex().flex_1().map(|this| {
+                    if let Some(editor) = &self.transcript_selection {
+                        this.child(self.render_transcript_selection(editor, cx))
+                            .into_any()
+                    } else if has_messages {
+                        this.child(
+                            list(
+                                self.list_state.clone(),
+                                cx.processor(|this, index: usize, window, cx| {
+                                    if index < this.first_live_entry {
+                                        return Empty.into_any();
+                                    }
+                                    let Some((entry, len)) = this.thread().and_then(|thread| {
+                                        let entries = &thread.read(cx).entries();
+                                        Some((entries.get(index)?, entries.len()))
+                                    }) else {
+                                        return Empty.into_any();
+                                    };
+                                    let entry = this.render_entry(index, len, entry, window, cx);
+                                    if index > 0 && index == this.first_live_entry {
+                                        v_flex()
+                                            .child(this.render_load_earlier_entries(cx))
+                                            .child(entry)
+                                            .into_any()
+                                    } else {
+                                        entry
+                                    }
+                                }),
+                            )
+                            .with_sizing_behavior(gpui::ListSizingBehavior::Auto)
+                            .flex_grow()
+                            .into_any(),
+                        )
+                        .vertical_scrollbar_for(self.list_state.clone(), window, cx)
+                        .into_any()
+                    } else {
+                        this.child(self.render_recent_history(cx)).into_any()
+                    }
+                }),
+            })
+            // The activity bar is intentionally rendered outside of the ThreadState::Ready match
+            // above so that the scrollbar doesn't render behind it. The current setup allows
+            // the scrollbar to stop exactly at the activity bar start.
+            .when(has_messages, |this| match &self.thread_state {
+                ThreadState::Ready { thread, .. } => {
+                    this.children(self.render_activity_bar(thread, window, cx))
+                }
+                _ => this,
+            })
+            .children(self.render_thread_retry_status_callout(window, cx))
+            .children(self.render_auto_accept_edits_callout(cx))
+            .children({
+                #[cfg(target_os = "windows")]
+                {
+                    self.render_codex_windows_warning(cx)
+                }
+                #[cfg(not(target_os = "windows"))]
+                {
+                    Vec::<Empty>::new()
+                }
+            })
+            .children(self.render_agent_memory_warning(cx))
+            .children(self.render_thread_error(cx))
+            .when_some(
+                self.new_server_version_available.as_ref().filter(|_| {
+                    !has_messages || !matches!(self.thread_state, ThreadState::Ready { .. })
+                }),
+                |this, version| this.child(self.render_new_version_callout(&version, cx)),
+            )
+            .children(
+                if let Some(usage_callout) = self.render_usage_callout(line_height, cx) {
+                    Some(usage_callout.into_any_element())
+                } else {
+                    self.render_token_limit_callout(line_height, cx)
+                        .map(|token_limit_callout| token_limit_callout.into_any_element())
+                },
+            )
+            .child(self.render_message_editor(window, cx))
+    }
+}
+
+fn default_markdown_style(
+    buffer_font: bool,
+    muted_text: bool,
+    window: &Window,
+    cx: &App,
+) -> MarkdownStyle {
+    let theme_settings = ThemeSettings::get_global(cx);
+    let colors = cx.theme().colors();
+
+    let buffer_font_size = TextSize::Small.rems(cx);
+
+    let mut text_style = window.text_style();
+    let line_height = buffer_font_size * 1.75;
+
+    let font_family = if buffer_font {
+        theme_settings.buffer_font.family.clone()
+    } else {
+        theme_settings.ui_font.family.clone()
+    };
+
+    let font_size = if buffer_font {
+        TextSize::Small.rems(cx)
+    } else {
+        TextSize::Default.rems(cx)
+    };
+
+    let text_color = if muted_text {
+        colors.text_muted
+    } else {
+        colors.text
+    };
+
+    text_style.refine(&TextStyleRefinement {
+        font_family: Some(font_family),
+        font_fallbacks: theme_settings.ui_font.fallbacks.clone(),
+        font_features: Some(theme_settings.ui_font.features.clone()),
+        font_size: Some(font_size.into()),
+        line_height: Some(line_height.into()),
+        color: Some(text_color),
+        ..Default::default()
+    });
+
+    MarkdownStyle {
+        base_text_style: text_style.clone(),
+        syntax: cx.theme().syntax().clone(),
+        selection_background_color: colors.element_selection_background,
+        code_block_overflow_x_scroll: true,
+        table_overflow_x_scroll: true,
+        heading_level_styles: Some(HeadingLevelStyles {
+            h1: Some(TextStyleRefinement {
+                font_size: Some(rems(1.15).into()),
+                ..Default::default()
+            }),
+            h2: Some(TextStyleRefinement {
+                font_size: Some(rems(1.1).into()),
+                ..Default::default()
+            }),
+            h3: Some(TextStyleRefinement {
+                font_size: Some(rems(1.05).into()),
+                ..Default::default()
+            }),
+            h4: Some(TextStyleRefinement {
+                font_size: Some(rems(1.).into()),
+                ..Default::default()
+            }),
+            h5: Some(TextStyleRefinement {
+                font_size: Some(rems(0.95).into()),
+                ..Default::default()
+            }),
+            h6: Some(TextStyleRefinement {
+                font_size: Some(rems(0.875).into()),
+                ..Default::default()
+            }),
+        }),
+        code_block: StyleRefinement {
+            padding: EdgesRefinement {
+                top: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
+                left: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
+                right: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
+                bottom: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
+            },
+            margin: EdgesRefinement {
+                top: Some(Length::Definite(px(8.).into())),
+                left: Some(Length::Definite(px(0.).into())),
+                right: Some(Length::Definite(px(0.).into())),
+                bottom: Some(Length::Definite(px(12.).into())),
+            },
+            border_style: Some(BorderStyle::Solid),
+            border_widths: EdgesRefinement {
+                top: Some(AbsoluteLength::Pixels(px(1.))),
+                left: Some(AbsoluteLength::Pixels(px(1.))),
+                right: Some(AbsoluteLength::Pixels(px(1.))),
+                bottom: Some(AbsoluteLength::Pixels(px(1.))),
+            },
+            border_color: Some(colors.border_variant),
+            background: Some(colors.editor_background.into()),
+            text: Some(TextStyleRefinement {
+                font_family: Some(theme_settings.buffer_font.family.clone()),
+                font_fallbacks: theme_settings.buffer_font.fallbacks.clone(),
+                font_features: Some(theme_settings.buffer_font.features.clone()),
+                font_size: Some(buffer_font_size.into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        inline_code: TextStyleRefinement {
+            font_family: Some(theme_settings.buffer_font.family.clone()),
+            font_fallbacks: theme_settings.buffer_font.fallbacks.clone(),
+            font_features: Some(theme_settings.buffer_font.features.clone()),
+            font_size: Some(buffer_font_size.into()),
+            background_color: Some(colors.editor_foreground.opacity(0.08)),
+            ..Default::default()
+        },
+        link: TextStyleRefinement {
+            background_color: Some(colors.editor_foreground.opacity(0.025)),
+            underline: Some(UnderlineStyle {
+                color: Some(colors.text_accent.opacity(0.5)),
+                thickness: px(1.),
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn plan_label_markdown_style(
+    status: &acp::PlanEntryStatus,
+    window: &Window,
+    cx: &App,
+) -> MarkdownStyle {
+    let default_md_style = default_markdown_style(false, false, window, cx);
+
+    MarkdownStyle {
+        base_text_style: TextStyle {
+            color: cx.theme().colors().text_muted,
+            strikethrough: if matches!(status, acp::PlanEntryStatus::Completed) {
+                Some(gpui::StrikethroughStyle {
+                    thickness: px(1.),
+                    color: Some(cx.theme().colors().text_muted.opacity(0.8)),
+                })
+            } else {
+                None
+            },
+            ..default_md_style.base_text_style
+        },
+        ..default_md_style
+    }
+}
+
+fn terminal_command_markdown_style(soft_wrap: bool, window: &Window, cx: &App) -> MarkdownStyle {
+    let default_md_style = default_markdown_style(true, false, window, cx);
+
+    MarkdownStyle {
+        base_text_style: TextStyle {
+            white_space: if soft_wrap {
+                WhiteSpace::Normal
+            } else {
+                WhiteSpace::Nowrap
+            },
+            ..default_md_style.base_text_style
+        },
+        code_block_overflow_x_scroll: !soft_wrap,
+        selection_background_color: cx.theme().colors().element_selection_background,
+        ..Default::default()
+    }
+}
+
+/// How long a tool call of this kind waits for confirmation before it's allowed.
+fn auto_approve_countdown(
+    kind: acp::ToolKind,
+    countdowns: &agent_settings::AutoApproveCountdown,
+) -> Duration {
+    match kind {
+        acp::ToolKind::Edit | acp::ToolKind::Delete | acp::ToolKind::Move => countdowns.edits,
+        acp::ToolKind::Execute => countdowns.executes,
+        acp::ToolKind::Fetch => countdowns.fetches,
+        // Switching modes changes how the rest of the turn runs, so it always waits.
+        acp::ToolKind::SwitchMode => Duration::ZERO,
+        _ => countdowns.others,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use acp_thread::StubAgentConnection;
+    use agent_client_protocol::SessionId;
+    use assistant_text_thread::TextThreadStore;
+    use editor::EditorSettings;
+    use fs::FakeFs;
+    use gpui::{EventEmitter, SemanticVersion, TestAppContext, VisualTestContext};
+    use project::Project;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::any::Any;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use util::rel_path::rel_path;
+    use workspace::Item;
+
+    use super::*;
+
+    #[test]
+    fn test_search_tool_query_from_raw_input() {
+        assert_eq!(
+            SearchToolQuery::from_raw_input(&json!({
+                "regex": "fn \\w+",
+                "include_pattern": "src/**/*.rs"
+            })),
+            Some(SearchToolQuery {
+                query: "fn \\w+".into(),
+                regex: true,
+                included_files: Some("src/**/*.rs".into()),
+            })
+        );
+        assert_eq!(
+            SearchToolQuery::from_raw_input(&json!({ "query": "TODO" })),
+            Some(SearchToolQuery {
+                query: "TODO".into(),
+                regex: false,
+                included_files: None,
+            })
+        );
+        assert_eq!(
+            SearchToolQuery::from_raw_input(&json!({ "pattern": "**/*.rs" })),
+            None
+        );
+    }
+
+    #[gpui::test]
+    fn test_message_branch_positions(cx: &mut TestAppContext) {
+        let transcript = |cx: &mut TestAppContext| {
+            cx.new(|cx| Markdown::new("transcript".into(), None, None, cx))
+        };
+        let mut branches = MessageBranches {
+            archived: vec![transcript(cx), transcript(cx)],
+            viewing: None,
+        };
+        assert_eq!((branches.position(), branches.len()), (3, 3));
+
+        branches.select(1);
+        assert_eq!((branches.viewing, branches.position()), (Some(0), 1));
+        branches.select(2);
+        assert_eq!((branches.viewing, branches.position()), (Some(1), 2));
+        branches.select(3);
+        assert_eq!((branches.viewing, branches.position()), (None, 3));
+    }
+
+    #[test]
+    fn test_auto_approve_countdown() {
+        let countdowns = agent_settings::AutoApproveCountdown {
+            edits: Duration::from_secs(10),
+            executes: Duration::ZERO,
+            fetches: Duration::from_secs(5),
+            others: Duration::from_secs(3),
+        };
+        assert_eq!(
+            auto_approve_countdown(acp::ToolKind::Delete, &countdowns),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            auto_approve_countdown(acp::ToolKind::Execute, &countdowns),
+            Duration::ZERO
+        );
+        assert_eq!(
+            auto_approve_countdown(acp::ToolKind::Read, &countdowns),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            auto_approve_countdown(acp::ToolKind::SwitchMode, &countdowns),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_file_change_kind_then() {
+        use FileChangeKind::*;
+        assert_eq!(Created.then(Modified), Some(Created));
+        assert_eq!(Created.then(Deleted), None);
+        assert_eq!(Modified.then(Deleted), Some(Deleted));
+        assert_eq!(Deleted.then(Created), Some(Modified));
+        assert_eq!(Modified.then(Modified), Some(Modified));
+    }
+
+    #[test]
+    fn test_user_message_attachments() {
+        let file = MentionUri::File {
+            abs_path: PathBuf::from("/project/main.rs"),
+        };
+        let link = |uri: &MentionUri| {
+            acp::ContentBlock::ResourceLink(acp::ResourceLink {
+                uri: uri.to_uri().to_string(),
+                name: uri.name(),
+                annotations: None,
+                description: None,
+                mime_type: None,
+                size: None,
+                title: None,
+                meta: None,
+            })
+        };
+        let chunks = vec![
+            acp::ContentBlock::Text(acp::TextContent {
+                text: "Look at ".into(),
+                annotations: None,
+                meta: None,
+            }),
+            link(&file),
+            link(&file),
+            acp::ContentBlock::Image(acp::ImageContent {
+                data: String::new(),
+                mime_type: "image/png".into(),
+                uri: None,
+                annotations: None,
+                meta: None,
+            }),
+        ];
+        assert_eq!(
+            user_message_attachments(&chunks),
+            vec![file, MentionUri::PastedImage]
+        );
+    }
+
+    #[test]
+    fn test_attachment_content() {
+        let file = MentionUri::File {
+            abs_path: PathBuf::from("/project/main.rs"),
+        };
+        let other = MentionUri::File {
+            abs_path: PathBuf::from("/project/lib.rs"),
+        };
+        let chunks = vec![acp::ContentBlock::Resource(acp::EmbeddedResource {
+            resource: acp::EmbeddedResourceResource::TextResourceContents(
+                acp::TextResourceContents {
+                    uri: file.to_uri().to_string(),
+                    mime_type: None,
+                    text: "fn main() {}".into(),
+                    meta: None,
+                },
+            ),
+            annotations: None,
+            meta: None,
+        })];
+        assert_eq!(user_message_attachments(&chunks), vec![file.clone()]);
+        assert_eq!(attachment_content(&chunks, &file), Some("fn main() {}"));
+        assert_eq!(attachment_content(&chunks, &other), None);
+    }
+    #[test]
+    fn test_quote_lines() {
+        assert_eq!(quote_lines("\n  \n"), None);
+        assert_eq!(
+            quote_lines("First line\n\nSecond line  "),
+            Some("> First line\n> Second line".to_string())
+        );
+
+        let text = (1..=FOLLOW_UP_QUOTE_LINES + 2)
+            .map(|ix| format!("Line {ix}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let quote = quote_lines(&text).unwrap();
+        assert!(quote.starts_with("> Line 1\n"));
+        assert!(quote.contains(&format!("> Line {FOLLOW_UP_QUOTE_LINES}\n")));
+        assert!(!quote.contains(&format!("Line {}", FOLLOW_UP_QUOTE_LINES + 1)));
+        assert!(quote.ends_with("\n> …"));
+    }
+
+    #[test]
+    fn test_parse_json_output() {
+        assert_eq!(
+            parse_json_output("```json\n{\"a\": [1, 2]}\n```"),
+            Some(serde_json::json!({"a": [1, 2]}))
+        );
+        assert_eq!(
+            parse_json_output(" [true] "),
+            Some(serde_json::json!([true]))
+        );
+        assert_eq!(parse_json_output("{}"), None);
+        assert_eq!(parse_json_output("42"), None);
+        assert_eq!(parse_json_output("{ not json"), None);
+
+        assert_eq!(json_member_path("$", "name"), "$.name");
+        assert_eq!(json_member_path("$.a[0]", "b_2"), "$.a[0].b_2");
+        assert_eq!(json_member_path("$", "two words"), "$[\"two words\"]");
+        assert_eq!(json_member_path("$", "1st"), "$[\"1st\"]");
+    }
+
+    #[test]
+    fn test_format_turn_duration() {
+        assert_eq!(format_turn_duration(Duration::from_millis(1300)), "1.3s");
+        assert_eq!(format_turn_duration(Duration::from_secs(59)), "59.0s");
+        assert_eq!(format_turn_duration(Duration::from_secs(125)), "2m 5s");
+    }
+
+    #[gpui::test]
+    async fn test_drop(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, _cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+        let weak_view = thread_view.downgrade();
+        drop(thread_view);
+        assert!(!weak_view.is_upgradable());
+    }
+
+    #[gpui::test]
+    async fn test_notification_for_stop_event(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+
+        cx.deactivate_window();
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+
+        cx.run_until_parked();
+
+        assert!(
+            cx.windows()
+                .iter()
+                .any(|window| window.downcast::<AgentNotification>().is_some())
+        );
+    }
+
+    #[gpui::test]
+    async fn test_send_prompt_keeps_draft(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Half-written draft", window, cx);
+        });
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send_prompt("Quick question", window, cx);
+        });
+        cx.run_until_parked();
+
+        thread_view.read_with(cx, |thread_view, cx| {
+            let thread = thread_view.thread().unwrap().read(cx);
+            let Some(AgentThreadEntry::UserMessage(message)) = thread.entries().first() else {
+                panic!("expected a user message");
+            };
+            assert_eq!(message.content.to_markdown(cx), "Quick question");
+        });
+        message_editor.read_with(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "Half-written draft");
+        });
+    }
+
+    #[gpui::test]
+    async fn test_notification_for_error(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(SaboteurAgentConnection), cx).await;
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+
+        cx.deactivate_window();
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+
+        cx.run_until_parked();
+
+        assert!(
+            cx.windows()
+                .iter()
+                .any(|window| window.downcast::<AgentNotification>().is_some())
+        );
+    }
+
+    #[gpui::test]
+    async fn test_refusal_handling(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(RefusalAgentConnection), cx).await;
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Do something harmful", window, cx);
+        });
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+
+        cx.run_until_parked();
+
+        // Check that the refusal error is set
+        thread_view.read_with(cx, |thread_view, _cx| {
+            assert!(
+                matches!(thread_view.thread_error, Some(ThreadError::Refusal)),
+                "Expected refusal error to be set"
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_notification_for_tool_authorization(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let tool_call_id = acp::ToolCallId("1".into());
+        let tool_call = acp::ToolCall {
+            id: tool_call_id.clone(),
+            title: "Label".into(),
+            kind: acp::ToolKind::Edit,
+            status: acp::ToolCallStatus::Pending,
+            content: vec!["hi".into()],
+            locations: vec![],
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        };
+        let connection =
+            StubAgentConnection::new().with_permission_requests(HashMap::from_iter([(
+                tool_call_id,
+                vec![acp::PermissionOption {
+                    id: acp::PermissionOptionId("1".into()),
+                    name: "Allow".into(),
+                    kind: acp::PermissionOptionKind::AllowOnce,
+                    meta: None,
+                }],
+            )]));
+
+        connection.set_next_prompt_updates(vec![acp::SessionUpdate::ToolCall(tool_call)]);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::new(connection), cx).await;
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+
+        cx.deactivate_window();
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+
+        cx.run_until_parked();
+
+        assert!(
+            cx.windows()
+                .iter()
+                .any(|window| window.downcast::<AgentNotification>().is_some())
+        );
+    }
+
+    #[gpui::test]
+    async fn test_authentication_flow(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let connection = AuthGatedAgentConnection::new(StubAgentConnection::new());
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(connection.clone()), cx).await;
+
+        thread_view.read_with(cx, |thread_view, _| {
+            assert!(matches!(
+                thread_view.thread_state,
+                ThreadState::Unauthenticated { .. }
+            ));
+        });
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.authenticate(acp::AuthMethodId("test-login".into()), window, cx);
+        });
+        cx.run_until_parked();
+
+        assert!(connection.is_authenticated());
+        thread_view.read_with(cx, |thread_view, _| {
+            assert!(matches!(
+                thread_view.thread_state,
+                ThreadState::Ready { .. }
+            ));
+            assert!(thread_view.thread_error.is_none());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_tool_confirmation_flow(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let tool_call_id = acp::ToolCallId("1".into());
+        let connection =
+            StubAgentConnection::new().with_permission_requests(HashMap::from_iter([(
+                tool_call_id.clone(),
+                vec![
+                    acp::PermissionOption {
+                        id: acp::PermissionOptionId("allow".into()),
+                        name: "Allow".into(),
+                        kind: acp::PermissionOptionKind::AllowOnce,
+                        meta: None,
+                    },
+                    acp::PermissionOption {
+                        id: acp::PermissionOptionId("reject".into()),
+                        name: "Reject".into(),
+                        kind: acp::PermissionOptionKind::RejectOnce,
+                        meta: None,
+                    },
+                ],
+            )]));
+        connection.set_next_prompt_updates(vec![
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: tool_call_id.clone(),
+                title: "Run `make`".into(),
+                kind: acp::ToolKind::Execute,
+                status: acp::ToolCallStatus::InProgress,
+                content: vec![],
+                locations: vec![],
+                raw_input: None,
+                raw_output: None,
+                meta: None,
+            }),
+            acp::SessionUpdate::AgentMessageChunk {
+                content: "Done".into(),
+            },
+        ]);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::new(connection), cx).await;
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Build it", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        let thread = thread_view.read_with(cx, |view, _| view.thread().cloned().unwrap());
+        thread.read_with(cx, |thread, _| {
+            assert_eq!(thread.status(), ThreadStatus::Generating);
+            let tool_call = find_tool_call(thread, &tool_call_id);
+            assert!(matches!(
+                tool_call.status,
+                ToolCallStatus::WaitingForConfirmation { .. }
+            ));
+        });
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.authorize_tool_call(
+                tool_call_id.clone(),
+                acp::PermissionOptionId("allow".into()),
+                acp::PermissionOptionKind::AllowOnce,
+                window,
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        thread.read_with(cx, |thread, cx| {
+            assert_eq!(thread.status(), ThreadStatus::Idle);
+            let tool_call = find_tool_call(thread, &tool_call_id);
+            assert!(!matches!(
+                tool_call.status,
+                ToolCallStatus::WaitingForConfirmation { .. } | ToolCallStatus::Rejected
+            ));
+            assert!(thread.to_markdown(cx).ends_with("Done\n\n"));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_select_permission_option(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let tool_call_id = acp::ToolCallId("1".into());
+        let connection =
+            StubAgentConnection::new().with_permission_requests(HashMap::from_iter([(
+                tool_call_id.clone(),
+                vec![
+                    acp::PermissionOption {
+                        id: acp::PermissionOptionId("allow".into()),
+                        name: "Allow".into(),
+                        kind: acp::PermissionOptionKind::AllowOnce,
+                        meta: None,
+                    },
+                    acp::PermissionOption {
+                        id: acp::PermissionOptionId("reject".into()),
+                        name: "Reject".into(),
+                        kind: acp::PermissionOptionKind::RejectOnce,
+                        meta: None,
+                    },
+                ],
+            )]));
+        connection.set_next_prompt_updates(vec![acp::SessionUpdate::ToolCall(acp::ToolCall {
+            id: tool_call_id.clone(),
+            title: "Run `make`".into(),
+            kind: acp::ToolKind::Execute,
+            status: acp::ToolCallStatus::InProgress,
+            content: vec![],
+            locations: vec![],
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        })]);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::new(connection), cx).await;
+        add_to_workspace(thread_view.clone(), cx);
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        cx.focus(&message_editor);
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Build it", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        // The confirmation takes the focus from the empty message editor.
+        thread_view.update_in(cx, |thread_view, window, _| {
+            assert!(thread_view.confirmation_focus_handle.is_focused(window));
+        });
+
+        thread_view.update_in(cx, |_, window, cx| {
+            window.dispatch_action(Box::new(SelectPermissionOption(1)), cx);
+        });
+        cx.run_until_parked();
+
+        let thread = thread_view.read_with(cx, |view, _| view.thread().cloned().unwrap());
+        thread.read_with(cx, |thread, _| {
+            let tool_call = find_tool_call(thread, &tool_call_id);
+            assert!(matches!(tool_call.status, ToolCallStatus::Rejected));
+        });
+        message_editor.update_in(cx, |editor, window, cx| {
+            assert!(editor.focus_handle(cx).is_focused(window));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_prompt_error_is_shown(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(SaboteurAgentConnection), cx).await;
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        thread_view.read_with(cx, |thread_view, cx| {
+            assert!(matches!(
+                &thread_view.thread_error,
+                Some(ThreadError::Other(message)) if message.as_ref() == "Error prompting"
+            ));
+            assert_eq!(
+                thread_view.thread().unwrap().read(cx).status(),
+                ThreadStatus::Idle
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_message_restored_after_send_failure(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(SaboteurAgentConnection), cx).await;
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        message_editor.read_with(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "Hello");
+        });
+    }
+
+    #[gpui::test]
+    async fn test_external_thread_is_saved_to_history(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Hello", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        let (history_store, session_id) = thread_view.read_with(cx, |thread_view, cx| {
+            (
+                thread_view.history_store.clone(),
+                thread_view.thread().unwrap().read(cx).session_id().clone(),
+            )
+        });
+        history_store.read_with(cx, |history_store, _| {
+            assert!(history_store.thread_from_session_id(&session_id).is_some());
+        });
+
+        let db_thread = history_store
+            .update(cx, |history_store, cx| {
+                history_store.load_thread(session_id, cx)
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(db_thread.messages.len(), 2);
+        assert_eq!(
+            db_thread.messages[1].as_agent_message().unwrap().content,
+            vec![agent::AgentMessageContent::Text("Default response".into())]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_branch_change(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                ".git": {},
+                "main.rs": "fn main() {}",
+            }),
+        )
+        .await;
+        fs.set_branch_name(Path::new("/project/.git"), Some("main"));
+        let project = Project::test(fs.clone(), [Path::new("/project")], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+
+        let text_thread_store =
+            cx.update(|_window, cx| cx.new(|cx| TextThreadStore::fake(project.clone(), cx)));
+        let history_store =
+            cx.update(|_window, cx| cx.new(|cx| HistoryStore::new(text_thread_store, cx)));
+
+        let thread_view = cx.update(|window, cx| {
+            cx.new(|cx| {
+                AcpThreadView::new(
+                    Rc::new(StubAgentServer::default_response()),
+                    None,
+                    None,
+                    workspace.downgrade(),
+                    project.clone(),
+                    history_store,
+                    None,
+                    window,
+                    cx,
+                )
+            })
+        });
+        cx.run_until_parked();
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        let send = |text: &str, cx: &mut VisualTestContext| {
+            message_editor.update_in(cx, |editor, window, cx| {
+                editor.set_text(text, window, cx);
+            });
+            thread_view.update_in(cx, |thread_view, window, cx| {
+                thread_view.send(window, cx);
+            });
+            cx.run_until_parked();
+        };
+
+        // Switching the branch before the first message has nothing to warn about.
+        fs.set_branch_name(Path::new("/project/.git"), Some("develop"));
+        cx.run_until_parked();
+        thread_view.read_with(cx, |thread_view, _| {
+            assert_eq!(thread_view.branch.as_deref(), Some("develop"));
+            assert!(thread_view.branch_changes.is_empty());
+        });
+
+        send("Hello", cx);
+        fs.set_branch_name(Path::new("/project/.git"), Some("feature-x"));
+        cx.run_until_parked();
+        thread_view.read_with(cx, |thread_view, cx| {
+            let thread = thread_view.thread().unwrap().read(cx);
+            let message_id = thread.entries()[0]
+                .user_message()
+                .unwrap()
+                .id
+                .clone()
+                .unwrap();
+            let [change] = thread_view.branch_changes.as_slice() else {
+                panic!("expected a single branch change");
+            };
+            assert_eq!(change.anchor, BranchChangeAnchor::Turn(message_id));
+            assert_eq!(change.from.as_ref(), "develop");
+            assert_eq!(change.to.as_ref(), "feature-x");
+        });
+
+        send("Continue", cx);
+        thread_view.read_with(cx, |thread_view, cx| {
+            let thread = thread_view.thread().unwrap().read(cx);
+            let Some(AgentThreadEntry::UserMessage(message)) = thread
+                .entries()
+                .iter()
+                .rfind(|entry| matches!(entry, AgentThreadEntry::UserMessage(_)))
+            else {
+                panic!("expected a user message");
+            };
+            assert!(
+                attachment_content(&message.chunks, &MentionUri::WorkspaceState)
+                    .unwrap()
+                    .contains(
+                        "The git branch was switched from develop to feature-x since your last turn."
+                    )
+            );
+            assert!(!message.content.to_markdown(cx).contains("git branch"));
+            assert!(thread_view.unsent_branch_change.is_none());
+        });
     }
-}
 
-fn default_markdown_style(
-    buffer_font: bool,
-    muted_text: bool,
-    window: &Window,
-    cx: &App,
-) -> MarkdownStyle {
-    let theme_settings = ThemeSettings::get_global(cx);
-    let colors = cx.theme().colors();
+    #[gpui::test]
+    async fn test_file_changes_are_sent_to_agent(cx: &mut TestAppContext) {
+        init_test(cx);
 
-    let buffer_font_size = TextSize::Small.rems(cx);
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                "a.txt": "a",
+                "b.txt": "b",
+            }),
+        )
+        .await;
+        let project = Project::test(fs.clone(), [Path::new("/project")], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
 
-    let mut text_style = window.text_style();
-    let line_height = buffer_font_size * 1.75;
+        let text_thread_store =
+            cx.update(|_window, cx| cx.new(|cx| TextThreadStore::fake(project.clone(), cx)));
+        let history_store =
+            cx.update(|_window, cx| cx.new(|cx| HistoryStore::new(text_thread_store, cx)));
 
-    let font_family = if buffer_font {
-        theme_settings.buffer_font.family.clone()
-    } else {
-        theme_settings.ui_font.family.clone()
-    };
+        let connection = StubAgentConnection::new();
+        let _thread_view = cx.update(|window, cx| {
+            cx.new(|cx| {
+                AcpThreadView::new(
+                    Rc::new(StubAgentServer::new(connection.clone())),
+                    None,
+                    None,
+                    workspace.downgrade(),
+                    project.clone(),
+                    history_store,
+                    None,
+                    window,
+                    cx,
+                )
+            })
+        });
+        cx.run_until_parked();
 
-    let font_size = if buffer_font {
-        TextSize::Small.rems(cx)
-    } else {
-        TextSize::Default.rems(cx)
-    };
+        fs.insert_file("/project/a.txt", b"changed".to_vec()).await;
+        fs.insert_file("/project/c.txt", b"c".to_vec()).await;
+        cx.run_until_parked();
+        // Changes are held back until files stop changing.
+        assert!(connection.notified_file_changes().is_empty());
 
-    let text_color = if muted_text {
-        colors.text_muted
-    } else {
-        colors.text
-    };
+        cx.executor().advance_clock(FILE_CHANGES_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(
+            connection.notified_file_changes(),
+            vec![
+                PathBuf::from("/project/a.txt"),
+                PathBuf::from("/project/c.txt"),
+            ]
+        );
 
-    text_style.refine(&TextStyleRefinement {
-        font_family: Some(font_family),
-        font_fallbacks: theme_settings.ui_font.fallbacks.clone(),
-        font_features: Some(theme_settings.ui_font.features.clone()),
-        font_size: Some(font_size.into()),
-        line_height: Some(line_height.into()),
-        color: Some(text_color),
-        ..Default::default()
-    });
+        cx.update(|_window, cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings
+                        .agent
+                        .get_or_insert_default()
+                        .notify_agent_of_file_changes = Some(false);
+                });
+            });
+        });
+        fs.insert_file("/project/b.txt", b"changed".to_vec()).await;
+        cx.run_until_parked();
+        cx.executor().advance_clock(FILE_CHANGES_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(connection.notified_file_changes().len(), 2);
+    }
 
-    MarkdownStyle {
-        base_text_style: text_style.clone(),
-        syntax: cx.theme().syntax().clone(),
-        selection_background_color: colors.element_selection_background,
-        code_block_overflow_x_scroll: true,
-        table_overflow_x_scroll: true,
-        heading_level_styles: Some(HeadingLevelStyles {
-            h1: Some(TextStyleRefinement {
-                font_size: Some(rems(1.15).into()),
-                ..Default::default()
-            }),
-            h2: Some(TextStyleRefinement {
-                font_size: Some(rems(1.1).into()),
-                ..Default::default()
-            }),
-            h3: Some(TextStyleRefinement {
-                font_size: Some(rems(1.05).into()),
-                ..Default::default()
-            }),
-            h4: Some(TextStyleRefinement {
-                font_size: Some(rems(1.).into()),
-                ..Default::default()
-            }),
-            h5: Some(TextStyleRefinement {
-                font_size: Some(rems(0.95).into()),
-                ..Default::default()
-            }),
-            h6: Some(TextStyleRefinement {
-                font_size: Some(rems(0.875).into()),
-                ..Default::default()
-            }),
-        }),
-        code_block: StyleRefinement {
-            padding: EdgesRefinement {
-                top: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
-                left: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
-                right: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
-                bottom: Some(DefiniteLength::Absolute(AbsoluteLength::Pixels(px(8.)))),
-            },
-            margin: EdgesRefinement {
-                top: Some(Length::Definite(px(8.).into())),
-                left: Some(Length::Definite(px(0.).into())),
-                right: Some(Length::Definite(px(0.).into())),
-                bottom: Some(Length::Definite(px(12.).into())),
+    #[gpui::test]
+    async fn test_turn_phases(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let tool_call = |id: &str, kind| {
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: acp::ToolCallId(id.into()),
+                title: id.to_string(),
+                kind,
+                status: acp::ToolCallStatus::Completed,
+                content: vec![],
+                locations: vec![],
+                raw_input: None,
+                raw_output: None,
+                meta: None,
+            })
+        };
+        let connection = StubAgentConnection::new();
+        connection.set_next_prompt_updates(vec![
+            acp::SessionUpdate::AgentThoughtChunk {
+                content: "Where is the bug?".into(),
             },
-            border_style: Some(BorderStyle::Solid),
-            border_widths: EdgesRefinement {
-                top: Some(AbsoluteLength::Pixels(px(1.))),
-                left: Some(AbsoluteLength::Pixels(px(1.))),
-                right: Some(AbsoluteLength::Pixels(px(1.))),
-                bottom: Some(AbsoluteLength::Pixels(px(1.))),
+            tool_call("read", acp::ToolKind::Read),
+            tool_call("search", acp::ToolKind::Search),
+            acp::SessionUpdate::AgentMessageChunk {
+                content: "Found it.".into(),
             },
-            border_color: Some(colors.border_variant),
-            background: Some(colors.editor_background.into()),
-            text: Some(TextStyleRefinement {
-                font_family: Some(theme_settings.buffer_font.family.clone()),
-                font_fallbacks: theme_settings.buffer_font.fallbacks.clone(),
-                font_features: Some(theme_settings.buffer_font.features.clone()),
-                font_size: Some(buffer_font_size.into()),
-                ..Default::default()
-            }),
-            ..Default::default()
-        },
-        inline_code: TextStyleRefinement {
-            font_family: Some(theme_settings.buffer_font.family.clone()),
-            font_fallbacks: theme_settings.buffer_font.fallbacks.clone(),
-            font_features: Some(theme_settings.buffer_font.features.clone()),
-            font_size: Some(buffer_font_size.into()),
-            background_color: Some(colors.editor_foreground.opacity(0.08)),
-            ..Default::default()
-        },
-        link: TextStyleRefinement {
-            background_color: Some(colors.editor_foreground.opacity(0.025)),
-            underline: Some(UnderlineStyle {
-                color: Some(colors.text_accent.opacity(0.5)),
-                thickness: px(1.),
-                ..Default::default()
-            }),
-            ..Default::default()
-        },
-        ..Default::default()
-    }
-}
+            tool_call("edit-1", acp::ToolKind::Edit),
+            tool_call("edit-2", acp::ToolKind::Edit),
+            tool_call("test", acp::ToolKind::Execute),
+        ]);
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(connection.clone()), cx).await;
 
-fn plan_label_markdown_style(
-    status: &acp::PlanEntryStatus,
-    window: &Window,
-    cx: &App,
-) -> MarkdownStyle {
-    let default_md_style = default_markdown_style(false, false, window, cx);
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Fix the bug", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
 
-    MarkdownStyle {
-        base_text_style: TextStyle {
-            color: cx.theme().colors().text_muted,
-            strikethrough: if matches!(status, acp::PlanEntryStatus::Completed) {
-                Some(gpui::StrikethroughStyle {
-                    thickness: px(1.),
-                    color: Some(cx.theme().colors().text_muted.opacity(0.8)),
-                })
-            } else {
-                None
-            },
-            ..default_md_style.base_text_style
-        },
-        ..default_md_style
+        thread_view.read_with(cx, |thread_view, cx| {
+            let thread = thread_view.thread().unwrap().read(cx);
+            assert_eq!(
+                TurnPhase::for_last_turn(thread.entries()),
+                vec![
+                    TurnPhase::Thinking,
+                    TurnPhase::Searching,
+                    TurnPhase::Editing,
+                    TurnPhase::Verifying,
+                ]
+            );
+        });
     }
-}
 
-fn terminal_command_markdown_style(window: &Window, cx: &App) -> MarkdownStyle {
-    let default_md_style = default_markdown_style(true, false, window, cx);
+    #[gpui::test]
+    async fn test_expand_and_collapse_all_entries(cx: &mut TestAppContext) {
+        init_test(cx);
 
-    MarkdownStyle {
-        base_text_style: TextStyle {
-            ..default_md_style.base_text_style
-        },
-        selection_background_color: cx.theme().colors().element_selection_background,
-        ..Default::default()
-    }
-}
+        let updates = |id: &str| {
+            vec![
+                acp::SessionUpdate::AgentThoughtChunk {
+                    content: "Which file?".into(),
+                },
+                acp::SessionUpdate::ToolCall(acp::ToolCall {
+                    id: acp::ToolCallId(id.to_string().into()),
+                    title: "Read".to_string(),
+                    kind: acp::ToolKind::Read,
+                    status: acp::ToolCallStatus::Completed,
+                    content: vec!["fn main() {}".into()],
+                    locations: vec![],
+                    raw_input: None,
+                    raw_output: None,
+                    meta: None,
+                }),
+            ]
+        };
+        let connection = StubAgentConnection::new();
+        connection.set_next_prompt_updates(updates("read-1"));
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(connection.clone()), cx).await;
 
-#[cfg(test)]
-pub(crate) mod tests {
-    use acp_thread::StubAgentConnection;
-    use agent_client_protocol::SessionId;
-    use assistant_text_thread::TextThreadStore;
-    use editor::EditorSettings;
-    use fs::FakeFs;
-    use gpui::{EventEmitter, SemanticVersion, TestAppContext, VisualTestContext};
-    use project::Project;
-    use serde_json::json;
-    use settings::SettingsStore;
-    use std::any::Any;
-    use std::path::Path;
-    use workspace::Item;
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        let send = |message: &str, cx: &mut VisualTestContext| {
+            message_editor.update_in(cx, |editor, window, cx| {
+                editor.set_text(message, window, cx);
+            });
+            thread_view.update_in(cx, |thread_view, window, cx| {
+                thread_view.send(window, cx);
+            });
+            cx.run_until_parked();
+        };
+        send("Read a file", cx);
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            assert!(thread_view.expanded_tool_calls.is_empty());
+            assert!(thread_view.expanded_thinking_blocks.is_empty());
+
+            thread_view.expand_all_entries(&ExpandAllEntries, window, cx);
+            assert!(
+                thread_view
+                    .expanded_tool_calls
+                    .contains(&acp::ToolCallId("read-1".into()))
+            );
+            assert!(thread_view.expanded_thinking_blocks.contains(&(1, 0)));
+
+            thread_view.collapse_all_entries(&CollapseAllEntries, window, cx);
+            assert!(thread_view.expanded_tool_calls.is_empty());
+            assert!(thread_view.expanded_thinking_blocks.is_empty());
+        });
+
+        // New entries start out expanded with the setting enabled.
+        cx.update(|_window, cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.agent.get_or_insert_default().expand_entries = Some(true);
+                });
+            });
+        });
+        connection.set_next_prompt_updates(updates("read-2"));
+        send("Read another file", cx);
 
-    use super::*;
+        thread_view.read_with(cx, |thread_view, _| {
+            assert_eq!(
+                thread_view.expanded_tool_calls,
+                HashSet::from_iter([acp::ToolCallId("read-2".into())])
+            );
+            assert_eq!(
+                thread_view.expanded_thinking_blocks,
+                HashSet::from_iter([(4, 0)])
+            );
+        });
+    }
 
     #[gpui::test]
-    async fn test_drop(cx: &mut TestAppContext) {
+    async fn test_tabs_follow_deleted_and_moved_paths(cx: &mut TestAppContext) {
         init_test(cx);
 
-        let (thread_view, _cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
-        let weak_view = thread_view.downgrade();
-        drop(thread_view);
-        assert!(!weak_view.is_upgradable());
-    }
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/project", json!({ "a.txt": "a", "b.txt": "b" }))
+            .await;
+        let project = Project::test(fs.clone(), [Path::new("/project")], cx).await;
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
 
-    #[gpui::test]
-    async fn test_notification_for_stop_event(cx: &mut TestAppContext) {
-        init_test(cx);
+        let text_thread_store =
+            cx.update(|_window, cx| cx.new(|cx| TextThreadStore::fake(project.clone(), cx)));
+        let history_store =
+            cx.update(|_window, cx| cx.new(|cx| HistoryStore::new(text_thread_store, cx)));
+        let connection = StubAgentConnection::new();
+        let thread_view = cx.update(|window, cx| {
+            cx.new(|cx| {
+                AcpThreadView::new(
+                    Rc::new(StubAgentServer::new(connection.clone())),
+                    None,
+                    None,
+                    workspace.downgrade(),
+                    project.clone(),
+                    history_store,
+                    None,
+                    window,
+                    cx,
+                )
+            })
+        });
+        cx.run_until_parked();
 
-        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+        let worktree_id = project.read_with(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        for file in ["a.txt", "b.txt"] {
+            workspace
+                .update_in(cx, |workspace, window, cx| {
+                    workspace.open_path(
+                        (worktree_id, rel_path(file).into_arc()),
+                        None,
+                        true,
+                        window,
+                        cx,
+                    )
+                })
+                .await
+                .unwrap();
+        }
 
+        // The agent deletes and moves the files itself, as external agents do.
+        fs.remove_file(Path::new("/project/a.txt"), Default::default())
+            .await
+            .unwrap();
+        fs.rename(
+            Path::new("/project/b.txt"),
+            Path::new("/project/c.txt"),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        let tool_call = |id: &str, kind, paths: &[&str]| {
+            acp::SessionUpdate::ToolCall(acp::ToolCall {
+                id: acp::ToolCallId(id.to_string().into()),
+                title: id.to_string(),
+                kind,
+                status: acp::ToolCallStatus::Completed,
+                content: vec![],
+                locations: paths
+                    .iter()
+                    .map(|path| acp::ToolCallLocation {
+                        path: PathBuf::from(path),
+                        line: None,
+                        meta: None,
+                    })
+                    .collect(),
+                raw_input: None,
+                raw_output: None,
+                meta: None,
+            })
+        };
+        connection.set_next_prompt_updates(vec![
+            tool_call("delete", acp::ToolKind::Delete, &["/project/a.txt"]),
+            tool_call(
+                "move",
+                acp::ToolKind::Move,
+                &["/project/b.txt", "/project/c.txt"],
+            ),
+        ]);
         let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
         message_editor.update_in(cx, |editor, window, cx| {
-            editor.set_text("Hello", window, cx);
+            editor.set_text("Clean up", window, cx);
         });
-
-        cx.deactivate_window();
-
         thread_view.update_in(cx, |thread_view, window, cx| {
             thread_view.send(window, cx);
         });
-
         cx.run_until_parked();
 
-        assert!(
-            cx.windows()
-                .iter()
-                .any(|window| window.downcast::<AgentNotification>().is_some())
-        );
+        workspace.read_with(cx, |workspace, cx| {
+            let open_paths = workspace
+                .active_pane()
+                .read(cx)
+                .items()
+                .filter_map(|item| item.project_path(cx))
+                .map(|path| path.path)
+                .collect::<Vec<_>>();
+            assert_eq!(open_paths, vec![rel_path("c.txt").into_arc()]);
+        });
     }
 
     #[gpui::test]
-    async fn test_notification_for_error(cx: &mut TestAppContext) {
+    async fn test_message_recall(cx: &mut TestAppContext) {
         init_test(cx);
 
-        let (thread_view, cx) =
-            setup_thread_view(StubAgentServer::new(SaboteurAgentConnection), cx).await;
-
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
         let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
-        message_editor.update_in(cx, |editor, window, cx| {
-            editor.set_text("Hello", window, cx);
-        });
-
-        cx.deactivate_window();
+        for message in ["Hello", "World"] {
+            message_editor.update_in(cx, |editor, window, cx| {
+                editor.set_text(message, window, cx);
+            });
+            thread_view.update_in(cx, |thread_view, window, cx| {
+                thread_view.send(window, cx);
+            });
+            cx.run_until_parked();
+        }
 
-        thread_view.update_in(cx, |thread_view, window, cx| {
-            thread_view.send(window, cx);
+        let recall = |older: bool, cx: &mut VisualTestContext| {
+            thread_view.update_in(cx, |thread_view, window, cx| {
+                let recalled = thread_view.recall_message(older, window, cx);
+                (recalled, thread_view.message_editor.read(cx).text(cx))
+            })
+        };
+        assert_eq!(recall(true, cx), (true, "World".to_string()));
+        assert_eq!(recall(true, cx), (true, "Hello".to_string()));
+        // The oldest message stays.
+        assert_eq!(recall(true, cx), (true, "Hello".to_string()));
+        assert_eq!(recall(false, cx), (true, "World".to_string()));
+        // Going past the newest message clears the editor.
+        assert_eq!(recall(false, cx), (true, String::new()));
+        assert_eq!(recall(false, cx), (false, String::new()));
+
+        // Edited messages aren't replaced.
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Draft", window, cx);
         });
-
-        cx.run_until_parked();
-
-        assert!(
-            cx.windows()
-                .iter()
-                .any(|window| window.downcast::<AgentNotification>().is_some())
-        );
+        assert_eq!(recall(true, cx), (false, "Draft".to_string()));
     }
 
     #[gpui::test]
-    async fn test_refusal_handling(cx: &mut TestAppContext) {
+    async fn test_view_state_is_restored(cx: &mut TestAppContext) {
         init_test(cx);
 
+        let connection = StubAgentConnection::new();
+        connection.set_next_prompt_updates(vec![acp::SessionUpdate::ToolCall(acp::ToolCall {
+            id: acp::ToolCallId("read".into()),
+            title: "Read".to_string(),
+            kind: acp::ToolKind::Read,
+            status: acp::ToolCallStatus::Completed,
+            content: vec![],
+            locations: vec![],
+            raw_input: None,
+            raw_output: None,
+            meta: None,
+        })]);
         let (thread_view, cx) =
-            setup_thread_view(StubAgentServer::new(RefusalAgentConnection), cx).await;
+            setup_thread_view(StubAgentServer::new(connection.clone()), cx).await;
 
         let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
         message_editor.update_in(cx, |editor, window, cx| {
-            editor.set_text("Do something harmful", window, cx);
+            editor.set_text("Read the file", window, cx);
         });
-
         thread_view.update_in(cx, |thread_view, window, cx| {
             thread_view.send(window, cx);
         });
-
         cx.run_until_parked();
 
-        // Check that the refusal error is set
-        thread_view.read_with(cx, |thread_view, _cx| {
+        thread_view.update(cx, |thread_view, cx| {
+            let thread = thread_view.thread().unwrap().clone();
+            thread_view
+                .expanded_tool_calls
+                .insert(acp::ToolCallId("read".into()));
+            thread_view.list_state.scroll_to(ListOffset {
+                item_ix: 0,
+                offset_in_item: px(10.),
+            });
+            thread_view.save_view_state(cx);
+
+            thread_view.expanded_tool_calls.clear();
+            thread_view
+                .list_state
+                .reset(thread.read(cx).entries().len());
+            thread_view.restore_view_state(&thread, cx);
+
             assert!(
-                matches!(thread_view.thread_error, Some(ThreadError::Refusal)),
-                "Expected refusal error to be set"
+                thread_view
+                    .expanded_tool_calls
+                    .contains(&acp::ToolCallId("read".into()))
             );
+            let scroll_top = thread_view.list_state.logical_scroll_top();
+            assert_eq!(scroll_top.item_ix, 0);
+            assert_eq!(scroll_top.offset_in_item, px(10.));
         });
     }
 
     #[gpui::test]
-    async fn test_notification_for_tool_authorization(cx: &mut TestAppContext) {
+    async fn test_cancel_marks_running_tool_calls(cx: &mut TestAppContext) {
         init_test(cx);
 
-        let tool_call_id = acp::ToolCallId("1".into());
-        let tool_call = acp::ToolCall {
-            id: tool_call_id.clone(),
-            title: "Label".into(),
-            kind: acp::ToolKind::Edit,
-            status: acp::ToolCallStatus::Pending,
-            content: vec!["hi".into()],
-            locations: vec![],
-            raw_input: None,
-            raw_output: None,
-            meta: None,
-        };
-        let connection =
-            StubAgentConnection::new().with_permission_requests(HashMap::from_iter([(
-                tool_call_id,
-                vec![acp::PermissionOption {
-                    id: acp::PermissionOptionId("1".into()),
-                    name: "Allow".into(),
-                    kind: acp::PermissionOptionKind::AllowOnce,
-                    meta: None,
-                }],
-            )]));
-
-        connection.set_next_prompt_updates(vec![acp::SessionUpdate::ToolCall(tool_call)]);
-
-        let (thread_view, cx) = setup_thread_view(StubAgentServer::new(connection), cx).await;
+        let connection = StubAgentConnection::new();
+        let (thread_view, cx) =
+            setup_thread_view(StubAgentServer::new(connection.clone()), cx).await;
 
         let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
         message_editor.update_in(cx, |editor, window, cx| {
-            editor.set_text("Hello", window, cx);
+            editor.set_text("Read the file", window, cx);
         });
-
-        cx.deactivate_window();
-
         thread_view.update_in(cx, |thread_view, window, cx| {
             thread_view.send(window, cx);
         });
+        cx.run_until_parked();
 
+        let thread = cx.read(|cx| thread_view.read(cx).thread().unwrap().clone());
+        let session_id = cx.read(|cx| thread.read(cx).session_id().clone());
+        cx.update(|_, cx| {
+            connection.send_update(
+                session_id,
+                acp::SessionUpdate::ToolCall(acp::ToolCall {
+                    id: acp::ToolCallId("read".into()),
+                    title: "Read".to_string(),
+                    kind: acp::ToolKind::Read,
+                    status: acp::ToolCallStatus::InProgress,
+                    content: vec![],
+                    locations: vec![],
+                    raw_input: None,
+                    raw_output: None,
+                    meta: None,
+                }),
+                cx,
+            );
+        });
+        thread_view.update(cx, |thread_view, cx| thread_view.cancel_generation(cx));
         cx.run_until_parked();
 
-        assert!(
-            cx.windows()
-                .iter()
-                .any(|window| window.downcast::<AgentNotification>().is_some())
-        );
+        cx.read(|cx| {
+            let tool_call = find_tool_call(thread.read(cx), &acp::ToolCallId("read".into()));
+            assert!(matches!(tool_call.status, ToolCallStatus::Canceled));
+            assert_eq!(
+                canceled_tool_call_note(tool_call, cx),
+                "Canceled when the generation was stopped"
+            );
+        });
+    }
+
+    fn find_tool_call<'a>(thread: &'a AcpThread, id: &acp::ToolCallId) -> &'a ToolCall {
+        thread
+            .entries()
+            .iter()
+            .find_map(|entry| match entry {
+                AgentThreadEntry::ToolCall(tool_call) if &tool_call.id == id => Some(tool_call),
+                _ => None,
+            })
+            .unwrap()
     }
 
     async fn setup_thread_view(
@@ -6018,6 +11627,77 @@ pub(crate) mod tests {
         }
     }
 
+    /// Requires authenticating before threads can be created, like agents that need a login.
+    #[derive(Clone)]
+    struct AuthGatedAgentConnection {
+        inner: StubAgentConnection,
+        auth_methods: Vec<acp::AuthMethod>,
+        authenticated: Arc<AtomicBool>,
+    }
+
+    impl AuthGatedAgentConnection {
+        fn new(inner: StubAgentConnection) -> Self {
+            Self {
+                inner,
+                auth_methods: vec![acp::AuthMethod {
+                    id: acp::AuthMethodId("test-login".into()),
+                    name: "Log In".into(),
+                    description: None,
+                    meta: None,
+                }],
+                authenticated: Arc::default(),
+            }
+        }
+
+        fn is_authenticated(&self) -> bool {
+            self.authenticated.load(SeqCst)
+        }
+    }
+
+    impl AgentConnection for AuthGatedAgentConnection {
+        fn new_thread(
+            self: Rc<Self>,
+            project: Entity<Project>,
+            cwd: &Path,
+            cx: &mut gpui::App,
+        ) -> Task<gpui::Result<Entity<AcpThread>>> {
+            if !self.is_authenticated() {
+                return Task::ready(Err(AuthRequired::new().into()));
+            }
+            Rc::new(self.inner.clone()).new_thread(project, cwd, cx)
+        }
+
+        fn auth_methods(&self) -> &[acp::AuthMethod] {
+            &self.auth_methods
+        }
+
+        fn authenticate(
+            &self,
+            _method_id: acp::AuthMethodId,
+            _cx: &mut App,
+        ) -> Task<gpui::Result<()>> {
+            self.authenticated.store(true, SeqCst);
+            Task::ready(Ok(()))
+        }
+
+        fn prompt(
+            &self,
+            id: Option<acp_thread::UserMessageId>,
+            params: acp::PromptRequest,
+            cx: &mut App,
+        ) -> Task<gpui::Result<acp::PromptResponse>> {
+            self.inner.prompt(id, params, cx)
+        }
+
+        fn cancel(&self, session_id: &acp::SessionId, cx: &mut App) {
+            self.inner.cancel(session_id, cx)
+        }
+
+        fn into_any(self: Rc<Self>) -> Rc<dyn Any> {
+            self
+        }
+    }
+
     #[derive(Clone)]
     struct SaboteurAgentConnection;
 
@@ -6147,6 +11827,7 @@ pub(crate) mod tests {
             language::init(cx);
             Project::init_settings(cx);
             AgentSettings::register(cx);
+            IssueLinkSettings::register(cx);
             workspace::init_settings(cx);
             theme::init(theme::LoadThemes::JustBase, cx);
             release_channel::init(SemanticVersion::default(), cx);
@@ -6392,6 +12073,61 @@ pub(crate) mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_edit_user_message(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+        add_to_workspace(thread_view.clone(), cx);
+
+        let message_editor = cx.read(|cx| thread_view.read(cx).message_editor.clone());
+        message_editor.update_in(cx, |editor, window, cx| {
+            editor.set_text("Original message to edit", window, cx);
+        });
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.send(window, cx);
+        });
+        cx.run_until_parked();
+
+        thread_view.update_in(cx, |thread_view, window, cx| {
+            thread_view.edit_user_message(0, window, cx);
+        });
+        cx.run_until_parked();
+
+        thread_view.read_with(cx, |view, _cx| {
+            assert_eq!(view.editing_message, Some(0));
+            assert!(view.expanded_user_messages.contains(&0));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_open_code_in_new_buffer(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let (thread_view, cx) = setup_thread_view(StubAgentServer::default_response(), cx).await;
+        add_to_workspace(thread_view.clone(), cx);
+
+        let workspace = thread_view.read_with(cx, |thread_view, _cx| thread_view.workspace.clone());
+        cx.update(|window, cx| {
+            AcpThreadView::open_code_in_new_buffer(
+                &workspace,
+                "fn main() {}".into(),
+                None,
+                window,
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        let editor = workspace
+            .read_with(cx, |workspace, cx| workspace.active_item_as::<Editor>(cx))
+            .unwrap()
+            .expect("a new buffer should be open");
+        editor.read_with(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), "fn main() {}");
+        });
+    }
+
     #[gpui::test]
     async fn test_message_doesnt_send_if_empty(cx: &mut TestAppContext) {
         init_test(cx);
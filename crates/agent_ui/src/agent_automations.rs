@@ -0,0 +1,324 @@
+use std::{
+    fmt::Write as _,
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use acp_thread::{AcpThread, AgentConnection};
+use agent::{DbThreadMetadata, HistoryStore};
+use agent_servers::AgentServerDelegate;
+use agent_settings::AgentSettings;
+use anyhow::{Context as _, anyhow};
+use assistant_slash_command::SlashCommandOutput;
+use assistant_slash_commands::collect_buffer_diagnostics;
+use chrono::Utc;
+use collections::{HashMap, HashSet};
+use fs::Fs;
+use futures::FutureExt as _;
+use gpui::{App, AppContext as _, Context, Entity, SharedString, Subscription, Task, WeakEntity};
+use language::{Buffer, BufferEvent};
+use project::{Project, ProjectItem as _, ProjectPath, buffer_store::BufferStoreEvent};
+use settings::{AgentAutomationContent, AgentAutomationTrigger, Settings as _};
+use ui::IconName;
+use util::paths::PathMatcher;
+use workspace::{
+    Workspace,
+    notifications::{NotificationId, simple_message_notification::MessageNotification},
+};
+
+use crate::{ExternalAgent, agent_panel::AgentPanel};
+
+/// How long a rule doesn't run again for a file after it ran for it, so that saves and
+/// diagnostics caused by acting on a proposal don't retrigger the rule right away.
+const RUN_COOLDOWN: Duration = Duration::from_secs(60);
+/// How long the agent may run for a rule before it's stopped, so that a stuck run doesn't keep
+/// the rule from running for the file again.
+const RUN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How long diagnostics must stay without new errors after a save to be considered settled.
+const DIAGNOSTICS_SETTLE: Duration = Duration::from_secs(5);
+
+/// Runs the agent in the background on the files of the project that the
+/// `agent.automations` rules match, when they're saved or gain errors, and notifies about
+/// the changes it proposes. The threads run in plan mode, so the agent can't edit files, and
+/// unattended, as nobody is there to confirm its tool calls.
+pub struct AgentAutomations {
+    workspace: WeakEntity<Workspace>,
+    project: Entity<Project>,
+    fs: Arc<dyn Fs>,
+    history_store: Entity<HistoryStore>,
+    /// The error counts of files with `new_errors` rules when they were saved, until their
+    /// diagnostics gain errors or settle, or they're edited again. The task forgets the count
+    /// once the diagnostics settle.
+    errors_before_save: HashMap<ProjectPath, (usize, Task<()>)>,
+    /// The files and indices of the rules that the agent is running for.
+    running: HashSet<(ProjectPath, usize)>,
+    /// When the agent last finished running for each file and rule index.
+    last_runs: HashMap<(ProjectPath, usize), Instant>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl AgentAutomations {
+    pub fn new(
+        workspace: WeakEntity<Workspace>,
+        project: Entity<Project>,
+        fs: Arc<dyn Fs>,
+        history_store: Entity<HistoryStore>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let buffer_store = project.read(cx).buffer_store().clone();
+        for buffer in buffer_store.read(cx).buffers().collect::<Vec<_>>() {
+            Self::observe_buffer(&buffer, cx);
+        }
+        let subscriptions = vec![
+            cx.subscribe(&buffer_store, |_, _, event, cx| {
+                if let BufferStoreEvent::BufferAdded(buffer) = event {
+                    Self::observe_buffer(buffer, cx);
+                }
+            }),
+            cx.subscribe(&project, |this, _, event, cx| {
+                if let project::Event::DiagnosticsUpdated { paths, .. } = event {
+                    this.diagnostics_updated(paths, cx);
+                }
+            }),
+        ];
+        Self {
+            workspace,
+            project,
+            fs,
+            history_store,
+            errors_before_save: HashMap::default(),
+            running: HashSet::default(),
+            last_runs: HashMap::default(),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn observe_buffer(buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        cx.subscribe(buffer, |this, buffer, event, cx| match event {
+            BufferEvent::Saved => this.buffer_saved(buffer, cx),
+            BufferEvent::Edited => {
+                if let Some(path) = buffer.read(cx).project_path(cx) {
+                    this.errors_before_save.remove(&path);
+                }
+            }
+            _ => {}
+        })
+        .detach();
+    }
+
+    fn buffer_saved(&mut self, buffer: Entity<Buffer>, cx: &mut Context<Self>) {
+        let Some(path) = buffer.read(cx).project_path(cx) else {
+            return;
+        };
+        for (ix, automation) in self.automations_for(&path, AgentAutomationTrigger::Save, cx) {
+            self.run(ix, automation, &buffer, cx);
+        }
+        if !self
+            .automations_for(&path, AgentAutomationTrigger::NewErrors, cx)
+            .is_empty()
+        {
+            let error_count = self
+                .project
+                .read(cx)
+                .diagnostic_summary_for_path(&path, cx)
+                .error_count;
+            self.errors_before_save
+                .insert(path, (error_count, Task::ready(())));
+        }
+    }
+
+    fn diagnostics_updated(&mut self, paths: &[ProjectPath], cx: &mut Context<Self>) {
+        for path in paths {
+            let Some((errors_before_save, _)) = self.errors_before_save.get(path) else {
+                continue;
+            };
+            let errors_before_save = *errors_before_save;
+            let project = self.project.read(cx);
+            // Language servers may clear diagnostics before publishing new ones, so the count
+            // is compared on every update until it increases or stops changing for a while.
+            if project.diagnostic_summary_for_path(path, cx).error_count <= errors_before_save {
+                let settle_path = path.clone();
+                let settle = cx.spawn(async move |this, cx| {
+                    cx.background_executor().timer(DIAGNOSTICS_SETTLE).await;
+                    this.update(cx, |this, _| {
+                        this.errors_before_save.remove(&settle_path);
+                    })
+                    .ok();
+                });
+                self.errors_before_save
+                    .insert(path.clone(), (errors_before_save, settle));
+                continue;
+            }
+            self.errors_before_save.remove(path);
+            let Some(buffer) = project.get_open_buffer(path, cx) else {
+                continue;
+            };
+            for (ix, automation) in
+                self.automations_for(path, AgentAutomationTrigger::NewErrors, cx)
+            {
+                self.run(ix, automation, &buffer, cx);
+            }
+        }
+    }
+
+    /// The rules with the trigger matching the path, with their indices in the settings.
+    fn automations_for(
+        &self,
+        path: &ProjectPath,
+        trigger: AgentAutomationTrigger,
+        cx: &App,
+    ) -> Vec<(usize, AgentAutomationContent)> {
+        let path_style = self.project.read(cx).path_style(cx);
+        AgentSettings::get_global(cx)
+            .automations
+            .iter()
+            .enumerate()
+            .filter(|(_, automation)| {
+                automation.trigger == trigger
+                    && PathMatcher::new([&automation.path], path_style)
+                        .is_ok_and(|matcher| matcher.is_match(path.path.as_std_path()))
+            })
+            .map(|(ix, automation)| (ix, automation.clone()))
+            .collect()
+    }
+
+    fn run(
+        &mut self,
+        ix: usize,
+        automation: AgentAutomationContent,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) {
+        let buffer = buffer.read(cx);
+        let (Some(path), Some(file)) = (buffer.project_path(cx), buffer.file()) else {
+            return;
+        };
+        let Some(root_dir) = self
+            .project
+            .read(cx)
+            .worktree_for_id(path.worktree_id, cx)
+            .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+        else {
+            return;
+        };
+        let key = (path.clone(), ix);
+        self.last_runs
+            .retain(|_, finished| finished.elapsed() < RUN_COOLDOWN);
+        if self.last_runs.contains_key(&key) || !self.running.insert(key) {
+            return;
+        }
+
+        let full_path = file.full_path(cx);
+        let mut prompt = format!("{}\n\nFile: {}", automation.prompt, full_path.display());
+        let mut errors = SlashCommandOutput::default();
+        collect_buffer_diagnostics(&mut errors, &buffer.snapshot(), false);
+        if !errors.text.is_empty() {
+            write!(prompt, "\n\nErrors:\n{}", errors.text).ok();
+        }
+
+        let project = self.project.clone();
+        let server = ExternalAgent::NativeAgent.server(self.fs.clone(), self.history_store.clone());
+        let delegate = AgentServerDelegate::new(
+            project.read(cx).agent_server_store().clone(),
+            project.clone(),
+            None,
+            None,
+        );
+        let connect = server.connect(Some(&root_dir), delegate, cx);
+        cx.spawn(async move |this, cx| {
+            // The run is dropped once it times out, which ends the thread.
+            let result = {
+                let timeout = cx.background_executor().timer(RUN_TIMEOUT).fuse();
+                let run = async {
+                    let (connection, _) = connect.await?;
+                    let thread = cx
+                        .update(|cx| connection.clone().new_thread(project, &root_dir, cx))?
+                        .await?;
+                    thread
+                        .update(cx, |thread, cx| {
+                            thread.set_plan_mode(true, cx);
+                            thread.set_unattended(true, cx);
+                            thread.send(vec![prompt.as_str().into()], cx)
+                        })?
+                        .await
+                        .context("running the agent")?;
+                    anyhow::Ok((connection, thread))
+                }
+                .fuse();
+                futures::pin_mut!(timeout, run);
+                futures::select_biased! {
+                    result = run => result,
+                    _ = timeout => {
+                        Err(anyhow!("timed out after {} seconds", RUN_TIMEOUT.as_secs()))
+                    }
+                }
+            };
+            this.update(cx, |this, cx| {
+                let key = (path, ix);
+                this.running.remove(&key);
+                this.last_runs.insert(key, Instant::now());
+                match result {
+                    Ok((connection, thread)) => {
+                        this.show_proposal(&full_path, connection, thread, cx)
+                    }
+                    Err(error) => log::error!(
+                        "agent automation for {} failed: {error:#}",
+                        full_path.display()
+                    ),
+                }
+            })
+        })
+        .detach();
+    }
+
+    fn show_proposal(
+        &self,
+        path: &Path,
+        connection: Rc<dyn AgentConnection>,
+        thread: Entity<AcpThread>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let message = format!("The agent proposed changes for {}", path.display());
+        let id = NotificationId::composite::<Self>(SharedString::from(
+            thread.read(cx).session_id().0.clone(),
+        ));
+        // The connection stays with the notification, for the agent to finish saving the
+        // thread before it's reviewed.
+        let proposal = (connection, thread);
+        let weak_workspace = self.workspace.clone();
+        workspace.update(cx, |workspace, cx| {
+            workspace.show_notification(id, cx, |cx| {
+                cx.new(|cx| {
+                    MessageNotification::new(message, cx)
+                        .primary_message("Review")
+                        .primary_icon(IconName::ZedAgent)
+                        .primary_on_click(move |window, cx| {
+                            let (_, thread) = &proposal;
+                            let thread = thread.read(cx);
+                            let metadata = DbThreadMetadata {
+                                id: thread.session_id().clone(),
+                                title: thread.title(),
+                                updated_at: Utc::now(),
+                            };
+                            weak_workspace
+                                .update(cx, |workspace, cx| {
+                                    if let Some(panel) =
+                                        workspace.focus_panel::<AgentPanel>(window, cx)
+                                    {
+                                        panel.update(cx, |panel, cx| {
+                                            panel.load_agent_thread(metadata, window, cx)
+                                        });
+                                    }
+                                })
+                                .ok();
+                        })
+                })
+            });
+        });
+    }
+}
@@ -26,12 +26,16 @@ use crate::{
     AddContextServer, AgentDiffPane, DeleteRecentlyOpenThread, Follow, InlineAssistant,
     NewTextThread, NewThread, OpenActiveThreadAsMarkdown, OpenAgentDiff, OpenHistory,
     ResetTrialEndUpsell, ResetTrialUpsell, ToggleNavigationMenu, ToggleNewThreadMenu,
-    ToggleOptionsMenu,
+    ToggleOptionsMenu, ToggleZoomThread,
     acp::AcpThreadView,
+    agent_automations::AgentAutomations,
     agent_configuration::{AgentConfiguration, AssistantConfigurationEvent},
     slash_command::SlashCommandCompletionProvider,
     text_thread_editor::{AgentPanelDelegate, TextThreadEditor, make_lsp_adapter_delegate},
-    ui::{AgentOnboardingModal, EndTrialUpsell},
+    ui::{
+        AgentOnboardingModal, AlwaysAllowedModal, EndTrialUpsell, GenerationParametersModal,
+        ProjectMemoryModal, QuickPromptModal,
+    },
 };
 use crate::{
     ExternalAgent, NewExternalAgentThread, NewNativeAgentThreadFromSummary, placeholder_command,
@@ -68,12 +72,13 @@ use ui::{
 use util::ResultExt as _;
 use workspace::{
     CollaboratorId, DraggedSelection, DraggedTab, ToggleZoom, ToolbarItemView, Workspace,
-    dock::{DockPosition, Panel, PanelEvent},
+    dock::{DockPosition, Panel, PanelEvent, PanelHandle as _},
 };
 use zed_actions::{
     DecreaseBufferFontSize, IncreaseBufferFontSize, ResetBufferFontSize,
     agent::{
-        OpenAcpOnboardingModal, OpenOnboardingModal, OpenSettings, ResetAgentZoom, ResetOnboarding,
+        OpenAcpOnboardingModal, OpenAlwaysAllowed, OpenGenerationParameters, OpenOnboardingModal,
+        OpenProjectMemory, OpenSettings, QuickPrompt, ResetAgentZoom, ResetOnboarding,
     },
     assistant::{OpenRulesLibrary, ToggleFocus},
 };
@@ -83,6 +88,10 @@ const AGENT_PANEL_KEY: &str = "agent_panel";
 #[derive(Serialize, Deserialize, Debug)]
 struct SerializedAgentPanel {
     width: Option<Pixels>,
+    #[serde(default)]
+    dock: Option<settings::DockPosition>,
+    #[serde(default)]
+    zoomed: bool,
     selected_agent: Option<AgentType>,
 }
 
@@ -106,6 +115,11 @@ pub fn init(cx: &mut App) {
                         }
                     },
                 )
+                .register_action(|workspace, _: &ToggleZoomThread, window, cx| {
+                    if let Some(panel) = workspace.focus_panel::<AgentPanel>(window, cx) {
+                        panel.update(cx, |panel, cx| panel.toggle_zoom(&ToggleZoom, window, cx));
+                    }
+                })
                 .register_action(|workspace, _: &OpenHistory, window, cx| {
                     if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
                         workspace.focus_panel::<AgentPanel>(window, cx);
@@ -132,6 +146,14 @@ pub fn init(cx: &mut App) {
                         });
                     }
                 })
+                .register_action(
+                    |workspace, _: &zed_actions::dev::OpenDebugAgentThread, window, cx| {
+                        if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
+                            workspace.focus_panel::<AgentPanel>(window, cx);
+                            panel.update(cx, |panel, cx| panel.debug_agent_thread(window, cx));
+                        }
+                    },
+                )
                 .register_action(|workspace, action: &OpenRulesLibrary, window, cx| {
                     if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
                         workspace.focus_panel::<AgentPanel>(window, cx);
@@ -196,6 +218,18 @@ pub fn init(cx: &mut App) {
                 .register_action(|_workspace, _: &ResetTrialEndUpsell, _window, cx| {
                     TrialEndUpsell::set_dismissed(false, cx);
                 })
+                .register_action(|workspace, _: &QuickPrompt, window, cx| {
+                    QuickPromptModal::toggle(workspace, window, cx)
+                })
+                .register_action(|workspace, _: &OpenProjectMemory, window, cx| {
+                    ProjectMemoryModal::toggle(workspace, window, cx)
+                })
+                .register_action(|workspace, _: &OpenAlwaysAllowed, window, cx| {
+                    AlwaysAllowedModal::toggle(workspace, window, cx)
+                })
+                .register_action(|workspace, _: &OpenGenerationParameters, window, cx| {
+                    GenerationParametersModal::toggle(workspace, window, cx)
+                })
                 .register_action(|workspace, _: &ResetAgentZoom, window, cx| {
                     if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
                         panel.update(cx, |panel, cx| {
@@ -439,22 +473,52 @@ pub struct AgentPanel {
     _extension_subscription: Option<Subscription>,
     width: Option<Pixels>,
     height: Option<Pixels>,
+    /// The dock the panel was moved to in this workspace, overriding the `agent.dock`
+    /// setting until that changes.
+    dock_position: Option<DockPosition>,
     zoomed: bool,
+    /// Whether the panel was zoomed when serialized, to zoom it in again once it's shown.
+    restore_zoom: bool,
+    _settings_subscription: Subscription,
     pending_serialization: Option<Task<Result<()>>>,
     onboarding: Entity<AgentPanelOnboarding>,
     selected_agent: AgentType,
+    /// A quick prompt waiting for the thread that was started to receive it.
+    pending_quick_prompt: Option<String>,
+    _automations: Entity<AgentAutomations>,
 }
 
 impl AgentPanel {
+    /// The panel is serialized per workspace, falling back to a key shared by all
+    /// workspaces when the workspace isn't persisted.
+    fn serialization_key(workspace: &Workspace) -> String {
+        workspace
+            .database_id()
+            .map(|id| i64::from(id).to_string())
+            .or(workspace.session_id())
+            .map(|id| format!("{}-{:?}", AGENT_PANEL_KEY, id))
+            .unwrap_or_else(|| AGENT_PANEL_KEY.to_string())
+    }
+
     fn serialize(&mut self, cx: &mut Context<Self>) {
+        let Ok(serialization_key) = self
+            .workspace
+            .read_with(cx, |workspace, _| Self::serialization_key(workspace))
+        else {
+            return;
+        };
         let width = self.width;
+        let dock = self.dock_position.map(Into::into);
+        let zoomed = self.zoomed;
         let selected_agent = self.selected_agent.clone();
         self.pending_serialization = Some(cx.background_spawn(async move {
             KEY_VALUE_STORE
                 .write_kvp(
-                    AGENT_PANEL_KEY.into(),
+                    serialization_key,
                     serde_json::to_string(&SerializedAgentPanel {
                         width,
+                        dock,
+                        zoomed,
                         selected_agent: Some(selected_agent),
                     })?,
                 )
@@ -474,8 +538,16 @@ impl AgentPanel {
                 Ok(prompt_store) => prompt_store.await.ok(),
                 Err(_) => None,
             };
+            let serialization_key =
+                workspace.read_with(cx, |workspace, _| Self::serialization_key(workspace))?;
             let serialized_panel = if let Some(panel) = cx
-                .background_spawn(async move { KEY_VALUE_STORE.read_kvp(AGENT_PANEL_KEY) })
+                .background_spawn(async move {
+                    // Workspaces that haven't been serialized yet start from the shared state.
+                    match KEY_VALUE_STORE.read_kvp(&serialization_key)? {
+                        Some(panel) => Ok(Some(panel)),
+                        None => KEY_VALUE_STORE.read_kvp(AGENT_PANEL_KEY),
+                    }
+                })
                 .await
                 .log_err()
                 .flatten()
@@ -506,6 +578,8 @@ impl AgentPanel {
                 if let Some(serialized_panel) = serialized_panel {
                     panel.update(cx, |panel, cx| {
                         panel.width = serialized_panel.width.map(|w| w.round());
+                        panel.dock_position = serialized_panel.dock.map(Into::into);
+                        panel.restore_zoom = serialized_panel.zoomed;
                         if let Some(selected_agent) = serialized_panel.selected_agent {
                             panel.selected_agent = selected_agent.clone();
                             panel.new_agent_thread(selected_agent, window, cx);
@@ -636,6 +710,16 @@ impl AgentPanel {
                 .ok();
         });
 
+        let automations = cx.new(|cx| {
+            AgentAutomations::new(
+                workspace.clone(),
+                project.clone(),
+                fs.clone(),
+                history_store.clone(),
+                cx,
+            )
+        });
+
         let onboarding = cx.new(|cx| {
             AgentPanelOnboarding::new(
                 user_store.clone(),
@@ -647,6 +731,19 @@ impl AgentPanel {
             )
         });
 
+        // Changing the `agent.dock` setting moves the panel in every workspace, dropping the
+        // position it was moved to in this one.
+        let mut dock_setting = agent_panel_dock_position(cx);
+        let settings_subscription = cx.observe_global::<SettingsStore>(move |this, cx| {
+            let new_dock_setting = agent_panel_dock_position(cx);
+            if new_dock_setting != dock_setting {
+                dock_setting = new_dock_setting;
+                if this.dock_position.take().is_some() {
+                    this.serialize(cx);
+                }
+            }
+        });
+
         // Subscribe to extension events to sync agent servers when extensions change
         let extension_subscription = if let Some(extension_events) = ExtensionEvents::try_global(cx)
         {
@@ -684,13 +781,18 @@ impl AgentPanel {
             agent_navigation_menu: None,
             _extension_subscription: extension_subscription,
             width: None,
+            dock_position: None,
             height: None,
             zoomed: false,
+            restore_zoom: false,
+            _settings_subscription: settings_subscription,
             pending_serialization: None,
             onboarding,
             acp_history,
             history_store,
             selected_agent: AgentType::default(),
+            pending_quick_prompt: None,
+            _automations: automations,
             loading: false,
         };
 
@@ -729,7 +831,7 @@ impl AgentPanel {
         &self.context_server_registry
     }
 
-    fn active_thread_view(&self) -> Option<&Entity<AcpThreadView>> {
+    pub(crate) fn active_thread_view(&self) -> Option<&Entity<AcpThreadView>> {
         match &self.active_view {
             ActiveView::ExternalAgentThread { thread_view, .. } => Some(thread_view),
             ActiveView::TextThread { .. } | ActiveView::History | ActiveView::Configuration => None,
@@ -895,12 +997,53 @@ impl AgentPanel {
                     )
                 });
 
+                if let Some(prompt) = this.pending_quick_prompt.take() {
+                    thread_view.update(cx, |thread_view, cx| {
+                        thread_view.send_prompt(&prompt, window, cx)
+                    });
+                }
                 this.set_active_view(ActiveView::ExternalAgentThread { thread_view }, window, cx);
             })
         })
         .detach_and_log_err(cx);
     }
 
+    /// Opens a thread with the scripted [`agent_servers::DebugAgent`]. It isn't remembered
+    /// as the last used agent.
+    fn debug_agent_thread(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let thread_view = cx.new(|cx| {
+            crate::acp::AcpThreadView::new(
+                Rc::new(agent_servers::DebugAgent),
+                None,
+                None,
+                self.workspace.clone(),
+                self.project.clone(),
+                self.history_store.clone(),
+                self.prompt_store.clone(),
+                window,
+                cx,
+            )
+        });
+        self.set_active_view(ActiveView::ExternalAgentThread { thread_view }, window, cx);
+    }
+
+    /// Sends a prompt to the active thread, or to a new one when no thread is active.
+    pub fn send_quick_prompt(
+        &mut self,
+        prompt: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(thread_view) = self.active_thread_view() {
+            thread_view.update(cx, |thread_view, cx| {
+                thread_view.send_prompt(&prompt, window, cx)
+            });
+        } else {
+            self.pending_quick_prompt = Some(prompt);
+            self.external_thread(None, None, None, window, cx);
+        }
+    }
+
     fn deploy_rules_library(
         &mut self,
         action: &OpenRulesLibrary,
@@ -1483,19 +1626,44 @@ impl Panel for AgentPanel {
     }
 
     fn position(&self, _window: &Window, cx: &App) -> DockPosition {
-        agent_panel_dock_position(cx)
+        self.dock_position
+            .unwrap_or_else(|| agent_panel_dock_position(cx))
     }
 
     fn position_is_valid(&self, position: DockPosition) -> bool {
         position != DockPosition::Bottom
     }
 
-    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
-        settings::update_settings_file(self.fs.clone(), cx, move |settings, _| {
-            settings
-                .agent
-                .get_or_insert_default()
-                .set_dock(position.into());
+    /// Moves the panel in this workspace only, leaving the `agent.dock` setting as the
+    /// position of the panel in the others.
+    fn set_position(
+        &mut self,
+        position: DockPosition,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.dock_position = Some(position);
+        self.serialize(cx);
+
+        let workspace = self.workspace.clone();
+        let panel = cx.entity();
+        window.defer(cx, move |window, cx| {
+            workspace
+                .update(cx, |workspace, cx| {
+                    let was_open = workspace.all_docks().into_iter().any(|dock| {
+                        let dock = dock.read(cx);
+                        dock.is_open()
+                            && dock
+                                .visible_panel()
+                                .is_some_and(|visible| visible.panel_id() == panel.entity_id())
+                    });
+                    workspace.remove_panel(&panel, window, cx);
+                    workspace.add_panel(panel, window, cx);
+                    if was_open {
+                        workspace.open_panel::<AgentPanel>(window, cx);
+                    }
+                })
+                .ok();
         });
     }
 
@@ -1518,7 +1686,11 @@ impl Panel for AgentPanel {
         cx.notify();
     }
 
-    fn set_active(&mut self, _active: bool, _window: &mut Window, _cx: &mut Context<Self>) {}
+    fn set_active(&mut self, active: bool, _window: &mut Window, cx: &mut Context<Self>) {
+        if active && std::mem::take(&mut self.restore_zoom) {
+            cx.emit(PanelEvent::ZoomIn);
+        }
+    }
 
     fn remote_id() -> Option<proto::PanelId> {
         Some(proto::PanelId::AssistantPanel)
@@ -1550,6 +1722,7 @@ impl Panel for AgentPanel {
 
     fn set_zoomed(&mut self, zoomed: bool, _window: &mut Window, cx: &mut Context<Self>) {
         self.zoomed = zoomed;
+        self.serialize(cx);
         cx.notify();
     }
 }
@@ -1671,6 +1844,12 @@ impl AgentPanel {
         };
 
         let selected_agent = self.selected_agent.clone();
+        let has_thread = self
+            .active_thread_view()
+            .is_some_and(|thread_view| thread_view.read(cx).thread().is_some());
+        let agent_process_usage = self
+            .active_thread_view()
+            .and_then(|thread_view| thread_view.read(cx).agent_process_usage());
 
         PopoverMenu::new("agent-options-menu")
             .trigger_with_tooltip(
@@ -1747,6 +1926,15 @@ impl AgentPanel {
 
                         menu = menu
                             .action("Rules", Box::new(OpenRulesLibrary::default()))
+                            .action("Project Memory", Box::new(OpenProjectMemory))
+                            .action("Always Allowed Tool Calls", Box::new(OpenAlwaysAllowed));
+                        if has_thread {
+                            menu = menu.action(
+                                "Generation Parameters",
+                                Box::new(OpenGenerationParameters),
+                            );
+                        }
+                        menu = menu
                             .action("Settings", Box::new(OpenSettings))
                             .separator()
                             .action(full_screen_label, Box::new(ToggleZoom));
@@ -1755,6 +1943,14 @@ impl AgentPanel {
                             menu = menu.action("Reauthenticate", Box::new(ReauthenticateAgent))
                         }
 
+                        if let Some(usage) = agent_process_usage {
+                            menu = menu.separator().label(format!(
+                                "Agent Process: {}, {:.0}% CPU",
+                                util::size::format_file_size(usage.memory, false),
+                                usage.cpu
+                            ));
+                        }
+
                         menu
                     }))
                 }
@@ -1,4 +1,5 @@
 mod acp;
+mod agent_automations;
 mod agent_configuration;
 mod agent_diff;
 mod agent_model_selector;
@@ -24,7 +25,7 @@ mod ui;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use agent_settings::{AgentProfileId, AgentSettings};
+use agent_settings::{AgentProfileId, AgentSettings, IssueLinkSettings};
 use assistant_slash_command::SlashCommandRegistry;
 use client::Client;
 use command_palette_hooks::CommandPaletteFilter;
@@ -43,6 +44,7 @@ use serde::{Deserialize, Serialize};
 use settings::{LanguageModelSelection, Settings as _, SettingsStore};
 use std::any::TypeId;
 
+pub use crate::acp::{ComposerCommand, MessageEditor, register_composer_command};
 use crate::agent_configuration::{ConfigureContextServerModal, ManageProfilesModal};
 pub use crate::agent_panel::{AgentPanel, ConcreteAssistantPanelDelegate};
 pub use crate::inline_assistant::InlineAssistant;
@@ -115,6 +117,18 @@ actions!(
         AllowAlways,
         /// Reject this operation only this time.
         RejectOnce,
+        /// Stops the agent's current turn.
+        CancelGeneration,
+        /// Scrolls to the next entry of the thread.
+        SelectNextEntry,
+        /// Scrolls to the previous entry of the thread.
+        SelectPreviousEntry,
+        /// Expands all thinking blocks of the thread, or collapses them if any is expanded.
+        ToggleThinking,
+        /// Expands all tool calls and thinking blocks of the thread.
+        ExpandAllEntries,
+        /// Collapses all tool calls and thinking blocks of the thread.
+        CollapseAllEntries,
         /// Follows the agent's suggestions.
         Follow,
         /// Resets the trial upsell notification.
@@ -127,6 +141,12 @@ actions!(
         ContinueWithBurnMode,
         /// Toggles burn mode for faster responses.
         ToggleBurnMode,
+        /// Toggles plan mode, in which the agent only proposes changes, without making them.
+        TogglePlanMode,
+        /// Toggles accepting the agent's edits without confirmation in the current thread.
+        ToggleAutoAcceptEdits,
+        /// Lets the agent panel take over the center area, or returns it to its dock.
+        ToggleZoomThread,
     ]
 );
 
@@ -145,6 +165,11 @@ pub struct NewExternalAgentThread {
     agent: Option<ExternalAgent>,
 }
 
+/// Picks an option of the focused tool call confirmation by its index.
+#[derive(Clone, PartialEq, Debug, Deserialize, JsonSchema, Default, Action)]
+#[action(namespace = agent)]
+pub struct SelectPermissionOption(pub usize);
+
 #[derive(Clone, PartialEq, Deserialize, JsonSchema, Action)]
 #[action(namespace = agent)]
 #[serde(deny_unknown_fields)]
@@ -249,6 +274,7 @@ pub fn init(
     cx: &mut App,
 ) {
     AgentSettings::register(cx);
+    IssueLinkSettings::register(cx);
 
     assistant_text_thread::init(client.clone(), cx);
     rules_library::init(cx);
@@ -1,19 +1,27 @@
 mod acp_onboarding_modal;
 mod agent_notification;
+mod always_allowed_modal;
 mod burn_mode_tooltip;
 mod claude_code_onboarding_modal;
 mod context_pill;
 mod end_trial_upsell;
+mod generation_parameters_modal;
 mod onboarding_modal;
+mod project_memory_modal;
+mod quick_prompt_modal;
 mod unavailable_editing_tooltip;
 mod usage_callout;
 
 pub use acp_onboarding_modal::*;
 pub use agent_notification::*;
+pub use always_allowed_modal::*;
 pub use burn_mode_tooltip::*;
 pub use claude_code_onboarding_modal::*;
 pub use context_pill::*;
 pub use end_trial_upsell::*;
+pub use generation_parameters_modal::*;
 pub use onboarding_modal::*;
+pub use project_memory_modal::*;
+pub use quick_prompt_modal::*;
 pub use unavailable_editing_tooltip::*;
 pub use usage_callout::*;
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use acp_thread::ToolPermission;
+use agent_settings::AgentSettings;
+use fs::Fs;
+use gpui::{DismissEvent, EventEmitter, FocusHandle, Focusable, Subscription};
+use settings::{Settings as _, SettingsStore, update_settings_file};
+use ui::{ListItem, Tooltip, prelude::*};
+use workspace::{ModalView, Workspace};
+
+/// Lists the tool calls "Always Allow" was chosen for, to revoke them.
+pub struct AlwaysAllowedModal {
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    _subscription: Subscription,
+}
+
+impl AlwaysAllowedModal {
+    pub fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        let fs = workspace.app_state().fs.clone();
+        workspace.toggle_modal(window, cx, |_window, cx| Self {
+            fs,
+            focus_handle: cx.focus_handle(),
+            _subscription: cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
+        });
+    }
+
+    fn revoke(&mut self, permission: ToolPermission, cx: &mut Context<Self>) {
+        update_settings_file(self.fs.clone(), cx, move |settings, _| {
+            if let Some(always_allowed) = settings
+                .agent
+                .as_mut()
+                .and_then(|agent| agent.always_allowed.as_mut())
+            {
+                permission.revoke(always_allowed);
+            }
+        });
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn render_permission(
+        &self,
+        ix: usize,
+        permission: ToolPermission,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        ListItem::new(ix)
+            .child(Label::new(permission.label()).size(LabelSize::Small))
+            .end_slot(
+                IconButton::new(("revoke-permission", ix), IconName::Trash)
+                    .icon_size(IconSize::Small)
+                    .icon_color(Color::Muted)
+                    .tooltip(Tooltip::text("Ask Again"))
+                    .on_click(
+                        cx.listener(move |this, _, _window, cx| {
+                            this.revoke(permission.clone(), cx)
+                        }),
+                    ),
+            )
+    }
+}
+
+impl ModalView for AlwaysAllowedModal {}
+
+impl EventEmitter<DismissEvent> for AlwaysAllowedModal {}
+
+impl Focusable for AlwaysAllowedModal {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for AlwaysAllowedModal {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let permissions = ToolPermission::granted(&AgentSettings::get_global(cx).always_allowed)
+            .into_iter()
+            .enumerate()
+            .map(|(ix, permission)| {
+                self.render_permission(ix, permission, cx)
+                    .into_any_element()
+            })
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("AlwaysAllowed")
+            .track_focus(&self.focus_handle)
+            .w(rems(34.))
+            .elevation_3(cx)
+            .on_action(cx.listener(Self::cancel))
+            .child(
+                v_flex()
+                    .px_2()
+                    .py_1p5()
+                    .gap_0p5()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(Label::new("Always Allowed Tool Calls"))
+                    .child(
+                        Label::new("Allowed without confirmation on every thread.")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .id("always-allowed-permissions")
+                    .max_h(rems(24.))
+                    .overflow_y_scroll()
+                    .p_1()
+                    .map(|this| {
+                        if permissions.is_empty() {
+                            this.child(
+                                div().p_1().child(
+                                    Label::new("\"Always Allow\" hasn't been chosen yet.")
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                ),
+                            )
+                        } else {
+                            this.children(permissions)
+                        }
+                    }),
+            )
+    }
+}
@@ -0,0 +1,268 @@
+use acp_thread::{AcpThread, GenerationParameters};
+use editor::Editor;
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable};
+use ui::prelude::*;
+use workspace::{ModalView, Workspace};
+
+use crate::agent_panel::AgentPanel;
+
+/// Edits the generation parameters of the active thread, which are sent to its agent along
+/// with every message.
+pub struct GenerationParametersModal {
+    thread: Entity<AcpThread>,
+    /// `None` for threads of the native agent, whose requests always use the model's maximum.
+    max_output_tokens_editor: Option<Entity<Editor>>,
+    temperature_editor: Entity<Editor>,
+    stop_sequences_editor: Entity<Editor>,
+    error: Option<SharedString>,
+}
+
+impl GenerationParametersModal {
+    pub fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        let Some((thread, is_native)) = workspace.panel::<AgentPanel>(cx).and_then(|panel| {
+            let thread_view = panel.read(cx).active_thread_view()?.read(cx);
+            let thread = thread_view.thread()?.clone();
+            Some((thread, thread_view.as_native_thread(cx).is_some()))
+        }) else {
+            return;
+        };
+        let parameters = thread.read(cx).generation_parameters().clone();
+        workspace.toggle_modal(window, cx, |window, cx| {
+            let mut editor = |text: String, placeholder: &str| {
+                cx.new(|cx| {
+                    let mut editor = Editor::single_line(window, cx);
+                    editor.set_placeholder_text(placeholder, window, cx);
+                    editor.set_text(text, window, cx);
+                    editor
+                })
+            };
+            Self {
+                max_output_tokens_editor: (!is_native).then(|| {
+                    editor(
+                        parameters
+                            .max_output_tokens
+                            .map(|tokens| tokens.to_string())
+                            .unwrap_or_default(),
+                        "The agent's default",
+                    )
+                }),
+                temperature_editor: editor(
+                    parameters
+                        .temperature
+                        .map(|temperature| temperature.to_string())
+                        .unwrap_or_default(),
+                    "The agent's default",
+                ),
+                stop_sequences_editor: editor(
+                    format_stop_sequences(&parameters.stop_sequences),
+                    "None, or e.g. END, \\n\\n",
+                ),
+                thread,
+                error: None,
+            }
+        });
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        let max_output_tokens = self
+            .max_output_tokens_editor
+            .as_ref()
+            .map(|editor| editor.read(cx).text(cx))
+            .unwrap_or_default();
+        match parse_parameters(
+            &max_output_tokens,
+            &self.temperature_editor.read(cx).text(cx),
+            &self.stop_sequences_editor.read(cx).text(cx),
+        ) {
+            Ok(parameters) => {
+                self.thread.update(cx, |thread, cx| {
+                    thread.set_generation_parameters(parameters, cx)
+                });
+                cx.emit(DismissEvent);
+            }
+            Err(error) => {
+                self.error = Some(error);
+                cx.notify();
+            }
+        }
+    }
+
+    fn render_field(
+        &self,
+        label: &'static str,
+        editor: &Entity<Editor>,
+        cx: &Context<Self>,
+    ) -> impl IntoElement {
+        v_flex()
+            .gap_0p5()
+            .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+            .child(
+                div()
+                    .px_1p5()
+                    .py_1()
+                    .rounded_sm()
+                    .border_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(editor.clone()),
+            )
+    }
+}
+
+fn parse_parameters(
+    max_output_tokens: &str,
+    temperature: &str,
+    stop_sequences: &str,
+) -> Result<GenerationParameters, SharedString> {
+    let max_output_tokens = match max_output_tokens.trim() {
+        "" => None,
+        tokens => match tokens.parse::<u64>() {
+            Ok(tokens) if tokens > 0 => Some(tokens),
+            _ => return Err("The maximum of output tokens must be a positive whole number".into()),
+        },
+    };
+    let temperature = match temperature.trim() {
+        "" => None,
+        temperature => match temperature.parse::<f32>() {
+            Ok(temperature) if (0.0..=2.0).contains(&temperature) => Some(temperature),
+            _ => return Err("The temperature must be a number from 0 to 2".into()),
+        },
+    };
+    Ok(GenerationParameters {
+        max_output_tokens,
+        temperature,
+        stop_sequences: parse_stop_sequences(stop_sequences),
+    })
+}
+
+/// Parses stop sequences separated by commas, in which `\n`, `\t`, `\,` and `\\` are escapes.
+fn parse_stop_sequences(text: &str) -> Vec<String> {
+    let mut sequences = vec![String::new()];
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        let sequence = sequences.last_mut().unwrap();
+        match char {
+            '\\' => match chars.next() {
+                Some('n') => sequence.push('\n'),
+                Some('t') => sequence.push('\t'),
+                Some(escaped) => sequence.push(escaped),
+                None => sequence.push('\\'),
+            },
+            ',' => sequences.push(String::new()),
+            char => sequence.push(char),
+        }
+    }
+    sequences
+        .into_iter()
+        .map(|sequence| sequence.trim_matches(' ').to_string())
+        .filter(|sequence| !sequence.is_empty())
+        .collect()
+}
+
+fn format_stop_sequences(sequences: &[String]) -> String {
+    sequences
+        .iter()
+        .map(|sequence| {
+            sequence
+                .replace('\\', "\\\\")
+                .replace('\n', "\\n")
+                .replace('\t', "\\t")
+                .replace(',', "\\,")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl ModalView for GenerationParametersModal {}
+
+impl EventEmitter<DismissEvent> for GenerationParametersModal {}
+
+impl Focusable for GenerationParametersModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.max_output_tokens_editor
+            .as_ref()
+            .unwrap_or(&self.temperature_editor)
+            .focus_handle(cx)
+    }
+}
+
+impl Render for GenerationParametersModal {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("GenerationParameters")
+            .w(rems(34.))
+            .elevation_3(cx)
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                v_flex()
+                    .px_2()
+                    .py_1p5()
+                    .gap_0p5()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(Label::new("Generation Parameters"))
+                    .child(
+                        Label::new(
+                            "Sent to the agent with every message of this thread. Agents may ignore the ones they don't support.",
+                        )
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .p_2()
+                    .gap_2()
+                    .children(
+                        self.max_output_tokens_editor
+                            .as_ref()
+                            .map(|editor| self.render_field("Max Output Tokens", editor, cx)),
+                    )
+                    .child(self.render_field("Temperature", &self.temperature_editor, cx))
+                    .child(self.render_field(
+                        "Stop Sequences, Separated by Commas",
+                        &self.stop_sequences_editor,
+                        cx,
+                    ))
+                    .children(self.error.clone().map(|error| {
+                        Label::new(error)
+                            .size(LabelSize::Small)
+                            .color(Color::Error)
+                    })),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_parameters() {
+        assert_eq!(
+            parse_parameters("", " ", ""),
+            Ok(GenerationParameters::default())
+        );
+        assert_eq!(
+            parse_parameters("4096", "0.5", "END, \\n\\n, a\\,b,,"),
+            Ok(GenerationParameters {
+                max_output_tokens: Some(4096),
+                temperature: Some(0.5),
+                stop_sequences: vec!["END".into(), "\n\n".into(), "a,b".into()],
+            })
+        );
+        assert!(parse_parameters("0", "", "").is_err());
+        assert!(parse_parameters("", "3", "").is_err());
+        assert!(parse_parameters("", "warm", "").is_err());
+
+        let sequences = vec!["END".to_string(), "\n\n".to_string(), "a,b\\".to_string()];
+        assert_eq!(
+            parse_stop_sequences(&format_stop_sequences(&sequences)),
+            sequences
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use agent::ProjectMemory;
+use editor::{Editor, actions::SelectAll};
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Subscription};
+use ui::{ListItem, Tooltip, prelude::*};
+use workspace::{ModalView, Workspace};
+
+/// Lists the facts saved for the project, to add, edit or delete them.
+pub struct ProjectMemoryModal {
+    memory: Entity<ProjectMemory>,
+    fact_editor: Entity<Editor>,
+    /// The fact being edited in the editor; a new one is added otherwise.
+    editing: Option<usize>,
+    _subscription: Subscription,
+}
+
+impl ProjectMemoryModal {
+    pub fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        let memory = ProjectMemory::for_project(workspace.project(), cx);
+        workspace.toggle_modal(window, cx, |window, cx| {
+            let fact_editor = cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                editor.set_placeholder_text(
+                    "Add a fact, e.g. \"we use pnpm, not npm\"",
+                    window,
+                    cx,
+                );
+                editor
+            });
+            let subscription = cx.observe(&memory, |_, _, cx| cx.notify());
+            Self {
+                memory,
+                fact_editor,
+                editing: None,
+                _subscription: subscription,
+            }
+        });
+    }
+
+    fn edit(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(fact) = self.memory.read(cx).facts().get(ix).cloned() else {
+            return;
+        };
+        self.editing = Some(ix);
+        self.fact_editor.update(cx, |editor, cx| {
+            editor.set_text(fact, window, cx);
+            editor.select_all(&SelectAll, window, cx);
+        });
+        window.focus(&self.fact_editor.focus_handle(cx));
+        cx.notify();
+    }
+
+    fn remove(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(editing) = self.editing {
+            if editing == ix {
+                self.reset_editor(window, cx);
+            } else if editing > ix {
+                self.editing = Some(editing - 1);
+            }
+        }
+        self.memory.update(cx, |memory, cx| memory.remove(ix, cx));
+    }
+
+    fn reset_editor(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.editing = None;
+        self.fact_editor
+            .update(cx, |editor, cx| editor.set_text("", window, cx));
+        cx.notify();
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        if self.editing.is_some() {
+            self.reset_editor(window, cx);
+        } else {
+            cx.emit(DismissEvent);
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let fact = self.fact_editor.read(cx).text(cx);
+        let editing = self.editing;
+        self.memory.update(cx, |memory, cx| match editing {
+            Some(ix) => memory.replace(ix, fact, cx),
+            None => memory.add(fact, cx).detach(),
+        });
+        self.reset_editor(window, cx);
+    }
+
+    fn render_fact(&self, ix: usize, fact: &str, cx: &Context<Self>) -> impl IntoElement {
+        ListItem::new(ix)
+            .toggle_state(self.editing == Some(ix))
+            .child(Label::new(fact.to_string()).size(LabelSize::Small))
+            .end_slot(
+                h_flex()
+                    .gap_0p5()
+                    .child(
+                        IconButton::new(("edit-fact", ix), IconName::Pencil)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .tooltip(Tooltip::text("Edit"))
+                            .on_click(
+                                cx.listener(move |this, _, window, cx| this.edit(ix, window, cx)),
+                            ),
+                    )
+                    .child(
+                        IconButton::new(("delete-fact", ix), IconName::Trash)
+                            .icon_size(IconSize::Small)
+                            .icon_color(Color::Muted)
+                            .tooltip(Tooltip::text("Delete"))
+                            .on_click(
+                                cx.listener(move |this, _, window, cx| this.remove(ix, window, cx)),
+                            ),
+                    ),
+            )
+    }
+}
+
+impl ModalView for ProjectMemoryModal {}
+
+impl EventEmitter<DismissEvent> for ProjectMemoryModal {}
+
+impl Focusable for ProjectMemoryModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.fact_editor.focus_handle(cx)
+    }
+}
+
+impl Render for ProjectMemoryModal {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let facts = self
+            .memory
+            .read(cx)
+            .facts()
+            .iter()
+            .enumerate()
+            .map(|(ix, fact)| self.render_fact(ix, fact, cx).into_any_element())
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .key_context("ProjectMemory")
+            .w(rems(34.))
+            .elevation_3(cx)
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                v_flex()
+                    .px_2()
+                    .py_1p5()
+                    .gap_0p5()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(Label::new("Project Memory"))
+                    .child(
+                        Label::new("Facts attached to every new thread in this project.")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .child(
+                v_flex()
+                    .id("project-memory-facts")
+                    .max_h(rems(24.))
+                    .overflow_y_scroll()
+                    .p_1()
+                    .map(|this| {
+                        if facts.is_empty() {
+                            this.child(
+                                div().p_1().child(
+                                    Label::new("No facts have been saved yet.")
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                ),
+                            )
+                        } else {
+                            this.children(facts)
+                        }
+                    }),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1p5()
+                    .gap_2()
+                    .border_t_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        Icon::new(if self.editing.is_some() {
+                            IconName::Pencil
+                        } else {
+                            IconName::Plus
+                        })
+                        .size(IconSize::Small)
+                        .color(Color::Muted),
+                    )
+                    .child(self.fact_editor.clone()),
+            )
+    }
+}
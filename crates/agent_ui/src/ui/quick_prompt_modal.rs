@@ -0,0 +1,94 @@
+use editor::{Editor, EditorEvent};
+use gpui::{DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Subscription, WeakEntity};
+use ui::prelude::*;
+use workspace::{ModalView, Workspace};
+
+use crate::agent_panel::AgentPanel;
+
+/// A one-line prompt that is sent to the agent without navigating to the panel first.
+pub struct QuickPromptModal {
+    workspace: WeakEntity<Workspace>,
+    prompt_editor: Entity<Editor>,
+    _subscription: Subscription,
+}
+
+impl QuickPromptModal {
+    pub fn toggle(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        if workspace.panel::<AgentPanel>(cx).is_none() {
+            return;
+        }
+        let workspace_handle = cx.entity().downgrade();
+        workspace.toggle_modal(window, cx, |window, cx| {
+            let prompt_editor = cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                editor.set_placeholder_text("Ask the agent…", window, cx);
+                editor
+            });
+            let subscription = cx.subscribe(&prompt_editor, |_, _, event: &EditorEvent, cx| {
+                if let EditorEvent::Blurred = event {
+                    cx.emit(DismissEvent);
+                }
+            });
+            Self {
+                workspace: workspace_handle,
+                prompt_editor,
+                _subscription: subscription,
+            }
+        });
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let prompt = self.prompt_editor.read(cx).text(cx);
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            return;
+        }
+        let prompt = prompt.to_string();
+        cx.emit(DismissEvent);
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let Some(panel) = workspace.focus_panel::<AgentPanel>(window, cx) else {
+                    return;
+                };
+                panel.update(cx, |panel, cx| panel.send_quick_prompt(prompt, window, cx));
+            })
+            .ok();
+    }
+}
+
+impl ModalView for QuickPromptModal {}
+
+impl EventEmitter<DismissEvent> for QuickPromptModal {}
+
+impl Focusable for QuickPromptModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.prompt_editor.focus_handle(cx)
+    }
+}
+
+impl Render for QuickPromptModal {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("QuickPrompt")
+            .w(rems(34.))
+            .elevation_3(cx)
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1p5()
+                    .gap_2()
+                    .child(
+                        Icon::new(IconName::ZedAssistant)
+                            .size(IconSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(self.prompt_editor.clone()),
+            )
+    }
+}
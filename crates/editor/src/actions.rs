@@ -433,6 +433,9 @@ actions!(
         CopyFileNameWithoutExtension,
         /// Copies a permalink to the current line.
         CopyPermalinkToLine,
+        /// Copies the `path:line` locations of all references to the symbol whose
+        /// reference-count hint is under the cursor.
+        CopyReferencesList,
         /// Cuts selected text to the clipboard.
         Cut,
         /// Deletes the character after the cursor.
@@ -617,6 +620,8 @@ actions!(
         NextEditPrediction,
         /// Scrolls to the next screen.
         NextScreen,
+        /// Moves cursor to the next symbol whose reference hint shows no references.
+        NextUnreferencedSymbol,
         /// Opens the context menu at cursor position.
         OpenContextMenu,
         /// Opens excerpts from the current file.
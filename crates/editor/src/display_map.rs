@@ -503,6 +503,14 @@ impl DisplayMap {
         }
     }
 
+    pub(crate) fn clear_inlay_highlights(&mut self, type_id: TypeId, inlay_ids: &[InlayId]) {
+        self.inlay_highlights.update(&type_id, |highlights| {
+            for inlay_id in inlay_ids {
+                highlights.remove(inlay_id);
+            }
+        });
+    }
+
     pub fn text_highlights(&self, type_id: TypeId) -> Option<(HighlightStyle, &[Range<Anchor>])> {
         let highlights = self.text_highlights.get(&HighlightKey::Type(type_id))?;
         Some((highlights.0, &highlights.1))
@@ -107,12 +107,11 @@ impl Inlay {
         }
     }
 
-    pub fn symbol_ref_hint<T: Into<Rope>>(id: usize, position: Anchor, text: T) -> Self {
+    pub fn provider<T: Into<Rope>>(namespace: u32, id: u32, position: Anchor, text: T) -> Self {
         Self {
-            id: InlayId::SymbolRefHint(id),
+            id: InlayId::Provider(namespace, id),
             position,
-            text: text.into(),
-            color: None,
+            content: InlayContent::Text(text.into()),
         }
     }
 
@@ -382,7 +381,7 @@ impl<'a> Iterator for InlayChunks<'a> {
                     }),
                     InlayId::Hint(_) => self.highlight_styles.inlay_hint,
                     InlayId::DebuggerValue(_) => self.highlight_styles.inlay_hint,
-                    InlayId::SymbolRefHint(_) => self.highlight_styles.inlay_hint,
+                    InlayId::Provider(..) => self.highlight_styles.inlay_hint,
                     InlayId::Color(_) => {
                         if let InlayContent::Color(color) = inlay.content {
                             renderer = Some(ChunkRenderer {
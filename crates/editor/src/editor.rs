@@ -25,6 +25,7 @@ mod hover_links;
 pub mod hover_popover;
 mod indent_guides;
 mod inlay_hint_cache;
+mod inlay_provider;
 pub mod items;
 mod jsx_tag_auto_close;
 mod linked_editing_ranges;
@@ -46,6 +47,7 @@ mod edit_prediction_tests;
 #[cfg(test)]
 mod editor_tests;
 mod signature_help;
+mod symbol_reference_counts;
 #[cfg(any(test, feature = "test-support"))]
 pub mod test;
 
@@ -61,6 +63,7 @@ pub use element::{
 };
 pub use git::blame::BlameRenderer;
 pub use hover_popover::hover_markdown_style;
+pub use inlay_provider::{EditorInlayProvider, ProviderInlay};
 pub use items::MAX_TAB_TITLE_LEN;
 pub use lsp::CompletionContext;
 pub use lsp_ext::lsp_tasks;
@@ -71,6 +74,7 @@ pub use multi_buffer::{
 pub use proposed_changes_editor::{
     ProposedChangeLocation, ProposedChangesEditor, ProposedChangesEditorToolbar,
 };
+pub use symbol_reference_counts::SymbolReferenceCounts;
 pub use text::Bias;
 
 use ::git::{
@@ -116,6 +120,7 @@ use hover_links::{HoverLink, HoveredLinkState, InlayHighlight, find_file};
 use hover_popover::{HoverState, hide_hover};
 use indent_guides::ActiveIndentGuidesState;
 use inlay_hint_cache::{InlayHintCache, InlaySplice, InvalidationStrategy};
+use inlay_provider::RegisteredInlayProvider;
 use itertools::{Either, Itertools};
 use language::{
     AutoindentMode, BlockCommentConfig, BracketMatch, BracketPair, Buffer, BufferRow,
@@ -281,7 +286,8 @@ impl InlineValueCache {
 pub enum InlayId {
     EditPrediction(u32),
     DebuggerValue(u32),
-    SymbolRefHint(u32),
+    /// An inlay of an [`EditorInlayProvider`], by provider namespace and index.
+    Provider(u32, u32),
     // LSP
     Hint(u32),
     Color(u32),
@@ -292,7 +298,7 @@ impl InlayId {
         match self {
             Self::EditPrediction(id) => *id,
             Self::DebuggerValue(id) => *id,
-            Self::SymbolRefHint(id) => *id,
+            Self::Provider(_, id) => *id,
             Self::Hint(id) => *id,
             Self::Color(id) => *id,
         }
@@ -1115,6 +1121,7 @@ pub struct Editor {
     hovered_link_state: Option<HoveredLinkState>,
     edit_prediction_provider: Option<RegisteredEditPredictionProvider>,
     code_action_providers: Vec<Rc<dyn CodeActionProvider>>,
+    inlay_providers: Vec<RegisteredInlayProvider>,
     active_edit_prediction: Option<EditPredictionState>,
     /// Used to prevent flickering as the user types while the menu is open
     stale_edit_prediction_in_menu: Option<EditPredictionState>,
@@ -2146,6 +2153,7 @@ impl Editor {
             next_completion_id: 0,
             next_inlay_id: 0,
             code_action_providers,
+            inlay_providers: Vec::new(),
             available_code_actions: None,
             code_actions_task: None,
             quick_selection_highlight_task: None,
@@ -16260,6 +16268,43 @@ impl Editor {
         }
     }
 
+    /// Moves the cursor to the next symbol counted as having no references, wrapping
+    /// around to the start of the file.
+    pub fn next_unreferenced_symbol(
+        &mut self,
+        _: &NextUnreferencedSymbol,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(buffer) = self.buffer.read(cx).as_singleton() else {
+            return;
+        };
+        let snapshot = buffer.read(cx).snapshot();
+        let cursor = self
+            .selections
+            .newest_anchor()
+            .head()
+            .text_anchor
+            .to_offset(&snapshot);
+        let Some(target) = SymbolReferenceCounts::next_unreferenced(&snapshot, cursor, cx) else {
+            return;
+        };
+        let Some(target) = self
+            .buffer
+            .read(cx)
+            .snapshot(cx)
+            .as_singleton_anchor(target)
+        else {
+            return;
+        };
+        self.change_selections(
+            SelectionEffects::scroll(Autoscroll::center()),
+            window,
+            cx,
+            |s| s.select_anchor_ranges([target..target]),
+        );
+    }
+
     pub fn go_to_next_document_highlight(
         &mut self,
         _: &GoToNextDocumentHighlight,
@@ -20860,6 +20905,7 @@ impl Editor {
                 self.active_indent_guides_state.dirty = true;
                 self.refresh_active_diagnostics(cx);
                 self.refresh_code_actions(window, cx);
+                self.refresh_inlay_providers(window, cx);
                 self.refresh_selected_text_highlights(true, window, cx);
                 self.refresh_single_line_folds(window, cx);
                 refresh_matching_bracket_highlights(self, cx);
@@ -0,0 +1,359 @@
+use std::{any::TypeId, rc::Rc, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::future::join_all;
+use gpui::{App, Context, Entity, Global, HighlightStyle, SharedString, Task, Window};
+use language::{Buffer, language_settings::all_language_settings};
+use text::Anchor;
+use util::ResultExt as _;
+
+use crate::{Editor, InlayId, display_map::Inlay, hover_links::InlayHighlight};
+
+/// A source of inlays that the editor keeps up to date as its buffers change,
+/// such as per-function coverage or complexity annotations.
+pub trait EditorInlayProvider {
+    /// Identifies the provider; inlays of providers with different ids never replace each other.
+    fn id(&self) -> Arc<str>;
+
+    /// Computes every inlay the provider wants to show in the buffer.
+    fn inlays(
+        &self,
+        buffer: &Entity<Buffer>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<ProviderInlay>>>;
+}
+
+/// An inlay computed by an [`EditorInlayProvider`].
+#[derive(Clone, Debug)]
+pub struct ProviderInlay {
+    pub position: Anchor,
+    pub text: SharedString,
+    /// Layered over the inlay hint style.
+    pub style: Option<HighlightStyle>,
+}
+
+/// Inlay id namespaces handed out per provider id, stable for the whole session
+/// so that a provider's ids are the same in every editor.
+#[derive(Default)]
+struct InlayProviderNamespaces {
+    namespaces: HashMap<Arc<str>, u32>,
+}
+
+impl Global for InlayProviderNamespaces {}
+
+fn namespace_for(id: Arc<str>, cx: &mut App) -> u32 {
+    let namespaces = &mut cx.default_global::<InlayProviderNamespaces>().namespaces;
+    let next_namespace = namespaces.len() as u32;
+    *namespaces.entry(id).or_insert(next_namespace)
+}
+
+enum ProviderInlayHighlights {}
+
+pub(crate) struct RegisteredInlayProvider {
+    provider: Rc<dyn EditorInlayProvider>,
+    namespace: u32,
+    /// Ids of the inlays currently shown for this provider.
+    inlays: Vec<InlayId>,
+    /// Bumped on every refresh, so that results of superseded refreshes are dropped.
+    revision: u64,
+    refresh_task: Task<()>,
+}
+
+impl Editor {
+    pub fn add_inlay_provider(
+        &mut self,
+        provider: Rc<dyn EditorInlayProvider>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let id = provider.id();
+        if self
+            .inlay_providers
+            .iter()
+            .any(|registered| registered.provider.id() == id)
+        {
+            return;
+        }
+
+        self.inlay_providers.push(RegisteredInlayProvider {
+            provider,
+            namespace: namespace_for(id.clone(), cx),
+            inlays: Vec::new(),
+            revision: 0,
+            refresh_task: Task::ready(()),
+        });
+        self.refresh_inlay_provider(id, Duration::ZERO, window, cx);
+    }
+
+    pub fn remove_inlay_provider(&mut self, id: Arc<str>, cx: &mut Context<Self>) {
+        let Some(ix) = self
+            .inlay_providers
+            .iter()
+            .position(|registered| registered.provider.id() == id)
+        else {
+            return;
+        };
+        let registered = self.inlay_providers.remove(ix);
+        self.display_map.update(cx, |display_map, cx| {
+            display_map.clear_inlay_highlights(
+                TypeId::of::<ProviderInlayHighlights>(),
+                &registered.inlays,
+            );
+            display_map.splice_inlays(&registered.inlays, Vec::new(), cx);
+        });
+        cx.notify();
+    }
+
+    /// Whether the inlay is currently shown for the provider with the given id.
+    pub fn is_provider_inlay(&self, id: &str, inlay_id: InlayId) -> bool {
+        self.inlay_providers.iter().any(|registered| {
+            registered.provider.id().as_ref() == id && registered.inlays.contains(&inlay_id)
+        })
+    }
+
+    /// Recomputes the inlays of every registered provider after a debounce.
+    pub fn refresh_inlay_providers(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let debounce = Duration::from_millis(
+            all_language_settings(None, cx)
+                .defaults
+                .inlay_hints
+                .edit_debounce_ms,
+        );
+        let ids = self
+            .inlay_providers
+            .iter()
+            .map(|registered| registered.provider.id())
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.refresh_inlay_provider(id, debounce, window, cx);
+        }
+    }
+
+    /// Recomputes the inlays of a single provider, e.g. when only its own inputs changed.
+    pub fn refresh_inlay_provider(
+        &mut self,
+        id: Arc<str>,
+        debounce: Duration,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let buffers = self
+            .buffer
+            .read(cx)
+            .all_buffers()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let Some(registered) = self
+            .inlay_providers
+            .iter_mut()
+            .find(|registered| registered.provider.id() == id)
+        else {
+            return;
+        };
+        registered.revision += 1;
+        let revision = registered.revision;
+        let provider = registered.provider.clone();
+        registered.refresh_task = cx.spawn_in(window, async move |editor, cx| {
+            if !debounce.is_zero() {
+                cx.background_executor().timer(debounce).await;
+            }
+            let Ok(tasks) = cx.update(|window, cx| {
+                buffers
+                    .iter()
+                    .map(|buffer| provider.inlays(buffer, window, cx))
+                    .collect::<Vec<_>>()
+            }) else {
+                return;
+            };
+            let results = join_all(tasks).await;
+            editor
+                .update(cx, |editor, cx| {
+                    let inlays = buffers
+                        .iter()
+                        .zip(results)
+                        .filter_map(|(buffer, result)| Some((buffer, result.log_err()?)))
+                        .flat_map(|(buffer, inlays)| {
+                            editor.provider_inlays_in_excerpts(buffer, inlays, cx)
+                        })
+                        .collect();
+                    editor.splice_provider_inlays(&id, revision, inlays, cx);
+                })
+                .ok();
+        });
+    }
+
+    /// Resolves buffer positions to the excerpts showing them, dropping positions not in view.
+    fn provider_inlays_in_excerpts(
+        &self,
+        buffer: &Entity<Buffer>,
+        inlays: Vec<ProviderInlay>,
+        cx: &App,
+    ) -> Vec<(crate::Anchor, ProviderInlay)> {
+        let multi_buffer = self.buffer.read(cx);
+        let multi_buffer_snapshot = multi_buffer.snapshot(cx);
+        let buffer_snapshot = buffer.read(cx).snapshot();
+        let excerpts = multi_buffer.excerpts_for_buffer(buffer_snapshot.remote_id(), cx);
+        inlays
+            .into_iter()
+            .filter_map(|inlay| {
+                let position = excerpts.iter().find_map(|(excerpt_id, range)| {
+                    let in_excerpt = range
+                        .context
+                        .start
+                        .cmp(&inlay.position, &buffer_snapshot)
+                        .is_le()
+                        && inlay
+                            .position
+                            .cmp(&range.context.end, &buffer_snapshot)
+                            .is_le();
+                    in_excerpt
+                        .then(|| {
+                            multi_buffer_snapshot.anchor_in_excerpt(*excerpt_id, inlay.position)
+                        })
+                        .flatten()
+                })?;
+                Some((position, inlay))
+            })
+            .collect()
+    }
+
+    fn splice_provider_inlays(
+        &mut self,
+        id: &Arc<str>,
+        revision: u64,
+        inlays: Vec<(crate::Anchor, ProviderInlay)>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(registered) = self
+            .inlay_providers
+            .iter_mut()
+            .find(|registered| &registered.provider.id() == id)
+        else {
+            return;
+        };
+        if registered.revision != revision {
+            return;
+        }
+
+        let namespace = registered.namespace;
+        let mut highlights = Vec::new();
+        let to_insert = inlays
+            .into_iter()
+            .enumerate()
+            .map(|(ix, (position, inlay))| {
+                if let Some(style) = inlay.style {
+                    highlights.push((
+                        InlayHighlight {
+                            inlay: InlayId::Provider(namespace, ix as u32),
+                            inlay_position: position,
+                            range: 0..inlay.text.len(),
+                        },
+                        style,
+                    ));
+                }
+                Inlay::provider(namespace, ix as u32, position, inlay.text.as_ref())
+            })
+            .collect::<Vec<_>>();
+        let to_remove = std::mem::replace(
+            &mut registered.inlays,
+            to_insert.iter().map(|inlay| inlay.id).collect(),
+        );
+
+        self.display_map.update(cx, |display_map, cx| {
+            // Ids are reused across batches, so the previous batch's styles must not linger.
+            display_map.clear_inlay_highlights(TypeId::of::<ProviderInlayHighlights>(), &to_remove);
+            display_map.splice_inlays(&to_remove, to_insert, cx);
+            for (highlight, style) in highlights {
+                display_map.highlight_inlays(
+                    TypeId::of::<ProviderInlayHighlights>(),
+                    vec![highlight],
+                    style,
+                );
+            }
+        });
+        cx.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{editor_tests::init_test, test::editor_test_context::EditorTestContext};
+    use gpui::{FontWeight, TestAppContext};
+    use std::cell::RefCell;
+
+    /// Shows an inlay at each offset of its list, with the given style.
+    struct TestInlayProvider(Rc<RefCell<Vec<(usize, Option<HighlightStyle>)>>>);
+
+    impl EditorInlayProvider for TestInlayProvider {
+        fn id(&self) -> Arc<str> {
+            "test".into()
+        }
+
+        fn inlays(
+            &self,
+            buffer: &Entity<Buffer>,
+            _: &mut Window,
+            cx: &mut App,
+        ) -> Task<Result<Vec<ProviderInlay>>> {
+            let buffer = buffer.read(cx);
+            Task::ready(Ok(self
+                .0
+                .borrow()
+                .iter()
+                .map(|(offset, style)| ProviderInlay {
+                    position: buffer.anchor_after(*offset),
+                    text: "hint".into(),
+                    style: *style,
+                })
+                .collect()))
+        }
+    }
+
+    #[gpui::test]
+    async fn test_provider_inlay_highlights_are_replaced(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        let mut cx = EditorTestContext::new(cx).await;
+        cx.set_state("one two threeˇ");
+
+        let highlighted_inlays = |cx: &mut EditorTestContext| {
+            cx.update_editor(|editor, _, cx| {
+                editor
+                    .display_map
+                    .update(cx, |display_map, cx| display_map.snapshot(cx))
+                    .inlay_highlights::<ProviderInlayHighlights>()
+                    .map_or(0, |highlights| highlights.iter().count())
+            })
+        };
+        let refresh = |cx: &mut EditorTestContext| {
+            cx.update_editor(|editor, window, cx| {
+                editor.refresh_inlay_provider("test".into(), Duration::ZERO, window, cx)
+            });
+            cx.run_until_parked();
+        };
+
+        let style = HighlightStyle {
+            font_weight: Some(FontWeight::BOLD),
+            ..Default::default()
+        };
+        let inlays = Rc::new(RefCell::new(vec![(3, Some(style)), (7, Some(style))]));
+        cx.update_editor(|editor, window, cx| {
+            editor.add_inlay_provider(Rc::new(TestInlayProvider(inlays.clone())), window, cx)
+        });
+        cx.run_until_parked();
+        assert_eq!(highlighted_inlays(&mut cx), 2);
+
+        *inlays.borrow_mut() = vec![(3, None)];
+        refresh(&mut cx);
+        assert_eq!(highlighted_inlays(&mut cx), 0);
+
+        *inlays.borrow_mut() = vec![(7, Some(style))];
+        refresh(&mut cx);
+        assert_eq!(highlighted_inlays(&mut cx), 1);
+
+        cx.update_editor(|editor, _, cx| editor.remove_inlay_provider("test".into(), cx));
+        assert_eq!(highlighted_inlays(&mut cx), 0);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     Anchor, Autoscroll, Editor, EditorEvent, EditorSettings, ExcerptId, ExcerptRange, FormatTarget,
     MultiBuffer, MultiBufferSnapshot, NavigationData, ReportEditorEvent, SearchWithinRange,
-    SelectionEffects, ToPoint as _,
+    SelectionEffects, SymbolReferenceCounts, ToPoint as _,
     display_map::HighlightKey,
     editor_settings::SeedQuerySetting,
     persistence::{DB, SerializedEditor},
@@ -718,6 +718,16 @@ impl Item for Editor {
             .and_then(|buffer| buffer.read(cx).file())
             .is_some_and(|file| file.disk_state() == DiskState::Deleted);
 
+        let is_edited_by_agent = self
+            .buffer()
+            .read(cx)
+            .as_singleton()
+            .and_then(|buffer| {
+                let path = buffer.read(cx).project_path(cx)?;
+                Some(self.project()?.read(cx).is_edited_by_agent(&path))
+            })
+            .unwrap_or(false);
+
         h_flex()
             .gap_2()
             .child(
@@ -733,6 +743,13 @@ impl Item for Editor {
                         .color(Color::Muted),
                 )
             })
+            .when(is_edited_by_agent, |this| {
+                this.child(
+                    Icon::new(IconName::Diff)
+                        .size(IconSize::XSmall)
+                        .color(Color::Accent),
+                )
+            })
             .into_any_element()
     }
 
@@ -971,10 +988,21 @@ impl Item for Editor {
             font: Some(settings.buffer_font.clone()),
         }];
 
-        breadcrumbs.extend(symbols.into_iter().map(|symbol| BreadcrumbText {
-            text: symbol.text,
-            highlights: Some(symbol.highlight_ranges),
-            font: Some(settings.buffer_font.clone()),
+        let buffer_snapshot = buffer.snapshot();
+        breadcrumbs.extend(symbols.into_iter().map(|symbol| {
+            let mut text = symbol.text;
+            if let Some(count) = SymbolReferenceCounts::count_at(
+                &buffer_snapshot,
+                symbol.range.start.text_anchor,
+                cx,
+            ) {
+                text.push_str(&format!(" ({count})"));
+            }
+            BreadcrumbText {
+                text,
+                highlights: Some(symbol.highlight_ranges),
+                font: Some(settings.buffer_font.clone()),
+            }
         }));
         Some(breadcrumbs)
     }
@@ -0,0 +1,63 @@
+use collections::HashMap;
+use gpui::{App, Global};
+use language::BufferSnapshot;
+use text::{Anchor, BufferId, ToOffset};
+
+/// Reference counts computed for the symbols of open buffers, shared so that other views
+/// (the outline panel, breadcrumbs) can display them without querying language servers again.
+#[derive(Default)]
+pub struct SymbolReferenceCounts {
+    buffers: HashMap<BufferId, Vec<(Anchor, usize)>>,
+}
+
+impl Global for SymbolReferenceCounts {}
+
+impl SymbolReferenceCounts {
+    /// The id of the [`crate::EditorInlayProvider`] showing the counts next to symbols.
+    pub const INLAY_PROVIDER_ID: &str = "symbol_reference_hints";
+
+    /// Replaces the counts for a buffer, keyed by the start of each symbol's outline item.
+    /// They must be in the order of the outline items, which edits preserve.
+    pub fn set(buffer_id: BufferId, counts: Vec<(Anchor, usize)>, cx: &mut App) {
+        cx.default_global::<Self>()
+            .buffers
+            .insert(buffer_id, counts);
+    }
+
+    pub fn clear(buffer_id: BufferId, cx: &mut App) {
+        if cx
+            .try_global::<Self>()
+            .is_some_and(|counts| counts.buffers.contains_key(&buffer_id))
+        {
+            cx.global_mut::<Self>().buffers.remove(&buffer_id);
+        }
+    }
+
+    /// Returns the count for the symbol whose outline item starts at `position`, if known.
+    /// It's looked up for every row of the outline, so it's a binary search.
+    pub fn count_at(buffer: &BufferSnapshot, position: Anchor, cx: &App) -> Option<usize> {
+        let counts = cx.try_global::<Self>()?.buffers.get(&buffer.remote_id())?;
+        let offset = position.to_offset(buffer);
+        let ix = counts
+            .binary_search_by_key(&offset, |(start, _)| start.to_offset(buffer))
+            .ok()?;
+        Some(counts[ix].1)
+    }
+
+    /// Returns the start of the first symbol without references after `offset`,
+    /// or the first one in the buffer if there is none after it.
+    pub fn next_unreferenced(buffer: &BufferSnapshot, offset: usize, cx: &App) -> Option<Anchor> {
+        let counts = cx.try_global::<Self>()?.buffers.get(&buffer.remote_id())?;
+        let mut unreferenced = counts
+            .iter()
+            .filter(|(_, count)| *count == 0)
+            .map(|(start, _)| (start.to_offset(buffer), *start))
+            .collect::<Vec<_>>();
+        unreferenced.sort_by_key(|(start, _)| *start);
+        unreferenced
+            .iter()
+            .find(|(start, _)| *start > offset)
+            .or_else(|| unreferenced.first())
+            .map(|(_, start)| *start)
+    }
+}
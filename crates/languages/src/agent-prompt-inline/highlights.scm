@@ -0,0 +1,15 @@
+(emphasis) @emphasis.markup
+(strong_emphasis) @emphasis.strong.markup
+(code_span) @text.literal.markup
+
+; Mentions are inserted as links whose text starts with `@`.
+(inline_link
+  (link_text) @link_text.markup
+  (#match? @link_text.markup "^@"))
+(inline_link
+  (link_destination) @link_uri.markup)
+
+[
+  (uri_autolink)
+  (email_autolink)
+] @link_uri.markup
@@ -0,0 +1,23 @@
+[
+  (atx_heading)
+  (setext_heading)
+] @title.markup
+
+[
+  (list_marker_plus)
+  (list_marker_minus)
+  (list_marker_star)
+  (list_marker_dot)
+  (list_marker_parenthesis)
+] @punctuation.list_marker.markup
+
+(block_quote_marker) @punctuation.markup
+
+[
+  (fenced_code_block_delimiter)
+  (info_string)
+] @punctuation.embedded.markup
+
+(fenced_code_block
+  (code_fence_content) @text.literal.markup)
+(indented_code_block) @text.literal.markup
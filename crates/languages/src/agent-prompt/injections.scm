@@ -0,0 +1,7 @@
+(fenced_code_block
+  (info_string
+    (language) @injection.language)
+  (code_fence_content) @injection.content)
+
+((inline) @injection.content
+ (#set! injection.language "Agent Prompt Inline"))
@@ -108,6 +108,14 @@ pub fn init(languages: Arc<LanguageRegistry>, fs: Arc<dyn Fs>, node: NodeRuntime
     let yaml_lsp_adapter = Arc::new(yaml::YamlLspAdapter::new(node));
 
     let built_in_languages = [
+        LanguageInfo {
+            name: "agent-prompt",
+            ..Default::default()
+        },
+        LanguageInfo {
+            name: "agent-prompt-inline",
+            ..Default::default()
+        },
         LanguageInfo {
             name: "bash",
             context: Some(Arc::new(bash::bash_task_context())),
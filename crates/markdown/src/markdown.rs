@@ -27,7 +27,10 @@ use gpui::{
 };
 use language::{Language, LanguageRegistry, Rope};
 use parser::CodeBlockMetadata;
-use parser::{MarkdownEvent, MarkdownTag, MarkdownTagEnd, parse_links_only, parse_markdown};
+pub use parser::LinkDetector;
+use parser::{
+    MarkdownEvent, MarkdownTag, MarkdownTagEnd, parse_links_only, parse_markdown_with_links,
+};
 use pulldown_cmark::Alignment;
 use sum_tree::TreeMap;
 use theme::SyntaxTheme;
@@ -112,6 +115,7 @@ pub struct Markdown {
 
 struct Options {
     parse_links_only: bool,
+    link_detector: Option<LinkDetector>,
 }
 
 pub enum CodeBlockRenderer {
@@ -142,6 +146,11 @@ pub type CodeBlockRenderFn = Arc<
 pub type CodeBlockTransformFn =
     Arc<dyn Fn(AnyDiv, Range<usize>, CodeBlockMetadata, &mut Window, &App) -> AnyDiv>;
 
+/// Renders buttons shown next to the copy button of a code block, given the end of its
+/// range in the source, its content and its language.
+pub type CodeBlockActionsFn =
+    Arc<dyn Fn(usize, &str, Option<&Arc<Language>>, &mut Window, &App) -> AnyElement>;
+
 actions!(
     markdown,
     [
@@ -174,6 +183,7 @@ impl Markdown {
             fallback_code_block_language,
             options: Options {
                 parse_links_only: false,
+                link_detector: None,
             },
             copied_code_blocks: HashSet::default(),
         };
@@ -197,6 +207,7 @@ impl Markdown {
             fallback_code_block_language: None,
             options: Options {
                 parse_links_only: true,
+                link_detector: None,
             },
             copied_code_blocks: HashSet::default(),
         };
@@ -222,6 +233,24 @@ impl Markdown {
         self.parse(cx);
     }
 
+    /// Links what the detector finds in the text, besides URLs.
+    pub fn set_link_detector(
+        &mut self,
+        link_detector: Option<LinkDetector>,
+        cx: &mut Context<Self>,
+    ) {
+        let unchanged = match (&self.options.link_detector, &link_detector) {
+            (Some(current), Some(new)) => Arc::ptr_eq(current, new),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+        self.options.link_detector = link_detector;
+        self.parse(cx);
+    }
+
     pub fn reset(&mut self, source: SharedString, cx: &mut Context<Self>) {
         if source == self.source() {
             return;
@@ -299,6 +328,7 @@ impl Markdown {
     fn start_background_parse(&self, cx: &Context<Self>) -> Task<()> {
         let source = self.source.clone();
         let should_parse_links_only = self.options.parse_links_only;
+        let link_detector = self.options.link_detector.clone();
         let language_registry = self.language_registry.clone();
         let fallback = self.fallback_code_block_language.clone();
 
@@ -315,7 +345,8 @@ impl Markdown {
                 );
             }
 
-            let (events, language_names, paths) = parse_markdown(&source);
+            let (events, language_names, paths) =
+                parse_markdown_with_links(&source, link_detector.as_ref());
             let mut images_by_source_offset = HashMap::default();
             let mut languages_by_name = TreeMap::default();
             let mut languages_by_path = TreeMap::default();
@@ -454,6 +485,7 @@ pub struct MarkdownElement {
     markdown: Entity<Markdown>,
     style: MarkdownStyle,
     code_block_renderer: CodeBlockRenderer,
+    code_block_actions: Option<CodeBlockActionsFn>,
     on_url_click: Option<Box<dyn Fn(SharedString, &mut Window, &mut App)>>,
 }
 
@@ -467,6 +499,7 @@ impl MarkdownElement {
                 copy_button_on_hover: false,
                 border: false,
             },
+            code_block_actions: None,
             on_url_click: None,
         }
     }
@@ -497,6 +530,16 @@ impl MarkdownElement {
         self
     }
 
+    /// Adds buttons next to the copy button of the code blocks rendered by
+    /// [`CodeBlockRenderer::Default`].
+    pub fn code_block_actions(
+        mut self,
+        render: impl Fn(usize, &str, Option<&Arc<Language>>, &mut Window, &App) -> AnyElement + 'static,
+    ) -> Self {
+        self.code_block_actions = Some(Arc::new(render));
+        self
+    }
+
     pub fn on_url_click(
         mut self,
         handler: impl Fn(SharedString, &mut Window, &mut App) + 'static,
@@ -764,6 +807,7 @@ impl Element for MarkdownElement {
         };
 
         let mut current_code_block_metadata = None;
+        let mut current_code_block_language = None;
         let mut current_img_block_range: Option<Range<usize>> = None;
         for (range, event) in parsed_markdown.events.iter() {
             // Skip alt text for images that rendered
@@ -839,6 +883,7 @@ impl Element for MarkdownElement {
                             };
 
                             current_code_block_metadata = Some(metadata.clone());
+                            current_code_block_language = language.clone();
 
                             let is_indented = matches!(kind, CodeBlockKind::Indented);
 
@@ -1058,6 +1103,7 @@ impl Element for MarkdownElement {
                         }
 
                         let metadata = current_code_block_metadata.take();
+                        let language = current_code_block_language.take();
 
                         if let CodeBlockRenderer::Custom {
                             transform: Some(transform),
@@ -1087,6 +1133,9 @@ impl Element for MarkdownElement {
                                     ..content_range.end + range.start;
 
                                 let code = parsed_markdown.source()[content_range].to_string();
+                                let actions = self.code_block_actions.as_ref().map(|render| {
+                                    render(range.end, &code, language.as_ref(), window, cx)
+                                });
                                 let codeblock = render_copy_code_block_button(
                                     range.end,
                                     code,
@@ -1095,11 +1144,12 @@ impl Element for MarkdownElement {
                                 );
                                 el.child(
                                     h_flex()
-                                        .w_4()
                                         .absolute()
                                         .top_1p5()
                                         .right_1p5()
+                                        .gap_1()
                                         .justify_end()
+                                        .children(actions)
                                         .child(codeblock),
                                 )
                             });
@@ -1118,6 +1168,9 @@ impl Element for MarkdownElement {
                                     ..content_range.end + range.start;
 
                                 let code = parsed_markdown.source()[content_range].to_string();
+                                let actions = self.code_block_actions.as_ref().map(|render| {
+                                    render(range.end, &code, language.as_ref(), window, cx)
+                                });
                                 let codeblock = render_copy_code_block_button(
                                     range.end,
                                     code,
@@ -1126,12 +1179,13 @@ impl Element for MarkdownElement {
                                 );
                                 el.child(
                                     h_flex()
-                                        .w_4()
                                         .absolute()
                                         .top_0()
                                         .right_0()
+                                        .gap_1()
                                         .justify_end()
                                         .visible_on_hover("code_block")
+                                        .children(actions)
                                         .child(codeblock),
                                 )
                             });
@@ -20,12 +20,28 @@ const PARSE_OPTIONS: Options = Options::ENABLE_TABLES
     .union(Options::ENABLE_OLD_FOOTNOTES)
     .union(Options::ENABLE_GFM);
 
+/// Finds links in text outside of links and code that aren't URLs, such as issue
+/// references, returning the range of each in the text and its destination.
+pub type LinkDetector = Arc<dyn Fn(&str) -> Vec<(Range<usize>, SharedString)> + Send + Sync>;
+
 pub fn parse_markdown(
     text: &str,
 ) -> (
     Vec<(Range<usize>, MarkdownEvent)>,
     HashSet<SharedString>,
     HashSet<Arc<str>>,
+) {
+    parse_markdown_with_links(text, None)
+}
+
+/// Parses the markdown like [`parse_markdown`], also linking what the detector finds.
+pub fn parse_markdown_with_links(
+    text: &str,
+    link_detector: Option<&LinkDetector>,
+) -> (
+    Vec<(Range<usize>, MarkdownEvent)>,
+    HashSet<SharedString>,
+    HashSet<Arc<str>>,
 ) {
     let mut events = Vec::new();
     let mut language_names = HashSet::default();
@@ -228,9 +244,30 @@ pub fn parse_markdown(
                     finder.kinds(&[linkify::LinkKind::Url]);
 
                     // Find links in the merged text
-                    for link in finder.links(&merged_text) {
-                        let link_start_in_merged = link.start();
-                        let link_end_in_merged = link.end();
+                    let mut links = finder
+                        .links(&merged_text)
+                        .map(|link| {
+                            (
+                                link.start()..link.end(),
+                                SharedString::from(link.as_str().to_string()),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    if let Some(link_detector) = link_detector {
+                        for (range, dest_url) in link_detector(&merged_text) {
+                            if !links
+                                .iter()
+                                .any(|(link, _)| link.start < range.end && range.start < link.end)
+                            {
+                                links.push((range, dest_url));
+                            }
+                        }
+                        links.sort_by_key(|(range, _)| range.start);
+                    }
+
+                    for (link, dest_url) in links {
+                        let link_start_in_merged = link.start;
+                        let link_end_in_merged = link.end;
 
                         while ranges
                             .peek()
@@ -290,7 +327,7 @@ pub fn parse_markdown(
                             link_range.clone(),
                             MarkdownEvent::Start(MarkdownTag::Link {
                                 link_type: LinkType::Autolink,
-                                dest_url: SharedString::from(link.as_str().to_string()),
+                                dest_url,
                                 title: SharedString::default(),
                                 id: SharedString::default(),
                             }),
@@ -621,6 +658,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_detected_links() {
+        let link_detector: LinkDetector = Arc::new(|text: &str| {
+            text.match_indices('#')
+                .map(|(start, _)| {
+                    let digits = text[start + 1..]
+                        .chars()
+                        .take_while(char::is_ascii_digit)
+                        .count();
+                    let end = start + 1 + digits;
+                    (
+                        start..end,
+                        format!("https://x/{}", &text[start + 1..end]).into(),
+                    )
+                })
+                .collect()
+        });
+        assert_eq!(
+            parse_markdown_with_links("Fixes #12 in `#34`", Some(&link_detector)).0,
+            vec![
+                (0..18, Start(Paragraph)),
+                (0..6, Text),
+                (
+                    6..9,
+                    Start(Link {
+                        link_type: LinkType::Autolink,
+                        dest_url: "https://x/12".into(),
+                        title: "".into(),
+                        id: "".into(),
+                    })
+                ),
+                (6..9, Text),
+                (6..9, End(MarkdownTagEnd::Link)),
+                (9..13, Text),
+                (14..17, Code),
+                (0..18, End(MarkdownTagEnd::Paragraph))
+            ]
+        );
+    }
     #[test]
     fn test_incomplete_link() {
         assert_eq!(
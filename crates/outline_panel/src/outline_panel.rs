@@ -5,7 +5,7 @@ use collections::{BTreeSet, HashMap, HashSet, hash_map};
 use db::kvp::KEY_VALUE_STORE;
 use editor::{
     AnchorRangeExt, Bias, DisplayPoint, Editor, EditorEvent, ExcerptId, ExcerptRange,
-    MultiBufferSnapshot, RangeToAnchorExt, SelectionEffects,
+    MultiBufferSnapshot, RangeToAnchorExt, SelectionEffects, SymbolReferenceCounts,
     display_map::ToDisplayPoint,
     items::{entry_git_aware_label_color, entry_label_color},
     scroll::{Autoscroll, ScrollAnchor},
@@ -48,7 +48,7 @@ use theme::{SyntaxTheme, ThemeSettings};
 use ui::{
     ActiveTheme, ButtonCommon, Clickable, Color, ContextMenu, DynamicSpacing, FluentBuilder,
     HighlightedLabel, Icon, IconButton, IconButtonShape, IconName, IconSize, IndentGuideColors,
-    IndentGuideLayout, Label, LabelCommon, ListItem, ScrollAxes, Scrollbars, StyledExt,
+    IndentGuideLayout, Label, LabelCommon, LabelSize, ListItem, ScrollAxes, Scrollbars, StyledExt,
     StyledTypography, Toggleable, Tooltip, WithScrollbar, h_flex, v_flex,
 };
 use util::{RangeExt, ResultExt, TryFutureExt, debug_panic, rel_path::RelPath};
@@ -777,6 +777,10 @@ impl OutlinePanel {
             let icons_subscription = cx.observe_global::<FileIcons>(|_, cx| {
                 cx.notify();
             });
+            let reference_counts_subscription =
+                cx.observe_global::<SymbolReferenceCounts>(|_, cx| {
+                    cx.notify();
+                });
 
             let mut outline_panel_settings = *OutlinePanelSettings::get_global(cx);
             let mut current_theme = ThemeSettings::get_global(cx).clone();
@@ -887,6 +891,7 @@ impl OutlinePanel {
                 _subscriptions: vec![
                     settings_subscription,
                     icons_subscription,
+                    reference_counts_subscription,
                     focus_subscription,
                     workspace_subscription,
                     filter_update_subscription,
@@ -2197,14 +2202,28 @@ impl OutlinePanel {
             outline.buffer_id, outline.excerpt_id, outline.outline.range, &outline.outline.text,
         )));
 
-        let label_element = outline::render_item(
-            &outline.outline,
-            string_match
-                .map(|string_match| string_match.ranges().collect::<Vec<_>>())
-                .unwrap_or_default(),
-            cx,
-        )
-        .into_any_element();
+        let reference_count =
+            self.buffer_snapshot_for_id(outline.buffer_id, cx)
+                .and_then(|snapshot| {
+                    SymbolReferenceCounts::count_at(&snapshot, outline.outline.range.start, cx)
+                });
+        let label_element = h_flex()
+            .gap_1()
+            .child(outline::render_item(
+                &outline.outline,
+                string_match
+                    .map(|string_match| string_match.ranges().collect::<Vec<_>>())
+                    .unwrap_or_default(),
+                cx,
+            ))
+            .when_some(reference_count, |this, count| {
+                this.child(
+                    Label::new(count.to_string())
+                        .size(LabelSize::XSmall)
+                        .color(Color::Muted),
+                )
+            })
+            .into_any_element();
 
         let is_active = match self.selected_entry() {
             Some(PanelEntry::Outline(OutlineEntry::Outline(selected))) => {
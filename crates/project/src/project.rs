@@ -215,6 +215,8 @@ pub struct Project {
     settings_observer: Entity<SettingsObserver>,
     toolchain_store: Option<Entity<ToolchainStore>>,
     agent_location: Option<AgentLocation>,
+    /// Files the agent edited since the user last opened them.
+    agent_edited_paths: HashSet<ProjectPath>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -344,6 +346,7 @@ pub enum Event {
     ExpandedAllForEntry(WorktreeId, ProjectEntryId),
     EntryRenamed(ProjectTransaction),
     AgentLocationChanged,
+    AgentEditedPathsChanged,
 }
 
 pub struct AgentLocationChanged;
@@ -1223,6 +1226,7 @@ impl Project {
                 toolchain_store: Some(toolchain_store),
 
                 agent_location: None,
+                agent_edited_paths: HashSet::default(),
             }
         })
     }
@@ -1408,6 +1412,7 @@ impl Project {
 
                 toolchain_store: Some(toolchain_store),
                 agent_location: None,
+                agent_edited_paths: HashSet::default(),
             };
 
             // remote server -> local machine handlers
@@ -1661,6 +1666,7 @@ impl Project {
                 remotely_created_models: Arc::new(Mutex::new(RemotelyCreatedModels::default())),
                 toolchain_store: None,
                 agent_location: None,
+                agent_edited_paths: HashSet::default(),
             };
             project.set_role(role, cx);
             for worktree in worktrees {
@@ -4444,6 +4450,11 @@ impl Project {
     }
 
     pub fn set_active_path(&mut self, entry: Option<ProjectPath>, cx: &mut Context<Self>) {
+        if let Some(project_path) = &entry
+            && self.agent_edited_paths.remove(project_path)
+        {
+            cx.emit(Event::AgentEditedPathsChanged);
+        }
         let new_active_entry = entry.and_then(|project_path| {
             let worktree = self.worktree_for_id(project_path.worktree_id, cx)?;
             let entry = worktree.read(cx).entry_for_path(&project_path.path)?;
@@ -5228,6 +5239,59 @@ impl Project {
         })
     }
 
+    /// Whether any language server for the buffer supports both `textDocument/references`
+    /// and `textDocument/documentSymbol`.
+    pub fn any_language_server_supports_symbol_references(
+        &self,
+        buffer: &Buffer,
+        cx: &App,
+    ) -> bool {
+        self.any_language_server_capabilities(buffer, cx, |capabilities| {
+            one_of_supported(&capabilities.references_provider)
+                && one_of_supported(&capabilities.document_symbol_provider)
+        })
+    }
+
+    /// Whether any language server for the buffer supports both `textDocument/documentHighlight`
+    /// and `textDocument/documentSymbol`.
+    pub fn any_language_server_supports_document_highlights(
+        &self,
+        buffer: &Buffer,
+        cx: &App,
+    ) -> bool {
+        self.any_language_server_capabilities(buffer, cx, |capabilities| {
+            one_of_supported(&capabilities.document_highlight_provider)
+                && one_of_supported(&capabilities.document_symbol_provider)
+        })
+    }
+
+    fn any_language_server_capabilities(
+        &self,
+        buffer: &Buffer,
+        cx: &App,
+        predicate: impl Fn(&lsp::ServerCapabilities) -> bool,
+    ) -> bool {
+        let Some(language) = buffer.language().cloned() else {
+            return false;
+        };
+        let lsp_store = self.lsp_store.read(cx);
+        let relevant_language_servers = lsp_store
+            .languages
+            .lsp_adapters(&language.name())
+            .into_iter()
+            .map(|lsp_adapter| lsp_adapter.name())
+            .collect::<HashSet<_>>();
+        lsp_store
+            .language_server_statuses()
+            .filter_map(|(server_id, server_status)| {
+                relevant_language_servers
+                    .contains(&server_status.name)
+                    .then_some(server_id)
+            })
+            .filter_map(|server_id| lsp_store.lsp_server_capabilities.get(&server_id))
+            .any(predicate)
+    }
+
     pub fn language_server_id_for_name(
         &self,
         buffer: &Buffer,
@@ -5363,6 +5427,21 @@ impl Project {
         self.agent_location.clone()
     }
 
+    /// Marks a file as edited by the agent until the user opens it, unless it's open already.
+    pub fn mark_edited_by_agent(&mut self, project_path: ProjectPath, cx: &mut Context<Self>) {
+        let entry_id = self.entry_for_path(&project_path, cx).map(|entry| entry.id);
+        if entry_id.is_some() && entry_id == self.active_entry {
+            return;
+        }
+        if self.agent_edited_paths.insert(project_path) {
+            cx.emit(Event::AgentEditedPathsChanged);
+        }
+    }
+
+    pub fn is_edited_by_agent(&self, project_path: &ProjectPath) -> bool {
+        self.agent_edited_paths.contains(project_path)
+    }
+
     pub fn path_style(&self, cx: &App) -> PathStyle {
         self.worktree_store.read(cx).path_style()
     }
@@ -5731,6 +5810,14 @@ fn provide_inline_values(
     variables
 }
 
+fn one_of_supported<T>(provider: &Option<lsp::OneOf<bool, T>>) -> bool {
+    match provider {
+        Some(lsp::OneOf::Left(supported)) => *supported,
+        Some(lsp::OneOf::Right(_)) => true,
+        None => false,
+    }
+}
+
 #[cfg(test)]
 mod disable_ai_settings_tests {
     use super::*;
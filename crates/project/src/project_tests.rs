@@ -10046,3 +10046,36 @@ async fn test_find_project_path_abs(
         );
     });
 }
+
+#[gpui::test]
+async fn test_agent_edited_paths(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/dir"), json!({ "a.rs": "", "b.rs": "" }))
+        .await;
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let worktree_id = project.read_with(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let a = ProjectPath {
+        worktree_id,
+        path: rel_path("a.rs").into(),
+    };
+    let b = ProjectPath {
+        worktree_id,
+        path: rel_path("b.rs").into(),
+    };
+
+    project.update(cx, |project, cx| {
+        project.set_active_path(Some(a.clone()), cx);
+        project.mark_edited_by_agent(a.clone(), cx);
+        project.mark_edited_by_agent(b.clone(), cx);
+        // The open file was seen being edited.
+        assert!(!project.is_edited_by_agent(&a));
+        assert!(project.is_edited_by_agent(&b));
+
+        project.set_active_path(Some(b.clone()), cx);
+        assert!(!project.is_edited_by_agent(&b));
+    });
+}
@@ -208,6 +208,7 @@ struct EntryDetails {
     sticky: Option<StickyDetails>,
     filename_text_color: Color,
     diagnostic_severity: Option<DiagnosticSeverity>,
+    is_edited_by_agent: bool,
     git_status: GitSummary,
     is_private: bool,
     worktree_id: WorktreeId,
@@ -545,6 +546,7 @@ impl ProjectPanel {
                     project::Event::ActivateProjectPanel => {
                         cx.emit(PanelEvent::Activate);
                     }
+                    project::Event::AgentEditedPathsChanged => cx.notify(),
                     project::Event::DiskBasedDiagnosticsFinished { .. }
                     | project::Event::DiagnosticsUpdated { .. } => {
                         if ProjectPanelSettings::get_global(cx).show_diagnostics
@@ -4290,6 +4292,7 @@ impl ProjectPanel {
 
         let filename_text_color = details.filename_text_color;
         let diagnostic_severity = details.diagnostic_severity;
+        let is_edited_by_agent = details.is_edited_by_agent;
         let item_colors = get_item_color(is_sticky, cx);
 
         let canonical_path = details
@@ -4698,6 +4701,20 @@ impl ProjectPanel {
                                 .into_any_element(),
                         )
                     })
+                    .when(is_edited_by_agent, |this| {
+                        this.end_slot::<AnyElement>(
+                            div()
+                                .id("agent_edited_icon")
+                                .pr_3()
+                                .tooltip(Tooltip::text("Edited by the Agent"))
+                                .child(
+                                    Icon::new(IconName::Diff)
+                                        .size(IconSize::Indicator)
+                                        .color(Color::Accent),
+                                )
+                                .into_any_element(),
+                        )
+                    })
                     .child(if let Some(icon) = &icon {
                         if let Some((_, decoration_color)) =
                             entry_diagnostic_aware_icon_decoration_and_color(diagnostic_severity)
@@ -5017,6 +5034,10 @@ impl ProjectPanel {
             .diagnostics
             .get(&(worktree_id, entry.path.clone()))
             .cloned();
+        let is_edited_by_agent = self.project.read(cx).is_edited_by_agent(&ProjectPath {
+            worktree_id,
+            path: entry.path.clone(),
+        });
 
         let filename_text_color =
             entry_git_aware_label_color(git_status, entry.is_ignored, is_marked);
@@ -5042,6 +5063,7 @@ impl ProjectPanel {
             sticky,
             filename_text_color,
             diagnostic_severity,
+            is_edited_by_agent,
             git_status,
             is_private: entry.is_private,
             worktree_id,
@@ -925,6 +925,35 @@ impl ProjectSearchView {
         });
     }
 
+    /// Opens a new project search for `query` and runs it right away, e.g. to browse
+    /// the full results of a search performed elsewhere.
+    pub fn new_search_with_query(
+        workspace: &mut Workspace,
+        query: &str,
+        regex: bool,
+        included_files: Option<&str>,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let weak_workspace = cx.entity().downgrade();
+
+        let entity = cx.new(|cx| ProjectSearch::new(workspace.project().clone(), cx));
+        let search = cx.new(|cx| ProjectSearchView::new(weak_workspace, entity, window, cx, None));
+        workspace.add_item_to_active_pane(Box::new(search.clone()), None, true, window, cx);
+        search.update(cx, |search, cx| {
+            if search.search_options.contains(SearchOptions::REGEX) != regex {
+                search.toggle_search_option(SearchOptions::REGEX, cx);
+            }
+            search.set_query(query, window, cx);
+            if let Some(included_files) = included_files {
+                search.set_search_editor(SearchInputKind::Include, included_files, window, cx);
+                search.filters_enabled = true;
+            }
+            search.search(cx);
+            search.focus_results_editor(window, cx);
+        });
+    }
+
     /// Re-activate the most recently activated search in this pane or the most recent if it has been closed.
     /// If no search exists in the workspace, create a new one.
     pub fn deploy_search(
@@ -146,6 +146,15 @@ pub struct SettingsContent {
 
     pub title_bar: Option<TitleBarSettingsContent>,
 
+    /// Configuration for inline symbol reference-count hints.
+    pub symbol_reference_hints: Option<SymbolReferenceHintsSettingsContent>,
+
+    /// Cyclomatic complexity shown next to functions.
+    pub complexity_hints: Option<ComplexityHintsSettingsContent>,
+
+    /// When and by whom each top-level symbol was last modified, from git blame.
+    pub git_blame_hints: Option<GitBlameHintsSettingsContent>,
+
     /// Whether or not to enable Vim mode.
     ///
     /// Default: false
@@ -293,6 +302,98 @@ pub struct TitleBarSettingsContent {
     pub show_menus: Option<bool>,
 }
 
+/// Configuration for inline symbol reference-count hints.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom, Debug)]
+pub struct SymbolReferenceHintsSettingsContent {
+    /// Whether to also show how many more or fewer times the name of each symbol occurs in
+    /// the file than in its last committed version, e.g. "5 · name ↑2". This is a textual
+    /// count, not a change in references: it includes comments, strings and other symbols
+    /// with the same name.
+    ///
+    /// Default: false
+    pub show_name_delta_since_head: Option<bool>,
+    /// Whether to count the symbol's own declaration as a reference, for language
+    /// servers that include it in their results.
+    ///
+    /// Default: false
+    pub include_declaration: Option<bool>,
+    /// Whether to count references in files matched by `file_scan_exclusions` or
+    /// `private_files`, such as vendored or generated code.
+    ///
+    /// Default: false
+    pub include_excluded_files: Option<bool>,
+    /// Whether to briefly emphasize hints whose count changed since the previous
+    /// refresh, e.g. when a refactor leaves a function unreferenced.
+    ///
+    /// Default: true
+    pub highlight_changed_counts: Option<bool>,
+    /// Symbols that are expected to have no references, such as `main` or tests, and
+    /// get no hint. Patterns made of identifier characters match symbol names, with `*`
+    /// as a wildcard; other patterns match text in the declaration line or the
+    /// attributes and decorators right above it.
+    ///
+    /// Default: ["main", "test_*", "#[test]", "#[no_mangle]", "pub extern"]
+    pub entry_points: Option<Vec<String>>,
+    /// Files whose outline has more symbols than this are skipped, unless forced
+    /// from the status bar.
+    ///
+    /// Default: 2000
+    pub max_symbols: Option<usize>,
+    /// How the reference-count hints are drawn.
+    pub style: Option<SymbolReferenceHintsStyleContent>,
+}
+
+/// Styling of the reference-count hints, layered over the default inlay hint style.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom, Debug)]
+pub struct SymbolReferenceHintsStyleContent {
+    /// Text color, either a syntax theme token such as "comment" or a hex color.
+    ///
+    /// Default: null (the inlay hint color)
+    pub color: Option<String>,
+    /// Background color, either a syntax theme token or a hex color.
+    ///
+    /// Default: null (the inlay hint background)
+    pub background: Option<String>,
+    /// Number of spaces between the count and the symbol it annotates.
+    ///
+    /// Default: 1
+    pub padding: Option<usize>,
+}
+
+/// Configuration for cyclomatic complexity hints.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom, Debug)]
+pub struct ComplexityHintsSettingsContent {
+    /// Whether to show the cyclomatic complexity of each function next to it.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// Complexity from which the hint is drawn in the warning color.
+    ///
+    /// Default: 10
+    pub warning_threshold: Option<u32>,
+    /// Complexity from which the hint is drawn in the error color.
+    ///
+    /// Default: 20
+    pub error_threshold: Option<u32>,
+}
+
+/// Configuration for git last-modified hints.
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom, Debug)]
+pub struct GitBlameHintsSettingsContent {
+    /// Whether to show when and by whom each top-level symbol was last modified.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// Whether to include the author of the last change in the hint.
+    ///
+    /// Default: true
+    pub show_author: Option<bool>,
+}
+
 /// Configuration of audio in Zed.
 #[skip_serializing_none]
 #[derive(Clone, PartialEq, Default, Serialize, Deserialize, JsonSchema, MergeFrom, Debug)]
@@ -62,6 +62,14 @@ pub struct AgentSettingsContent {
     ///
     /// Default: false
     pub always_allow_tool_actions: Option<bool>,
+    /// Kinds of low-risk tool calls that are allowed without asking for confirmation,
+    /// while the remaining ones, such as edits and terminal commands, still ask.
+    ///
+    /// Like `always_allow_tool_actions`, this has no effect on external agents that support permission modes.
+    pub auto_approve: Option<AutoApproveContent>,
+    /// Seconds after which a tool call waiting for confirmation is allowed, unless
+    /// the countdown is canceled. Zero waits for an explicit choice.
+    pub auto_approve_countdown: Option<AutoApproveCountdownContent>,
     /// Where to show a popup notification when the agent is waiting for user input.
     ///
     /// Default: "primary_screen"
@@ -70,6 +78,8 @@ pub struct AgentSettingsContent {
     ///
     /// Default: false
     pub play_sound_when_agent_done: Option<bool>,
+    /// How to notify about each change of a thread's status.
+    pub notifications: Option<AgentNotificationsContent>,
     /// Whether to display agent edits in single-file editors in addition to the review multibuffer pane.
     ///
     /// Default: true
@@ -107,6 +117,85 @@ pub struct AgentSettingsContent {
     ///
     /// Default: 4
     pub message_editor_min_lines: Option<usize>,
+    /// Whether to append the computed reference count of each symbol when a file
+    /// with reference hints is mentioned in the agent panel.
+    ///
+    /// Default: false
+    pub include_symbol_reference_counts: Option<bool>,
+    /// Whether to attach the path of the active file and the cursor position,
+    /// or the selected lines, to every message sent to the agent.
+    ///
+    /// Default: false
+    pub include_active_editor_context: Option<bool>,
+    /// Whether to tell the agent, with the next message, that the git branch was
+    /// switched since its last turn, as the files it has read may have changed.
+    ///
+    /// Default: true
+    pub notify_agent_of_branch_changes: Option<bool>,
+    /// Whether to tell agents that support it about files changed on disk outside
+    /// of their own edits, such as by the user or by git. Disable it for projects
+    /// whose files change too often.
+    ///
+    /// Default: true
+    pub notify_agent_of_file_changes: Option<bool>,
+    /// How many entries of a thread keep their views in memory. The views of older
+    /// entries are released behind a "load earlier entries" button, while the entries
+    /// themselves stay in the thread; 0 keeps every view.
+    ///
+    /// Default: 0
+    pub max_live_entries: Option<usize>,
+    /// Whether to render only prompts and answers, hiding thinking, tool calls that
+    /// don't need confirmation, and the end-of-turn summary.
+    ///
+    /// Default: false
+    pub minimal_transcript: Option<bool>,
+    /// Whether to discard the thinking of agents as it streams in, without storing
+    /// or rendering it.
+    ///
+    /// Default: false
+    pub discard_thinking: Option<bool>,
+    /// Whether to show a button that shares a thread as a secret GitHub gist. Sharing
+    /// needs a GITHUB_TOKEN that can create gists in the environment.
+    ///
+    /// Default: true
+    pub enable_thread_sharing: Option<bool>,
+    /// The memory use, in megabytes, above which a warning is shown for an
+    /// external agent's process; 0 disables the warning.
+    ///
+    /// Default: 2048
+    pub process_memory_warning_mb: Option<u64>,
+    /// Whether to summarize the earlier messages of a thread automatically once it
+    /// approaches the model's context window, instead of offering to do so.
+    ///
+    /// Default: false
+    pub auto_summarize_context: Option<bool>,
+    /// Whether tool calls and thinking blocks in the agent panel start out expanded.
+    ///
+    /// Default: false
+    pub expand_entries: Option<bool>,
+    /// Tool calls that "Always Allow" was chosen for, which are allowed without
+    /// confirmation on every thread from then on.
+    pub always_allowed: Option<AlwaysAllowedContent>,
+    /// A policy for the tool calls of every agent, applied before asking for confirmation.
+    /// It takes precedence over the other settings allowing tool calls.
+    ///
+    /// Default: "ask"
+    pub tool_permission_profile: Option<ToolPermissionProfile>,
+    /// Whether external agents may write files through Zed. Either way, they can only read and
+    /// write files in the project's root directories through Zed.
+    ///
+    /// Default: true
+    pub allow_agent_file_writes: Option<bool>,
+    /// Whether to also show the commands the agent runs in tabs of the terminal panel, to
+    /// interact with them and scroll through all of their output.
+    ///
+    /// Default: false
+    pub show_commands_in_terminal_panel: Option<bool>,
+    /// Rules running the agent in the background when files of the project are saved or
+    /// gain errors, to propose changes for review. The agent never edits files for them.
+    ///
+    /// Default: []
+    pub automations: Option<Vec<AgentAutomationContent>>,
 }
 
 impl AgentSettingsContent {
@@ -166,6 +255,102 @@ impl AgentSettingsContent {
     }
 }
 
+#[skip_serializing_none]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AutoApproveContent {
+    /// Whether tool calls that read files are allowed without confirmation.
+    ///
+    /// Default: false
+    pub reads: Option<bool>,
+    /// Whether tool calls that search the project are allowed without confirmation.
+    ///
+    /// Default: false
+    pub searches: Option<bool>,
+    /// Whether tool calls that fetch from the network are allowed without confirmation.
+    ///
+    /// Default: false
+    pub fetches: Option<bool>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AutoApproveCountdownContent {
+    /// Countdown for tool calls that edit, move or delete files.
+    ///
+    /// Default: 0
+    pub edits: Option<u64>,
+    /// Countdown for tool calls that run terminal commands.
+    ///
+    /// Default: 0
+    pub executes: Option<u64>,
+    /// Countdown for tool calls that fetch from the network.
+    ///
+    /// Default: 0
+    pub fetches: Option<u64>,
+    /// Countdown for any other tool call.
+    ///
+    /// Default: 0
+    pub others: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentAutomationContent {
+    /// When to run the agent.
+    pub trigger: AgentAutomationTrigger,
+    /// The files to run it for, as a glob matching their paths in the project's root
+    /// directories, e.g. "src/**/*.rs".
+    pub path: String,
+    /// What to ask the agent, e.g. "Propose a fix for these errors". The path of the file
+    /// and its errors are added to it.
+    pub prompt: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentAutomationTrigger {
+    /// Whenever a matching file is saved.
+    Save,
+    /// When the diagnostics of a matching file have more errors after it was saved than
+    /// before.
+    NewErrors,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AlwaysAllowedContent {
+    /// Programs, such as "cargo", whose terminal commands are allowed. Commands that
+    /// chain or redirect to other commands still ask.
+    ///
+    /// Default: []
+    pub commands: Option<Vec<String>>,
+    /// Tools, such as MCP tools, whose calls are allowed, by name.
+    ///
+    /// Default: []
+    pub tools: Option<Vec<String>>,
+    /// Kinds of tool calls, such as "edit" or "execute", that are allowed for agents
+    /// that don't name the tools they call.
+    ///
+    /// Default: []
+    pub kinds: Option<Vec<String>>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentNotificationsContent {
+    /// How to notify when the agent has finished its response.
+    ///
+    /// Default: "system_notification"
+    pub done: Option<AgentNotificationKind>,
+    /// How to notify when a tool call is waiting for confirmation.
+    ///
+    /// Default: "system_notification"
+    pub approval_needed: Option<AgentNotificationKind>,
+    /// How to notify when the agent stopped because of an error or a refusal.
+    ///
+    /// Default: "system_notification"
+    pub error: Option<AgentNotificationKind>,
+}
+
 #[skip_serializing_none]
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, JsonSchema, MergeFrom)]
 pub struct AgentProfileContent {
@@ -213,6 +398,59 @@ pub enum NotifyWhenAgentWaiting {
     Never,
 }
 
+#[derive(
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    MergeFrom,
+    PartialEq,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPermissionProfile {
+    /// Ask for confirmation as the other settings say.
+    #[default]
+    Ask,
+    /// Allow tool calls that read or search without confirmation.
+    AllowReads,
+    /// Allow every tool call without confirmation.
+    AllowAll,
+    /// Reject tool calls that run commands without confirmation.
+    DenyExecute,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    MergeFrom,
+    PartialEq,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentNotificationKind {
+    /// Don't notify.
+    None,
+    /// Show a toast in the window of the thread, unless the thread is focused.
+    Visual,
+    /// Play a sound, unless the window is active.
+    Sound,
+    /// Show a popup on the screens chosen by `notify_when_agent_waiting`,
+    /// unless the window is active.
+    #[default]
+    SystemNotification,
+}
+
 #[skip_serializing_none]
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq)]
 pub struct LanguageModelSelection {
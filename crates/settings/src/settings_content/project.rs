@@ -51,6 +51,45 @@ pub struct ProjectSettingsContent {
 
     /// The list of custom Git hosting providers.
     pub git_hosting_providers: Option<ExtendingVec<GitHostingProviderConfig>>,
+
+    /// Per-function test coverage shown next to symbols.
+    pub coverage_hints: Option<CoverageHintsSettingsContent>,
+
+    /// Links to the issues and pull requests referenced in agent threads.
+    pub issue_links: Option<IssueLinksSettingsContent>,
+}
+
+/// Configuration for test coverage hints.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct CoverageHintsSettingsContent {
+    /// Whether to show the coverage percentage of each function next to it.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// Coverage reports to read, in lcov or Cobertura XML format, relative to
+    /// the worktree root.
+    ///
+    /// Default: ["coverage/lcov.info", "lcov.info", "coverage.xml"]
+    pub files: Option<Vec<String>>,
+}
+
+/// Configuration for linking references to issues, such as `#1234`, in agent threads.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct IssueLinksSettingsContent {
+    /// The URL of an issue or pull request, with `{id}` standing for the referenced id,
+    /// e.g. "https://github.com/zed-industries/zed/issues/{id}". References aren't linked
+    /// when this isn't set.
+    ///
+    /// Default: null
+    pub url_template: Option<String>,
+    /// Regular expressions matching references of other issue trackers, such as
+    /// "[A-Z]+-\\d+", besides `#1234` and `GH-1234`. The first capture group, or
+    /// otherwise the whole match, is the id.
+    ///
+    /// Default: []
+    pub patterns: Option<Vec<String>>,
 }
 
 #[skip_serializing_none]
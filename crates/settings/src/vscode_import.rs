@@ -178,6 +178,7 @@ impl VsCodeSettings {
             base_keymap: Some(BaseKeymapContent::VSCode),
             calls: None,
             collaboration_panel: None,
+            complexity_hints: None,
             debugger: None,
             diagnostics: None,
             disable_ai: None,
@@ -185,6 +186,7 @@ impl VsCodeSettings {
             extension: ExtensionSettingsContent::default(),
             file_finder: None,
             git: self.git_settings_content(),
+            git_blame_hints: None,
             git_panel: self.git_panel_settings_content(),
             global_lsp_settings: None,
             helix_mode: None,
@@ -206,6 +208,7 @@ impl VsCodeSettings {
             server_url: None,
             session: None,
             status_bar: self.status_bar_settings_content(),
+            symbol_reference_hints: None,
             tab_bar: self.tab_bar_settings_content(),
             tabs: self.item_settings_content(),
             telemetry: self.telemetry_settings_content(),
@@ -396,6 +399,8 @@ impl VsCodeSettings {
             load_direnv: None,
             slash_commands: None,
             git_hosting_providers: None,
+            coverage_hints: None,
+            issue_links: None,
         }
     }
 
@@ -5541,6 +5541,72 @@ pub(crate) fn settings_data(cx: &App) -> Vec<SettingsPage> {
                     metadata: None,
                     files: USER,
                 }),
+                SettingsPageItem::SettingItem(SettingItem {
+                    title: "Tool Permission Profile",
+                    description: "A policy for the tool calls of every agent, applied before asking for confirmation.",
+                    field: Box::new(SettingField {
+                        json_path: Some("agent.tool_permission_profile"),
+                        pick: |settings_content| {
+                            settings_content
+                                .agent
+                                .as_ref()?
+                                .tool_permission_profile
+                                .as_ref()
+                        },
+                        write: |settings_content, value| {
+                            settings_content
+                                .agent
+                                .get_or_insert_default()
+                                .tool_permission_profile = value;
+                        },
+                    }),
+                    metadata: None,
+                    files: USER,
+                }),
+                SettingsPageItem::SettingItem(SettingItem {
+                    title: "Allow Agent File Writes",
+                    description: "Whether external agents may write files through Zed. Either way, they can only access files in the project's root directories through Zed.",
+                    field: Box::new(SettingField {
+                        json_path: Some("agent.allow_agent_file_writes"),
+                        pick: |settings_content| {
+                            settings_content
+                                .agent
+                                .as_ref()?
+                                .allow_agent_file_writes
+                                .as_ref()
+                        },
+                        write: |settings_content, value| {
+                            settings_content
+                                .agent
+                                .get_or_insert_default()
+                                .allow_agent_file_writes = value;
+                        },
+                    }),
+                    metadata: None,
+                    files: USER,
+                }),
+                SettingsPageItem::SettingItem(SettingItem {
+                    title: "Show Commands in Terminal Panel",
+                    description: "Whether to also show the commands the agent runs in tabs of the terminal panel.",
+                    field: Box::new(SettingField {
+                        json_path: Some("agent.show_commands_in_terminal_panel"),
+                        pick: |settings_content| {
+                            settings_content
+                                .agent
+                                .as_ref()?
+                                .show_commands_in_terminal_panel
+                                .as_ref()
+                        },
+                        write: |settings_content, value| {
+                            settings_content
+                                .agent
+                                .get_or_insert_default()
+                                .show_commands_in_terminal_panel = value;
+                        },
+                    }),
+                    metadata: None,
+                    files: USER,
+                }),
                 SettingsPageItem::SettingItem(SettingItem {
                     title: "Single File Review",
                     description: "When enabled, agent edits will also be displayed in single-file buffers for review.",
@@ -472,6 +472,7 @@ fn init_renderers(cx: &mut App) {
         .add_basic_renderer::<settings::SteppingGranularity>(render_dropdown)
         .add_basic_renderer::<settings::NotifyWhenAgentWaiting>(render_dropdown)
         .add_basic_renderer::<settings::NotifyWhenAgentWaiting>(render_dropdown)
+        .add_basic_renderer::<settings::ToolPermissionProfile>(render_dropdown)
         .add_basic_renderer::<settings::ImageFileSizeUnit>(render_dropdown)
         .add_basic_renderer::<settings::StatusStyle>(render_dropdown)
         .add_basic_renderer::<settings::PaneSplitDirectionHorizontal>(render_dropdown)
@@ -1094,7 +1095,12 @@ fn all_language_names(cx: &App) -> Vec<SharedString> {
                 .languages
                 .language_names()
                 .into_iter()
-                .filter(|name| name.as_ref() != "Zed Keybind Context")
+                .filter(|name| {
+                    !matches!(
+                        name.as_ref(),
+                        "Zed Keybind Context" | "Agent Prompt" | "Agent Prompt Inline"
+                    )
+                })
                 .map(Into::into)
                 .collect()
         })
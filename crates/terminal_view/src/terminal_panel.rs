@@ -744,6 +744,61 @@ impl TerminalPanel {
         })
     }
 
+    /// Shows a terminal created elsewhere, such as one running an agent's command, in a tab
+    /// of the panel, or activates the tab already showing it.
+    pub fn show_terminal(
+        workspace: &mut Workspace,
+        terminal: Entity<Terminal>,
+        focus: bool,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        let Some(terminal_panel) = workspace.panel::<Self>(cx) else {
+            return;
+        };
+        let existing = terminal_panel
+            .read(cx)
+            .center
+            .panes()
+            .into_iter()
+            .find_map(|pane| {
+                let index = pane.read(cx).items().position(|item| {
+                    item.act_as::<TerminalView>(cx)
+                        .is_some_and(|view| view.read(cx).terminal() == &terminal)
+                })?;
+                Some((pane.clone(), index))
+            });
+
+        if focus {
+            workspace.focus_panel::<Self>(window, cx);
+        } else {
+            workspace.open_panel::<Self>(window, cx);
+        }
+        if let Some((pane, index)) = existing {
+            terminal_panel.update(cx, |terminal_panel, cx| {
+                terminal_panel.activate_terminal_view(&pane, index, focus, window, cx)
+            });
+            return;
+        }
+
+        let terminal_view = Box::new(cx.new(|cx| {
+            TerminalView::new(
+                terminal,
+                workspace.weak_handle(),
+                workspace.database_id(),
+                workspace.project().downgrade(),
+                window,
+                cx,
+            )
+        }));
+        terminal_panel.update(cx, |terminal_panel, cx| {
+            terminal_panel.active_pane.update(cx, |pane, cx| {
+                pane.add_item(terminal_view, true, focus, None, window, cx);
+            });
+            terminal_panel.serialize(cx);
+        });
+    }
+
     pub fn add_terminal_task(
         &mut self,
         task: SpawnInTerminal,
@@ -1,11 +1,15 @@
 mod app_menus;
+pub(crate) mod complexity_hints;
 pub mod component_preview;
+pub(crate) mod coverage_hints;
 pub mod edit_prediction_registry;
+pub(crate) mod git_blame_hints;
 #[cfg(target_os = "macos")]
 pub(crate) mod mac_only_instance;
 mod migrate;
 mod open_listener;
 mod quick_action_bar;
+pub(crate) mod reference_summary;
 pub(crate) mod symbol_ref_hints;
 #[cfg(target_os = "windows")]
 pub(crate) mod windows_only_instance;
@@ -126,6 +130,12 @@ actions!(
         Zoom,
         /// Toggle Symbol Ref Hints (inline symbol reference-count hints)
         ToggleSymbolRefHints,
+        /// Toggles symbol reference hints for the active file only, for this session.
+        ToggleSymbolRefHintsForFile,
+        /// Toggles cyclomatic complexity hints next to functions.
+        ToggleComplexityHints,
+        /// Opens a report of the symbols with few or no references across the workspace.
+        OpenReferenceSummary,
         /// Triggers a test panic for debugging.
         TestPanic,
         /// Triggers a hard crash for debugging.
@@ -332,6 +342,11 @@ pub fn initialize_workspace(
     prompt_builder: Arc<PromptBuilder>,
     cx: &mut App,
 ) {
+    symbol_ref_hints::init(cx);
+    coverage_hints::init(cx);
+    complexity_hints::init(cx);
+    git_blame_hints::init(cx);
+
     let mut _on_close_subscription = bind_on_window_closed(cx);
     cx.observe_global::<SettingsStore>(move |cx| {
         _on_close_subscription = bind_on_window_closed(cx);
@@ -441,7 +456,25 @@ pub fn initialize_workspace(
             status_bar.add_right_item(symbol_ref_hints_for_status, window, cx);
         });
         workspace.register_action({
-            move |workspace, _: &ToggleSymbolRefHints, _window, cx| {
+            let symbol_ref_hints = symbol_ref_hints.clone();
+            move |workspace, _: &editor::actions::CopyReferencesList, _window, cx| {
+                if let Some(editor) = workspace.active_item_as::<Editor>(cx) {
+                    symbol_ref_hints
+                        .update(cx, |hints, cx| hints.copy_references_list(&editor, cx));
+                }
+            }
+        });
+        workspace.register_action({
+            let symbol_ref_hints = symbol_ref_hints.clone();
+            move |workspace, _: &ToggleSymbolRefHintsForFile, window, cx| {
+                if let Some(editor) = workspace.active_item_as::<Editor>(cx) {
+                    symbol_ref_hints
+                        .update(cx, |hints, cx| hints.toggle_for_file(&editor, window, cx));
+                }
+            }
+        });
+        workspace.register_action({
+            move |workspace, _: &ToggleSymbolRefHints, window, cx| {
                 let new_enabled = symbol_ref_hints.update(cx, |s, _| {
                     s.enabled = !s.enabled;
                     s.enabled
@@ -449,12 +482,8 @@ pub fn initialize_workspace(
                 if let Some(editor) = workspace.active_item_as::<Editor>(cx) {
                     let core_inlays_on = editor.read(cx).inlay_hints_enabled();
                     if !new_enabled || !core_inlays_on {
-                        editor.update(cx, |editor, cx| {
-                            let to_remove: Vec<editor::InlayId> = (0..1024)
-                                .map(|i| editor::InlayId::SymbolRefHint(900_000_000 + i as u32))
-                                .collect();
-                            editor.splice_inlays(&to_remove, Vec::new(), cx);
-                        });
+                        symbol_ref_hints
+                            .update(cx, |hints, cx| hints.bump_and_clear(&editor, window, cx));
                     }
                 }
             }
@@ -805,6 +834,18 @@ fn register_actions(
             })
             .detach()
         })
+        .register_action({
+            let fs = app_state.fs.clone();
+            move |_, _: &ToggleComplexityHints, _window, cx| {
+                let enabled = complexity_hints::ComplexityHintsSettings::get_global(cx).enabled;
+                update_settings_file(fs.clone(), cx, move |settings, _| {
+                    settings.complexity_hints.get_or_insert_default().enabled = Some(!enabled);
+                });
+            }
+        })
+        .register_action(|workspace, _: &OpenReferenceSummary, window, cx| {
+            reference_summary::ReferenceSummary::deploy(workspace, window, cx);
+        })
         .register_action({
             let fs = app_state.fs.clone();
             move |_, action: &zed_actions::IncreaseUiFontSize, _window, cx| {
@@ -0,0 +1,180 @@
+use editor::{Editor, EditorInlayProvider, ProviderInlay};
+use gpui::{App, Entity, HighlightStyle, Task, Window};
+use language::{Buffer, BufferSnapshot, ToOffset as _};
+use settings::{Settings, SettingsContent, SettingsStore};
+use std::{ops::Range, rc::Rc, sync::Arc};
+use ui::ActiveTheme as _;
+
+/// Syntax node kinds, across languages, that add a branch to a function's control flow.
+const DECISION_POINT_KINDS: &[&str] = &[
+    "if_expression",
+    "if_statement",
+    "elif_clause",
+    "else_if_clause",
+    "while_expression",
+    "while_statement",
+    "for_expression",
+    "for_statement",
+    "for_in_statement",
+    "loop_expression",
+    "do_statement",
+    "match_arm",
+    "case_clause",
+    "switch_case",
+    "catch_clause",
+    "except_clause",
+    "conditional_expression",
+    "ternary_expression",
+    "&&",
+    "||",
+    "and",
+    "or",
+];
+
+pub fn init(cx: &mut App) {
+    ComplexityHintsSettings::register(cx);
+
+    cx.observe_new(|editor: &mut Editor, window, cx| {
+        let Some(window) = window else {
+            return;
+        };
+        if !editor.mode().is_full() {
+            return;
+        }
+        editor.add_inlay_provider(Rc::new(ComplexityInlayProvider), window, cx);
+
+        let mut settings = ComplexityHintsSettings::get_global(cx).clone();
+        cx.observe_global_in::<SettingsStore>(window, move |editor, window, cx| {
+            let new_settings = ComplexityHintsSettings::get_global(cx);
+            if *new_settings != settings {
+                settings = new_settings.clone();
+                editor.refresh_inlay_providers(window, cx);
+            }
+        })
+        .detach();
+    })
+    .detach();
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplexityHintsSettings {
+    pub enabled: bool,
+    pub warning_threshold: u32,
+    pub error_threshold: u32,
+}
+
+impl Settings for ComplexityHintsSettings {
+    fn from_settings(content: &SettingsContent) -> Self {
+        let content = content.complexity_hints.clone().unwrap();
+        Self {
+            enabled: content.enabled.unwrap(),
+            warning_threshold: content.warning_threshold.unwrap(),
+            error_threshold: content.error_threshold.unwrap(),
+        }
+    }
+}
+
+struct ComplexityInlayProvider;
+
+impl EditorInlayProvider for ComplexityInlayProvider {
+    fn id(&self) -> Arc<str> {
+        "complexity".into()
+    }
+
+    fn inlays(
+        &self,
+        buffer: &Entity<Buffer>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<Vec<ProviderInlay>>> {
+        let settings = ComplexityHintsSettings::get_global(cx).clone();
+        if !settings.enabled {
+            return Task::ready(Ok(Vec::new()));
+        }
+        let status = cx.theme().status();
+        let (warning, error) = (status.warning, status.error);
+        let snapshot = buffer.read(cx).snapshot();
+
+        cx.background_spawn(async move {
+            Ok(snapshot
+                .outline(None)
+                .items
+                .into_iter()
+                .filter_map(|item| {
+                    let range =
+                        item.range.start.to_offset(&snapshot)..item.range.end.to_offset(&snapshot);
+                    let complexity = cyclomatic_complexity(&snapshot, range)?;
+                    let color = if complexity >= settings.error_threshold {
+                        Some(error)
+                    } else if complexity >= settings.warning_threshold {
+                        Some(warning)
+                    } else {
+                        None
+                    };
+                    Some(ProviderInlay {
+                        position: item.range.start,
+                        text: format!("CC {complexity} ").into(),
+                        style: color.map(|color| HighlightStyle {
+                            color: Some(color),
+                            ..HighlightStyle::default()
+                        }),
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+/// Counts one plus the decision points of the function-like node spanning `range`,
+/// or returns `None` when the range isn't a function.
+fn cyclomatic_complexity(snapshot: &BufferSnapshot, range: Range<usize>) -> Option<u32> {
+    let layer = snapshot.syntax_layer_at(range.start)?;
+    let node = layer
+        .node()
+        .descendant_for_byte_range(range.start, range.end)?;
+    if !is_function_like(node.kind()) {
+        return None;
+    }
+
+    let mut complexity = 1;
+    let mut cursor = node.walk();
+    'traversal: loop {
+        if DECISION_POINT_KINDS.contains(&cursor.node().kind()) {
+            complexity += 1;
+        }
+        if cursor.goto_first_child() {
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                break 'traversal;
+            }
+        }
+    }
+    Some(complexity)
+}
+
+fn is_function_like(kind: &str) -> bool {
+    kind.contains("function") || kind.contains("method")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{AppContext as _, TestAppContext};
+
+    #[gpui::test]
+    async fn test_cyclomatic_complexity(cx: &mut TestAppContext) {
+        let text = "fn f(x: i32) -> i32 {\n    if x > 0 && x < 10 { 1 } else { match x { 0 => 2, _ => 3 } }\n}\n";
+        let buffer = cx.new(|cx| Buffer::local(text, cx).with_language(language::rust_lang(), cx));
+        cx.executor().run_until_parked();
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+
+        assert_eq!(cyclomatic_complexity(&snapshot, 0..text.len() - 1), Some(5));
+        let condition = text.find("x > 0").unwrap();
+        assert_eq!(
+            cyclomatic_complexity(&snapshot, condition..condition + "x > 0".len()),
+            None
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use collections::HashMap;
+use editor::{Editor, EditorInlayProvider, ProviderInlay};
+use futures::{
+    FutureExt as _,
+    future::{Shared, join_all},
+};
+use gpui::{
+    App, AppContext as _, Context, Entity, EntityId, EventEmitter, Global, HighlightStyle,
+    Subscription, Task, WeakEntity, Window,
+};
+use language::ToPoint as _;
+use project::{Project, UpdatedEntriesSet, WorktreeId};
+use settings::{Settings, SettingsContent, SettingsLocation, SettingsStore};
+use std::{
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+use ui::ActiveTheme as _;
+
+pub fn init(cx: &mut App) {
+    CoverageHintsSettings::register(cx);
+
+    cx.observe_new(|editor: &mut Editor, window, cx| {
+        let Some(window) = window else {
+            return;
+        };
+        if !editor.mode().is_full() {
+            return;
+        }
+        let Some(project) = editor.project().cloned() else {
+            return;
+        };
+        let reports = CoverageReports::for_project(&project, cx);
+        editor.add_inlay_provider(
+            Rc::new(CoverageInlayProvider {
+                reports: reports.clone(),
+            }),
+            window,
+            cx,
+        );
+        cx.subscribe_in(
+            &reports,
+            window,
+            |editor, _, _: &CoverageReportsChanged, window, cx| {
+                editor.refresh_inlay_providers(window, cx);
+            },
+        )
+        .detach();
+    })
+    .detach();
+}
+
+#[derive(Clone, Debug)]
+pub struct CoverageHintsSettings {
+    pub enabled: bool,
+    pub files: Vec<String>,
+}
+
+impl Settings for CoverageHintsSettings {
+    fn from_settings(content: &SettingsContent) -> Self {
+        let content = content.project.coverage_hints.clone().unwrap();
+        Self {
+            enabled: content.enabled.unwrap(),
+            files: content.files.unwrap(),
+        }
+    }
+}
+
+/// Execution counts per zero-based line of each source file in a coverage report.
+#[derive(Debug, Default)]
+struct CoverageReport {
+    files: HashMap<PathBuf, HashMap<u32, u64>>,
+}
+
+impl CoverageReport {
+    /// Parses an lcov or Cobertura XML report, resolving relative source paths against `root`.
+    fn parse(text: &str, root: &Path) -> Self {
+        if text.trim_start().starts_with('<') {
+            Self::parse_cobertura(text, root)
+        } else {
+            Self::parse_lcov(text, root)
+        }
+    }
+
+    fn parse_lcov(text: &str, root: &Path) -> Self {
+        let mut report = Self::default();
+        let mut current_file = None;
+        for line in text.lines().map(str::trim) {
+            if let Some(path) = line.strip_prefix("SF:") {
+                current_file = Some(root.join(path));
+            } else if let Some(data) = line.strip_prefix("DA:")
+                && let Some(path) = &current_file
+            {
+                let mut fields = data.split(',');
+                let line_number = fields.next().and_then(|field| field.parse::<u32>().ok());
+                let hits = fields.next().and_then(|field| field.parse::<u64>().ok());
+                if let Some((line_number, hits)) = line_number.zip(hits) {
+                    report.record(path, line_number, hits);
+                }
+            } else if line == "end_of_record" {
+                current_file = None;
+            }
+        }
+        report
+    }
+
+    fn parse_cobertura(text: &str, root: &Path) -> Self {
+        let mut report = Self::default();
+        let mut current_file = None;
+        for tag in text.split('<').skip(1) {
+            let tag = tag.split('>').next().unwrap_or_default();
+            if tag.starts_with("class ") {
+                current_file = xml_attribute(tag, "filename").map(|path| root.join(path));
+            } else if tag.starts_with("line ")
+                && let Some(path) = &current_file
+            {
+                let line_number =
+                    xml_attribute(tag, "number").and_then(|value| value.parse::<u32>().ok());
+                let hits = xml_attribute(tag, "hits").and_then(|value| value.parse::<u64>().ok());
+                if let Some((line_number, hits)) = line_number.zip(hits) {
+                    report.record(path, line_number, hits);
+                }
+            }
+        }
+        report
+    }
+
+    /// Records the hits of a one-based line, as both formats number them.
+    fn record(&mut self, path: &Path, line_number: u32, hits: u64) {
+        self.files
+            .entry(path.to_path_buf())
+            .or_default()
+            .insert(line_number.saturating_sub(1), hits);
+    }
+}
+
+fn xml_attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let pattern = format!(" {name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let end = start + tag[start..].find('"')?;
+    Some(&tag[start..end])
+}
+
+/// Percentage of the instrumented lines within `rows` that were executed.
+fn coverage_percent(lines: &HashMap<u32, u64>, rows: RangeInclusive<u32>) -> Option<u32> {
+    let (instrumented, covered) = rows
+        .filter_map(|row| lines.get(&row))
+        .fold((0u32, 0u32), |(instrumented, covered), hits| {
+            (instrumented + 1, covered + (*hits > 0) as u32)
+        });
+    (instrumented > 0).then(|| covered * 100 / instrumented)
+}
+
+struct CoverageReportsChanged;
+
+/// The parsed coverage reports of a project, dropped whenever a report file changes.
+struct CoverageReports {
+    project: Entity<Project>,
+    reports: HashMap<PathBuf, Shared<Task<Option<Arc<CoverageReport>>>>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl EventEmitter<CoverageReportsChanged> for CoverageReports {}
+
+/// The coverage reports of each project, shared by the project's editors.
+#[derive(Default)]
+struct ProjectCoverageReports(HashMap<EntityId, WeakEntity<CoverageReports>>);
+
+impl Global for ProjectCoverageReports {}
+
+impl CoverageReports {
+    /// The reports of the project, kept while any of its editors is open.
+    fn for_project(project: &Entity<Project>, cx: &mut App) -> Entity<Self> {
+        let project_id = project.entity_id();
+        let reports_by_project = &mut cx.default_global::<ProjectCoverageReports>().0;
+        reports_by_project.retain(|_, reports| reports.upgrade().is_some());
+        if let Some(reports) = reports_by_project
+            .get(&project_id)
+            .and_then(WeakEntity::upgrade)
+        {
+            return reports;
+        }
+        let reports = cx.new(|cx| Self::new(project.clone(), cx));
+        cx.global_mut::<ProjectCoverageReports>()
+            .0
+            .insert(project_id, reports.downgrade());
+        reports
+    }
+
+    fn new(project: Entity<Project>, cx: &mut Context<Self>) -> Self {
+        let subscriptions = vec![
+            cx.subscribe(&project, |this, project, event, cx| {
+                if let project::Event::WorktreeUpdatedEntries(worktree_id, entries) = event {
+                    this.invalidate_changed_reports(&project, *worktree_id, entries, cx);
+                }
+            }),
+            cx.observe_global::<SettingsStore>(|this, cx| {
+                this.reports.clear();
+                cx.emit(CoverageReportsChanged);
+            }),
+        ];
+        Self {
+            project,
+            reports: HashMap::default(),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn invalidate_changed_reports(
+        &mut self,
+        project: &Entity<Project>,
+        worktree_id: WorktreeId,
+        entries: &UpdatedEntriesSet,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(worktree) = project.read(cx).worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        let root = worktree.read(cx).abs_path();
+        let reports_before = self.reports.len();
+        for (path, _, _) in entries.iter() {
+            self.reports.remove(&root.join(path.as_std_path()));
+        }
+        if self.reports.len() != reports_before {
+            cx.emit(CoverageReportsChanged);
+        }
+    }
+
+    fn load(
+        &mut self,
+        path: PathBuf,
+        root: Arc<Path>,
+        cx: &mut Context<Self>,
+    ) -> Shared<Task<Option<Arc<CoverageReport>>>> {
+        let fs = self.project.read(cx).fs().clone();
+        self.reports
+            .entry(path.clone())
+            .or_insert_with(|| {
+                cx.background_spawn(async move {
+                    let text = fs.load(&path).await.ok()?;
+                    Some(Arc::new(CoverageReport::parse(&text, &root)))
+                })
+                .shared()
+            })
+            .clone()
+    }
+}
+
+struct CoverageInlayProvider {
+    reports: Entity<CoverageReports>,
+}
+
+impl EditorInlayProvider for CoverageInlayProvider {
+    fn id(&self) -> Arc<str> {
+        "coverage".into()
+    }
+
+    fn inlays(
+        &self,
+        buffer: &Entity<language::Buffer>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<Vec<ProviderInlay>>> {
+        let snapshot = buffer.read(cx).snapshot();
+        let Some(file) = snapshot.file().cloned() else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        let worktree_id = file.worktree_id(cx);
+        let settings = CoverageHintsSettings::get(
+            Some(SettingsLocation {
+                worktree_id,
+                path: file.path(),
+            }),
+            cx,
+        )
+        .clone();
+        if !settings.enabled {
+            return Task::ready(Ok(Vec::new()));
+        }
+        let Some(root) = self
+            .reports
+            .read(cx)
+            .project
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)
+            .map(|worktree| worktree.read(cx).abs_path())
+        else {
+            return Task::ready(Ok(Vec::new()));
+        };
+
+        let abs_path = root.join(file.path().as_std_path());
+        let loads = settings
+            .files
+            .iter()
+            .map(|report_path| {
+                let report_path = root.join(report_path);
+                self.reports.update(cx, |reports, cx| {
+                    reports.load(report_path, root.clone(), cx)
+                })
+            })
+            .collect::<Vec<_>>();
+        let items = snapshot
+            .outline(None)
+            .items
+            .into_iter()
+            .map(|item| {
+                let start_row = item.range.start.to_point(&snapshot).row;
+                let end_row = item.range.end.to_point(&snapshot).row;
+                (item.range.start, start_row..=end_row)
+            })
+            .collect::<Vec<_>>();
+        let status = cx.theme().status();
+        let (low, medium, high) = (status.error, status.warning, status.success);
+
+        cx.background_spawn(async move {
+            let reports = join_all(loads).await;
+            let Some(lines) = reports
+                .iter()
+                .flatten()
+                .find_map(|report| report.files.get(&abs_path))
+            else {
+                return Ok(Vec::new());
+            };
+            Ok(items
+                .into_iter()
+                .filter_map(|(position, rows)| {
+                    let percent = coverage_percent(lines, rows)?;
+                    let color = match percent {
+                        0..50 => low,
+                        50..80 => medium,
+                        _ => high,
+                    };
+                    Some(ProviderInlay {
+                        position,
+                        text: format!("{percent}% ").into(),
+                        style: Some(HighlightStyle {
+                            color: Some(color),
+                            ..HighlightStyle::default()
+                        }),
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lcov() {
+        let report = CoverageReport::parse(
+            "TN:\nSF:src/lib.rs\nDA:1,3\nDA:2,0\nend_of_record\nSF:/abs/main.rs\nDA:5,1\nend_of_record\n",
+            Path::new("/root"),
+        );
+        let lib = &report.files[Path::new("/root/src/lib.rs")];
+        assert_eq!(lib.get(&0), Some(&3));
+        assert_eq!(lib.get(&1), Some(&0));
+        assert_eq!(report.files[Path::new("/abs/main.rs")].get(&4), Some(&1));
+    }
+
+    #[test]
+    fn test_parse_cobertura() {
+        let report = CoverageReport::parse(
+            r#"<?xml version="1.0" ?>
+            <coverage><packages><package name="p"><classes>
+            <class name="lib" filename="src/lib.py"><lines>
+            <line number="1" hits="2"/><line number="3" hits="0"/>
+            </lines></class></classes></package></packages></coverage>"#,
+            Path::new("/root"),
+        );
+        let lib = &report.files[Path::new("/root/src/lib.py")];
+        assert_eq!(lib.get(&0), Some(&2));
+        assert_eq!(lib.get(&2), Some(&0));
+    }
+
+    #[test]
+    fn test_coverage_percent() {
+        let lines = HashMap::from_iter([(1, 4), (2, 0), (3, 1), (9, 0)]);
+        assert_eq!(coverage_percent(&lines, 0..=4), Some(66));
+        assert_eq!(coverage_percent(&lines, 5..=8), None);
+    }
+}
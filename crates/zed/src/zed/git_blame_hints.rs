@@ -0,0 +1,232 @@
+use collections::HashMap;
+use editor::{Editor, EditorEvent, EditorInlayProvider, ProviderInlay};
+use futures::{FutureExt as _, future::Shared};
+use git::blame::{Blame, BlameEntry};
+use git_ui::commit_tooltip::blame_entry_relative_timestamp;
+use gpui::{App, Entity, Task, WeakEntity, Window};
+use language::{Buffer, BufferId, ToOffset as _, ToPoint as _};
+use project::Project;
+use settings::{Settings, SettingsContent, SettingsStore};
+use std::{cell::RefCell, ops::Range, rc::Rc, sync::Arc, time::Duration};
+
+const PROVIDER_ID: &str = "git_blame";
+
+/// How long scrolling has to pause before hints are computed for the newly visible symbols.
+const SCROLL_DEBOUNCE: Duration = Duration::from_millis(100);
+
+pub fn init(cx: &mut App) {
+    GitBlameHintsSettings::register(cx);
+
+    cx.observe_new(|editor: &mut Editor, window, cx| {
+        let Some(window) = window else {
+            return;
+        };
+        if !editor.mode().is_full() {
+            return;
+        }
+        let Some(project) = editor.project().cloned() else {
+            return;
+        };
+        editor.add_inlay_provider(
+            Rc::new(GitBlameInlayProvider {
+                project,
+                editor: cx.weak_entity(),
+                blames: RefCell::default(),
+            }),
+            window,
+            cx,
+        );
+
+        cx.subscribe_in(
+            &cx.entity(),
+            window,
+            |editor, _, event: &EditorEvent, window, cx| {
+                if let EditorEvent::ScrollPositionChanged { .. } = event
+                    && GitBlameHintsSettings::get_global(cx).enabled
+                {
+                    editor.refresh_inlay_provider(PROVIDER_ID.into(), SCROLL_DEBOUNCE, window, cx);
+                }
+            },
+        )
+        .detach();
+
+        let mut settings = GitBlameHintsSettings::get_global(cx).clone();
+        cx.observe_global_in::<SettingsStore>(window, move |editor, window, cx| {
+            let new_settings = GitBlameHintsSettings::get_global(cx);
+            if *new_settings != settings {
+                settings = new_settings.clone();
+                editor.refresh_inlay_providers(window, cx);
+            }
+        })
+        .detach();
+    })
+    .detach();
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GitBlameHintsSettings {
+    pub enabled: bool,
+    pub show_author: bool,
+}
+
+impl Settings for GitBlameHintsSettings {
+    fn from_settings(content: &SettingsContent) -> Self {
+        let content = content.git_blame_hints.clone().unwrap();
+        Self {
+            enabled: content.enabled.unwrap(),
+            show_author: content.show_author.unwrap(),
+        }
+    }
+}
+
+type BlameTask = Shared<Task<Option<Arc<Blame>>>>;
+
+struct GitBlameInlayProvider {
+    project: Entity<Project>,
+    editor: WeakEntity<Editor>,
+    /// The blame of each buffer, along with the buffer version it was computed for.
+    blames: RefCell<HashMap<BufferId, (clock::Global, BlameTask)>>,
+}
+
+impl GitBlameInlayProvider {
+    fn blame(&self, buffer: &Entity<Buffer>, cx: &mut App) -> BlameTask {
+        let (buffer_id, version) = {
+            let buffer = buffer.read(cx);
+            (buffer.remote_id(), buffer.version())
+        };
+        let mut blames = self.blames.borrow_mut();
+        if let Some((blamed_version, task)) = blames.get(&buffer_id)
+            && *blamed_version == version
+        {
+            return task.clone();
+        }
+
+        let blame = self.project.update(cx, |project, cx| {
+            project.blame_buffer(buffer, Some(version.clone()), cx)
+        });
+        let task = cx
+            .background_spawn(async move { blame.await.ok().flatten().map(Arc::new) })
+            .shared();
+        blames.insert(buffer_id, (version, task.clone()));
+        task
+    }
+
+    /// Offset ranges of the buffer that are currently on screen.
+    fn visible_ranges(&self, buffer: &Entity<Buffer>, cx: &mut App) -> Vec<Range<usize>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        self.editor
+            .update(cx, |editor, cx| {
+                editor
+                    .visible_excerpts(None, cx)
+                    .into_values()
+                    .filter(|(buffer, _, _)| buffer.read(cx).remote_id() == buffer_id)
+                    .map(|(_, _, range)| range)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl EditorInlayProvider for GitBlameInlayProvider {
+    fn id(&self) -> Arc<str> {
+        PROVIDER_ID.into()
+    }
+
+    fn inlays(
+        &self,
+        buffer: &Entity<Buffer>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<Vec<ProviderInlay>>> {
+        let settings = GitBlameHintsSettings::get_global(cx).clone();
+        if !settings.enabled {
+            return Task::ready(Ok(Vec::new()));
+        }
+        let visible_ranges = self.visible_ranges(buffer, cx);
+        if visible_ranges.is_empty() {
+            return Task::ready(Ok(Vec::new()));
+        }
+
+        let snapshot = buffer.read(cx).snapshot();
+        let items = snapshot
+            .outline(None)
+            .items
+            .into_iter()
+            .filter(|item| item.depth == 0)
+            .filter(|item| {
+                let range =
+                    item.range.start.to_offset(&snapshot)..item.range.end.to_offset(&snapshot);
+                visible_ranges
+                    .iter()
+                    .any(|visible| range.start <= visible.end && visible.start <= range.end)
+            })
+            .map(|item| {
+                let start_row = item.range.start.to_point(&snapshot).row;
+                let end_row = item.range.end.to_point(&snapshot).row;
+                (item.range.start, start_row..end_row + 1)
+            })
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            return Task::ready(Ok(Vec::new()));
+        }
+        let blame = self.blame(buffer, cx);
+
+        cx.background_spawn(async move {
+            let Some(blame) = blame.await else {
+                return Ok(Vec::new());
+            };
+            Ok(items
+                .into_iter()
+                .filter_map(|(position, rows)| {
+                    let entry = last_modified_entry(&blame.entries, rows)?;
+                    Some(ProviderInlay {
+                        position,
+                        text: hint_text(entry, settings.show_author).into(),
+                        style: None,
+                    })
+                })
+                .collect())
+        })
+    }
+}
+
+/// The most recently authored blame entry covering any of `rows`.
+fn last_modified_entry(entries: &[BlameEntry], rows: Range<u32>) -> Option<&BlameEntry> {
+    entries
+        .iter()
+        .filter(|entry| entry.range.start < rows.end && rows.start < entry.range.end)
+        .max_by_key(|entry| entry.author_time.unwrap_or(i64::MIN))
+}
+
+fn hint_text(entry: &BlameEntry, show_author: bool) -> String {
+    let timestamp = blame_entry_relative_timestamp(entry);
+    match entry.author.as_deref().filter(|_| show_author) {
+        Some(author) => format!("modified {timestamp} by {author} "),
+        None => format!("modified {timestamp} "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_modified_entry() {
+        let entry = |range: Range<u32>, author_time: i64| BlameEntry {
+            range,
+            author_time: Some(author_time),
+            ..BlameEntry::default()
+        };
+        let entries = vec![entry(0..3, 100), entry(3..5, 300), entry(5..9, 200)];
+
+        assert_eq!(
+            last_modified_entry(&entries, 0..4).map(|entry| entry.author_time),
+            Some(Some(300))
+        );
+        assert_eq!(
+            last_modified_entry(&entries, 5..6).map(|entry| entry.author_time),
+            Some(Some(200))
+        );
+        assert_eq!(last_modified_entry(&entries, 9..12), None);
+    }
+}
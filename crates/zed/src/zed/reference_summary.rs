@@ -0,0 +1,360 @@
+//! # Reference Summary
+//!
+//! A report of the symbols across the workspace that have few or no references,
+//! so that the reference hints are useful beyond the file that is open.
+
+use editor::Editor;
+use futures::future::join_all;
+use gpui::{
+    App, AsyncApp, Context, Entity, EventEmitter, FocusHandle, Focusable, Task,
+    UniformListScrollHandle, WeakEntity, Window, uniform_list,
+};
+use language::{Point, ToPoint as _};
+use project::{Project, ProjectPath};
+use settings::Settings as _;
+use std::ops::Range;
+use ui::{ListItem, prelude::*};
+use util::ResultExt as _;
+use workspace::{Item, Workspace, item::ItemEvent};
+
+use super::symbol_ref_hints::{
+    REFERENCES_BATCH_SIZE, SymbolRefHints, SymbolRefHintsSettings, count_locations, is_entry_point,
+};
+
+/// Symbols with at most this many references are listed.
+const LOW_REFERENCE_COUNT: usize = 1;
+
+struct SummaryEntry {
+    path: ProjectPath,
+    symbol: SharedString,
+    position: Point,
+    count: usize,
+}
+
+pub struct ReferenceSummary {
+    project: Entity<Project>,
+    workspace: WeakEntity<Workspace>,
+    focus_handle: FocusHandle,
+    scroll_handle: UniformListScrollHandle,
+    entries: Vec<SummaryEntry>,
+    most_referenced_first: bool,
+    /// Files scanned so far out of the total, while a scan is in flight.
+    progress: Option<(usize, usize)>,
+    scan_task: Task<()>,
+}
+
+impl ReferenceSummary {
+    /// Activates the existing summary, or opens one and starts scanning the workspace.
+    pub fn deploy(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+        if let Some(existing) = workspace.items_of_type::<Self>(cx).next() {
+            workspace.activate_item(&existing, true, true, window, cx);
+            return;
+        }
+        let project = workspace.project().clone();
+        let workspace_handle = cx.entity().downgrade();
+        let summary = cx.new(|cx| {
+            let mut summary = Self {
+                project,
+                workspace: workspace_handle,
+                focus_handle: cx.focus_handle(),
+                scroll_handle: UniformListScrollHandle::new(),
+                entries: Vec::new(),
+                most_referenced_first: false,
+                progress: None,
+                scan_task: Task::ready(()),
+            };
+            summary.scan(cx);
+            summary
+        });
+        workspace.add_item_to_active_pane(Box::new(summary), None, true, window, cx);
+    }
+
+    /// Files of the visible worktrees that a language server could count references in.
+    fn candidate_files(&self, cx: &App) -> Vec<ProjectPath> {
+        let project = self.project.read(cx);
+        let languages = project.languages().clone();
+        project
+            .visible_worktrees(cx)
+            .flat_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let worktree_id = worktree.id();
+                worktree
+                    .files(false, 0)
+                    .filter(|entry| !entry.is_private && !entry.is_external)
+                    .filter(|entry| {
+                        languages
+                            .language_for_file_path(entry.path.as_std_path())
+                            .is_some()
+                    })
+                    .map(|entry| ProjectPath {
+                        worktree_id,
+                        path: entry.path.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn scan(&mut self, cx: &mut Context<Self>) {
+        let files = self.candidate_files(cx);
+        let total = files.len();
+        self.entries.clear();
+        self.progress = Some((0, total));
+        let project = self.project.clone();
+        self.scan_task = cx.spawn(async move |this, cx| {
+            for (ix, path) in files.into_iter().enumerate() {
+                let entries = Self::scan_file(&project, path, cx)
+                    .await
+                    .unwrap_or_default();
+                let updated = this.update(cx, |this, cx| {
+                    this.entries.extend(entries);
+                    this.sort_entries();
+                    this.progress = Some((ix + 1, total));
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    return;
+                }
+            }
+            this.update(cx, |this, cx| {
+                this.progress = None;
+                cx.notify();
+            })
+            .ok();
+        });
+        cx.notify();
+    }
+
+    /// Counts the references to every symbol of a file, keeping the rarely referenced ones.
+    async fn scan_file(
+        project: &Entity<Project>,
+        path: ProjectPath,
+        cx: &mut AsyncApp,
+    ) -> Option<Vec<SummaryEntry>> {
+        let buffer = project
+            .update(cx, |project, cx| project.open_buffer(path.clone(), cx))
+            .ok()?
+            .await
+            .log_err()?;
+        let supports_references = project
+            .read_with(cx, |project, cx| {
+                project.any_language_server_supports_symbol_references(buffer.read(cx), cx)
+            })
+            .ok()?;
+        if !supports_references {
+            return None;
+        }
+        let symbols = project
+            .update(cx, |project, cx| project.document_symbols(&buffer, cx))
+            .ok()?
+            .await
+            .log_err()?;
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot()).ok()?;
+        let entry_points = cx
+            .update(|cx| SymbolRefHintsSettings::get_global(cx).entry_points.clone())
+            .ok()?;
+        let symbols = SymbolRefHints::flatten_document_symbols(symbols)
+            .into_iter()
+            .map(|symbol| {
+                let selection_range = symbol.selection_range.start.to_point(&snapshot)
+                    ..symbol.selection_range.end.to_point(&snapshot);
+                (SharedString::from(symbol.name), selection_range)
+            })
+            .filter(|(name, selection_range)| {
+                !is_entry_point(name, &snapshot, selection_range.start.row, &entry_points)
+            })
+            .collect::<Vec<_>>();
+
+        let mut entries = Vec::new();
+        for batch in symbols.chunks(REFERENCES_BATCH_SIZE) {
+            let tasks = project
+                .update(cx, |project, cx| {
+                    batch
+                        .iter()
+                        .map(|(_, selection_range)| {
+                            project.references(&buffer, selection_range.start, cx)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()?;
+            let results = join_all(tasks).await;
+            cx.update(|cx| {
+                for ((symbol, selection_range), result) in batch.iter().zip(results) {
+                    // A failed request says nothing about whether the symbol is used.
+                    let Ok(Some(locations)) = result else {
+                        continue;
+                    };
+                    let count = count_locations(&locations, &buffer, selection_range, cx);
+                    if count <= LOW_REFERENCE_COUNT {
+                        entries.push(SummaryEntry {
+                            path: path.clone(),
+                            symbol: symbol.clone(),
+                            position: selection_range.start,
+                            count,
+                        });
+                    }
+                }
+            })
+            .ok()?;
+        }
+        Some(entries)
+    }
+
+    fn sort_entries(&mut self) {
+        let most_referenced_first = self.most_referenced_first;
+        self.entries.sort_by(|a, b| {
+            let by_count = if most_referenced_first {
+                b.count.cmp(&a.count)
+            } else {
+                a.count.cmp(&b.count)
+            };
+            by_count
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.position.cmp(&b.position))
+        });
+    }
+
+    fn toggle_sort(&mut self, cx: &mut Context<Self>) {
+        self.most_referenced_first = !self.most_referenced_first;
+        self.sort_entries();
+        cx.notify();
+    }
+
+    fn open_entry(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(entry) = self.entries.get(ix) else {
+            return;
+        };
+        let position = entry.position;
+        let Some(open) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path(entry.path.clone(), None, true, window, cx)
+            })
+            .log_err()
+        else {
+            return;
+        };
+        cx.spawn_in(window, async move |_, cx| {
+            let item = open.await?;
+            if let Some(editor) = cx.update(|_, cx| item.act_as::<Editor>(cx))? {
+                editor.update_in(cx, |editor, window, cx| {
+                    editor.go_to_singleton_buffer_point(position, window, cx)
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_entry(&self, ix: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        let entry = &self.entries[ix];
+        let location = format!(
+            "{}:{}",
+            entry
+                .path
+                .path
+                .display(self.project.read(cx).path_style(cx)),
+            entry.position.row + 1
+        );
+        ListItem::new(ix)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Label::new(entry.count.to_string())
+                            .color(if entry.count == 0 {
+                                Color::Warning
+                            } else {
+                                Color::Muted
+                            })
+                            .size(LabelSize::Small),
+                    )
+                    .child(Label::new(entry.symbol.clone()))
+                    .child(
+                        Label::new(location)
+                            .color(Color::Muted)
+                            .size(LabelSize::Small),
+                    ),
+            )
+            .on_click(cx.listener(move |this, _, window, cx| this.open_entry(ix, window, cx)))
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let status = match self.progress {
+            Some((done, total)) => format!("Scanning {done}/{total} files…"),
+            None => format!("{} rarely referenced symbols", self.entries.len()),
+        };
+        let sort_label = if self.most_referenced_first {
+            "Most References First"
+        } else {
+            "Fewest References First"
+        };
+        h_flex()
+            .p_2()
+            .gap_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(Label::new(status).color(Color::Muted))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("toggle-sort", sort_label)
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(|this, _, _, cx| this.toggle_sort(cx))),
+                    )
+                    .child(
+                        Button::new("rescan", "Rescan")
+                            .label_size(LabelSize::Small)
+                            .disabled(self.progress.is_some())
+                            .on_click(cx.listener(|this, _, _, cx| this.scan(cx))),
+                    ),
+            )
+    }
+}
+
+impl Render for ReferenceSummary {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .child(
+                uniform_list(
+                    "reference-summary-entries",
+                    self.entries.len(),
+                    cx.processor(|this, range: Range<usize>, _window, cx| {
+                        range.map(|ix| this.render_entry(ix, cx)).collect()
+                    }),
+                )
+                .track_scroll(self.scroll_handle.clone())
+                .flex_1(),
+            )
+    }
+}
+
+impl EventEmitter<ItemEvent> for ReferenceSummary {}
+
+impl Focusable for ReferenceSummary {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Item for ReferenceSummary {
+    type Event = ItemEvent;
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        "Reference Summary".into()
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+}
@@ -1,26 +1,168 @@
-use editor::{Editor, EditorEvent, InlayId, display_map::Inlay};
-use gpui::{Context, Entity, Render, Subscription, Task, Window};
+use collections::{HashMap, HashSet};
+use editor::{Editor, EditorEvent, EditorInlayProvider, ProviderInlay, SymbolReferenceCounts};
+use gpui::{
+    ClipboardItem, Context, Entity, EntityId, FontWeight, HighlightStyle, Hsla, Render, Rgba,
+    Subscription, Task, WeakEntity, Window,
+};
 use language::language_settings::all_language_settings;
-use project::Project;
-use std::time::Duration;
+use language::{Anchor, Buffer, BufferId, BufferSnapshot, Location, OutlineItem, Point};
+use project::{Project, ProjectPath, WorktreeSettings};
+use settings::{Settings, SettingsContent};
+use std::{ops::Range, rc::Rc, sync::Arc, time::Duration};
 use ui::prelude::*;
 
 use language::{ToOffset, ToPoint};
 
 use workspace::{ItemHandle, StatusItemView, Workspace};
 
+pub fn init(cx: &mut App) {
+    SymbolRefHintsSettings::register(cx);
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolRefHintsSettings {
+    pub show_name_delta_since_head: bool,
+    pub include_declaration: bool,
+    pub include_excluded_files: bool,
+    pub highlight_changed_counts: bool,
+    pub entry_points: Vec<String>,
+    pub max_symbols: usize,
+    pub color: Option<String>,
+    pub background: Option<String>,
+    pub padding: usize,
+}
+
+impl Settings for SymbolRefHintsSettings {
+    fn from_settings(content: &SettingsContent) -> Self {
+        let content = content.symbol_reference_hints.clone().unwrap();
+        let style = content.style.unwrap();
+        Self {
+            show_name_delta_since_head: content.show_name_delta_since_head.unwrap(),
+            include_declaration: content.include_declaration.unwrap(),
+            include_excluded_files: content.include_excluded_files.unwrap(),
+            highlight_changed_counts: content.highlight_changed_counts.unwrap(),
+            entry_points: content.entry_points.unwrap(),
+            max_symbols: content.max_symbols.unwrap(),
+            color: style.color,
+            background: style.background,
+            padding: style.padding.unwrap(),
+        }
+    }
+}
+
 /// Adds inline reference-count hints next to symbols in the active editor and logs counts.
 pub struct SymbolRefHints {
     pub enabled: bool,
     project: Entity<Project>,
     _observe_active_editor: Option<Subscription>,
     _observe_settings: Option<Subscription>,
+    _observe_project: Option<Subscription>,
     ongoing_task: Task<()>,
     refresh_rev: u64,
+    /// Counts from earlier runs, so an interrupted refresh resumes rather than starting over.
+    cached_counts: HashMap<BufferId, HashMap<(String, usize), usize>>,
+    /// Counts shown at the end of the last completed refresh, to spot the ones that changed.
+    last_counts: HashMap<BufferId, HashMap<(String, usize), usize>>,
+    /// Fades out the emphasis on hints whose counts just changed.
+    emphasis_task: Task<()>,
+    /// Symbols counted so far out of the total, while a refresh is in flight.
+    progress: Option<(usize, usize)>,
+    /// Outline item and name ranges of the symbols that got hints, per buffer.
+    symbol_ranges: HashMap<BufferId, Vec<(Range<Anchor>, Range<Anchor>)>>,
+    /// Worktree entries changed since the project last went quiet.
+    recent_changed_entries: usize,
+    /// Set while a bulk operation (checkout, rename, agent edits) is rewriting the project.
+    bulk_operation_in_progress: bool,
+    /// Editor to refresh once the bulk operation settles.
+    pending_refresh: Option<WeakEntity<Editor>>,
+    settle_task: Task<()>,
+    /// Files whose hints were turned off for the rest of the session.
+    disabled_files: HashSet<ProjectPath>,
+    /// Buffers above `max_symbols` whose hints were forced on from the status bar.
+    forced_buffers: HashSet<BufferId>,
+    /// Whether the active file was skipped for having more than `max_symbols` symbols.
+    skipped_large_file: bool,
+    active_editor: Option<WeakEntity<Editor>>,
+    /// Forget the state kept per buffer once the buffers are released.
+    buffer_releases: HashMap<BufferId, Subscription>,
+    /// The hints shown in each editor, through its [`SymbolRefHintsProvider`].
+    shown: HashMap<EntityId, EditorHints>,
+    /// Forget the hints shown in editors once the editors are released.
+    editor_releases: HashMap<EntityId, Subscription>,
+}
+
+/// The hints shown in an editor, for the symbols of its buffer.
+struct EditorHints {
+    buffer_id: BufferId,
+    /// The index of each hinted symbol among the outline items, with its position and text.
+    hints: Vec<(usize, Anchor, String)>,
+    /// Whether the counts are being recomputed, so they may be outdated.
+    stale: bool,
+    /// The hints whose counts just changed, by index, with the emphasis they're shown with.
+    emphasis: Option<(Vec<usize>, HighlightStyle)>,
+}
+
+impl EditorHints {
+    fn inlays(&self, cx: &App) -> Vec<ProviderInlay> {
+        let style = hint_style(self.stale, cx);
+        self.hints
+            .iter()
+            .map(|(ix, position, text)| {
+                let emphasis = self
+                    .emphasis
+                    .as_ref()
+                    .filter(|(changed, _)| changed.contains(ix))
+                    .map(|(_, emphasis)| *emphasis);
+                ProviderInlay {
+                    position: *position,
+                    text: text.clone().into(),
+                    style: match (style, emphasis) {
+                        (Some(style), Some(emphasis)) => Some(style.highlight(emphasis)),
+                        (style, emphasis) => style.or(emphasis),
+                    },
+                }
+            })
+            .collect()
+    }
 }
 
-const HINT_BASE_ID: u32 = 900_000_000; // avoid collisions with other inlays
-const MAX_REMOVE: u32 = 1024; // remove up to this many old hints each refresh
+/// Shows the hints that [`SymbolRefHints`] computed for an editor.
+struct SymbolRefHintsProvider {
+    hints: WeakEntity<SymbolRefHints>,
+    editor: EntityId,
+}
+
+impl EditorInlayProvider for SymbolRefHintsProvider {
+    fn id(&self) -> Arc<str> {
+        SymbolReferenceCounts::INLAY_PROVIDER_ID.into()
+    }
+
+    fn inlays(
+        &self,
+        buffer: &Entity<Buffer>,
+        _window: &mut Window,
+        cx: &mut App,
+    ) -> Task<anyhow::Result<Vec<ProviderInlay>>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let inlays = self
+            .hints
+            .upgrade()
+            .and_then(|hints| {
+                let shown = hints.read(cx).shown.get(&self.editor)?;
+                (shown.buffer_id == buffer_id).then(|| shown.inlays(cx))
+            })
+            .unwrap_or_default();
+        Task::ready(Ok(inlays))
+    }
+}
+
+pub(crate) const REFERENCES_BATCH_SIZE: usize = 8; // concurrent reference requests per batch
+const HUGE_WORKSPACE_FILES: usize = 100_000; // above this, count usages within the file only
+const STALE_FADE_OUT: f32 = 0.6; // how much to dim hints whose counts may be outdated
+const BULK_OPERATION_ENTRIES: usize = 32; // changed entries that count as a bulk operation
+const BULK_OPERATION_SETTLE: Duration = Duration::from_millis(1500);
+const CHANGE_EMPHASIS: Duration = Duration::from_millis(2000); // how long changed counts stand out
+const CHANGE_EMPHASIS_STEPS: u32 = 8; // frames in which the emphasis fades away
 
 impl SymbolRefHints {
     pub fn new(workspace: &Workspace) -> Self {
@@ -29,28 +171,85 @@ impl SymbolRefHints {
             project: workspace.project().clone(),
             _observe_active_editor: None,
             _observe_settings: None,
+            _observe_project: None,
             ongoing_task: Task::ready(()),
             refresh_rev: 0,
+            cached_counts: HashMap::default(),
+            last_counts: HashMap::default(),
+            emphasis_task: Task::ready(()),
+            progress: None,
+            symbol_ranges: HashMap::default(),
+            recent_changed_entries: 0,
+            bulk_operation_in_progress: false,
+            pending_refresh: None,
+            settle_task: Task::ready(()),
+            disabled_files: HashSet::default(),
+            forced_buffers: HashSet::default(),
+            skipped_large_file: false,
+            active_editor: None,
+            buffer_releases: HashMap::default(),
+            shown: HashMap::default(),
+            editor_releases: HashMap::default(),
         }
     }
 
     fn cancel_task(&mut self) {
         self.ongoing_task = Task::ready(());
+        self.progress = None;
     }
 
-    fn removal_ids() -> Vec<InlayId> {
-        (0..MAX_REMOVE)
-            .map(|i| InlayId::SymbolRefHint(HINT_BASE_ID + i))
-            .collect()
+    /// Shows the hints in the editor through a [`SymbolRefHintsProvider`], unless it has one.
+    fn add_provider(
+        &mut self,
+        editor: &Entity<Editor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let editor_id = editor.entity_id();
+        if self.editor_releases.contains_key(&editor_id) {
+            return;
+        }
+        let provider = Rc::new(SymbolRefHintsProvider {
+            hints: cx.weak_entity(),
+            editor: editor_id,
+        });
+        editor.update(cx, |editor, cx| {
+            editor.add_inlay_provider(provider, window, cx)
+        });
+        let subscription = cx.observe_release(editor, move |this, _, _| {
+            this.shown.remove(&editor_id);
+            this.editor_releases.remove(&editor_id);
+        });
+        self.editor_releases.insert(editor_id, subscription);
     }
 
-    fn bump_and_clear(&mut self, editor: &Entity<Editor>, cx: &mut Context<Self>) {
-        self.refresh_rev = self.refresh_rev.wrapping_add(1);
+    fn refresh_provider(editor: &Entity<Editor>, window: &mut Window, cx: &mut App) {
         editor.update(cx, |editor, cx| {
-            editor.splice_inlays(&Self::removal_ids(), Vec::new(), cx)
+            editor.refresh_inlay_provider(
+                SymbolReferenceCounts::INLAY_PROVIDER_ID.into(),
+                Duration::ZERO,
+                window,
+                cx,
+            )
         });
     }
 
+    pub(crate) fn bump_and_clear(
+        &mut self,
+        editor: &Entity<Editor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.refresh_rev = self.refresh_rev.wrapping_add(1);
+        self.emphasis_task = Task::ready(());
+        if let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() {
+            SymbolReferenceCounts::clear(buffer.read(cx).remote_id(), cx);
+        }
+        if self.shown.remove(&editor.entity_id()).is_some() {
+            Self::refresh_provider(editor, window, cx);
+        }
+    }
+
     fn is_singleton(editor: &Entity<Editor>, cx: &mut Context<Self>) -> bool {
         editor.read_with(cx, |editor, app| {
             editor.buffer().read(app).as_singleton().is_some()
@@ -69,7 +268,7 @@ impl SymbolRefHints {
         })
     }
 
-    fn flatten_document_symbols(
+    pub(crate) fn flatten_document_symbols(
         mut doc_symbols: Vec<project::DocumentSymbol>,
     ) -> Vec<project::DocumentSymbol> {
         let mut flat_symbols: Vec<project::DocumentSymbol> = Vec::new();
@@ -96,18 +295,18 @@ impl SymbolRefHints {
     ) {
         if let EditorEvent::InlayHintsToggled { enabled } = event {
             if !enabled {
-                self.bump_and_clear(editor, cx);
+                self.bump_and_clear(editor, window, cx);
                 return;
             }
         }
 
         if !self.inlays_enabled(editor, cx) {
-            self.bump_and_clear(editor, cx);
+            self.bump_and_clear(editor, window, cx);
             return;
         }
 
         if !Self::is_singleton(editor, cx) {
-            self.bump_and_clear(editor, cx);
+            self.bump_and_clear(editor, window, cx);
             return;
         }
 
@@ -122,40 +321,71 @@ impl SymbolRefHints {
         cx: &mut Context<Self>,
         debounce: Duration,
     ) {
-        if !Self::is_singleton(editor, cx) {
-            self.bump_and_clear(editor, cx);
+        if self.bulk_operation_in_progress {
+            self.pending_refresh = Some(editor.downgrade());
+            return;
+        }
+        if !Self::is_singleton(editor, cx) || self.is_disabled_for_file(editor, cx) {
+            self.bump_and_clear(editor, window, cx);
             self.cancel_task();
             return;
         }
 
-        let maybe_data = editor
-            .read(cx)
-            .active_excerpt(cx)
-            .map(|(excerpt_id, buffer, _)| {
-                let items = buffer.read(cx).snapshot().outline(None).items;
-                (excerpt_id, buffer, items)
+        let entry_points = SymbolRefHintsSettings::get_global(cx).entry_points.clone();
+        let maybe_data = editor.read(cx).active_excerpt(cx).map(|(_, buffer, _)| {
+            let snapshot = buffer.read(cx).snapshot();
+            let mut items = snapshot.outline(None).items;
+            // Entry points are expected to have no references, so they get no hint.
+            items.retain(|item| {
+                let row = item.range.start.to_point(&snapshot).row;
+                !is_entry_point(&item_name(item), &snapshot, row, &entry_points)
             });
-        let Some((excerpt_id, buffer, items)) = maybe_data else {
+            (buffer, items)
+        });
+        let Some((buffer, items)) = maybe_data else {
+            return;
+        };
+        let buffer_id = buffer.read(cx).remote_id();
+        self.observe_buffer_release(&buffer, cx);
+        let max_symbols = SymbolRefHintsSettings::get_global(cx).max_symbols;
+        if items.len() > max_symbols && !self.forced_buffers.contains(&buffer_id) {
+            self.bump_and_clear(editor, window, cx);
+            self.cancel_task();
+            self.skipped_large_file = true;
+            cx.notify();
+            return;
+        }
+        self.skipped_large_file = false;
+        let project = self.project.read(cx);
+        let supports_references =
+            project.any_language_server_supports_symbol_references(buffer.read(cx), cx);
+        let supports_highlights =
+            project.any_language_server_supports_document_highlights(buffer.read(cx), cx);
+        let source = if supports_references
+            && !(supports_highlights && Self::is_huge_workspace(project, cx))
+        {
+            CountSource::References
+        } else if supports_highlights {
+            CountSource::DocumentHighlights
+        } else {
+            // Capabilities may not have arrived yet; a language server event will retrigger this.
+            self.bump_and_clear(editor, window, cx);
+            self.cancel_task();
             return;
         };
+        let local_counts = source == CountSource::DocumentHighlights;
+        // Until the new counts arrive, the existing ones may be wrong.
+        if let Some(shown) = self.shown.get_mut(&editor.entity_id()) {
+            shown.stale = true;
+            Self::refresh_provider(editor, window, cx);
+        }
         let project = self.project.clone();
         let editor_handle = editor.clone();
 
         let rev = self.refresh_rev;
         self.ongoing_task = cx.spawn_in(window, async move |this, cx| {
             cx.background_executor().timer(debounce).await;
-
-            let inlay_enabled = editor_handle
-                .read_with(cx, |editor, _| editor.inlay_hints_enabled())
-                .unwrap_or(false);
-            let our_enabled = this.update(cx, |this, _| this.enabled).unwrap_or(true);
-            if !(our_enabled && inlay_enabled) {
-                return;
-            }
-            let invalidated = this
-                .update(cx, |this, _| this.refresh_rev != rev)
-                .unwrap_or(true);
-            if invalidated {
+            if !Self::still_valid(&this, &editor_handle, rev, cx) {
                 return;
             }
 
@@ -170,7 +400,7 @@ impl SymbolRefHints {
 
             let flat_symbols = Self::flatten_document_symbols(doc_symbols);
 
-            let positions = editor_handle
+            let symbols = editor_handle
                 .read_with(cx, |_, app| {
                     let snapshot = buffer.read(app).snapshot();
                     items
@@ -196,75 +426,801 @@ impl SymbolRefHints {
                                 }
                             }
                             match best_symbol {
-                                Some(symbol) => symbol.selection_range.start.to_point(&snapshot),
-                                None => item.range.start.to_point(&snapshot),
+                                Some(symbol) => HintSymbol {
+                                    name: symbol.name.clone(),
+                                    selection_range: symbol
+                                        .selection_range
+                                        .start
+                                        .to_point(&snapshot)
+                                        ..symbol.selection_range.end.to_point(&snapshot),
+                                },
+                                None => {
+                                    let start = item.range.start.to_point(&snapshot);
+                                    HintSymbol {
+                                        name: item_name(item),
+                                        selection_range: start..start,
+                                    }
+                                }
                             }
                         })
                         .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
 
-            let mut counts: Vec<usize> = Vec::with_capacity(items.len());
-            for position in &positions {
-                let count = if let Some(task) = project
-                    .update(cx, |project, cx| project.references(&buffer, *position, cx))
-                    .ok()
-                {
-                    match task.await {
-                        Ok(Some(locations)) => locations.len(),
-                        Ok(None) => 0,
-                        Err(_) => 0,
-                    }
-                } else {
-                    0
-                };
-                counts.push(count);
-            }
+            let show_name_delta_since_head = cx
+                .update(|_, cx| SymbolRefHintsSettings::get_global(cx).show_name_delta_since_head)
+                .unwrap_or(false);
+            let deltas = if show_name_delta_since_head {
+                Self::name_deltas_since_head(&project, &buffer, &symbols, cx).await
+            } else {
+                None
+            };
 
-            let inlays = editor_handle
-                .read_with(cx, |editor, app| {
-                    let multi_buffer_snapshot = editor.buffer().read(app).snapshot(app);
+            // Start from whatever an earlier (possibly interrupted) run counted, and count
+            // symbols we know nothing about before re-verifying the ones we do.
+            let symbol_ranges = buffer
+                .read_with(cx, |buffer, _| {
                     items
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(i, item)| {
-                            let position = multi_buffer_snapshot
-                                .anchor_in_excerpt(excerpt_id, item.range.start)?;
-                            let text = format!("{} ", counts[i]);
-                            Some(Inlay::symbol_ref_hint(
-                                HINT_BASE_ID + i as u32,
-                                position,
-                                text,
-                            ))
+                        .iter()
+                        .zip(&symbols)
+                        .map(|(item, symbol)| {
+                            let selection_range = buffer.anchor_after(symbol.selection_range.start)
+                                ..buffer.anchor_before(symbol.selection_range.end);
+                            (item.range.clone(), selection_range)
                         })
-                        .collect::<Vec<Inlay>>()
+                        .collect::<Vec<_>>()
                 })
                 .unwrap_or_default();
+            this.update(cx, |this, _| {
+                this.symbol_ranges.insert(buffer_id, symbol_ranges);
+            })
+            .ok();
 
-            let inlay_enabled = editor_handle
-                .read_with(cx, |editor, _| editor.inlay_hints_enabled())
-                .unwrap_or(false);
-            let our_enabled = this.update(cx, |this, _| this.enabled).unwrap_or(true);
-            if inlays.is_empty() || !(our_enabled && inlay_enabled) {
+            let keys = symbol_keys(&symbols);
+            let mut counts = this
+                .read_with(cx, |this, _| {
+                    // Cached counts are workspace-wide, so don't mix them with local ones.
+                    let cached = this.cached_counts.get(&buffer_id).filter(|_| !local_counts);
+                    keys.iter()
+                        .map(|key| cached.and_then(|cached| cached.get(key).copied()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_else(|_| vec![None; keys.len()]);
+            let mut queue = (0..symbols.len()).collect::<Vec<_>>();
+            queue.sort_by_key(|&ix| counts[ix].is_some());
+
+            let total = queue.len();
+            let mut done = 0;
+            if !Self::still_valid(&this, &editor_handle, rev, cx) {
                 return;
             }
-            let invalidated = this
-                .update(cx, |this, _| this.refresh_rev != rev)
-                .unwrap_or(true);
-            if invalidated {
-                return;
+            // The counts stay dimmed until the last batch resolves, as any of them may change.
+            Self::splice_hints(
+                &this,
+                &editor_handle,
+                buffer_id,
+                &items,
+                &counts,
+                &deltas,
+                local_counts,
+                !queue.is_empty(),
+                cx,
+            );
+            let batch_count = queue.len().div_ceil(REFERENCES_BATCH_SIZE);
+            for (batch_ix, batch) in queue.chunks(REFERENCES_BATCH_SIZE).enumerate() {
+                this.update(cx, |this, cx| {
+                    this.progress = Some((done, total));
+                    cx.notify();
+                })
+                .ok();
+
+                let batch_counts = match source {
+                    CountSource::References => {
+                        Self::count_references(&project, &buffer, &symbols, batch, cx).await
+                    }
+                    CountSource::DocumentHighlights => {
+                        Self::count_highlights(&project, &buffer, &symbols, batch, cx).await
+                    }
+                };
+                for (&ix, count) in batch.iter().zip(batch_counts) {
+                    counts[ix] = Some(count);
+                }
+                done += batch.len();
+
+                if !local_counts {
+                    this.update(cx, |this, _| {
+                        let cached = this.cached_counts.entry(buffer_id).or_default();
+                        for &ix in batch {
+                            if let Some(count) = counts[ix] {
+                                cached.insert(keys[ix].clone(), count);
+                            }
+                        }
+                    })
+                    .ok();
+                }
+                if !Self::still_valid(&this, &editor_handle, rev, cx) {
+                    return;
+                }
+                Self::splice_hints(
+                    &this,
+                    &editor_handle,
+                    buffer_id,
+                    &items,
+                    &counts,
+                    &deltas,
+                    local_counts,
+                    batch_ix + 1 < batch_count,
+                    cx,
+                );
             }
 
-            let _ = editor_handle.update(cx, |editor, cx| {
-                editor.splice_inlays(&Self::removal_ids(), inlays, cx)
-            });
+            this.update_in(cx, |this, window, cx| {
+                this.progress = None;
+                let counts = keys
+                    .iter()
+                    .zip(&counts)
+                    .filter_map(|(key, count)| Some((key.clone(), (*count)?)))
+                    .collect::<HashMap<_, _>>();
+                if let Some(previous) = this.last_counts.insert(buffer_id, counts.clone()) {
+                    let changed = changed_counts(&keys, &previous, &counts);
+                    this.emphasize_changed_hints(&editor_handle, changed, window, cx);
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    /// Drops the counts and ranges kept for the buffer once it's released, so they don't pile
+    /// up for every buffer the editor ever showed.
+    fn observe_buffer_release(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        let buffer_id = buffer.read(cx).remote_id();
+        if self.buffer_releases.contains_key(&buffer_id) {
+            return;
+        }
+        let subscription = cx.observe_release(buffer, move |this, _, _| {
+            this.cached_counts.remove(&buffer_id);
+            this.last_counts.remove(&buffer_id);
+            this.symbol_ranges.remove(&buffer_id);
+            this.shown.retain(|_, shown| shown.buffer_id != buffer_id);
+            this.forced_buffers.remove(&buffer_id);
+            this.buffer_releases.remove(&buffer_id);
+        });
+        self.buffer_releases.insert(buffer_id, subscription);
+    }
+
+    /// Briefly highlights the hints at the given item indices, fading the highlight out.
+    fn emphasize_changed_hints(
+        &mut self,
+        editor: &Entity<Editor>,
+        changed: Vec<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if changed.is_empty() || !SymbolRefHintsSettings::get_global(cx).highlight_changed_counts {
+            return;
+        }
+        let background = cx.theme().status().warning_background;
+        let editor = editor.downgrade();
+        self.emphasis_task = cx.spawn_in(window, async move |this, cx| {
+            for step in 0..CHANGE_EMPHASIS_STEPS {
+                let remaining = 1. - step as f32 / CHANGE_EMPHASIS_STEPS as f32;
+                let style = HighlightStyle {
+                    background_color: Some(background.opacity(remaining)),
+                    font_weight: Some(FontWeight::BOLD),
+                    ..HighlightStyle::default()
+                };
+                let updated = this.update_in(cx, |this, window, cx| {
+                    this.set_emphasis(&editor, Some((changed.clone(), style)), window, cx)
+                });
+                if !matches!(updated, Ok(Some(()))) {
+                    return;
+                }
+                cx.background_executor()
+                    .timer(CHANGE_EMPHASIS / CHANGE_EMPHASIS_STEPS)
+                    .await;
+            }
+            this.update_in(cx, |this, window, cx| {
+                this.set_emphasis(&editor, None, window, cx)
+            })
+            .ok();
+        });
+    }
+
+    fn set_emphasis(
+        &mut self,
+        editor: &WeakEntity<Editor>,
+        emphasis: Option<(Vec<usize>, HighlightStyle)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<()> {
+        let editor = editor.upgrade()?;
+        self.shown.get_mut(&editor.entity_id())?.emphasis = emphasis;
+        Self::refresh_provider(&editor, window, cx);
+        Some(())
+    }
+
+    fn still_valid(
+        this: &WeakEntity<Self>,
+        editor: &Entity<Editor>,
+        rev: u64,
+        cx: &mut gpui::AsyncWindowContext,
+    ) -> bool {
+        let inlay_enabled = editor
+            .read_with(cx, |editor, _| editor.inlay_hints_enabled())
+            .unwrap_or(false);
+        let our_valid = this
+            .read_with(cx, |this, _| this.enabled && this.refresh_rev == rev)
+            .unwrap_or(false);
+        inlay_enabled && our_valid
+    }
+
+    /// Counts workspace-wide references to each symbol in the batch.
+    async fn count_references(
+        project: &Entity<Project>,
+        buffer: &Entity<Buffer>,
+        symbols: &[HintSymbol],
+        batch: &[usize],
+        cx: &mut gpui::AsyncWindowContext,
+    ) -> Vec<usize> {
+        let tasks = project
+            .update(cx, |project, cx| {
+                batch
+                    .iter()
+                    .map(|&ix| {
+                        let position = symbols[ix].selection_range.start;
+                        project.references(buffer, position, cx)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let results = futures::future::join_all(tasks).await;
+        cx.update(|_, cx| {
+            batch
+                .iter()
+                .zip(results)
+                .map(|(&ix, result)| match result {
+                    Ok(Some(locations)) => {
+                        count_locations(&locations, buffer, &symbols[ix].selection_range, cx)
+                    }
+                    Ok(None) | Err(_) => 0,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Counts usages of each symbol in the batch within its own file, via document highlights.
+    async fn count_highlights(
+        project: &Entity<Project>,
+        buffer: &Entity<Buffer>,
+        symbols: &[HintSymbol],
+        batch: &[usize],
+        cx: &mut gpui::AsyncWindowContext,
+    ) -> Vec<usize> {
+        let tasks = project
+            .update(cx, |project, cx| {
+                batch
+                    .iter()
+                    .map(|&ix| {
+                        let position = symbols[ix].selection_range.start;
+                        project.document_highlights(buffer, position, cx)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let results = futures::future::join_all(tasks).await;
+        cx.update(|_, cx| {
+            let include_declaration = SymbolRefHintsSettings::get_global(cx).include_declaration;
+            let snapshot = buffer.read(cx).snapshot();
+            batch
+                .iter()
+                .zip(results)
+                .map(|(&ix, result)| {
+                    let Ok(highlights) = result else {
+                        return 0;
+                    };
+                    highlights
+                        .iter()
+                        .filter(|highlight| {
+                            let range = highlight.range.start.to_point(&snapshot)
+                                ..highlight.range.end.to_point(&snapshot);
+                            include_declaration
+                                || !ranges_overlap(&range, &symbols[ix].selection_range)
+                        })
+                        .count()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    fn is_huge_workspace(project: &Project, cx: &App) -> bool {
+        project
+            .visible_worktrees(cx)
+            .map(|worktree| worktree.read(cx).file_count())
+            .sum::<usize>()
+            > HUGE_WORKSPACE_FILES
+    }
+
+    fn splice_hints(
+        this: &WeakEntity<Self>,
+        editor: &Entity<Editor>,
+        buffer_id: BufferId,
+        items: &[OutlineItem<Anchor>],
+        counts: &[Option<usize>],
+        deltas: &Option<Vec<isize>>,
+        local_counts: bool,
+        stale: bool,
+        cx: &mut gpui::AsyncWindowContext,
+    ) {
+        this.update_in(cx, |this, window, cx| {
+            let padding = SymbolRefHintsSettings::get_global(cx).padding;
+            let hints = items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| {
+                    let count = counts[i]?;
+                    let delta = deltas.as_ref().map_or(0, |deltas| deltas[i]);
+                    Some((
+                        i,
+                        item.range.start,
+                        format_hint(count, local_counts, delta, padding),
+                    ))
+                })
+                .collect();
+            let emphasis = this
+                .shown
+                .remove(&editor.entity_id())
+                .filter(|shown| shown.buffer_id == buffer_id)
+                .and_then(|shown| shown.emphasis);
+            this.shown.insert(
+                editor.entity_id(),
+                EditorHints {
+                    buffer_id,
+                    hints,
+                    stale,
+                    emphasis,
+                },
+            );
+            Self::refresh_provider(editor, window, cx);
+
+            let published_counts = items
+                .iter()
+                .zip(counts)
+                .filter_map(|(item, count)| Some((item.range.start, (*count)?)))
+                .collect();
+            SymbolReferenceCounts::set(buffer_id, published_counts, cx);
+        })
+        .ok();
+    }
+
+    /// Computes hints for the active file despite it exceeding `max_symbols`.
+    fn force_large_file(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.skipped_large_file = false;
+        let Some(editor) = self
+            .active_editor
+            .as_ref()
+            .and_then(|editor| editor.upgrade())
+        else {
+            return;
+        };
+        if let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() {
+            self.forced_buffers.insert(buffer.read(cx).remote_id());
+        }
+        if self.inlays_enabled(&editor, cx) {
+            self.refresh_symbol_ref_hints(&editor, window, cx, Duration::ZERO);
+        }
+        cx.notify();
+    }
+
+    fn is_disabled_for_file(&self, editor: &Entity<Editor>, cx: &App) -> bool {
+        editor
+            .project_path(cx)
+            .is_some_and(|path| self.disabled_files.contains(&path))
+    }
+
+    /// Turns the hints off or back on for the editor's file only, leaving other files as they are.
+    pub fn toggle_for_file(
+        &mut self,
+        editor: &Entity<Editor>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(path) = editor.project_path(cx) else {
+            return;
+        };
+        if self.disabled_files.remove(&path) {
+            if self.inlays_enabled(editor, cx) {
+                self.refresh_symbol_ref_hints(editor, window, cx, Duration::ZERO);
+            }
+        } else {
+            self.disabled_files.insert(path);
+            self.bump_and_clear(editor, window, cx);
+            self.cancel_task();
+        }
+        cx.notify();
+    }
+
+    /// Pauses refreshes while many files change at once, so the language server is not
+    /// queried with positions that are about to become stale.
+    fn note_project_activity(
+        &mut self,
+        editor: &WeakEntity<Editor>,
+        changed_entries: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.recent_changed_entries += changed_entries;
+        if !self.bulk_operation_in_progress && self.recent_changed_entries >= BULK_OPERATION_ENTRIES
+        {
+            self.bulk_operation_in_progress = true;
+            self.pending_refresh = Some(editor.clone());
+            self.cancel_task();
+            cx.notify();
+        }
+        self.settle_task = cx.spawn_in(window, async move |this, cx| {
+            cx.background_executor().timer(BULK_OPERATION_SETTLE).await;
+            this.update_in(cx, |this, window, cx| this.on_project_settled(window, cx))
+                .ok();
+        });
+    }
+
+    fn on_project_settled(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.recent_changed_entries = 0;
+        if !std::mem::take(&mut self.bulk_operation_in_progress) {
+            return;
+        }
+        if let Some(editor) = self
+            .pending_refresh
+            .take()
+            .and_then(|editor| editor.upgrade())
+            && self.inlays_enabled(&editor, cx)
+        {
+            self.refresh_symbol_ref_hints(&editor, window, cx, Duration::ZERO);
+        }
+    }
+
+    /// Copies `path:line` entries for every reference to the hinted symbol under the cursor.
+    pub fn copy_references_list(&mut self, editor: &Entity<Editor>, cx: &mut Context<Self>) {
+        let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+            return;
+        };
+        let cursor = editor
+            .read(cx)
+            .selections
+            .newest_anchor()
+            .head()
+            .text_anchor;
+        let snapshot = buffer.read(cx).snapshot();
+        let cursor_offset = cursor.to_offset(&snapshot);
+        let Some(selection_range) = self
+            .symbol_ranges
+            .get(&snapshot.remote_id())
+            .into_iter()
+            .flatten()
+            .filter(|(item_range, _)| {
+                item_range.start.to_offset(&snapshot) <= cursor_offset
+                    && cursor_offset <= item_range.end.to_offset(&snapshot)
+            })
+            .max_by_key(|(item_range, _)| item_range.start.to_offset(&snapshot))
+            .map(|(_, selection_range)| {
+                selection_range.start.to_point(&snapshot)..selection_range.end.to_point(&snapshot)
+            })
+        else {
+            return;
+        };
+
+        let references = self.project.update(cx, |project, cx| {
+            project.references(&buffer, selection_range.start, cx)
         });
+        cx.spawn(async move |_, cx| {
+            let locations = references.await?.unwrap_or_default();
+            cx.update(|cx| {
+                let settings = SymbolRefHintsSettings::get_global(cx);
+                let lines = locations
+                    .iter()
+                    .filter(|location| {
+                        (settings.include_declaration
+                            || !is_declaration(location, &buffer, &selection_range, cx))
+                            && (settings.include_excluded_files
+                                || !is_excluded_location(location, cx))
+                    })
+                    .map(|location| format_location(location, cx))
+                    .collect::<Vec<_>>();
+                cx.write_to_clipboard(ClipboardItem::new_string(lines.join("\n")));
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// How many more or fewer times each symbol's name occurs in the buffer's text than in
+    /// the file's last committed version. It's a textual count, unlike the reference counts.
+    async fn name_deltas_since_head(
+        project: &Entity<Project>,
+        buffer: &Entity<Buffer>,
+        symbols: &[HintSymbol],
+        cx: &mut gpui::AsyncWindowContext,
+    ) -> Option<Vec<isize>> {
+        let diff = project
+            .update(cx, |project, cx| {
+                project.open_uncommitted_diff(buffer.clone(), cx)
+            })
+            .ok()?
+            .await
+            .ok()?;
+        let head_text = diff
+            .read_with(cx, |diff, _| diff.base_text_string())
+            .ok()
+            .flatten()?;
+        let current_text = buffer.read_with(cx, |buffer, _| buffer.text()).ok()?;
+        Some(
+            symbols
+                .iter()
+                .map(|symbol| {
+                    count_word_occurrences(&current_text, &symbol.name) as isize
+                        - count_word_occurrences(&head_text, &symbol.name) as isize
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A symbol that gets a hint, with the range of its name in the buffer.
+struct HintSymbol {
+    name: String,
+    selection_range: Range<Point>,
+}
+
+/// Counts the reference locations to a symbol, leaving out its declaration and
+/// excluded files unless the settings ask for them.
+pub(crate) fn count_locations(
+    locations: &[Location],
+    buffer: &Entity<Buffer>,
+    selection_range: &Range<Point>,
+    cx: &App,
+) -> usize {
+    let settings = SymbolRefHintsSettings::get_global(cx);
+    locations
+        .iter()
+        .filter(|location| {
+            (settings.include_declaration || !is_declaration(location, buffer, selection_range, cx))
+                && (settings.include_excluded_files || !is_excluded_location(location, cx))
+        })
+        .count()
+}
+
+/// Whether a reference location is the symbol's own declaration, which some
+/// servers include in their results and others don't.
+fn is_declaration(
+    location: &Location,
+    buffer: &Entity<Buffer>,
+    selection_range: &Range<Point>,
+    cx: &App,
+) -> bool {
+    if location.buffer != *buffer {
+        return false;
+    }
+    let snapshot = location.buffer.read(cx).snapshot();
+    let range = location.range.to_point(&snapshot);
+    ranges_overlap(&range, selection_range)
+}
+
+/// Whether a reference lies in a file that `file_scan_exclusions` or `private_files` cover,
+/// such as vendored or generated code.
+fn is_excluded_location(location: &Location, cx: &App) -> bool {
+    let Some(file) = location.buffer.read(cx).file() else {
+        return false;
+    };
+    let project_path = ProjectPath {
+        worktree_id: file.worktree_id(cx),
+        path: file.path().clone(),
+    };
+    let settings = WorktreeSettings::get(Some((&project_path).into()), cx);
+    settings.is_path_excluded(&project_path.path) || settings.is_path_private(&project_path.path)
+}
+
+fn ranges_overlap(a: &Range<Point>, b: &Range<Point>) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+fn format_location(location: &Location, cx: &App) -> String {
+    let buffer = location.buffer.read(cx);
+    let row = location.range.start.to_point(&buffer.snapshot()).row + 1;
+    match buffer.file() {
+        Some(file) => format!("{}:{row}", file.full_path(cx).display()),
+        None => format!("untitled:{row}"),
+    }
+}
+
+/// Where symbol usage counts come from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CountSource {
+    /// `textDocument/references`, counting usages across the workspace.
+    References,
+    /// `textDocument/documentHighlight`, a cheap count of usages within the file.
+    DocumentHighlights,
+}
+
+/// Indices of the symbols whose count differs from the one they had before.
+fn changed_counts(
+    keys: &[(String, usize)],
+    previous: &HashMap<(String, usize), usize>,
+    current: &HashMap<(String, usize), usize>,
+) -> Vec<usize> {
+    keys.iter()
+        .enumerate()
+        .filter(|(_, key)| {
+            previous
+                .get(*key)
+                .zip(current.get(*key))
+                .is_some_and(|(previous, current)| previous != current)
+        })
+        .map(|(ix, _)| ix)
+        .collect()
+}
+
+/// Identifies symbols by name and the order in which that name appears,
+/// which survives edits that merely shift symbols around.
+fn symbol_keys(symbols: &[HintSymbol]) -> Vec<(String, usize)> {
+    let mut seen = HashMap::<&str, usize>::default();
+    symbols
+        .iter()
+        .map(|symbol| {
+            let occurrence = seen.entry(symbol.name.as_str()).or_default();
+            let key = (symbol.name.clone(), *occurrence);
+            *occurrence += 1;
+            key
+        })
+        .collect()
+}
+
+fn item_name(item: &language::OutlineItem<language::Anchor>) -> String {
+    item.name_ranges
+        .first()
+        .and_then(|range| item.text.get(range.clone()))
+        .unwrap_or(item.text.as_str())
+        .to_string()
+}
+
+/// Whether a symbol is an entry point, such as `main` or a test, whose lack of references
+/// is expected. Patterns made of identifier characters match the symbol name, with `*` as a
+/// wildcard; other patterns match text in the declaration line at `row` or its annotations.
+pub(crate) fn is_entry_point(
+    name: &str,
+    snapshot: &BufferSnapshot,
+    row: u32,
+    patterns: &[String],
+) -> bool {
+    let mut header = None;
+    patterns.iter().any(|pattern| {
+        if pattern
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '*')
+        {
+            wildcard_match(pattern, name)
+        } else {
+            header
+                .get_or_insert_with(|| declaration_header(snapshot, row))
+                .contains(pattern.as_str())
+        }
+    })
+}
+
+/// The line at `row` together with the annotation lines (`#[...]`, `@...`) right above it.
+fn declaration_header(snapshot: &BufferSnapshot, row: u32) -> String {
+    let line = |row: u32| {
+        snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+            .collect::<String>()
+    };
+    let mut header = line(row);
+    for row in (0..row).rev() {
+        let above = line(row);
+        let trimmed = above.trim_start();
+        if !trimmed.starts_with("#[") && !trimmed.starts_with('@') {
+            break;
+        }
+        header = format!("{above}\n{header}");
+    }
+    header
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(mut rest) = text.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        let Some(ix) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[ix + part.len()..];
+    }
+    rest.ends_with(last)
+}
+
+/// Formats a hint, marking counts that only cover the current file as lower bounds. The
+/// delta counts occurrences of the symbol's name, so it's labeled apart from the references.
+fn format_hint(count: usize, lower_bound: bool, delta: isize, padding: usize) -> String {
+    let count = if lower_bound {
+        format!("≥{count}")
+    } else {
+        count.to_string()
+    };
+    let padding = " ".repeat(padding);
+    match delta {
+        0 => format!("{count}{padding}"),
+        delta if delta > 0 => format!("{count} · name ↑{delta}{padding}"),
+        delta => format!("{count} · name ↓{}{padding}", delta.unsigned_abs()),
+    }
+}
+
+/// The configured hint colors, faded out while the counts are being recomputed.
+fn hint_style(stale: bool, cx: &App) -> Option<HighlightStyle> {
+    let settings = SymbolRefHintsSettings::get_global(cx);
+    let style = HighlightStyle {
+        color: settings
+            .color
+            .as_deref()
+            .and_then(|color| resolve_color(color, cx)),
+        background_color: settings
+            .background
+            .as_deref()
+            .and_then(|color| resolve_color(color, cx)),
+        fade_out: stale.then_some(STALE_FADE_OUT),
+        ..HighlightStyle::default()
+    };
+    (style != HighlightStyle::default()).then_some(style)
+}
+
+/// Resolves a hex color, or the color of a syntax theme token.
+fn resolve_color(value: &str, cx: &App) -> Option<Hsla> {
+    if value.starts_with('#') {
+        return Rgba::try_from(value).ok().map(Hsla::from);
+    }
+    cx.theme().syntax().get(value).color
+}
+
+fn count_word_occurrences(text: &str, word: &str) -> usize {
+    if word.is_empty() {
+        return 0;
     }
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word)
+        .filter(|(ix, _)| {
+            let before = text[..*ix].chars().next_back();
+            let after = text[ix + word.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+        .count()
 }
 
 impl Render for SymbolRefHints {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
-        div().w_0().invisible()
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.skipped_large_file {
+            return div().child(
+                Button::new(
+                    "force-symbol-ref-hints",
+                    "Reference hints disabled for this large file — click to force",
+                )
+                .label_size(LabelSize::Small)
+                .color(Color::Muted)
+                .on_click(cx.listener(|this, _, window, cx| this.force_large_file(window, cx))),
+            );
+        }
+        let Some((done, total)) = self.progress else {
+            return div().w_0().invisible();
+        };
+        div().child(
+            Label::new(format!("Refs {done}/{total}"))
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        )
     }
 }
 
@@ -276,7 +1232,12 @@ impl StatusItemView for SymbolRefHints {
         cx: &mut Context<Self>,
     ) {
         self.cancel_task();
+        self.skipped_large_file = false;
+        self.active_editor = active_pane_item
+            .and_then(|item| item.act_as::<Editor>(cx))
+            .map(|editor| editor.downgrade());
         if let Some(editor) = active_pane_item.and_then(|item| item.act_as::<Editor>(cx)) {
+            self.add_provider(&editor, window, cx);
             self._observe_active_editor = Some(cx.subscribe_in(
                 &editor,
                 window,
@@ -303,7 +1264,7 @@ impl StatusItemView for SymbolRefHints {
                         editor.buffer().read(app).as_singleton().is_some()
                     });
                     if !(our_enabled && inlay_enabled) || !is_singleton {
-                        this.bump_and_clear(&editor_for_settings, cx);
+                        this.bump_and_clear(&editor_for_settings, window, cx);
                         this.cancel_task();
                     } else {
                         let debounce = this.edit_debounce(&editor_for_settings, cx);
@@ -312,13 +1273,80 @@ impl StatusItemView for SymbolRefHints {
                 },
             ));
 
+            let editor_for_project = editor.downgrade();
+            self._observe_project = Some(cx.subscribe_in(
+                &self.project.clone(),
+                window,
+                move |this, _, event: &project::Event, window, cx| match event {
+                    project::Event::LanguageServerAdded(..)
+                    | project::Event::LanguageServerRemoved(_)
+                    | project::Event::LanguageServerBufferRegistered { .. } => {
+                        let Some(editor) = editor_for_project.upgrade() else {
+                            return;
+                        };
+                        if this.inlays_enabled(&editor, cx) {
+                            let debounce = this.edit_debounce(&editor, cx);
+                            this.refresh_symbol_ref_hints(&editor, window, cx, debounce);
+                        }
+                    }
+                    project::Event::WorktreeUpdatedEntries(_, entries) => {
+                        this.note_project_activity(&editor_for_project, entries.len(), window, cx);
+                    }
+                    _ => {}
+                },
+            ));
+
             let debounce = self.edit_debounce(&editor, cx);
             self.refresh_symbol_ref_hints(&editor, window, cx, debounce);
         } else {
             self._observe_active_editor = None;
             self._observe_settings = None;
+            self._observe_project = None;
             self.cancel_task();
         }
         cx.notify();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_word_occurrences() {
+        let text = "fn foo() {}\nfn foo_bar() { foo(); bar_foo(); }\n// foo";
+        assert_eq!(count_word_occurrences(text, "foo"), 3);
+        assert_eq!(count_word_occurrences(text, "foo_bar"), 1);
+        assert_eq!(count_word_occurrences(text, "baz"), 0);
+        assert_eq!(count_word_occurrences(text, ""), 0);
+    }
+
+    #[test]
+    fn test_format_hint() {
+        assert_eq!(format_hint(5, false, 0, 1), "5 ");
+        assert_eq!(format_hint(5, false, 2, 1), "5 · name ↑2 ");
+        assert_eq!(format_hint(1, false, -3, 1), "1 · name ↓3 ");
+        assert_eq!(format_hint(7, false, 0, 3), "7   ");
+        assert_eq!(format_hint(3, true, 0, 1), "≥3 ");
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("main", "main"));
+        assert!(!wildcard_match("main", "main2"));
+        assert!(wildcard_match("test_*", "test_parse"));
+        assert!(!wildcard_match("test_*", "parse_test"));
+        assert!(wildcard_match("*_handler", "on_click_handler"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_changed_counts() {
+        let key = |name: &str| (name.to_string(), 0);
+        let keys = vec![key("a"), key("b"), key("c"), key("d")];
+        let previous = HashMap::from_iter([(key("a"), 2), (key("b"), 1), (key("c"), 4)]);
+        let current = HashMap::from_iter([(key("a"), 2), (key("b"), 0), (key("d"), 1)]);
+        assert_eq!(changed_counts(&keys, &previous, &current), vec![1]);
+    }
+}
@@ -170,7 +170,10 @@ pub mod dev {
         dev,
         [
             /// Toggles the developer inspector for debugging UI elements.
-            ToggleInspector
+            ToggleInspector,
+            /// Opens an agent thread that replays a scripted turn, to work on the
+            /// agent panel without network access.
+            OpenDebugAgentThread
         ]
     );
 }
@@ -322,6 +325,14 @@ pub mod agent {
             AddSelectionToThread,
             /// Resets the agent panel zoom levels (agent UI and buffer font sizes).
             ResetAgentZoom,
+            /// Opens a one-line prompt over the workspace that is sent to the agent thread.
+            QuickPrompt,
+            /// Opens the facts saved for the project, which are attached to every new thread.
+            OpenProjectMemory,
+            /// Opens the tool calls "Always Allow" was chosen for, to revoke them.
+            OpenAlwaysAllowed,
+            /// Opens the generation parameters of the active thread, such as its temperature.
+            OpenGenerationParameters,
         ]
     );
 }